@@ -18,9 +18,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Setup in-memory adapters for demo
     let post_repo = Arc::new(InMemoryPostRepository::new());
     let events = Arc::new(InMemoryEventPublisher::new());
+    let users = Arc::new(InMemoryUserRepository::new());
+    let groups = Arc::new(InMemoryGroupRepository::new());
+
+    // Posts are written by someone allowed to
+    let mut author = User::new(
+        Email::new("admin@example.com")?,
+        "Admin".to_string(),
+        String::new(),
+    );
+    author.make_superuser();
+    users.save(&author).await?;
 
     // Create the post service
-    let post_service = Arc::new(PostServiceImpl::new(post_repo.clone(), events.clone()));
+    let permissions = Arc::new(RepositoryPermissionChecker::new(users, groups));
+    let post_service = Arc::new(PostServiceImpl::new(
+        post_repo.clone(),
+        events.clone(),
+        permissions,
+    ));
 
     // Create some sample posts
     println!("Creating sample posts...");
@@ -28,9 +44,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let post1 = post_service
         .create(CreatePostCommand {
             title: "Welcome to Ferreiro".to_string(),
-            slug: "welcome-to-ferreiro".to_string(),
+            slug: Some("welcome-to-ferreiro".to_string()),
             body: "Ferreiro is a Django-inspired web framework for Rust.".to_string(),
-            author_id: UserId::generate(),
+            author_id: author.id().clone(),
         })
         .await?;
 
@@ -39,23 +55,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let post2 = post_service
         .create(CreatePostCommand {
             title: "Why Hexagonal Architecture".to_string(),
-            slug: "why-hexagonal-architecture".to_string(),
+            slug: Some("why-hexagonal-architecture".to_string()),
             body: "Hexagonal architecture keeps your domain pure.".to_string(),
-            author_id: UserId::generate(),
+            author_id: author.id().clone(),
         })
         .await?;
 
     println!("✓ Created post: {}", post2.title());
 
     // Publish the first post
-    post_service.publish(post1.id()).await?;
+    post_service.publish(post1.id(), post1.author_id()).await?;
     println!("✓ Published: {}", post1.title());
 
-    // List all posts
+    // List all posts, drafts included for their author
     let result = post_service
         .list(ListPostsQuery {
             filter: PostFilter::default(),
             pagination: Pagination::default(),
+            viewer: Some(author.id().clone()),
         })
         .await?;
 
@@ -127,6 +144,7 @@ async fn list_posts(
         .list(ListPostsQuery {
             filter: PostFilter::default(),
             pagination: Pagination::default(),
+            viewer: None,
         })
         .await
         .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
@@ -142,7 +160,7 @@ async fn get_post(
 ) -> Result<Json<PostResponse>, (axum::http::StatusCode, String)> {
     let post = state
         .post_service
-        .get_by_slug(&slug)
+        .get_by_slug(&slug, None)
         .await
         .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
         .ok_or_else(|| {
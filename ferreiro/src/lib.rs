@@ -25,16 +25,28 @@
 //!     // Setup repositories
 //!     let repo = Arc::new(InMemoryPostRepository::new());
 //!     let events = Arc::new(InMemoryEventPublisher::new());
-//!
-//!     // Create service
-//!     let service = Arc::new(PostServiceImpl::new(repo, events));
+//!     let users = Arc::new(InMemoryUserRepository::new());
+//!     let groups = Arc::new(InMemoryGroupRepository::new());
+//!
+//!     // Someone allowed to write posts
+//!     let mut author = User::new(
+//!         Email::new("admin@example.com")?,
+//!         "Admin".to_string(),
+//!         String::new(),
+//!     );
+//!     author.make_superuser();
+//!     users.save(&author).await?;
+//!
+//!     // Create service; the checker decides who may do what
+//!     let permissions = Arc::new(RepositoryPermissionChecker::new(users, groups));
+//!     let service = Arc::new(PostServiceImpl::new(repo, events, permissions));
 //!
 //!     // Create a post
 //!     let post = service.create(CreatePostCommand {
 //!         title: "Hello World".to_string(),
-//!         slug: "hello-world".to_string(),
+//!         slug: Some("hello-world".to_string()),
 //!         body: "My first post".to_string(),
-//!         author_id: UserId::generate(),
+//!         author_id: author.id().clone(),
 //!     }).await?;
 //!
 //!     // Build HTTP API
@@ -65,6 +77,11 @@
 //! - [`http`]: HTTP server and routing
 //! - [`templates`]: Template engines (Tera, MiniJinja)
 //! - [`session`]: Session management
+//! - [`cache`]: Cache backends (in-memory, Redis with the `redis` feature)
+//! - [`i18n`]: Translation catalogs and locale negotiation
+//! - [`email`]: Outgoing mail (SMTP, console, file, in-memory)
+//! - [`jwt`]: Signed access and refresh tokens (HS256, RS256, EdDSA)
+//! - [`oauth`]: OAuth2 and OpenID Connect providers for social login
 //! - [`admin`]: Admin interface (coming soon)
 //! - [`prelude`]: Convenient imports for common use cases
 
@@ -72,8 +89,13 @@ pub mod prelude;
 
 // Re-export all major modules
 pub use ferreiro_adapters_admin as admin;
+pub use ferreiro_adapters_cache as cache;
 pub use ferreiro_adapters_db as db;
+pub use ferreiro_adapters_email as email;
 pub use ferreiro_adapters_http as http;
+pub use ferreiro_adapters_i18n as i18n;
+pub use ferreiro_adapters_jwt as jwt;
+pub use ferreiro_adapters_oauth as oauth;
 pub use ferreiro_adapters_session as session;
 pub use ferreiro_adapters_templates as templates;
 pub use ferreiro_application as application;
@@ -8,28 +8,87 @@
 // Domain exports
 pub use ferreiro_domain::errors::DomainError;
 pub use ferreiro_domain::events::DomainEvent;
-pub use ferreiro_domain::models::{Post, PostStatus, User};
+pub use ferreiro_domain::mail::{Attachment, EmailMessage, Mailbox};
+pub use ferreiro_domain::models::{
+    ApiToken, AuthUser, ExternalIdentity, Group, NewUser, Permission, Post, PostStatus,
+    ProviderTokens, TotpDevice, User, UserRecord,
+};
+pub use ferreiro_domain::passwords::{
+    CommonPasswords, MinimumLength, NumericPassword, PasswordPolicy, PasswordValidator,
+    UserAttributeSimilarity, UserAttributes, ValidatorConfigError,
+};
+pub use ferreiro_domain::policies::{Actor, DefaultPostPolicy, PostAction, PostPolicy, ViewScope};
 pub use ferreiro_domain::ports::driven::{
-    EventPublisher, PaginatedResult, Pagination, PasswordHasher, PostFilter, PostRepository,
-    RepositoryError, UserRepository,
+    AccessClaims, AccessTokenVerifier, ApiTokenRepository, AuthorizationRequest, BreachLookupError,
+    BreachedPasswords, Cache, CacheError, EmailError, EmailSender, EventPublisher,
+    ExternalIdentityRepository, ExternalProfile, GroupRepository, LoginFailureStore, LoginFailures,
+    MxLookup, MxLookupError, OAuthError, OAuthProvider, PaginatedResult, Pagination,
+    PasswordHasher, PermissionChecker, PostFilter, PostRepository, RefreshTokenRecord,
+    RefreshTokenRepository, RepositoryError, TokenError, TokenIssuer, TokenPair,
+    TotpDeviceRepository, Translator, UserRepository,
 };
 pub use ferreiro_domain::ports::driving::{
-    AuthService, CreatePostCommand, ListPostsQuery, PostService, RegisterCommand, ServiceError,
-    UpdatePostCommand,
+    ApiTokenService, AuthService, AuthorizationCallback, AuthorizationRedirect,
+    ChangePasswordCommand, CreateApiTokenCommand, CreatePostCommand, CreatedApiToken, Credentials,
+    ListPostsQuery, MfaService, PostService, RegisterCommand, ResetPasswordCommand, ServiceError,
+    SocialAuthService, TotpEnrollment, UpdatePostCommand,
+};
+pub use ferreiro_domain::values::{
+    ApiTokenId, Body, Email, GroupId, PostId, SecretToken, Slug, Title, UserId,
 };
-pub use ferreiro_domain::values::{Body, Email, PostId, Slug, Title, UserId};
 
 // Application exports
-pub use ferreiro_application::services::PostServiceImpl;
+pub use ferreiro_application::services::{
+    ensure_deliverable, ensure_not_breached, AccountTokens, ApiTokenServiceImpl, AuthServiceImpl,
+    FailureLimits, LoginThrottle, MfaServiceImpl, PostServiceImpl, RepositoryPermissionChecker,
+    SocialAuthServiceImpl, TokenPurpose, Totp,
+};
+pub use ferreiro_application::CacheExt;
 
 // Database adapters
-pub use ferreiro_adapters_db::{InMemoryEventPublisher, InMemoryPostRepository};
+pub use ferreiro_adapters_db::{
+    InMemoryApiTokenRepository, InMemoryBreachedPasswords, InMemoryEventPublisher,
+    InMemoryExternalIdentityRepository, InMemoryGroupRepository, InMemoryLoginFailureStore,
+    InMemoryMxLookup, InMemoryPostRepository, InMemoryRefreshTokenRepository,
+    InMemoryTotpDeviceRepository, InMemoryUserRepository,
+};
 
 // HTTP adapters
-pub use ferreiro_adapters_http::serve;
+pub use ferreiro_adapters_http::{
+    auth_middleware, forms::Choices, jwt_middleware, locale_middleware, page_cache_middleware,
+    serve, session_middleware, timezone_middleware, AuthConfig, Claims, CurrentUser, Field, Form,
+    FormData, JwtConfig, Locale, LocaleConfig, Localization, ModelForm, PageCacheConfig, PostForm,
+    Renderer, Session, SessionConfig, Template, Templates, Timezone, TimezoneConfig,
+};
+
+// i18n adapters
+pub use ferreiro_adapters_i18n::{Catalog, Catalogs};
+
+// Cache adapters
+pub use ferreiro_adapters_cache::{
+    CacheLoginFailureStore, CachedPostRepository, InMemoryCache, InvalidatingEventPublisher,
+    NamespacedCache,
+};
 
 // Template adapters
-pub use ferreiro_adapters_templates::{context, Context, TemplateEngine, TemplateError};
+pub use ferreiro_adapters_templates::{
+    context, Context, ContextProcessors, Localizer, TemplateEngine, TemplateError,
+};
+
+// Email adapters
+pub use ferreiro_adapters_email::{
+    ConsoleEmailSender, EmailTemplate, FileEmailSender, InMemoryEmailSender, SmtpEmailSender,
+    SmtpSecurity,
+};
+
+// JWT adapters
+pub use ferreiro_adapters_jwt::{JwtKey, JwtTokenIssuer, JwtVerifier, KeySet};
+
+// OAuth adapters
+pub use ferreiro_adapters_oauth::OidcProvider;
+
+// Admin adapters
+pub use ferreiro_adapters_admin::UserAdmin;
 
 // Session adapters
 pub use ferreiro_adapters_session::{SessionData, SessionError, SessionId, SessionStore};
@@ -1,5 +1,8 @@
 /// Admin introspection and auto-generation
 /// This will be expanded in future iterations
+pub mod users;
+
+pub use users::UserAdmin;
 
 #[derive(Debug, Clone)]
 pub struct AdminField {
@@ -0,0 +1,117 @@
+use crate::{AdminField, AdminFieldType, AdminModel, ModelAdmin};
+use ferreiro_domain::models::{AuthUser, User};
+use std::marker::PhantomData;
+
+/// The admin for a user model, `User` unless the project has its own
+///
+/// Lists users by their identifier, so a model that logs in with a
+/// username shows usernames. The password hash is never a field; add a
+/// model's own fields, such as an avatar, with `with_field`.
+pub struct UserAdmin<M: AuthUser = User> {
+    extra_fields: Vec<AdminField>,
+    model: PhantomData<fn() -> M>,
+}
+
+impl UserAdmin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<M: AuthUser> Default for UserAdmin<M> {
+    fn default() -> Self {
+        Self {
+            extra_fields: Vec::new(),
+            model: PhantomData,
+        }
+    }
+}
+
+impl<M: AuthUser> UserAdmin<M> {
+    pub fn with_field(mut self, field: AdminField) -> Self {
+        self.extra_fields.push(field);
+        self
+    }
+}
+
+fn field(name: &'static str, field_type: AdminFieldType, required: bool) -> AdminField {
+    AdminField {
+        name,
+        display_name: name.replace('_', " "),
+        field_type,
+        required,
+        editable: true,
+    }
+}
+
+impl<M: AuthUser> AdminModel for UserAdmin<M> {
+    fn name(&self) -> &'static str {
+        "user"
+    }
+
+    fn name_plural(&self) -> &'static str {
+        "users"
+    }
+
+    fn fields(&self) -> Vec<AdminField> {
+        let mut fields = vec![field(
+            M::IDENTIFIER_FIELD,
+            AdminFieldType::String {
+                max_length: Some(254),
+            },
+            true,
+        )];
+        if M::IDENTIFIER_FIELD != "email" {
+            fields.push(field(
+                "email",
+                AdminFieldType::String {
+                    max_length: Some(254),
+                },
+                true,
+            ));
+        }
+        fields.push(field(
+            "name",
+            AdminFieldType::String { max_length: None },
+            false,
+        ));
+        for flag in ["is_active", "is_staff", "is_superuser", "email_verified"] {
+            fields.push(field(flag, AdminFieldType::Boolean, false));
+        }
+        fields.extend(self.extra_fields.iter().cloned());
+        fields
+    }
+
+    fn primary_key(&self) -> &'static str {
+        "id"
+    }
+
+    fn display(&self, instance: &dyn std::any::Any) -> String {
+        instance
+            .downcast_ref::<M>()
+            .map(|user| user.identifier())
+            .unwrap_or_default()
+    }
+}
+
+impl<M: AuthUser> ModelAdmin for UserAdmin<M> {
+    fn list_display(&self) -> Vec<&'static str> {
+        vec![M::IDENTIFIER_FIELD, "name", "is_active", "is_staff"]
+    }
+
+    fn list_filter(&self) -> Vec<&'static str> {
+        vec!["is_active", "is_staff", "is_superuser"]
+    }
+
+    fn search_fields(&self) -> Vec<&'static str> {
+        let mut fields = vec![M::IDENTIFIER_FIELD, "name"];
+        if M::IDENTIFIER_FIELD != "email" {
+            fields.push("email");
+        }
+        fields
+    }
+
+    fn ordering(&self) -> Vec<&'static str> {
+        vec![M::IDENTIFIER_FIELD]
+    }
+}
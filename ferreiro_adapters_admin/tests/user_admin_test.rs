@@ -0,0 +1,38 @@
+use ferreiro_adapters_admin::{AdminField, AdminFieldType, AdminModel, ModelAdmin, UserAdmin};
+use ferreiro_domain::models::User;
+use ferreiro_domain::values::Email;
+
+#[test]
+fn test_user_admin_describes_the_model_without_its_password() {
+    let admin = UserAdmin::new().with_field(AdminField {
+        name: "avatar_url",
+        display_name: "avatar".to_string(),
+        field_type: AdminFieldType::String { max_length: None },
+        required: false,
+        editable: true,
+    });
+
+    let fields: Vec<&str> = admin.fields().iter().map(|field| field.name).collect();
+    assert_eq!(
+        fields,
+        [
+            "email",
+            "name",
+            "is_active",
+            "is_staff",
+            "is_superuser",
+            "email_verified",
+            "avatar_url"
+        ]
+    );
+    assert_eq!(admin.list_display()[0], "email");
+    assert_eq!(admin.search_fields(), ["email", "name"]);
+
+    let ana = User::new(
+        Email::new("Ana@Example.com").unwrap(),
+        "Ana".to_string(),
+        "plain$password".to_string(),
+    );
+    assert_eq!(admin.display(&ana), "ana@example.com");
+    assert_eq!(admin.display(&"not a user"), "");
+}
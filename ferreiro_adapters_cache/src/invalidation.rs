@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use ferreiro_domain::events::DomainEvent;
+use ferreiro_domain::ports::driven::{EventError, EventPublisher};
+use std::sync::Arc;
+
+use crate::NamespacedCache;
+
+/// Wraps an `EventPublisher` and invalidates the given caches whenever a
+/// post is published or archived, since either changes what listings show
+///
+/// ```rust,ignore
+/// let events = InvalidatingEventPublisher::new(events, vec![pages, fragments]);
+/// let service = PostServiceImpl::new(repo, Arc::new(events), permissions);
+/// ```
+pub struct InvalidatingEventPublisher<E: EventPublisher> {
+    inner: Arc<E>,
+    caches: Vec<NamespacedCache>,
+}
+
+impl<E: EventPublisher> InvalidatingEventPublisher<E> {
+    pub fn new(inner: Arc<E>, caches: Vec<NamespacedCache>) -> Self {
+        Self { inner, caches }
+    }
+
+    async fn invalidate(&self, event: &DomainEvent) -> Result<(), EventError> {
+        if !matches!(
+            event,
+            DomainEvent::PostPublished { .. } | DomainEvent::PostArchived { .. }
+        ) {
+            return Ok(());
+        }
+        for cache in &self.caches {
+            cache.invalidate().await.map_err(|e| {
+                EventError::PublishFailed(format!(
+                    "invalidating cache {}: {}",
+                    cache.namespace(),
+                    e
+                ))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<E: EventPublisher> EventPublisher for InvalidatingEventPublisher<E> {
+    async fn publish(&self, event: DomainEvent) -> Result<(), EventError> {
+        self.invalidate(&event).await?;
+        self.inner.publish(event).await
+    }
+
+    async fn publish_all(&self, events: Vec<DomainEvent>) -> Result<(), EventError> {
+        for event in &events {
+            self.invalidate(event).await?;
+        }
+        self.inner.publish_all(events).await
+    }
+}
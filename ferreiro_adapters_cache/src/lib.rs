@@ -0,0 +1,23 @@
+//! Cache backends implementing `ferreiro_domain::ports::driven::Cache`
+//!
+//! `NamespacedCache` groups keys so a whole set of entries (every cached
+//! page, every template fragment) can be dropped at once, and
+//! `InvalidatingEventPublisher` does that when posts change.
+//! `CachedPostRepository` keeps single posts out of the database on hot
+//! pages, and `CacheLoginFailureStore` counts failed logins in any cache,
+//! so servers sharing Redis throttle together.
+
+pub mod invalidation;
+pub mod login_failures;
+pub mod memory;
+pub mod namespace;
+pub mod repository;
+
+#[cfg(feature = "redis")]
+pub mod redis;
+
+pub use invalidation::InvalidatingEventPublisher;
+pub use login_failures::CacheLoginFailureStore;
+pub use memory::InMemoryCache;
+pub use namespace::NamespacedCache;
+pub use repository::CachedPostRepository;
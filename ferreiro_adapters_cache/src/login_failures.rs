@@ -0,0 +1,99 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ferreiro_domain::ports::driven::{
+    Cache, CacheError, LoginFailureStore, LoginFailures, RepositoryError,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Counts failed logins in a `Cache`
+///
+/// Each key keeps a count and the time of the last failure, both rewritten
+/// with the TTL on every failure. The count goes through `Cache::incr`,
+/// which Redis does atomically, so failures racing each other from
+/// different servers each get their own count; rewriting it for the TTL
+/// can, rarely, lose one of them.
+#[derive(Clone)]
+pub struct CacheLoginFailureStore {
+    cache: Arc<dyn Cache>,
+}
+
+impl CacheLoginFailureStore {
+    pub fn new(cache: Arc<dyn Cache>) -> Self {
+        Self { cache }
+    }
+
+    fn count_key(key: &str) -> String {
+        format!("{}:count", key)
+    }
+
+    fn last_failed_key(key: &str) -> String {
+        format!("{}:last", key)
+    }
+}
+
+#[async_trait]
+impl LoginFailureStore for CacheLoginFailureStore {
+    async fn get(&self, key: &str) -> Result<Option<LoginFailures>, RepositoryError> {
+        let count_key = Self::count_key(key);
+        let last_failed_key = Self::last_failed_key(key);
+        let values = self
+            .cache
+            .get_many(&[&count_key, &last_failed_key])
+            .await
+            .map_err(storage)?;
+        let [Some(count), Some(last_failed_at)] = &values[..] else {
+            return Ok(None);
+        };
+
+        let count = String::from_utf8_lossy(count).parse::<u32>().ok();
+        let last_failed_at = String::from_utf8_lossy(last_failed_at)
+            .parse::<i64>()
+            .ok()
+            .and_then(DateTime::from_timestamp_millis);
+        Ok(count
+            .zip(last_failed_at)
+            .map(|(count, last_failed_at)| LoginFailures {
+                count,
+                last_failed_at,
+            }))
+    }
+
+    async fn record(&self, key: &str, ttl: Duration) -> Result<LoginFailures, RepositoryError> {
+        let now = Utc::now();
+        let count_key = Self::count_key(key);
+        let count = self.cache.incr(&count_key, 1).await.map_err(storage)?;
+        self.cache
+            .set(&count_key, count.to_string().as_bytes(), Some(ttl))
+            .await
+            .map_err(storage)?;
+        self.cache
+            .set(
+                &Self::last_failed_key(key),
+                now.timestamp_millis().to_string().as_bytes(),
+                Some(ttl),
+            )
+            .await
+            .map_err(storage)?;
+
+        Ok(LoginFailures {
+            count: count.clamp(0, u32::MAX as i64) as u32,
+            last_failed_at: now,
+        })
+    }
+
+    async fn clear(&self, key: &str) -> Result<(), RepositoryError> {
+        self.cache
+            .delete(&Self::count_key(key))
+            .await
+            .map_err(storage)?;
+        self.cache
+            .delete(&Self::last_failed_key(key))
+            .await
+            .map_err(storage)
+    }
+}
+
+fn storage(error: CacheError) -> RepositoryError {
+    RepositoryError::Connection(error.to_string())
+}
@@ -0,0 +1,124 @@
+use async_trait::async_trait;
+use ferreiro_domain::ports::driven::{Cache, CacheError};
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+struct Entry {
+    value: Vec<u8>,
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|at| at <= Instant::now())
+    }
+}
+
+/// Per-process cache; expired entries are dropped when next read
+///
+/// `new` is unbounded. `with_capacity` keeps at most that many entries,
+/// evicting the least recently used one to make room.
+#[derive(Clone)]
+pub struct InMemoryCache {
+    entries: Arc<Mutex<LruCache<String, Entry>>>,
+}
+
+impl InMemoryCache {
+    pub fn new() -> Self {
+        Self::from_lru(LruCache::unbounded())
+    }
+
+    pub fn with_capacity(max_entries: NonZeroUsize) -> Self {
+        Self::from_lru(LruCache::new(max_entries))
+    }
+
+    fn from_lru(entries: LruCache<String, Entry>) -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(entries)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn read(entries: &mut LruCache<String, Entry>, key: &str) -> Option<Vec<u8>> {
+        match entries.get(key) {
+            Some(entry) if entry.is_expired() => {
+                entries.pop(key);
+                None
+            }
+            Some(entry) => Some(entry.value.clone()),
+            None => None,
+        }
+    }
+}
+
+impl Default for InMemoryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Cache for InMemoryCache {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, CacheError> {
+        Ok(Self::read(&mut self.entries.lock().unwrap(), key))
+    }
+
+    async fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<Vec<u8>>>, CacheError> {
+        let mut entries = self.entries.lock().unwrap();
+        Ok(keys
+            .iter()
+            .map(|key| Self::read(&mut entries, key))
+            .collect())
+    }
+
+    async fn set(&self, key: &str, value: &[u8], ttl: Option<Duration>) -> Result<(), CacheError> {
+        let entry = Entry {
+            value: value.to_vec(),
+            expires_at: ttl.map(|ttl| Instant::now() + ttl),
+        };
+        self.entries.lock().unwrap().put(key.to_string(), entry);
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), CacheError> {
+        self.entries.lock().unwrap().pop(key);
+        Ok(())
+    }
+
+    async fn incr(&self, key: &str, delta: i64) -> Result<i64, CacheError> {
+        let mut entries = self.entries.lock().unwrap();
+        let (current, expires_at) = match entries.get(key) {
+            Some(entry) if !entry.is_expired() => {
+                let current = std::str::from_utf8(&entry.value)
+                    .ok()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .ok_or_else(|| CacheError::NotAnInteger(key.to_string()))?;
+                (current, entry.expires_at)
+            }
+            _ => (0, None),
+        };
+
+        let value = current + delta;
+        entries.put(
+            key.to_string(),
+            Entry {
+                value: value.to_string().into_bytes(),
+                expires_at,
+            },
+        );
+        Ok(value)
+    }
+}
@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+use ferreiro_domain::ports::driven::{Cache, CacheError};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Prefixes every key with a namespace and a generation number
+///
+/// `invalidate` bumps the generation, so every entry written before it is
+/// never read again and simply expires — no need to enumerate keys, which
+/// Redis can't do cheaply.
+#[derive(Clone)]
+pub struct NamespacedCache {
+    inner: Arc<dyn Cache>,
+    namespace: String,
+}
+
+impl NamespacedCache {
+    pub fn new(inner: Arc<dyn Cache>, namespace: &str) -> Self {
+        Self {
+            inner,
+            namespace: namespace.to_string(),
+        }
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub async fn invalidate(&self) -> Result<(), CacheError> {
+        self.inner.incr(&self.generation_key(), 1).await.map(|_| ())
+    }
+
+    fn generation_key(&self) -> String {
+        format!("{}:generation", self.namespace)
+    }
+
+    async fn prefix(&self) -> Result<String, CacheError> {
+        let generation = match self.inner.get(&self.generation_key()).await? {
+            Some(value) => String::from_utf8_lossy(&value).into_owned(),
+            None => "0".to_string(),
+        };
+        Ok(format!("{}:{}:", self.namespace, generation))
+    }
+
+    async fn key(&self, key: &str) -> Result<String, CacheError> {
+        Ok(self.prefix().await? + key)
+    }
+}
+
+#[async_trait]
+impl Cache for NamespacedCache {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, CacheError> {
+        self.inner.get(&self.key(key).await?).await
+    }
+
+    async fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<Vec<u8>>>, CacheError> {
+        let prefix = self.prefix().await?;
+        let keys: Vec<String> = keys
+            .iter()
+            .map(|key| format!("{}{}", prefix, key))
+            .collect();
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        self.inner.get_many(&keys).await
+    }
+
+    async fn set(&self, key: &str, value: &[u8], ttl: Option<Duration>) -> Result<(), CacheError> {
+        self.inner.set(&self.key(key).await?, value, ttl).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), CacheError> {
+        self.inner.delete(&self.key(key).await?).await
+    }
+
+    async fn incr(&self, key: &str, delta: i64) -> Result<i64, CacheError> {
+        self.inner.incr(&self.key(key).await?, delta).await
+    }
+}
@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use ferreiro_domain::ports::driven::{Cache, CacheError};
+use redis::aio::ConnectionManager;
+use redis::{Client, RedisError};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+/// Cache shared by every server process
+///
+/// Commands go through redis' async `ConnectionManager`, opened on first
+/// use. It reconnects on its own after an error, so a Redis restart costs
+/// a few failed lookups instead of a server restart, and requests never
+/// block a runtime thread waiting on the network.
+pub struct RedisCache {
+    client: Client,
+    connection: OnceCell<ConnectionManager>,
+}
+
+impl RedisCache {
+    pub fn new(url: &str) -> Result<Self, CacheError> {
+        let client = Client::open(url).map_err(backend)?;
+        Ok(Self {
+            client,
+            connection: OnceCell::new(),
+        })
+    }
+
+    async fn query<T: redis::FromRedisValue>(&self, cmd: &redis::Cmd) -> Result<T, CacheError> {
+        let connection = self
+            .connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await
+            .map_err(backend)?;
+        // Clones share the one multiplexed connection
+        cmd.query_async(&mut connection.clone())
+            .await
+            .map_err(backend)
+    }
+}
+
+#[async_trait]
+impl Cache for RedisCache {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, CacheError> {
+        self.query(redis::cmd("GET").arg(key)).await
+    }
+
+    async fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<Vec<u8>>>, CacheError> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.query(redis::cmd("MGET").arg(keys)).await
+    }
+
+    async fn set(&self, key: &str, value: &[u8], ttl: Option<Duration>) -> Result<(), CacheError> {
+        let mut cmd = redis::cmd("SET");
+        cmd.arg(key).arg(value);
+        if let Some(ttl) = ttl {
+            cmd.arg("PX").arg(ttl.as_millis().max(1) as u64);
+        }
+        self.query(&cmd).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), CacheError> {
+        self.query(redis::cmd("DEL").arg(key)).await
+    }
+
+    async fn incr(&self, key: &str, delta: i64) -> Result<i64, CacheError> {
+        self.query(redis::cmd("INCRBY").arg(key).arg(delta))
+            .await
+            .map_err(|e| match e {
+                CacheError::Backend(message) if message.contains("not an integer") => {
+                    CacheError::NotAnInteger(key.to_string())
+                }
+                other => other,
+            })
+    }
+}
+
+fn backend(error: RedisError) -> CacheError {
+    CacheError::Backend(error.to_string())
+}
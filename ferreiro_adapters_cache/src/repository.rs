@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use ferreiro_domain::models::Post;
+use ferreiro_domain::ports::driven::{
+    Cache, PaginatedResult, Pagination, PostFilter, PostRepository, RepositoryError,
+};
+use ferreiro_domain::values::{PostId, Slug};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Caches single-post lookups in front of another `PostRepository`
+///
+/// Posts are cached by id, and slugs map to ids, so `save` and `delete`
+/// only have to drop the post's own entries. A slug that now belongs to a
+/// different post is caught on read. Cache failures fall through to the
+/// wrapped repository; `list` is never cached.
+///
+/// ```rust,ignore
+/// let repo = CachedPostRepository::new(Arc::new(repo), cache).ttl(Duration::from_secs(60));
+/// let service = PostServiceImpl::new(Arc::new(repo), events, permissions);
+/// ```
+pub struct CachedPostRepository<R: PostRepository> {
+    inner: Arc<R>,
+    cache: Arc<dyn Cache>,
+    ttl: Option<Duration>,
+}
+
+impl<R: PostRepository> CachedPostRepository<R> {
+    pub fn new(inner: Arc<R>, cache: Arc<dyn Cache>) -> Self {
+        Self {
+            inner,
+            cache,
+            ttl: Some(Duration::from_secs(300)),
+        }
+    }
+
+    /// `None` keeps posts until they are saved or deleted
+    pub fn ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    fn id_key(id: &PostId) -> String {
+        format!("post:id:{}", id)
+    }
+
+    fn slug_key(slug: &Slug) -> String {
+        format!("post:slug:{}", slug)
+    }
+
+    /// Entries are JSON; one that doesn't decode is a miss
+    async fn read<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let bytes = self.cache.get(key).await.ok()??;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    async fn write<T: Serialize + Sync>(&self, key: &str, value: &T) {
+        if let Ok(bytes) = serde_json::to_vec(value) {
+            let _ = self.cache.set(key, &bytes, self.ttl).await;
+        }
+    }
+
+    async fn remember(&self, post: &Post) {
+        self.write(&Self::id_key(post.id()), post).await;
+        self.write(&Self::slug_key(post.slug()), post.id()).await;
+    }
+
+    async fn forget(&self, id: &PostId) {
+        let _ = self.cache.delete(&Self::id_key(id)).await;
+    }
+}
+
+#[async_trait]
+impl<R: PostRepository> PostRepository for CachedPostRepository<R> {
+    async fn find_by_id(&self, id: &PostId) -> Result<Option<Post>, RepositoryError> {
+        if let Some(post) = self.read::<Post>(&Self::id_key(id)).await {
+            return Ok(Some(post));
+        }
+
+        let post = self.inner.find_by_id(id).await?;
+        if let Some(post) = &post {
+            self.remember(post).await;
+        }
+        Ok(post)
+    }
+
+    async fn find_by_slug(&self, slug: &Slug) -> Result<Option<Post>, RepositoryError> {
+        if let Some(id) = self.read::<PostId>(&Self::slug_key(slug)).await {
+            if let Some(post) = self.read::<Post>(&Self::id_key(&id)).await {
+                if post.slug() == slug {
+                    return Ok(Some(post));
+                }
+            }
+        }
+
+        let post = self.inner.find_by_slug(slug).await?;
+        match &post {
+            Some(post) => self.remember(post).await,
+            None => {
+                let _ = self.cache.delete(&Self::slug_key(slug)).await;
+            }
+        }
+        Ok(post)
+    }
+
+    async fn save(&self, post: &Post) -> Result<(), RepositoryError> {
+        self.inner.save(post).await?;
+        self.forget(post.id()).await;
+        let _ = self.cache.delete(&Self::slug_key(post.slug())).await;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &PostId) -> Result<(), RepositoryError> {
+        self.inner.delete(id).await?;
+        self.forget(id).await;
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        filter: PostFilter,
+        pagination: Pagination,
+    ) -> Result<PaginatedResult<Post>, RepositoryError> {
+        self.inner.list(filter, pagination).await
+    }
+
+    async fn exists_by_slug(&self, slug: &Slug) -> Result<bool, RepositoryError> {
+        self.inner.exists_by_slug(slug).await
+    }
+}
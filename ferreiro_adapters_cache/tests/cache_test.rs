@@ -0,0 +1,75 @@
+use ferreiro_adapters_cache::{InMemoryCache, NamespacedCache};
+use ferreiro_domain::ports::driven::{Cache, CacheError};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_memory_cache_expires_entries() {
+    let cache = InMemoryCache::new();
+    cache
+        .set("short", b"a", Some(Duration::from_millis(20)))
+        .await
+        .unwrap();
+    cache.set("forever", b"b", None).await.unwrap();
+
+    assert_eq!(cache.get("short").await.unwrap(), Some(b"a".to_vec()));
+    tokio::time::sleep(Duration::from_millis(40)).await;
+    assert_eq!(cache.get("short").await.unwrap(), None);
+    assert_eq!(cache.get("forever").await.unwrap(), Some(b"b".to_vec()));
+
+    cache.delete("forever").await.unwrap();
+    assert_eq!(cache.get("forever").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_memory_cache_incr() {
+    let cache = InMemoryCache::new();
+    assert_eq!(cache.incr("hits", 1).await.unwrap(), 1);
+    assert_eq!(cache.incr("hits", 5).await.unwrap(), 6);
+    assert_eq!(cache.incr("hits", -2).await.unwrap(), 4);
+    assert_eq!(cache.get("hits").await.unwrap(), Some(b"4".to_vec()));
+
+    cache.set("name", b"ferreiro", None).await.unwrap();
+    assert!(matches!(
+        cache.incr("name", 1).await,
+        Err(CacheError::NotAnInteger(key)) if key == "name"
+    ));
+}
+
+#[tokio::test]
+async fn test_namespaces_are_isolated_and_invalidated_separately() {
+    let backend: Arc<dyn Cache> = Arc::new(InMemoryCache::new());
+    let pages = NamespacedCache::new(backend.clone(), "pages");
+    let fragments = NamespacedCache::new(backend, "fragments");
+
+    pages.set("/", b"page", None).await.unwrap();
+    fragments.set("/", b"fragment", None).await.unwrap();
+    assert_eq!(pages.get("/").await.unwrap(), Some(b"page".to_vec()));
+    assert_eq!(
+        fragments.get("/").await.unwrap(),
+        Some(b"fragment".to_vec())
+    );
+
+    pages.invalidate().await.unwrap();
+    assert_eq!(pages.get("/").await.unwrap(), None);
+    assert_eq!(
+        fragments.get("/").await.unwrap(),
+        Some(b"fragment".to_vec())
+    );
+}
+
+#[tokio::test]
+async fn test_lru_capacity_evicts_least_recently_used() {
+    let cache = InMemoryCache::with_capacity(NonZeroUsize::new(2).unwrap());
+    cache.set("a", b"1", None).await.unwrap();
+    cache.set("b", b"2", None).await.unwrap();
+    cache.get("a").await.unwrap();
+    cache.set("c", b"3", None).await.unwrap();
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!(
+        cache.get_many(&["a", "b", "c"]).await.unwrap(),
+        vec![Some(b"1".to_vec()), None, Some(b"3".to_vec())]
+    );
+}
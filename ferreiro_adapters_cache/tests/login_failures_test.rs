@@ -0,0 +1,37 @@
+use ferreiro_adapters_cache::{CacheLoginFailureStore, InMemoryCache};
+use ferreiro_domain::ports::driven::LoginFailureStore;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_failures_are_counted_until_cleared_or_expired() {
+    let store = CacheLoginFailureStore::new(Arc::new(InMemoryCache::new()));
+    let ttl = Duration::from_secs(60);
+    assert_eq!(store.get("login:account:ana").await.unwrap(), None);
+
+    store.record("login:account:ana", ttl).await.unwrap();
+    let second = store.record("login:account:ana", ttl).await.unwrap();
+    assert_eq!(second.count, 2);
+    let stored = store.get("login:account:ana").await.unwrap().unwrap();
+    assert_eq!(stored.count, 2);
+    assert_eq!(
+        stored.last_failed_at.timestamp_millis(),
+        second.last_failed_at.timestamp_millis()
+    );
+    assert_eq!(store.get("login:account:bia").await.unwrap(), None);
+
+    store.clear("login:account:ana").await.unwrap();
+    assert_eq!(store.get("login:account:ana").await.unwrap(), None);
+
+    store
+        .record("login:ip:192.0.2.1", Duration::from_millis(20))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(40)).await;
+    assert_eq!(store.get("login:ip:192.0.2.1").await.unwrap(), None);
+    let fresh = store
+        .record("login:ip:192.0.2.1", Duration::from_millis(20))
+        .await
+        .unwrap();
+    assert_eq!(fresh.count, 1);
+}
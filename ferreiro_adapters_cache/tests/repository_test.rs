@@ -0,0 +1,65 @@
+use ferreiro_adapters_cache::{CachedPostRepository, InMemoryCache};
+use ferreiro_adapters_db::InMemoryPostRepository;
+use ferreiro_domain::models::Post;
+use ferreiro_domain::ports::driven::PostRepository;
+use ferreiro_domain::values::{Body, Slug, Title, UserId};
+use std::sync::Arc;
+
+fn post(title: &str, slug: &str) -> Post {
+    Post::new(
+        Title::new(title).unwrap(),
+        Slug::new(slug).unwrap(),
+        Body::new("Body"),
+        UserId::generate(),
+    )
+}
+
+#[tokio::test]
+async fn test_lookups_are_served_from_cache_until_saved() {
+    let db = Arc::new(InMemoryPostRepository::new());
+    let repo = CachedPostRepository::new(db.clone(), Arc::new(InMemoryCache::new()));
+
+    let mut original = post("Original", "hello");
+    repo.save(&original).await.unwrap();
+    assert_eq!(
+        repo.find_by_slug(original.slug())
+            .await
+            .unwrap()
+            .unwrap()
+            .title()
+            .as_str(),
+        "Original"
+    );
+
+    // Written behind the decorator's back, so the cached copy is still served
+    original.update_content(Title::new("Edited").unwrap(), Body::new("Body"));
+    db.save(&original).await.unwrap();
+    let cached = repo.find_by_id(original.id()).await.unwrap().unwrap();
+    assert_eq!(cached.title().as_str(), "Original");
+    let cached = repo.find_by_slug(original.slug()).await.unwrap().unwrap();
+    assert_eq!(cached.title().as_str(), "Original");
+
+    repo.save(&original).await.unwrap();
+    let fresh = repo.find_by_slug(original.slug()).await.unwrap().unwrap();
+    assert_eq!(fresh.title().as_str(), "Edited");
+}
+
+#[tokio::test]
+async fn test_delete_and_reused_slugs_invalidate() {
+    let db = Arc::new(InMemoryPostRepository::new());
+    let repo = CachedPostRepository::new(db.clone(), Arc::new(InMemoryCache::new()));
+
+    let first = post("First", "shared");
+    repo.save(&first).await.unwrap();
+    assert!(repo.find_by_slug(first.slug()).await.unwrap().is_some());
+
+    repo.delete(first.id()).await.unwrap();
+    assert!(repo.find_by_id(first.id()).await.unwrap().is_none());
+    assert!(repo.find_by_slug(first.slug()).await.unwrap().is_none());
+
+    // A new post takes the slug directly in the database
+    let second = post("Second", "shared");
+    db.save(&second).await.unwrap();
+    let found = repo.find_by_slug(second.slug()).await.unwrap().unwrap();
+    assert_eq!(found.id(), second.id());
+}
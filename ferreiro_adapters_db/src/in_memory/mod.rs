@@ -1,13 +1,19 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use ferreiro_domain::events::DomainEvent;
-use ferreiro_domain::models::Post;
+use ferreiro_domain::models::{
+    ApiToken, AuthUser, ExternalIdentity, Group, Post, PostStatus, TotpDevice, User,
+};
 use ferreiro_domain::ports::driven::{
-    EventError, EventPublisher, PaginatedResult, Pagination, PostFilter, PostRepository,
-    RepositoryError,
+    ApiTokenRepository, BreachLookupError, BreachedPasswords, EventError, EventPublisher,
+    ExternalIdentityRepository, GroupRepository, LoginFailureStore, LoginFailures, MxLookup,
+    MxLookupError, PaginatedResult, Pagination, PostFilter, PostRepository, RefreshTokenRecord,
+    RefreshTokenRepository, RepositoryError, TotpDeviceRepository, UserRepository,
 };
-use ferreiro_domain::values::{PostId, Slug};
+use ferreiro_domain::values::{ApiTokenId, Email, GroupId, PostId, Slug, UserId};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 /// In-memory implementation for testing
 #[derive(Clone)]
@@ -43,6 +49,12 @@ impl PostRepository for InMemoryPostRepository {
 
     async fn save(&self, post: &Post) -> Result<(), RepositoryError> {
         let mut posts = self.posts.write().unwrap();
+        if posts
+            .values()
+            .any(|p| p.slug() == post.slug() && p.id() != post.id())
+        {
+            return Err(RepositoryError::Conflict);
+        }
         posts.insert(post.id().clone(), post.clone());
         Ok(())
     }
@@ -68,6 +80,9 @@ impl PostRepository for InMemoryPostRepository {
         if let Some(status) = &filter.status {
             items.retain(|p| p.status() == status);
         }
+        if let Some(author_id) = &filter.published_or_by {
+            items.retain(|p| p.status() == &PostStatus::Published || p.author_id() == author_id);
+        }
         if let Some(published_after) = filter.published_after {
             items.retain(|p| {
                 p.published_at()
@@ -102,6 +117,383 @@ impl PostRepository for InMemoryPostRepository {
     }
 }
 
+/// In-memory user repository for testing; emails match by normalized form
+///
+/// Holds `User`s; `InMemoryUserRepository::<Member>::default()` holds a
+/// custom model instead.
+#[derive(Clone)]
+pub struct InMemoryUserRepository<M = User> {
+    users: Arc<RwLock<HashMap<UserId, M>>>,
+}
+
+impl InMemoryUserRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<M> Default for InMemoryUserRepository<M> {
+    fn default() -> Self {
+        Self {
+            users: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+#[async_trait]
+impl<M: AuthUser> UserRepository<M> for InMemoryUserRepository<M> {
+    async fn find_by_id(&self, id: &UserId) -> Result<Option<M>, RepositoryError> {
+        let users = self.users.read().unwrap();
+        Ok(users.get(id).cloned())
+    }
+
+    async fn find_by_email(&self, email: &Email) -> Result<Option<M>, RepositoryError> {
+        let normalized = email.normalized();
+        let users = self.users.read().unwrap();
+        Ok(users
+            .values()
+            .find(|u| u.email().normalized() == normalized)
+            .cloned())
+    }
+
+    async fn find_by_identifier(&self, identifier: &str) -> Result<Option<M>, RepositoryError> {
+        let users = self.users.read().unwrap();
+        Ok(users
+            .values()
+            .find(|u| u.identifier() == identifier)
+            .cloned())
+    }
+
+    async fn save(&self, user: &M) -> Result<(), RepositoryError> {
+        let mut users = self.users.write().unwrap();
+        let email = user.email().normalized();
+        let identifier = user.identifier();
+        if users.values().any(|u| {
+            u.id() != user.id() && (u.email().normalized() == email || u.identifier() == identifier)
+        }) {
+            return Err(RepositoryError::Conflict);
+        }
+        users.insert(user.id().clone(), user.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, id: &UserId) -> Result<(), RepositoryError> {
+        let mut users = self.users.write().unwrap();
+        users.remove(id);
+        Ok(())
+    }
+
+    async fn exists_by_email(&self, email: &Email) -> Result<bool, RepositoryError> {
+        Ok(self.find_by_email(email).await?.is_some())
+    }
+}
+
+/// In-memory group repository for testing
+#[derive(Clone, Default)]
+pub struct InMemoryGroupRepository {
+    groups: Arc<RwLock<HashMap<GroupId, Group>>>,
+}
+
+impl InMemoryGroupRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl GroupRepository for InMemoryGroupRepository {
+    async fn find_by_id(&self, id: &GroupId) -> Result<Option<Group>, RepositoryError> {
+        let groups = self.groups.read().unwrap();
+        Ok(groups.get(id).cloned())
+    }
+
+    async fn find_by_name(&self, name: &str) -> Result<Option<Group>, RepositoryError> {
+        let groups = self.groups.read().unwrap();
+        Ok(groups.values().find(|g| g.name() == name).cloned())
+    }
+
+    async fn find_many(&self, ids: &[GroupId]) -> Result<Vec<Group>, RepositoryError> {
+        let groups = self.groups.read().unwrap();
+        Ok(ids
+            .iter()
+            .filter_map(|id| groups.get(id).cloned())
+            .collect())
+    }
+
+    async fn list(&self) -> Result<Vec<Group>, RepositoryError> {
+        let groups = self.groups.read().unwrap();
+        let mut all: Vec<Group> = groups.values().cloned().collect();
+        all.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok(all)
+    }
+
+    async fn save(&self, group: &Group) -> Result<(), RepositoryError> {
+        let mut groups = self.groups.write().unwrap();
+        groups.insert(group.id().clone(), group.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, id: &GroupId) -> Result<(), RepositoryError> {
+        let mut groups = self.groups.write().unwrap();
+        groups.remove(id);
+        Ok(())
+    }
+}
+
+/// In-memory API token repository for testing
+#[derive(Clone, Default)]
+pub struct InMemoryApiTokenRepository {
+    tokens: Arc<RwLock<HashMap<ApiTokenId, ApiToken>>>,
+}
+
+impl InMemoryApiTokenRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepository for InMemoryApiTokenRepository {
+    async fn find_by_id(&self, id: &ApiTokenId) -> Result<Option<ApiToken>, RepositoryError> {
+        let tokens = self.tokens.read().unwrap();
+        Ok(tokens.get(id).cloned())
+    }
+
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>, RepositoryError> {
+        let tokens = self.tokens.read().unwrap();
+        Ok(tokens
+            .values()
+            .find(|t| t.token_hash() == token_hash)
+            .cloned())
+    }
+
+    async fn list_for_user(&self, user_id: &UserId) -> Result<Vec<ApiToken>, RepositoryError> {
+        let tokens = self.tokens.read().unwrap();
+        let mut owned: Vec<ApiToken> = tokens
+            .values()
+            .filter(|t| t.user_id() == user_id)
+            .cloned()
+            .collect();
+        owned.sort_by_key(|t| std::cmp::Reverse(t.created_at()));
+        Ok(owned)
+    }
+
+    async fn save(&self, token: &ApiToken) -> Result<(), RepositoryError> {
+        let mut tokens = self.tokens.write().unwrap();
+        tokens.insert(token.id().clone(), token.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, id: &ApiTokenId) -> Result<(), RepositoryError> {
+        let mut tokens = self.tokens.write().unwrap();
+        tokens.remove(id);
+        Ok(())
+    }
+}
+
+/// In-memory refresh token store for testing
+#[derive(Clone, Default)]
+pub struct InMemoryRefreshTokenRepository {
+    records: Arc<RwLock<HashMap<String, RefreshTokenRecord>>>,
+}
+
+impl InMemoryRefreshTokenRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RefreshTokenRepository for InMemoryRefreshTokenRepository {
+    async fn find(&self, id: &str) -> Result<Option<RefreshTokenRecord>, RepositoryError> {
+        let records = self.records.read().unwrap();
+        Ok(records.get(id).cloned())
+    }
+
+    async fn save(&self, record: &RefreshTokenRecord) -> Result<(), RepositoryError> {
+        let mut records = self.records.write().unwrap();
+        records.insert(record.id.clone(), record.clone());
+        Ok(())
+    }
+
+    async fn mark_used(&self, id: &str) -> Result<bool, RepositoryError> {
+        let mut records = self.records.write().unwrap();
+        let record = records.get_mut(id).ok_or(RepositoryError::NotFound)?;
+        Ok(!std::mem::replace(&mut record.used, true))
+    }
+
+    async fn revoke_family(&self, family: &str) -> Result<(), RepositoryError> {
+        let mut records = self.records.write().unwrap();
+        for record in records.values_mut().filter(|r| r.family == family) {
+            record.revoked = true;
+        }
+        Ok(())
+    }
+
+    async fn revoke_all_for_user(&self, user_id: &UserId) -> Result<(), RepositoryError> {
+        let mut records = self.records.write().unwrap();
+        for record in records.values_mut().filter(|r| &r.user_id == user_id) {
+            record.revoked = true;
+        }
+        Ok(())
+    }
+}
+
+/// In-memory external identity store for testing
+#[derive(Clone, Default)]
+pub struct InMemoryExternalIdentityRepository {
+    identities: Arc<RwLock<HashMap<(String, String), ExternalIdentity>>>,
+}
+
+impl InMemoryExternalIdentityRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ExternalIdentityRepository for InMemoryExternalIdentityRepository {
+    async fn find(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<ExternalIdentity>, RepositoryError> {
+        let identities = self.identities.read().unwrap();
+        Ok(identities
+            .get(&(provider.to_string(), subject.to_string()))
+            .cloned())
+    }
+
+    async fn list_for_user(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Vec<ExternalIdentity>, RepositoryError> {
+        let identities = self.identities.read().unwrap();
+        let mut linked: Vec<ExternalIdentity> = identities
+            .values()
+            .filter(|i| i.user_id() == user_id)
+            .cloned()
+            .collect();
+        linked.sort_by_key(|i| i.linked_at());
+        Ok(linked)
+    }
+
+    async fn save(&self, identity: &ExternalIdentity) -> Result<(), RepositoryError> {
+        let mut identities = self.identities.write().unwrap();
+        identities.insert(
+            (
+                identity.provider().to_string(),
+                identity.subject().to_string(),
+            ),
+            identity.clone(),
+        );
+        Ok(())
+    }
+
+    async fn delete(&self, provider: &str, subject: &str) -> Result<(), RepositoryError> {
+        let mut identities = self.identities.write().unwrap();
+        identities.remove(&(provider.to_string(), subject.to_string()));
+        Ok(())
+    }
+}
+
+/// In-memory TOTP device store for testing
+#[derive(Clone, Default)]
+pub struct InMemoryTotpDeviceRepository {
+    devices: Arc<RwLock<HashMap<UserId, TotpDevice>>>,
+}
+
+impl InMemoryTotpDeviceRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TotpDeviceRepository for InMemoryTotpDeviceRepository {
+    async fn find(&self, user_id: &UserId) -> Result<Option<TotpDevice>, RepositoryError> {
+        let devices = self.devices.read().unwrap();
+        Ok(devices.get(user_id).cloned())
+    }
+
+    async fn save(&self, device: &TotpDevice) -> Result<(), RepositoryError> {
+        let mut devices = self.devices.write().unwrap();
+        devices.insert(device.user_id().clone(), device.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, user_id: &UserId) -> Result<(), RepositoryError> {
+        let mut devices = self.devices.write().unwrap();
+        devices.remove(user_id);
+        Ok(())
+    }
+
+    async fn use_step(&self, user_id: &UserId, step: u64) -> Result<bool, RepositoryError> {
+        let mut devices = self.devices.write().unwrap();
+        let device = devices.get_mut(user_id).ok_or(RepositoryError::NotFound)?;
+        Ok(device.use_step(step))
+    }
+
+    async fn use_recovery_code(
+        &self,
+        user_id: &UserId,
+        code_hash: &str,
+    ) -> Result<bool, RepositoryError> {
+        let mut devices = self.devices.write().unwrap();
+        let device = devices.get_mut(user_id).ok_or(RepositoryError::NotFound)?;
+        Ok(device.use_recovery_code(code_hash))
+    }
+}
+
+/// Failures under a key, and when they are forgotten
+type ExpiringFailures = (LoginFailures, DateTime<Utc>);
+
+/// In-memory login failure counts for testing and single-process apps
+#[derive(Clone, Default)]
+pub struct InMemoryLoginFailureStore {
+    failures: Arc<RwLock<HashMap<String, ExpiringFailures>>>,
+}
+
+impl InMemoryLoginFailureStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl LoginFailureStore for InMemoryLoginFailureStore {
+    async fn get(&self, key: &str) -> Result<Option<LoginFailures>, RepositoryError> {
+        let failures = self.failures.read().unwrap();
+        Ok(failures
+            .get(key)
+            .filter(|(_, expires_at)| *expires_at > Utc::now())
+            .map(|(failures, _)| *failures))
+    }
+
+    async fn record(&self, key: &str, ttl: Duration) -> Result<LoginFailures, RepositoryError> {
+        let now = Utc::now();
+        let ttl =
+            chrono::Duration::from_std(ttl).map_err(|e| RepositoryError::Query(e.to_string()))?;
+        let mut failures = self.failures.write().unwrap();
+        let count = failures
+            .get(key)
+            .filter(|(_, expires_at)| *expires_at > now)
+            .map_or(0, |(failures, _)| failures.count);
+        let recorded = LoginFailures {
+            count: count + 1,
+            last_failed_at: now,
+        };
+        failures.insert(key.to_string(), (recorded, now + ttl));
+        Ok(recorded)
+    }
+
+    async fn clear(&self, key: &str) -> Result<(), RepositoryError> {
+        let mut failures = self.failures.write().unwrap();
+        failures.remove(key);
+        Ok(())
+    }
+}
+
 /// In-memory event publisher for testing
 #[derive(Clone)]
 pub struct InMemoryEventPublisher {
@@ -142,3 +534,80 @@ impl EventPublisher for InMemoryEventPublisher {
         Ok(())
     }
 }
+
+/// Fixed MX records for testing; unknown domains have none
+#[derive(Clone, Default)]
+pub struct InMemoryMxLookup {
+    records: Arc<RwLock<HashMap<String, Vec<String>>>>,
+}
+
+impl InMemoryMxLookup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_domain(self, domain: &str, hosts: &[&str]) -> Self {
+        self.records.write().unwrap().insert(
+            domain.to_lowercase(),
+            hosts.iter().map(|host| host.to_string()).collect(),
+        );
+        self
+    }
+}
+
+#[async_trait]
+impl MxLookup for InMemoryMxLookup {
+    async fn mx_hosts(&self, domain: &str) -> Result<Vec<String>, MxLookupError> {
+        let records = self.records.read().unwrap();
+        Ok(records
+            .get(&domain.to_lowercase())
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// Breached-password hashes loaded from a fixture, for testing offline
+///
+/// The fixture has one `SHA1:COUNT` line per password, with the full
+/// uppercase SHA-1 hash, such as the Pwned Passwords downloader writes.
+/// Lookups return ranges as the real API does.
+#[derive(Clone, Default)]
+pub struct InMemoryBreachedPasswords {
+    hashes: Arc<RwLock<HashMap<String, u64>>>,
+}
+
+impl InMemoryBreachedPasswords {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Blank lines and lines that aren't `SHA1:COUNT` are skipped
+    pub fn from_hashes(fixture: &str) -> Self {
+        let hashes = fixture
+            .lines()
+            .filter_map(|line| {
+                let (hash, count) = line.trim().split_once(':')?;
+                let count = count.trim().parse().ok()?;
+                (hash.len() == 40).then(|| (hash.to_uppercase(), count))
+            })
+            .collect();
+        Self {
+            hashes: Arc::new(RwLock::new(hashes)),
+        }
+    }
+}
+
+#[async_trait]
+impl BreachedPasswords for InMemoryBreachedPasswords {
+    async fn range(&self, prefix: &str) -> Result<Vec<(String, u64)>, BreachLookupError> {
+        let prefix = prefix.to_uppercase();
+        let hashes = self.hashes.read().unwrap();
+        Ok(hashes
+            .iter()
+            .filter_map(|(hash, count)| {
+                hash.strip_prefix(&prefix)
+                    .map(|suffix| (suffix.to_string(), *count))
+            })
+            .collect())
+    }
+}
@@ -1,5 +1,7 @@
 pub mod in_memory;
 pub mod postgres;
 pub mod sqlite;
+#[cfg(feature = "test-support")]
+pub mod testing;
 
 pub use in_memory::*;
@@ -0,0 +1,15 @@
+use ferreiro_domain::ports::driven::{HashError, PasswordHasher};
+
+/// Stores passwords as `plain$<password>`, so tests run fast and can see
+/// what was stored; never use it outside tests
+pub struct PlainTextHasher;
+
+impl PasswordHasher for PlainTextHasher {
+    fn hash(&self, password: &str) -> Result<String, HashError> {
+        Ok(format!("plain${}", password))
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<bool, HashError> {
+        Ok(hash.strip_prefix("plain$") == Some(password))
+    }
+}
@@ -0,0 +1,43 @@
+use crate::message::to_mime;
+use async_trait::async_trait;
+use chrono::Utc;
+use ferreiro_domain::mail::{EmailMessage, Mailbox};
+use ferreiro_domain::ports::driven::{EmailError, EmailSender};
+use ferreiro_domain::values::Email;
+use std::io::Write;
+
+/// Prints each message to stdout instead of sending it, for development
+pub struct ConsoleEmailSender {
+    default_from: Mailbox,
+}
+
+impl ConsoleEmailSender {
+    pub fn new() -> Self {
+        Self {
+            default_from: Mailbox::new(Email::from_trusted("webmaster@localhost".to_string())),
+        }
+    }
+
+    /// Sender for messages that don't set their own `from`;
+    /// `webmaster@localhost` by default
+    pub fn default_from(mut self, mailbox: Mailbox) -> Self {
+        self.default_from = mailbox;
+        self
+    }
+}
+
+impl Default for ConsoleEmailSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl EmailSender for ConsoleEmailSender {
+    async fn send(&self, message: &EmailMessage) -> Result<(), EmailError> {
+        let mime = to_mime(message, &self.default_from, Utc::now())?;
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}\n{}", mime.replace("\r\n", "\n"), "-".repeat(79))
+            .map_err(|e| EmailError::Storage(e.to_string()))
+    }
+}
@@ -0,0 +1,55 @@
+use crate::message::to_mime;
+use async_trait::async_trait;
+use chrono::Utc;
+use ferreiro_domain::mail::{EmailMessage, Mailbox};
+use ferreiro_domain::ports::driven::{EmailError, EmailSender};
+use ferreiro_domain::values::Email;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Writes each message to its own `.eml` file in a directory, for staging
+/// servers and for inspecting mail in any mail client
+///
+/// Files are named by time sent, so a directory listing is in order. The
+/// directory is created on first send.
+pub struct FileEmailSender {
+    dir: PathBuf,
+    default_from: Mailbox,
+}
+
+impl FileEmailSender {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            default_from: Mailbox::new(Email::from_trusted("webmaster@localhost".to_string())),
+        }
+    }
+
+    /// Sender for messages that don't set their own `from`;
+    /// `webmaster@localhost` by default
+    pub fn default_from(mut self, mailbox: Mailbox) -> Self {
+        self.default_from = mailbox;
+        self
+    }
+}
+
+#[async_trait]
+impl EmailSender for FileEmailSender {
+    async fn send(&self, message: &EmailMessage) -> Result<(), EmailError> {
+        let now = Utc::now();
+        let mime = to_mime(message, &self.default_from, now)?;
+        let storage = |e: std::io::Error| EmailError::Storage(e.to_string());
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(storage)?;
+        let name = format!(
+            "{}-{}.eml",
+            now.format("%Y%m%d-%H%M%S%.6f"),
+            &Uuid::new_v4().simple().to_string()[..8]
+        );
+        tokio::fs::write(self.dir.join(name), mime)
+            .await
+            .map_err(storage)
+    }
+}
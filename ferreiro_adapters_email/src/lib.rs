@@ -0,0 +1,20 @@
+//! Email backends implementing `ferreiro_domain::ports::driven::EmailSender`
+//!
+//! `SmtpEmailSender` delivers to a mail server. `ConsoleEmailSender` and
+//! `FileEmailSender` show what would be sent during development, and
+//! `InMemoryEmailSender` collects it for tests. `EmailTemplate` builds a
+//! message from templates rendered by any `TemplateEngine`.
+
+pub mod console;
+pub mod file;
+pub mod memory;
+pub mod message;
+pub mod smtp;
+pub mod template;
+
+pub use console::ConsoleEmailSender;
+pub use file::FileEmailSender;
+pub use memory::InMemoryEmailSender;
+pub use message::to_mime;
+pub use smtp::{SmtpEmailSender, SmtpSecurity};
+pub use template::EmailTemplate;
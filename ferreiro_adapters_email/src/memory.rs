@@ -0,0 +1,37 @@
+use crate::message::validate;
+use async_trait::async_trait;
+use ferreiro_domain::mail::EmailMessage;
+use ferreiro_domain::ports::driven::{EmailError, EmailSender};
+use std::sync::{Arc, RwLock};
+
+/// Keeps sent messages in an outbox instead of sending them, for tests
+///
+/// Clones share the outbox, so keep one to inspect what the code under
+/// test sent.
+#[derive(Clone, Default)]
+pub struct InMemoryEmailSender {
+    outbox: Arc<RwLock<Vec<EmailMessage>>>,
+}
+
+impl InMemoryEmailSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn messages(&self) -> Vec<EmailMessage> {
+        self.outbox.read().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.outbox.write().unwrap().clear();
+    }
+}
+
+#[async_trait]
+impl EmailSender for InMemoryEmailSender {
+    async fn send(&self, message: &EmailMessage) -> Result<(), EmailError> {
+        validate(message)?;
+        self.outbox.write().unwrap().push(message.clone());
+        Ok(())
+    }
+}
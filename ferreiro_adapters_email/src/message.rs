@@ -0,0 +1,258 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use ferreiro_domain::mail::{Attachment, EmailMessage, Mailbox};
+use ferreiro_domain::ports::driven::EmailError;
+use uuid::Uuid;
+
+/// Longest line, without its CRLF, for quoted-printable and base64 bodies
+const LINE_LENGTH: usize = 76;
+
+/// UTF-8 bytes per encoded word, so each stays under 75 characters
+const ENCODED_WORD_BYTES: usize = 45;
+
+/// Rejects messages no backend could deliver: no recipients, or a line
+/// break in a header, which would let the value add headers of its own;
+/// attachment filenames and content types end up in headers too
+pub fn validate(message: &EmailMessage) -> Result<(), EmailError> {
+    if message.recipients().next().is_none() {
+        return Err(EmailError::InvalidMessage("no recipients".to_string()));
+    }
+
+    let names = message
+        .from_mailbox()
+        .into_iter()
+        .chain(message.reply_to_mailbox())
+        .chain(message.recipients())
+        .filter_map(Mailbox::name);
+    let header_values = message
+        .headers()
+        .iter()
+        .flat_map(|(name, value)| [name.as_str(), value.as_str()]);
+    let attachment_values = message
+        .attachments()
+        .iter()
+        .flat_map(|attachment| [attachment.filename(), attachment.content_type()]);
+    let has_line_break = [message.subject()]
+        .into_iter()
+        .chain(names)
+        .chain(header_values)
+        .chain(attachment_values)
+        .any(|value| value.contains(['\r', '\n']));
+    if has_line_break {
+        return Err(EmailError::InvalidMessage(
+            "header values can't contain line breaks".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The message as RFC 5322 text, with CRLF line endings, ready for SMTP
+/// `DATA` or an `.eml` file
+///
+/// `from` is used when the message doesn't set its own. A message with
+/// both bodies is `multipart/alternative`; attachments wrap that in
+/// `multipart/mixed`.
+pub fn to_mime(
+    message: &EmailMessage,
+    from: &Mailbox,
+    date: DateTime<Utc>,
+) -> Result<String, EmailError> {
+    validate(message)?;
+    let from = message.from_mailbox().unwrap_or(from);
+
+    let mut headers = vec![
+        ("From", mailbox_header(from)),
+        ("Date", date.to_rfc2822()),
+        (
+            "Message-ID",
+            format!("<{}@{}>", Uuid::new_v4().simple(), from.email().domain()),
+        ),
+        ("Subject", encode_header(message.subject())),
+    ];
+    if !message.to_mailboxes().is_empty() {
+        headers.push(("To", mailbox_list(message.to_mailboxes())));
+    }
+    if !message.cc_mailboxes().is_empty() {
+        headers.push(("Cc", mailbox_list(message.cc_mailboxes())));
+    }
+    if let Some(reply_to) = message.reply_to_mailbox() {
+        headers.push(("Reply-To", mailbox_header(reply_to)));
+    }
+    headers.push(("MIME-Version", "1.0".to_string()));
+
+    let mut mime: String = headers
+        .into_iter()
+        .map(|(name, value)| format!("{}: {}\r\n", name, value))
+        .collect();
+    for (name, value) in message.headers() {
+        mime.push_str(&format!("{}: {}\r\n", name, encode_header(value)));
+    }
+    mime.push_str(&body(message));
+    Ok(mime)
+}
+
+/// Content headers, a blank line and the content
+fn body(message: &EmailMessage) -> String {
+    let text = message
+        .text_body()
+        .map(|text| text_part("text/plain", text));
+    let html = message.html_body().map(|html| text_part("text/html", html));
+
+    let content = match (text, html) {
+        (Some(text), Some(html)) => multipart("alternative", &[text, html]),
+        (Some(part), None) | (None, Some(part)) => part,
+        (None, None) => text_part("text/plain", ""),
+    };
+    if message.attachments().is_empty() {
+        return content;
+    }
+
+    let parts: Vec<String> = std::iter::once(content)
+        .chain(message.attachments().iter().map(attachment_part))
+        .collect();
+    multipart("mixed", &parts)
+}
+
+fn text_part(content_type: &str, text: &str) -> String {
+    format!(
+        "Content-Type: {}; charset=utf-8\r\nContent-Transfer-Encoding: quoted-printable\r\n\r\n{}",
+        content_type,
+        quoted_printable(text)
+    )
+}
+
+fn attachment_part(attachment: &Attachment) -> String {
+    let filename = filename_param(attachment.filename());
+    format!(
+        "Content-Type: {}; name{}\r\nContent-Disposition: attachment; filename{}\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+        attachment.content_type(),
+        filename,
+        filename,
+        wrapped_base64(attachment.data())
+    )
+}
+
+fn multipart(subtype: &str, parts: &[String]) -> String {
+    // Quoted-printable and base64 bodies can't contain "=_", so the
+    // boundary never shows up inside a part
+    let boundary = format!("=_{}", Uuid::new_v4().simple());
+    let mut mime = format!(
+        "Content-Type: multipart/{}; boundary=\"{}\"\r\n\r\n",
+        subtype, boundary
+    );
+    for part in parts {
+        mime.push_str(&format!("--{}\r\n{}\r\n", boundary, part));
+    }
+    mime.push_str(&format!("--{}--\r\n", boundary));
+    mime
+}
+
+fn mailbox_list(mailboxes: &[Mailbox]) -> String {
+    mailboxes
+        .iter()
+        .map(mailbox_header)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `Name <address>`, quoting or encoding the name as needed
+fn mailbox_header(mailbox: &Mailbox) -> String {
+    let address = mailbox.email().as_str();
+    let Some(name) = mailbox.name().filter(|name| !name.is_empty()) else {
+        return address.to_string();
+    };
+
+    let name = if !name.is_ascii() {
+        encode_words(name)
+    } else if name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == ' ' || "!#$%&'*+-/=?^_`{|}~".contains(c))
+    {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+    };
+    format!("{} <{}>", name, address)
+}
+
+/// Non-ASCII values become RFC 2047 encoded words
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        encode_words(value)
+    }
+}
+
+fn encode_words(value: &str) -> String {
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    for c in value.chars() {
+        if chunk.len() + c.len_utf8() > ENCODED_WORD_BYTES {
+            words.push(std::mem::take(&mut chunk));
+        }
+        chunk.push(c);
+    }
+    words.push(chunk);
+
+    words
+        .iter()
+        .map(|word| format!("=?utf-8?b?{}?=", BASE64.encode(word)))
+        .collect::<Vec<_>>()
+        .join("\r\n ")
+}
+
+/// `="name"`, or RFC 2231 `*=utf-8''…` for non-ASCII names
+fn filename_param(filename: &str) -> String {
+    if filename.is_ascii() {
+        return format!(
+            "=\"{}\"",
+            filename.replace('\\', "\\\\").replace('"', "\\\"")
+        );
+    }
+    let encoded: String = filename
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect();
+    format!("*=utf-8''{}", encoded)
+}
+
+fn wrapped_base64(data: &[u8]) -> String {
+    BASE64
+        .encode(data)
+        .as_bytes()
+        .chunks(LINE_LENGTH)
+        .map(|line| format!("{}\r\n", String::from_utf8_lossy(line)))
+        .collect()
+}
+
+/// RFC 2045 quoted-printable, with soft breaks to keep lines short
+fn quoted_printable(text: &str) -> String {
+    let mut encoded = String::new();
+    for line in text.replace("\r\n", "\n").split('\n') {
+        let bytes = line.as_bytes();
+        let mut length = 0;
+        for (i, &byte) in bytes.iter().enumerate() {
+            let last = i + 1 == bytes.len();
+            let token = match byte {
+                b' ' | b'\t' if !last => (byte as char).to_string(),
+                b'!'..=b'<' | b'>'..=b'~' => (byte as char).to_string(),
+                _ => format!("={:02X}", byte),
+            };
+            // Leave room for the "=" of a soft break
+            if length + token.len() > LINE_LENGTH - 1 {
+                encoded.push_str("=\r\n");
+                length = 0;
+            }
+            length += token.len();
+            encoded.push_str(&token);
+        }
+        encoded.push_str("\r\n");
+    }
+    encoded
+}
@@ -0,0 +1,377 @@
+use crate::message::to_mime;
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::Utc;
+use ferreiro_domain::mail::{EmailMessage, Mailbox};
+use ferreiro_domain::ports::driven::{EmailError, EmailSender};
+use std::future::Future;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+/// How `SmtpEmailSender` protects the connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// Upgrade with `STARTTLS`, usually on port 587; servers that don't
+    /// offer it are refused. The default.
+    StartTls,
+    /// TLS from the start, usually on port 465
+    Tls,
+    /// Plain text, for a relay on the same host or private network, such as
+    /// a local Postfix. Credentials are only sent to `localhost` this way.
+    None,
+}
+
+/// Sends mail to an SMTP server, one connection per `send` (or per
+/// `send_all` batch)
+///
+/// Connections are upgraded with `STARTTLS` unless `security` says
+/// otherwise, and the server's certificate is checked against the Mozilla
+/// roots. Credentials are sent with `AUTH PLAIN` when the server offers it.
+///
+/// As Django's backend, a message goes to the recipients the server
+/// accepts; it fails only if the server refuses them all. A message the
+/// server refuses doesn't stop the rest of a batch: the first refusal is
+/// returned once the others are sent.
+///
+/// ```rust,ignore
+/// let mailer = SmtpEmailSender::new("smtp.example.com", 587)
+///     .credentials(&username, &password)
+///     .default_from(Mailbox::named("Ferreiro", Email::new("noreply@example.com")?));
+/// ```
+#[derive(Clone)]
+pub struct SmtpEmailSender {
+    host: String,
+    port: u16,
+    security: SmtpSecurity,
+    tls: Arc<ClientConfig>,
+    hello_name: String,
+    credentials: Option<(String, String)>,
+    default_from: Option<Mailbox>,
+    timeout: Duration,
+}
+
+impl SmtpEmailSender {
+    pub fn new(host: &str, port: u16) -> Self {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let tls = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .expect("the TLS provider supports the default protocol versions")
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Self {
+            host: host.to_string(),
+            port,
+            security: SmtpSecurity::StartTls,
+            tls: Arc::new(tls),
+            hello_name: "localhost".to_string(),
+            credentials: None,
+            default_from: None,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// `SmtpSecurity::StartTls` by default
+    pub fn security(mut self, security: SmtpSecurity) -> Self {
+        self.security = security;
+        self
+    }
+
+    /// Sender for messages that don't set their own `from`
+    pub fn default_from(mut self, mailbox: Mailbox) -> Self {
+        self.default_from = Some(mailbox);
+        self
+    }
+
+    pub fn credentials(mut self, username: &str, password: &str) -> Self {
+        self.credentials = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// The name given in `EHLO`; `localhost` by default
+    pub fn hello_name(mut self, name: &str) -> Self {
+        self.hello_name = name.to_string();
+        self
+    }
+
+    /// Limit for connecting and for each reply; 30 seconds by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn is_loopback(&self) -> bool {
+        self.host.eq_ignore_ascii_case("localhost")
+            || self
+                .host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    }
+
+    async fn tls<S: Stream + 'static>(&self, stream: S) -> Result<Box<dyn Stream>, EmailError> {
+        let name = ServerName::try_from(self.host.trim_start_matches('[').trim_end_matches(']'))
+            .map_err(|e| EmailError::Connection(e.to_string()))?
+            .to_owned();
+        let stream = within(
+            self.timeout,
+            TlsConnector::from(self.tls.clone()).connect(name, stream),
+        )
+        .await?
+        .map_err(|e| EmailError::Connection(e.to_string()))?;
+        Ok(Box::new(stream))
+    }
+
+    async fn connect(&self) -> Result<Connection, EmailError> {
+        if self.credentials.is_some() && self.security == SmtpSecurity::None && !self.is_loopback()
+        {
+            return Err(EmailError::Connection(
+                "refusing to send credentials without TLS".to_string(),
+            ));
+        }
+
+        let tcp = within(
+            self.timeout,
+            TcpStream::connect((self.host.as_str(), self.port)),
+        )
+        .await?
+        .map_err(|e| EmailError::Connection(e.to_string()))?;
+        let stream = match self.security {
+            SmtpSecurity::Tls => self.tls(tcp).await?,
+            _ => Box::new(tcp),
+        };
+
+        let mut connection = Connection::new(stream, self.timeout);
+        connection.expect(&[220]).await?;
+        let mut capabilities = connection.hello(&self.hello_name).await?;
+
+        if self.security == SmtpSecurity::StartTls {
+            if !offers(&capabilities, "STARTTLS", &[]) {
+                return Err(EmailError::Connection(
+                    "server does not offer STARTTLS".to_string(),
+                ));
+            }
+            connection.command("STARTTLS", &[220]).await?;
+            // Anything past the reply was sent before the handshake, where
+            // anyone on the way could have added it
+            if !connection.stream.buffer().is_empty() {
+                return Err(EmailError::Connection(
+                    "server sent data before the TLS handshake".to_string(),
+                ));
+            }
+            let stream = self.tls(connection.stream.into_inner()).await?;
+            connection = Connection::new(stream, self.timeout);
+            // What the server offered in plain text can't be trusted
+            capabilities = connection.hello(&self.hello_name).await?;
+        }
+
+        if let Some((username, password)) = &self.credentials {
+            if !offers(&capabilities, "AUTH", &["PLAIN"]) {
+                return Err(EmailError::Connection(
+                    "server does not offer AUTH PLAIN".to_string(),
+                ));
+            }
+            let token = BASE64.encode(format!("\0{}\0{}", username, password));
+            connection
+                .command(&format!("AUTH PLAIN {}", token), &[235])
+                .await?;
+        }
+        Ok(connection)
+    }
+
+    /// The envelope sender and the MIME text, or why the message can't go
+    fn prepare<'a>(
+        &'a self,
+        message: &'a EmailMessage,
+    ) -> Result<(&'a Mailbox, String), EmailError> {
+        let from = message
+            .from_mailbox()
+            .or(self.default_from.as_ref())
+            .ok_or_else(|| EmailError::InvalidMessage("no From address".to_string()))?;
+        Ok((from, to_mime(message, from, Utc::now())?))
+    }
+
+    async fn transmit(
+        &self,
+        connection: &mut Connection,
+        message: &EmailMessage,
+        from: &Mailbox,
+        mime: &str,
+    ) -> Result<(), EmailError> {
+        connection
+            .command(&format!("MAIL FROM:<{}>", from.email()), &[250])
+            .await?;
+        let mut accepted = 0;
+        let mut refusal = None;
+        for recipient in message.recipients() {
+            match connection
+                .command(&format!("RCPT TO:<{}>", recipient.email()), &[250, 251])
+                .await
+            {
+                Ok(_) => accepted += 1,
+                Err(e @ EmailError::Rejected { .. }) => refusal = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+        if accepted == 0 {
+            return Err(
+                refusal.unwrap_or_else(|| EmailError::InvalidMessage("no recipients".to_string()))
+            );
+        }
+        connection.command("DATA", &[354]).await?;
+        connection.write(&dot_stuffed(mime)).await?;
+        connection.command(".", &[250]).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EmailSender for SmtpEmailSender {
+    async fn send(&self, message: &EmailMessage) -> Result<(), EmailError> {
+        self.send_all(std::slice::from_ref(message)).await
+    }
+
+    async fn send_all(&self, messages: &[EmailMessage]) -> Result<(), EmailError> {
+        // Every message is checked before connecting
+        let prepared = messages
+            .iter()
+            .map(|message| self.prepare(message))
+            .collect::<Result<Vec<_>, _>>()?;
+        if prepared.is_empty() {
+            return Ok(());
+        }
+
+        let mut connection = self.connect().await?;
+        let mut refusal = None;
+        for (message, (from, mime)) in messages.iter().zip(&prepared) {
+            match self.transmit(&mut connection, message, from, mime).await {
+                Ok(()) => {}
+                // The server forgets the refused message and takes the next
+                Err(e @ EmailError::Rejected { .. }) => {
+                    connection.command("RSET", &[250]).await?;
+                    refusal.get_or_insert(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        // The messages are accepted by now; a failed goodbye doesn't matter
+        connection.command("QUIT", &[221]).await.ok();
+        refusal.map_or(Ok(()), Err)
+    }
+}
+
+/// A TCP connection, or one with TLS over it
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// Whether the `EHLO` reply has a `keyword` line, such as `STARTTLS`,
+/// naming each of `params`, as `AUTH` names `PLAIN`
+fn offers(capabilities: &str, keyword: &str, params: &[&str]) -> bool {
+    capabilities.lines().any(|line| {
+        let line = line.to_ascii_uppercase();
+        let words: Vec<_> = line.split_whitespace().collect();
+        words.first() == Some(&keyword) && params.iter().all(|param| words.contains(param))
+    })
+}
+
+struct Connection {
+    stream: BufReader<Box<dyn Stream>>,
+    timeout: Duration,
+}
+
+impl Connection {
+    fn new(stream: Box<dyn Stream>, timeout: Duration) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+            timeout,
+        }
+    }
+
+    /// `EHLO`, or `HELO` for servers from before ESMTP; the capabilities
+    /// the server lists
+    async fn hello(&mut self, name: &str) -> Result<String, EmailError> {
+        match self.command(&format!("EHLO {}", name), &[250]).await {
+            Err(EmailError::Rejected { .. }) => {
+                self.command(&format!("HELO {}", name), &[250]).await
+            }
+            reply => reply,
+        }
+    }
+
+    async fn write(&mut self, data: &str) -> Result<(), EmailError> {
+        within(
+            self.timeout,
+            self.stream.get_mut().write_all(data.as_bytes()),
+        )
+        .await?
+        .map_err(|e| EmailError::Connection(e.to_string()))
+    }
+
+    async fn command(&mut self, command: &str, expected: &[u16]) -> Result<String, EmailError> {
+        self.write(&format!("{}\r\n", command)).await?;
+        self.expect(expected).await
+    }
+
+    /// Reads a reply, which may span several `250-…` lines, and returns its
+    /// text if the code is one of `expected`
+    async fn expect(&mut self, expected: &[u16]) -> Result<String, EmailError> {
+        let mut text = Vec::new();
+        loop {
+            let mut line = String::new();
+            let read = within(self.timeout, self.stream.read_line(&mut line))
+                .await?
+                .map_err(|e| EmailError::Connection(e.to_string()))?;
+            if read == 0 {
+                return Err(EmailError::Connection(
+                    "server closed the connection".to_string(),
+                ));
+            }
+
+            let line = line.trim_end();
+            let code = line
+                .get(..3)
+                .and_then(|code| code.parse::<u16>().ok())
+                .ok_or_else(|| EmailError::Connection(format!("unexpected reply: {}", line)))?;
+            text.push(line.get(4..).unwrap_or_default().to_string());
+
+            if line.as_bytes().get(3) != Some(&b'-') {
+                let message = text.join("\n");
+                if !expected.contains(&code) {
+                    return Err(EmailError::Rejected { code, message });
+                }
+                return Ok(message);
+            }
+        }
+    }
+}
+
+async fn within<F: Future>(timeout: Duration, future: F) -> Result<F::Output, EmailError> {
+    tokio::time::timeout(timeout, future)
+        .await
+        .map_err(|_| EmailError::Connection("timed out".to_string()))
+}
+
+/// Doubles a leading `.` on every line, so none reads as the end of `DATA`
+fn dot_stuffed(mime: &str) -> String {
+    let mut data = String::with_capacity(mime.len() + 2);
+    for line in mime.split_inclusive("\r\n") {
+        if line.starts_with('.') {
+            data.push('.');
+        }
+        data.push_str(line);
+    }
+    if !data.ends_with("\r\n") {
+        data.push_str("\r\n");
+    }
+    data
+}
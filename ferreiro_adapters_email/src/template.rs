@@ -0,0 +1,69 @@
+use ferreiro_adapters_templates::{Context, TemplateEngine, TemplateError};
+use ferreiro_domain::mail::EmailMessage;
+
+/// An email whose subject and bodies are templates
+///
+/// The subject template is rendered onto one line. Add an HTML alternative
+/// with `html`; the plain-text body is always sent, for clients that don't
+/// show HTML.
+///
+/// ```rust,ignore
+/// let message = EmailTemplate::password_reset()
+///     .render(&engine, &context! { name: user.name(), url: reset_url })?
+///     .to(user.email().clone());
+/// mailer.send(&message).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct EmailTemplate {
+    subject: String,
+    text: String,
+    html: Option<String>,
+}
+
+impl EmailTemplate {
+    pub fn new(subject_template: &str, text_template: &str) -> Self {
+        Self {
+            subject: subject_template.to_string(),
+            text: text_template.to_string(),
+            html: None,
+        }
+    }
+
+    pub fn html(mut self, html_template: &str) -> Self {
+        self.html = Some(html_template.to_string());
+        self
+    }
+
+    /// The built-in reset email; its context needs `name` and `url`
+    pub fn password_reset() -> Self {
+        Self::new(
+            "ferreiro/auth/password_reset_subject.txt",
+            "ferreiro/auth/password_reset_email.txt",
+        )
+    }
+
+    /// The built-in verification email; its context needs `name`, `email`
+    /// and `url`
+    pub fn email_verification() -> Self {
+        Self::new(
+            "ferreiro/auth/verification_subject.txt",
+            "ferreiro/auth/verification_email.txt",
+        )
+    }
+
+    /// A message with subject and bodies filled in; add recipients to it
+    pub fn render(
+        &self,
+        engine: &dyn TemplateEngine,
+        context: &Context,
+    ) -> Result<EmailMessage, TemplateError> {
+        let subject = engine.render(&self.subject, context)?;
+        let subject = subject.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        let mut message = EmailMessage::new(subject).text(engine.render(&self.text, context)?);
+        if let Some(html) = &self.html {
+            message = message.html(engine.render(html, context)?);
+        }
+        Ok(message)
+    }
+}
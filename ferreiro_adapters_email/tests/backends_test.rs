@@ -0,0 +1,137 @@
+use chrono::{TimeZone, Utc};
+use ferreiro_adapters_email::{to_mime, EmailTemplate, FileEmailSender, InMemoryEmailSender};
+use ferreiro_adapters_templates::context;
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_domain::mail::{Attachment, EmailMessage, Mailbox};
+use ferreiro_domain::ports::driven::{EmailError, EmailSender};
+use ferreiro_domain::values::Email;
+
+fn mailbox(address: &str) -> Mailbox {
+    Mailbox::new(Email::new(address).unwrap())
+}
+
+#[test]
+fn test_mime_encodes_headers_and_bodies() {
+    let from = Mailbox::named("Ferreiro, Inc.", Email::new("noreply@example.com").unwrap());
+    let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+
+    let plain = EmailMessage::new("Hello")
+        .to(Mailbox::named(
+            "Ana Silva",
+            Email::new("ana@example.com").unwrap(),
+        ))
+        .text("a = b");
+    let mime = to_mime(&plain, &from, date).unwrap();
+    assert!(mime.starts_with("From: \"Ferreiro, Inc.\" <noreply@example.com>\r\n"));
+    assert!(mime.contains("Date: Wed, 1 May 2024 12:00:00 +0000\r\n"));
+    assert!(mime.contains("Subject: Hello\r\n"));
+    assert!(mime.contains("To: Ana Silva <ana@example.com>\r\n"));
+    assert!(mime.contains("Content-Type: text/plain; charset=utf-8\r\n"));
+    assert!(mime.ends_with("\r\n\r\na =3D b\r\n"));
+
+    let long_line = "x".repeat(100);
+    let rich = EmailMessage::new("Olá, João")
+        .to(Mailbox::named(
+            "João",
+            Email::new("joao@example.com").unwrap(),
+        ))
+        .bcc(mailbox("audit@example.com"))
+        .text(format!("Olá\n{}", long_line))
+        .html("<p>Olá</p>")
+        .attach(Attachment::new(
+            "relatório.pdf",
+            "application/pdf",
+            vec![0u8, 1, 2],
+        ));
+    let mime = to_mime(&rich, &from, date).unwrap();
+    assert!(mime.contains("Subject: =?utf-8?b?T2zDoSwgSm/Do28=?=\r\n"));
+    assert!(mime.contains("To: =?utf-8?b?Sm/Do28=?= <joao@example.com>\r\n"));
+    assert!(!mime.contains("audit@example.com"));
+    assert!(mime.contains("Content-Type: multipart/mixed;"));
+    assert!(mime.contains("Content-Type: multipart/alternative;"));
+    assert!(mime.contains("Ol=C3=A1\r\n"));
+    // Soft line break after 75 characters
+    assert!(mime.contains(&format!("{}=\r\n{}\r\n", "x".repeat(75), "x".repeat(25))));
+    assert!(mime.contains("filename*=utf-8''relat%C3%B3rio.pdf"));
+    assert!(mime.contains("Content-Transfer-Encoding: base64\r\n\r\nAAEC\r\n"));
+}
+
+#[tokio::test]
+async fn test_outbox_and_file_backends() {
+    let outbox = InMemoryEmailSender::new();
+    let message = EmailMessage::new("Hi")
+        .to(mailbox("ana@example.com"))
+        .text("Hello");
+    outbox.send(&message).await.unwrap();
+    assert_eq!(outbox.messages(), std::slice::from_ref(&message));
+
+    // Rejected by every backend
+    for invalid in [
+        EmailMessage::new("No one to send to").text("Hi"),
+        EmailMessage::new("Hi\r\nBcc: victim@example.com").to(mailbox("ana@example.com")),
+        message.clone().header("X-Tag", "a\nb"),
+        message.clone().attach(Attachment::new(
+            "a.txt\r\nContent-Type: text/html",
+            "text/plain",
+            "Hi",
+        )),
+        message
+            .clone()
+            .attach(Attachment::new("a.txt", "text/plain\r\nX-Evil: 1", "Hi")),
+    ] {
+        assert!(matches!(
+            outbox.send(&invalid).await,
+            Err(EmailError::InvalidMessage(_))
+        ));
+    }
+    assert_eq!(outbox.messages().len(), 1);
+
+    let dir = tempfile::tempdir().unwrap();
+    let mail_dir = dir.path().join("mail");
+    let files = FileEmailSender::new(&mail_dir).default_from(mailbox("app@example.com"));
+    files.send_all(&[message.clone(), message]).await.unwrap();
+
+    let mut written: Vec<_> = std::fs::read_dir(&mail_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    written.sort();
+    assert_eq!(written.len(), 2);
+    assert!(written[0].extension().is_some_and(|ext| ext == "eml"));
+    let eml = std::fs::read_to_string(&written[0]).unwrap();
+    assert!(eml.starts_with("From: app@example.com\r\n"));
+}
+
+#[test]
+fn test_email_templates_render_subject_and_bodies() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("welcome_subject.txt"),
+        "Welcome,\n{{ name }}!\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("welcome.txt"), "Hi {{ name }}").unwrap();
+    std::fs::write(dir.path().join("welcome.html"), "<p>Hi {{ name }}</p>").unwrap();
+    let engine = MiniJinjaEngine::new(dir.path().to_str().unwrap()).unwrap();
+
+    let message = EmailTemplate::new("welcome_subject.txt", "welcome.txt")
+        .html("welcome.html")
+        .render(&engine, &context! { name: "Ana & co" })
+        .unwrap();
+    assert_eq!(message.subject(), "Welcome, Ana & co!");
+    assert_eq!(message.text_body(), Some("Hi Ana & co"));
+    assert_eq!(message.html_body(), Some("<p>Hi Ana &amp; co</p>"));
+
+    let reset = EmailTemplate::password_reset()
+        .render(
+            &engine,
+            &context! { name: "Ana", url: "https://example.com/reset/abc" },
+        )
+        .unwrap();
+    assert_eq!(reset.subject(), "Reset your password");
+    assert!(reset
+        .text_body()
+        .unwrap()
+        .contains("https://example.com/reset/abc"));
+    assert!(reset.html_body().is_none());
+}
@@ -0,0 +1,201 @@
+use ferreiro_adapters_email::{SmtpEmailSender, SmtpSecurity};
+use ferreiro_domain::mail::{Attachment, EmailMessage, Mailbox};
+use ferreiro_domain::ports::driven::{EmailError, EmailSender};
+use ferreiro_domain::values::Email;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+/// What the stand-in server saw: every command, and each message as sent
+#[derive(Default)]
+struct Transcript {
+    commands: Vec<String>,
+    messages: Vec<String>,
+}
+
+/// A minimal SMTP server on a free local port that accepts one connection
+/// and refuses recipients at `nobody@…`
+async fn smtp_stand_in() -> (u16, Arc<Mutex<Transcript>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let transcript = Arc::new(Mutex::new(Transcript::default()));
+    let seen = transcript.clone();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"220 stand-in ESMTP\r\n").await.unwrap();
+
+        while let Some(line) = lines.next_line().await.unwrap() {
+            seen.lock().unwrap().commands.push(line.clone());
+            let verb = line.split([' ', ':']).next().unwrap().to_uppercase();
+            let reply: &[u8] = match verb.as_str() {
+                "EHLO" => b"250-stand-in\r\n250-SIZE 1000000\r\n250 AUTH LOGIN PLAIN\r\n",
+                "AUTH" => b"235 Authenticated\r\n",
+                "RCPT" if line.contains("nobody@") => b"550 No such user\r\n",
+                "MAIL" | "RCPT" | "RSET" => b"250 OK\r\n",
+                "DATA" => {
+                    writer.write_all(b"354 Go ahead\r\n").await.unwrap();
+                    let mut data = String::new();
+                    while let Some(line) = lines.next_line().await.unwrap() {
+                        if line == "." {
+                            break;
+                        }
+                        data.push_str(&line);
+                        data.push('\n');
+                    }
+                    seen.lock().unwrap().messages.push(data);
+                    b"250 Queued\r\n"
+                }
+                "QUIT" => {
+                    writer.write_all(b"221 Bye\r\n").await.unwrap();
+                    break;
+                }
+                _ => b"502 Not implemented\r\n",
+            };
+            writer.write_all(reply).await.unwrap();
+        }
+    });
+
+    (port, transcript)
+}
+
+fn mailbox(address: &str) -> Mailbox {
+    Mailbox::new(Email::new(address).unwrap())
+}
+
+#[tokio::test]
+async fn test_smtp_delivers_to_every_recipient() {
+    let (port, transcript) = smtp_stand_in().await;
+    let sender = SmtpEmailSender::new("127.0.0.1", port)
+        .security(SmtpSecurity::None)
+        .hello_name("app.example.com")
+        .credentials("mailer", "s3cret")
+        .default_from(Mailbox::named(
+            "Ferreiro",
+            Email::new("noreply@example.com").unwrap(),
+        ));
+
+    let message = EmailMessage::new("Weekly digest")
+        .to(mailbox("ana@example.com"))
+        .cc(mailbox("bob@example.com"))
+        .bcc(mailbox("audit@example.com"))
+        .text("Hello!\n.leading dot\nBye")
+        .html("<p>Hello!</p>")
+        .attach(Attachment::new("notes.txt", "text/plain", "notes"));
+    sender.send(&message).await.unwrap();
+
+    let transcript = transcript.lock().unwrap();
+    assert_eq!(
+        transcript.commands,
+        [
+            "EHLO app.example.com",
+            // base64 of "\0mailer\0s3cret"
+            "AUTH PLAIN AG1haWxlcgBzM2NyZXQ=",
+            "MAIL FROM:<noreply@example.com>",
+            "RCPT TO:<ana@example.com>",
+            "RCPT TO:<bob@example.com>",
+            "RCPT TO:<audit@example.com>",
+            "DATA",
+            "QUIT",
+        ]
+    );
+
+    let data = &transcript.messages[0];
+    assert!(data.contains("From: Ferreiro <noreply@example.com>\n"));
+    assert!(data.contains("To: ana@example.com\n"));
+    assert!(data.contains("Cc: bob@example.com\n"));
+    assert!(!data.contains("audit@example.com"));
+    assert!(data.contains("multipart/alternative"));
+    // Dot-stuffed on the wire
+    assert!(data.contains("\n..leading dot\n"));
+}
+
+#[tokio::test]
+async fn test_smtp_reports_rejections() {
+    let (port, transcript) = smtp_stand_in().await;
+    let sender = SmtpEmailSender::new("127.0.0.1", port).security(SmtpSecurity::None);
+    let message = EmailMessage::new("Hi")
+        .from(mailbox("app@example.com"))
+        .to(mailbox("nobody@example.com"))
+        .text("Hi");
+
+    match sender.send(&message).await {
+        Err(EmailError::Rejected { code, message }) => {
+            assert_eq!(code, 550);
+            assert_eq!(message, "No such user");
+        }
+        other => panic!("expected a rejection, got {:?}", other),
+    }
+    assert!(transcript.lock().unwrap().messages.is_empty());
+
+    // Nothing to send from
+    let unsent = EmailMessage::new("Hi").to(mailbox("ana@example.com"));
+    assert!(matches!(
+        SmtpEmailSender::new("127.0.0.1", port).send(&unsent).await,
+        Err(EmailError::InvalidMessage(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_smtp_sends_what_the_server_accepts() {
+    let (port, transcript) = smtp_stand_in().await;
+    let sender = SmtpEmailSender::new("127.0.0.1", port).security(SmtpSecurity::None);
+    let message = |to: &[&str]| {
+        to.iter().fold(
+            EmailMessage::new("Hi")
+                .from(mailbox("app@example.com"))
+                .text("Hi"),
+            |message, address| message.to(mailbox(address)),
+        )
+    };
+
+    // The refused message doesn't keep the next from going
+    let result = sender
+        .send_all(&[
+            message(&["ana@example.com", "nobody@example.com"]),
+            message(&["nobody@example.com"]),
+            message(&["bob@example.com"]),
+        ])
+        .await;
+    assert!(matches!(
+        result,
+        Err(EmailError::Rejected { code: 550, .. })
+    ));
+
+    let transcript = transcript.lock().unwrap();
+    assert_eq!(transcript.messages.len(), 2);
+    let commands: Vec<_> = transcript
+        .commands
+        .iter()
+        .filter(|command| ["DATA", "RSET"].contains(&command.as_str()))
+        .collect();
+    assert_eq!(commands, ["DATA", "RSET", "DATA"]);
+}
+
+#[tokio::test]
+async fn test_smtp_sends_credentials_only_over_tls() {
+    let (port, transcript) = smtp_stand_in().await;
+    let message = EmailMessage::new("Hi")
+        .from(mailbox("app@example.com"))
+        .to(mailbox("ana@example.com"))
+        .text("Hi");
+
+    // The stand-in doesn't offer STARTTLS
+    let sender = SmtpEmailSender::new("127.0.0.1", port).credentials("mailer", "s3cret");
+    assert!(matches!(
+        sender.send(&message).await,
+        Err(EmailError::Connection(_))
+    ));
+    assert_eq!(transcript.lock().unwrap().commands, ["EHLO localhost"]);
+
+    // Nor are they sent in plain text to anywhere but this host
+    let sender = SmtpEmailSender::new("smtp.example.com", 25)
+        .security(SmtpSecurity::None)
+        .credentials("mailer", "s3cret");
+    assert!(matches!(
+        sender.send(&message).await,
+        Err(EmailError::Connection(_))
+    ));
+}
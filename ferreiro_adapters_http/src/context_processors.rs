@@ -0,0 +1,65 @@
+//! Built-in context processors for `Templates`
+
+use crate::middleware::locale::Locale;
+use crate::middleware::session::Session;
+use crate::middleware::timezone::Timezone;
+use axum::http::request::Parts;
+use ferreiro_adapters_templates::i18n::{LANGUAGE_CODE, TIME_ZONE};
+use ferreiro_adapters_templates::Context;
+use serde_json::json;
+
+/// Exposes pending flash messages as `messages`, consuming them
+///
+/// Each entry has a `level` (`debug`, `info`, `success`, `warning`, `error`)
+/// and a `text`, so the same template works with Tera and MiniJinja:
+///
+/// ```text
+/// {% for message in messages %}
+///   <div class="alert alert-{{ message.level }}">{{ message.text }}</div>
+/// {% endfor %}
+/// ```
+///
+/// Requires `session_middleware`; without a session the list is empty.
+pub fn messages(parts: &Parts, context: &mut Context) {
+    let messages = parts
+        .extensions
+        .get::<Session>()
+        .map(Session::take_messages)
+        .unwrap_or_default();
+    context.insert("messages", messages);
+}
+
+/// Exposes the request as `request.method`, `request.path` and `request.query`
+pub fn request(parts: &Parts, context: &mut Context) {
+    context.insert(
+        "request",
+        json!({
+            "method": parts.method.as_str(),
+            "path": parts.uri.path(),
+            "query": parts.uri.query().unwrap_or(""),
+        }),
+    );
+}
+
+/// Exposes the negotiated locale as `LANGUAGE_CODE`, which `trans` uses
+///
+/// Requires `locale_middleware`; without it `trans` uses the default locale.
+pub fn i18n(parts: &Parts, context: &mut Context) {
+    if let Some(locale) = parts.extensions.get::<Locale>() {
+        context.insert(LANGUAGE_CODE, locale.as_str());
+    }
+}
+
+/// Exposes the active time zone as `TIME_ZONE`, which `date` and `localtime` use
+///
+/// Requires `timezone_middleware`; without it times are shown in UTC.
+pub fn timezone(parts: &Parts, context: &mut Context) {
+    if let Some(timezone) = parts.extensions.get::<Timezone>() {
+        context.insert(TIME_ZONE, timezone.name());
+    }
+}
+
+/// Adds fixed values, such as site settings, to every context
+pub fn constants(values: Context) -> impl Fn(&Parts, &mut Context) + Send + Sync + 'static {
+    move |_, context| context.extend(values.clone())
+}
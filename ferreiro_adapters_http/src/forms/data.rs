@@ -0,0 +1,101 @@
+use axum::extract::{FromRequest, Multipart, Request};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::Form as UrlEncoded;
+
+/// Submitted form values, in the order they were sent
+///
+/// Extracts from `application/x-www-form-urlencoded` and
+/// `multipart/form-data` bodies (and from the query string of a GET).
+/// File parts of a multipart body are skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormData {
+    pairs: Vec<(String, String)>,
+}
+
+impl FormData {
+    pub fn from_pairs<K, V>(pairs: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        Self {
+            pairs: pairs
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into()))
+                .collect(),
+        }
+    }
+
+    /// The first value sent for `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.pairs
+            .iter()
+            .filter(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    /// Replaces every value sent for `name`
+    pub fn set(&mut self, name: &str, value: impl Into<String>) {
+        self.pairs.retain(|(key, _)| key != name);
+        self.pairs.push((name.to_string(), value.into()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    async fn from_multipart(mut multipart: Multipart) -> Result<Self, (StatusCode, String)> {
+        let invalid = |e: axum::extract::multipart::MultipartError| {
+            (StatusCode::BAD_REQUEST, format!("Invalid form body: {}", e))
+        };
+
+        let mut pairs = Vec::new();
+        while let Some(field) = multipart.next_field().await.map_err(invalid)? {
+            let Some(name) = field.name().map(str::to_string) else {
+                continue;
+            };
+            if field.file_name().is_some() {
+                continue;
+            }
+            pairs.push((name, field.text().await.map_err(invalid)?));
+        }
+        Ok(Self { pairs })
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequest<S> for FormData
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let multipart = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("multipart/form-data"));
+
+        if multipart {
+            let multipart = Multipart::from_request(req, state)
+                .await
+                .map_err(|e| (e.status(), e.body_text()))?;
+            return Self::from_multipart(multipart).await;
+        }
+
+        let UrlEncoded(pairs) = UrlEncoded::<Vec<(String, String)>>::from_request(req, state)
+            .await
+            .map_err(|e| (e.status(), e.body_text()))?;
+        Ok(Self { pairs })
+    }
+}
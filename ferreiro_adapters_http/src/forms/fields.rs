@@ -0,0 +1,215 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::models::PostStatus;
+use ferreiro_domain::values::{Email, Slug};
+use std::sync::Arc;
+
+type Validator = Arc<dyn Fn(&str) -> Result<(), DomainError> + Send + Sync>;
+
+/// What a field accepts, and the input it renders as
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Textarea,
+    /// Validated with `Email::new`
+    Email,
+    /// Validated with `Slug::new`
+    Slug,
+    /// One of these `(value, label)` pairs
+    Choice(Vec<(String, String)>),
+    /// RFC 3339, or `datetime-local` input, which is taken as UTC
+    DateTime,
+    Checkbox,
+}
+
+/// A type a choice field selects from, such as `PostStatus`
+pub trait Choices: Sized {
+    /// `(value, label)` pairs, in display order
+    fn choices() -> Vec<(String, String)>;
+
+    fn from_choice(value: &str) -> Option<Self>;
+}
+
+impl Choices for PostStatus {
+    fn choices() -> Vec<(String, String)> {
+        [
+            ("draft", "Draft"),
+            ("published", "Published"),
+            ("archived", "Archived"),
+        ]
+        .into_iter()
+        .map(|(value, label)| (value.to_string(), label.to_string()))
+        .collect()
+    }
+
+    fn from_choice(value: &str) -> Option<Self> {
+        match value {
+            "draft" => Some(PostStatus::Draft),
+            "published" => Some(PostStatus::Published),
+            "archived" => Some(PostStatus::Archived),
+            _ => None,
+        }
+    }
+}
+
+/// One form input: its name, label, kind and validation
+///
+/// Fields are required unless marked `optional`, except checkboxes, where
+/// unchecked is a valid answer.
+#[derive(Clone)]
+pub struct Field {
+    pub(crate) name: String,
+    pub(crate) label: String,
+    pub(crate) kind: FieldKind,
+    pub(crate) required: bool,
+    pub(crate) help_text: Option<String>,
+    validators: Vec<Validator>,
+}
+
+impl Field {
+    pub fn new(name: &str, kind: FieldKind) -> Self {
+        Self {
+            name: name.to_string(),
+            label: humanize(name),
+            required: kind != FieldKind::Checkbox,
+            kind,
+            help_text: None,
+            validators: Vec::new(),
+        }
+    }
+
+    pub fn text(name: &str) -> Self {
+        Self::new(name, FieldKind::Text)
+    }
+
+    pub fn textarea(name: &str) -> Self {
+        Self::new(name, FieldKind::Textarea)
+    }
+
+    pub fn email(name: &str) -> Self {
+        Self::new(name, FieldKind::Email)
+    }
+
+    pub fn slug(name: &str) -> Self {
+        Self::new(name, FieldKind::Slug)
+    }
+
+    pub fn choice<T: Choices>(name: &str) -> Self {
+        Self::new(name, FieldKind::Choice(T::choices()))
+    }
+
+    pub fn datetime(name: &str) -> Self {
+        Self::new(name, FieldKind::DateTime)
+    }
+
+    pub fn checkbox(name: &str) -> Self {
+        Self::new(name, FieldKind::Checkbox)
+    }
+
+    /// Defaults to the name with underscores as spaces: "publish_at" → "Publish at"
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = label.to_string();
+        self
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn help_text(mut self, help_text: &str) -> Self {
+        self.help_text = Some(help_text.to_string());
+        self
+    }
+
+    /// Runs after the kind's own check, on non-empty values; value object
+    /// constructors fit directly:
+    ///
+    /// ```rust,ignore
+    /// Field::text("title").validate(Title::new)
+    /// ```
+    pub fn validate<T, F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&str) -> Result<T, DomainError> + Send + Sync + 'static,
+    {
+        self.validators
+            .push(Arc::new(move |value| validator(value).map(|_| ())));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> &FieldKind {
+        &self.kind
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// Checks one submitted value; `value` is already trimmed
+    pub(crate) fn clean(&self, value: &str) -> Result<(), DomainError> {
+        if self.kind == FieldKind::Checkbox {
+            return match is_checked(value) || !self.required {
+                true => Ok(()),
+                false => Err(DomainError::FieldRequired),
+            };
+        }
+        if value.is_empty() {
+            return match self.required {
+                true => Err(DomainError::FieldRequired),
+                false => Ok(()),
+            };
+        }
+
+        match &self.kind {
+            FieldKind::Email => Email::new(value).map(|_| ())?,
+            FieldKind::Slug => Slug::new(value).map(|_| ())?,
+            FieldKind::Choice(choices) => {
+                if !choices.iter().any(|(choice, _)| choice == value) {
+                    return Err(DomainError::InvalidChoice);
+                }
+            }
+            FieldKind::DateTime => {
+                parse_datetime(value).ok_or(DomainError::InvalidDateTime)?;
+            }
+            FieldKind::Text | FieldKind::Textarea | FieldKind::Checkbox => {}
+        }
+
+        self.validators
+            .iter()
+            .try_for_each(|validator| validator(value))
+    }
+}
+
+/// HTML sends "on" for a ticked box and nothing for an unticked one
+pub(crate) fn is_checked(value: &str) -> bool {
+    !matches!(value, "" | "false" | "off" | "0")
+}
+
+/// RFC 3339, or the `datetime-local` formats, read as UTC
+pub fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    ["%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|naive| naive.and_utc())
+}
+
+fn humanize(name: &str) -> String {
+    let spaced = name.replace('_', " ");
+    let mut chars = spaced.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => spaced,
+    }
+}
@@ -0,0 +1,296 @@
+//! HTML forms: declare fields, bind a submission, validate, re-render
+//!
+//! Validation goes through the domain's value objects, so a form rejects
+//! exactly what the domain would, with the same `DomainError`s:
+//!
+//! ```rust,ignore
+//! fn post_form() -> Form {
+//!     Form::new()
+//!         .field(Field::text("title").validate(Title::new))
+//!         .field(Field::slug("slug"))
+//!         .field(Field::choice::<PostStatus>("status"))
+//!         .field(Field::datetime("publish_at").optional())
+//!         .field(Field::checkbox("featured"))
+//! }
+//!
+//! async fn create(renderer: Renderer, data: FormData) -> Response {
+//!     let form = post_form().bind(&data);
+//!     if !form.is_valid() {
+//!         return renderer.render("posts/new.html", context! { form: form }).into_response();
+//!     }
+//!     let slug = form.slug("slug").unwrap();
+//!     // ...
+//! }
+//! ```
+//!
+//! Templates render a form with the built-in `ferreiro/forms/form.html`,
+//! which shows each field with its previous value and errors:
+//!
+//! ```text
+//! <form method="post">{% include "ferreiro/forms/form.html" %}<button>Save</button></form>
+//! ```
+
+pub mod data;
+pub mod fields;
+pub mod model;
+pub mod post;
+
+pub use data::FormData;
+pub use fields::{parse_datetime, Choices, Field, FieldKind};
+pub use model::ModelForm;
+pub use post::PostForm;
+
+use chrono::{DateTime, Utc};
+use ferreiro_adapters_templates::{context, TemplateEngine, TemplateError};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::ports::driven::Translator;
+use ferreiro_domain::values::{Email, Slug};
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+
+/// The built-in template that renders a form's fields
+pub const FORM_TEMPLATE: &str = "ferreiro/forms/form.html";
+
+/// A set of fields, plus the values and errors of one submission
+#[derive(Clone, Default)]
+pub struct Form {
+    fields: Vec<Field>,
+    values: HashMap<String, String>,
+    errors: BTreeMap<String, Vec<DomainError>>,
+    non_field_errors: Vec<DomainError>,
+    bound: bool,
+}
+
+impl Form {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(mut self, field: Field) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Prefills a field on an unbound form, such as an edit page
+    pub fn initial(mut self, name: &str, value: impl Into<String>) -> Self {
+        if !self.bound {
+            self.values.insert(name.to_string(), value.into());
+        }
+        self
+    }
+
+    /// Takes each field's value from `data` and validates it
+    pub fn bind(mut self, data: &FormData) -> Self {
+        self.values.clear();
+        self.errors.clear();
+        self.non_field_errors.clear();
+        self.bound = true;
+
+        for field in &self.fields {
+            let value = data.get(&field.name).unwrap_or_default().trim();
+            if let Err(error) = field.clean(value) {
+                self.errors
+                    .entry(field.name.clone())
+                    .or_default()
+                    .push(error);
+            }
+            self.values.insert(field.name.clone(), value.to_string());
+        }
+        self
+    }
+
+    pub fn is_bound(&self) -> bool {
+        self.bound
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.bound && self.errors.is_empty() && self.non_field_errors.is_empty()
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    pub fn errors(&self, name: &str) -> &[DomainError] {
+        self.errors.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn non_field_errors(&self) -> &[DomainError] {
+        &self.non_field_errors
+    }
+
+    /// Reports an error found after validation, such as a slug the
+    /// repository already has
+    pub fn add_error(&mut self, name: &str, error: DomainError) {
+        self.errors.entry(name.to_string()).or_default().push(error);
+    }
+
+    pub fn add_non_field_error(&mut self, error: DomainError) {
+        self.non_field_errors.push(error);
+    }
+
+    /// The submitted (or initial) value, `None` when empty
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values
+            .get(name)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.value(name).map(str::to_string)
+    }
+
+    pub fn email(&self, name: &str) -> Option<Email> {
+        self.value(name).and_then(|value| Email::new(value).ok())
+    }
+
+    pub fn slug(&self, name: &str) -> Option<Slug> {
+        self.value(name).and_then(|value| Slug::new(value).ok())
+    }
+
+    pub fn choice<T: Choices>(&self, name: &str) -> Option<T> {
+        self.value(name).and_then(T::from_choice)
+    }
+
+    pub fn datetime(&self, name: &str) -> Option<DateTime<Utc>> {
+        self.value(name).and_then(parse_datetime)
+    }
+
+    pub fn checked(&self, name: &str) -> bool {
+        self.value(name).is_some_and(fields::is_checked)
+    }
+
+    /// What templates see, with errors in English
+    pub fn view(&self) -> FormView {
+        self.build_view(&|error| error.to_string())
+    }
+
+    /// What templates see, with errors translated into `locale`
+    pub fn localized_view(&self, translator: &dyn Translator, locale: &str) -> FormView {
+        self.build_view(&|error| error.localized(translator, locale))
+    }
+
+    /// Renders the fields with `ferreiro/forms/form.html`
+    pub fn render(&self, engine: &dyn TemplateEngine) -> Result<String, TemplateError> {
+        self.view().render(engine)
+    }
+
+    fn build_view(&self, message: &dyn Fn(&DomainError) -> String) -> FormView {
+        let errors = |errors: &[DomainError]| {
+            errors
+                .iter()
+                .map(|error| ErrorView {
+                    key: error.message_key(),
+                    message: message(error),
+                })
+                .collect()
+        };
+
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| {
+                let value = self.value(&field.name).unwrap_or_default();
+                let choices = match &field.kind {
+                    FieldKind::Choice(choices) => choices
+                        .iter()
+                        .map(|(choice, label)| ChoiceView {
+                            value: choice.clone(),
+                            label: label.clone(),
+                            selected: choice == value,
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                let value = match field.kind {
+                    // `datetime-local` inputs only accept this form
+                    FieldKind::DateTime => parse_datetime(value)
+                        .map(|datetime| datetime.format("%Y-%m-%dT%H:%M").to_string())
+                        .unwrap_or_else(|| value.to_string()),
+                    _ => value.to_string(),
+                };
+
+                FieldView {
+                    name: field.name.clone(),
+                    label: field.label.clone(),
+                    widget: widget(&field.kind),
+                    checked: field.kind == FieldKind::Checkbox && self.checked(&field.name),
+                    value,
+                    required: field.required,
+                    help_text: field.help_text.clone(),
+                    errors: errors(self.errors(&field.name)),
+                    choices,
+                }
+            })
+            .collect();
+
+        FormView {
+            fields,
+            errors: errors(&self.non_field_errors),
+            is_bound: self.bound,
+            is_valid: self.is_valid(),
+        }
+    }
+}
+
+impl Serialize for Form {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.view().serialize(serializer)
+    }
+}
+
+fn widget(kind: &FieldKind) -> &'static str {
+    match kind {
+        FieldKind::Text | FieldKind::Slug => "text",
+        FieldKind::Textarea => "textarea",
+        FieldKind::Email => "email",
+        FieldKind::Choice(_) => "select",
+        FieldKind::DateTime => "datetime-local",
+        FieldKind::Checkbox => "checkbox",
+    }
+}
+
+/// A form as the template context sees it
+#[derive(Debug, Clone, Serialize)]
+pub struct FormView {
+    pub fields: Vec<FieldView>,
+    /// Errors not tied to a field
+    pub errors: Vec<ErrorView>,
+    pub is_bound: bool,
+    pub is_valid: bool,
+}
+
+impl FormView {
+    pub fn render(&self, engine: &dyn TemplateEngine) -> Result<String, TemplateError> {
+        engine.render(FORM_TEMPLATE, &context! { form: self })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldView {
+    pub name: String,
+    pub label: String,
+    /// The input `type`, or `textarea`/`select`
+    pub widget: &'static str,
+    pub value: String,
+    pub checked: bool,
+    pub required: bool,
+    pub help_text: Option<String>,
+    pub errors: Vec<ErrorView>,
+    pub choices: Vec<ChoiceView>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorView {
+    /// The `DomainError` message key, for templates that translate themselves
+    pub key: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChoiceView {
+    pub value: String,
+    pub label: String,
+    pub selected: bool,
+}
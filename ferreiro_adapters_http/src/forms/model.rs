@@ -0,0 +1,111 @@
+use crate::forms::{Form, FormData};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::ports::driving::ServiceError;
+
+/// A form bound to an aggregate: its fields, how to prefill them from an
+/// existing instance, and which field each error belongs to
+///
+/// `PostForm` is the built-in one; implement this for your own aggregates
+/// to get the same create/edit flow:
+///
+/// ```rust,ignore
+/// struct ProductForm;
+///
+/// impl ModelForm for ProductForm {
+///     type Model = Product;
+///
+///     fn form() -> Form {
+///         Form::new()
+///             .field(Field::text("name"))
+///             .field(Field::slug("sku"))
+///     }
+///
+///     fn initial(product: &Product) -> Vec<(&'static str, String)> {
+///         vec![("name", product.name().to_string()), ("sku", product.sku().to_string())]
+///     }
+///
+///     fn error_field(error: &DomainError) -> Option<&'static str> {
+///         matches!(error, DomainError::SlugAlreadyExists).then_some("sku")
+///     }
+/// }
+/// ```
+pub trait ModelForm {
+    type Model;
+
+    /// The fields of the create form
+    fn form() -> Form;
+
+    /// The fields of the edit form; defaults to the create form's
+    fn edit_form() -> Form {
+        Self::form()
+    }
+
+    /// Values to prefill the edit form with
+    fn initial(model: &Self::Model) -> Vec<(&'static str, String)>;
+
+    /// Fills in derived values, such as a slug from the title, before the
+    /// submission is validated
+    fn prepare(_data: &mut FormData) {}
+
+    /// The field a domain error is about; `None` shows it above the form
+    fn error_field(_error: &DomainError) -> Option<&'static str> {
+        None
+    }
+
+    /// A conflict the service reported that belongs to a field
+    fn conflict_error(_message: &str) -> Option<(&'static str, DomainError)> {
+        None
+    }
+
+    /// An unbound create form
+    fn new_form() -> Form {
+        Self::form()
+    }
+
+    /// An unbound edit form, prefilled from `model`
+    fn edit(model: &Self::Model) -> Form {
+        Self::initial(model)
+            .into_iter()
+            .fold(Self::edit_form(), |form, (name, value)| {
+                form.initial(name, value)
+            })
+    }
+
+    fn bind(data: &FormData) -> Form {
+        Self::form().bind(&prepared::<Self>(data))
+    }
+
+    fn bind_edit(data: &FormData) -> Form {
+        Self::edit_form().bind(&prepared::<Self>(data))
+    }
+
+    /// Shows a service error on the form so it can be re-rendered
+    ///
+    /// Domain errors and conflicts the form knows about are attached and
+    /// `Ok` is returned; anything else (not found, internal) is handed back.
+    fn add_service_error(form: &mut Form, error: ServiceError) -> Result<(), ServiceError> {
+        match error {
+            ServiceError::Domain(error) => {
+                match Self::error_field(&error) {
+                    Some(field) => form.add_error(field, error),
+                    None => form.add_non_field_error(error),
+                }
+                Ok(())
+            }
+            ServiceError::Conflict(message) => match Self::conflict_error(&message) {
+                Some((field, error)) => {
+                    form.add_error(field, error);
+                    Ok(())
+                }
+                None => Err(ServiceError::Conflict(message)),
+            },
+            other => Err(other),
+        }
+    }
+}
+
+fn prepared<F: ModelForm + ?Sized>(data: &FormData) -> FormData {
+    let mut data = data.clone();
+    F::prepare(&mut data);
+    data
+}
@@ -0,0 +1,95 @@
+use crate::forms::{Field, Form, ModelForm};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::models::Post;
+use ferreiro_domain::ports::driving::{CreatePostCommand, UpdatePostCommand};
+use ferreiro_domain::values::{PostId, Title, UserId};
+
+/// Create and edit forms for posts
+///
+/// A blank slug is left to the service, which generates a unique one from
+/// the title. A slug typed in that is already taken comes back as an error
+/// on the `slug` field:
+///
+/// ```rust,ignore
+/// async fn create(renderer: Renderer, session: Session, data: FormData) -> Result<Response, ServiceError> {
+///     let author_id = session.get::<UserId>("user_id").ok_or(ServiceError::Unauthorized)?;
+///     let mut form = PostForm::bind(&data);
+///     if let Some(cmd) = PostForm::create_command(&form, author_id) {
+///         match service.create(cmd).await {
+///             Ok(post) => return Ok(Redirect::to(&format!("/posts/{}", post.slug())).into_response()),
+///             Err(error) => PostForm::add_service_error(&mut form, error)?,
+///         }
+///     }
+///     Ok(renderer.render("posts/new.html", context! { form: form }).into_response())
+/// }
+/// ```
+pub struct PostForm;
+
+impl PostForm {
+    /// `None` unless the form is valid
+    pub fn create_command(form: &Form, author_id: UserId) -> Option<CreatePostCommand> {
+        form.is_valid().then(|| CreatePostCommand {
+            title: form.text("title").unwrap_or_default(),
+            slug: form.text("slug"),
+            body: form.text("body").unwrap_or_default(),
+            author_id,
+        })
+    }
+
+    /// `None` unless the form is valid
+    pub fn update_command(form: &Form, id: PostId, actor: UserId) -> Option<UpdatePostCommand> {
+        form.is_valid().then(|| UpdatePostCommand {
+            id,
+            title: form.text("title").unwrap_or_default(),
+            body: form.text("body").unwrap_or_default(),
+            actor,
+        })
+    }
+}
+
+impl ModelForm for PostForm {
+    type Model = Post;
+
+    fn form() -> Form {
+        Form::new()
+            .field(Field::text("title").validate(Title::new))
+            .field(
+                Field::slug("slug")
+                    .optional()
+                    .help_text("Leave blank to generate it from the title"),
+            )
+            .field(Field::textarea("body").optional())
+    }
+
+    /// Slugs are fixed once a post exists, so editing leaves them out
+    fn edit_form() -> Form {
+        Form::new()
+            .field(Field::text("title").validate(Title::new))
+            .field(Field::textarea("body").optional())
+    }
+
+    fn initial(post: &Post) -> Vec<(&'static str, String)> {
+        vec![
+            ("title", post.title().as_str().to_string()),
+            ("slug", post.slug().as_str().to_string()),
+            ("body", post.body().as_str().to_string()),
+        ]
+    }
+
+    fn error_field(error: &DomainError) -> Option<&'static str> {
+        match error {
+            DomainError::EmptyTitle | DomainError::TitleTooLong { .. } => Some("title"),
+            DomainError::EmptySlug
+            | DomainError::SlugTooLong { .. }
+            | DomainError::InvalidSlugCharacters
+            | DomainError::SlugAlreadyExists => Some("slug"),
+            DomainError::EmptyBody | DomainError::CannotPublishEmptyPost => Some("body"),
+            _ => None,
+        }
+    }
+
+    fn conflict_error(message: &str) -> Option<(&'static str, DomainError)> {
+        let taken = DomainError::SlugAlreadyExists;
+        (message == taken.to_string()).then_some(("slug", taken))
+    }
+}
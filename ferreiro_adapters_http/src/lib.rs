@@ -1,4 +1,14 @@
+pub mod context_processors;
+pub mod forms;
 pub mod middleware;
 pub mod server;
+pub mod templates;
 
+pub use forms::{Field, Form, FormData, ModelForm, PostForm};
+pub use middleware::{
+    auth_middleware, jwt_middleware, locale_middleware, page_cache_middleware, session_middleware,
+    timezone_middleware, AuthConfig, Claims, ConfigError, CurrentUser, JwtConfig, Locale,
+    LocaleConfig, Localization, PageCacheConfig, Session, SessionConfig, Timezone, TimezoneConfig,
+};
 pub use server::serve;
+pub use templates::{Renderer, Template, Templates};
@@ -0,0 +1,217 @@
+use crate::middleware::session::Session;
+use axum::extract::{FromRequestParts, Request, State};
+use axum::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use ferreiro_domain::models::{AuthUser, User};
+use ferreiro_domain::ports::driving::{ApiTokenService, AuthService, ServiceError};
+use ferreiro_domain::values::UserId;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// Session key holding the token `AuthService::login` returned
+///
+/// Set it after logging in:
+///
+/// ```rust,ignore
+/// let authenticated = auth.login(cmd).await?;
+/// session.set(AUTH_SESSION_KEY, authenticated.session_token());
+/// ```
+pub const AUTH_SESSION_KEY: &str = "_auth_token";
+
+/// Session key holding a login that awaits its second factor
+///
+/// Nobody is logged in while it is set; `auth_middleware` ignores it.
+///
+/// ```rust,ignore
+/// let authenticated = auth.login(cmd).await?;
+/// if let Some(pending) = authenticated.pending_mfa() {
+///     session.set(MFA_SESSION_KEY, pending);
+///     return Redirect::to("/login/code");
+/// }
+/// // ... then, on the code form:
+/// let pending: String = session.get(MFA_SESSION_KEY).ok_or(StatusCode::BAD_REQUEST)?;
+/// let authenticated = auth.complete_login(&pending, &form.code).await?;
+/// session.remove(MFA_SESSION_KEY);
+/// session.set(AUTH_SESSION_KEY, authenticated.session_token());
+/// ```
+pub const MFA_SESSION_KEY: &str = "_mfa_pending";
+
+/// What `auth_middleware` authenticates users of model `M` with, `User`
+/// unless the project has its own
+pub struct AuthConfig<M: AuthUser = User> {
+    auth: Arc<dyn AuthService<M>>,
+    api_tokens: Option<Arc<dyn ApiTokenService<M>>>,
+}
+
+impl<M: AuthUser> Clone for AuthConfig<M> {
+    fn clone(&self) -> Self {
+        Self {
+            auth: self.auth.clone(),
+            api_tokens: self.api_tokens.clone(),
+        }
+    }
+}
+
+impl<M: AuthUser> AuthConfig<M> {
+    /// Session auth only; `Authorization: Bearer` requests are refused
+    pub fn new(auth: Arc<dyn AuthService<M>>) -> Self {
+        Self {
+            auth,
+            api_tokens: None,
+        }
+    }
+
+    /// Also accept personal access tokens as `Authorization: Bearer`
+    pub fn api_tokens(mut self, api_tokens: Arc<dyn ApiTokenService<M>>) -> Self {
+        self.api_tokens = Some(api_tokens);
+        self
+    }
+
+    /// `Err` is the response to send instead, for a bad bearer token
+    async fn resolve(
+        &self,
+        authorization: Option<HeaderValue>,
+        session: Option<Session>,
+    ) -> Result<Option<CurrentUser<M>>, Response> {
+        if let Some(header) = authorization {
+            let secret = bearer_token(&header).ok_or_else(invalid_token)?;
+            let api_tokens = self.api_tokens.as_ref().ok_or_else(invalid_token)?;
+            return match api_tokens.authenticate(secret).await {
+                Ok((user, token)) => Ok(Some(CurrentUser {
+                    user,
+                    scopes: Some(token.scopes().clone()),
+                })),
+                Err(ServiceError::Unauthorized) => Err(invalid_token()),
+                Err(e) => Err(internal_error(e)),
+            };
+        }
+
+        let Some(session) = session else {
+            return Ok(None);
+        };
+        let Some(session_token) = session.get::<String>(AUTH_SESSION_KEY) else {
+            return Ok(None);
+        };
+        match self.auth.get_user_by_session(&session_token).await {
+            Ok(Some(user)) if user.is_active() => Ok(Some(CurrentUser { user, scopes: None })),
+            Ok(_) => {
+                session.remove(AUTH_SESSION_KEY);
+                Ok(None)
+            }
+            Err(e) => Err(internal_error(e)),
+        }
+    }
+}
+
+/// The credentials of an `Authorization: Bearer` header; the scheme name
+/// is case-insensitive, so `bearer` and `BEARER` work too
+pub(crate) fn bearer_token(header: &HeaderValue) -> Option<&str> {
+    let (scheme, token) = header.to_str().ok()?.split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("Bearer") && !token.is_empty()).then_some(token)
+}
+
+pub(crate) fn invalid_token() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(WWW_AUTHENTICATE, r#"Bearer error="invalid_token""#)],
+        "Invalid token",
+    )
+        .into_response()
+}
+
+fn internal_error(error: ServiceError) -> Response {
+    tracing::error!("Failed to authenticate request: {}", error);
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+/// The user a request is made by, whether through a session or a token
+///
+/// Extracting it rejects anonymous requests with 401; take
+/// `Option<CurrentUser>` for pages that anyone may see.
+#[derive(Debug, Clone)]
+pub struct CurrentUser<M = User> {
+    user: M,
+    /// `None` for sessions, which aren't limited to scopes
+    scopes: Option<BTreeSet<String>>,
+}
+
+impl<M: AuthUser> CurrentUser<M> {
+    pub fn user(&self) -> &M {
+        &self.user
+    }
+    pub fn id(&self) -> &UserId {
+        self.user.id()
+    }
+    /// Whether the request carried a bearer token rather than a session
+    pub fn is_token(&self) -> bool {
+        self.scopes.is_some()
+    }
+    /// Always true for sessions; tokens need the scope granted
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes
+            .as_ref()
+            .is_none_or(|scopes| scopes.contains(scope))
+    }
+}
+
+#[axum::async_trait]
+impl<S, M> FromRequestParts<S> for CurrentUser<M>
+where
+    S: Send + Sync,
+    M: AuthUser,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<CurrentUser<M>>()
+            .cloned()
+            .ok_or((StatusCode::UNAUTHORIZED, "Authentication required"))
+    }
+}
+
+/// Identifies the user behind a request as a `CurrentUser`, or a
+/// `CurrentUser<M>` with an `AuthConfig<M>`
+///
+/// A request with `Authorization: Bearer <token>` is authenticated by the
+/// token alone, and answered with 401 if the token is unknown, expired or
+/// revoked. Otherwise the session's `AUTH_SESSION_KEY` is used, so install
+/// it inside `session_middleware`:
+///
+/// ```rust,ignore
+/// let app = Router::new()
+///     .route("/api/posts", get(list_posts))
+///     .layer(axum::middleware::from_fn_with_state(
+///         AuthConfig::new(auth_service).api_tokens(api_token_service),
+///         auth_middleware,
+///     ))
+///     .layer(axum::middleware::from_fn_with_state(sessions, session_middleware));
+///
+/// async fn list_posts(user: CurrentUser) -> Result<Json<Vec<Post>>, StatusCode> {
+///     if !user.has_scope("posts:read") {
+///         return Err(StatusCode::FORBIDDEN);
+///     }
+///     // ...
+/// }
+/// ```
+pub async fn auth_middleware<M: AuthUser>(
+    State(config): State<AuthConfig<M>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let authorization = req.headers().get(AUTHORIZATION).cloned();
+    let session = req.extensions().get::<Session>().cloned();
+    match config.resolve(authorization, session).await {
+        Ok(Some(current_user)) => {
+            req.extensions_mut().insert(current_user);
+        }
+        Ok(None) => {}
+        Err(response) => return response,
+    }
+    next.run(req).await
+}
@@ -0,0 +1,94 @@
+use crate::middleware::auth::{bearer_token, invalid_token};
+use axum::extract::{FromRequestParts, Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use ferreiro_domain::ports::driven::{AccessClaims, AccessTokenVerifier, TokenError};
+use std::ops::Deref;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct JwtConfig {
+    verifier: Arc<dyn AccessTokenVerifier>,
+}
+
+impl JwtConfig {
+    pub fn new(verifier: Arc<dyn AccessTokenVerifier>) -> Self {
+        Self { verifier }
+    }
+}
+
+/// The verified claims of the request's access token
+///
+/// Extracting it rejects requests without a token with 401; take
+/// `Option<Claims>` where a token is optional.
+#[derive(Debug, Clone)]
+pub struct Claims(pub AccessClaims);
+
+impl Deref for Claims {
+    type Target = AccessClaims;
+
+    fn deref(&self) -> &AccessClaims {
+        &self.0
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Claims
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Claims>()
+            .cloned()
+            .ok_or((StatusCode::UNAUTHORIZED, "Authentication required"))
+    }
+}
+
+/// Verifies `Authorization: Bearer` access tokens, without any lookups
+///
+/// A valid token's claims are available to handlers as `Claims`; an
+/// invalid or expired one is answered with 401. Requests without a token
+/// pass through, and are refused by handlers that extract `Claims`.
+///
+/// ```rust,ignore
+/// let verifier = JwtVerifier::new(keys.verifying_keys()).issuer("https://auth.example.com");
+/// let app = Router::new()
+///     .route("/api/orders", get(list_orders))
+///     .layer(axum::middleware::from_fn_with_state(
+///         JwtConfig::new(Arc::new(verifier)),
+///         jwt_middleware,
+///     ));
+///
+/// async fn list_orders(claims: Claims) -> String {
+///     format!("orders for {}", claims.user_id)
+/// }
+/// ```
+pub async fn jwt_middleware(
+    State(config): State<JwtConfig>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    if let Some(header) = req.headers().get(AUTHORIZATION) {
+        let Some(token) = bearer_token(header) else {
+            return invalid_token();
+        };
+        match config.verifier.verify(token) {
+            Ok(claims) => {
+                req.extensions_mut().insert(Claims(claims));
+            }
+            Err(TokenError::Key(e)) => {
+                tracing::error!("Failed to verify access token: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+            Err(_) => return invalid_token(),
+        }
+    }
+    next.run(req).await
+}
@@ -0,0 +1,103 @@
+use crate::middleware::session::Session;
+use crate::middleware::ConfigError;
+use axum::extract::{FromRequestParts, Request, State};
+use axum::http::header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE, VARY};
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use ferreiro_adapters_i18n::{canonical_locale, negotiate};
+
+/// Session key that pins a user's language, overriding `Accept-Language`
+///
+/// Copy `User::locale` here at login, as with `TIMEZONE_SESSION_KEY`.
+pub const LOCALE_SESSION_KEY: &str = "_locale";
+
+#[derive(Clone)]
+pub struct LocaleConfig {
+    supported: Vec<String>,
+}
+
+impl LocaleConfig {
+    /// The first locale is the default, used when nothing else matches
+    pub fn new(supported: &[&str]) -> Result<Self, ConfigError> {
+        if supported.is_empty() {
+            return Err(ConfigError::NoLocales);
+        }
+        Ok(Self {
+            supported: supported.iter().map(|l| canonical_locale(l)).collect(),
+        })
+    }
+
+    fn resolve(&self, req: &Request) -> String {
+        let supported: Vec<&str> = self.supported.iter().map(String::as_str).collect();
+
+        let chosen = req
+            .extensions()
+            .get::<Session>()
+            .and_then(|session| session.get::<String>(LOCALE_SESSION_KEY))
+            .and_then(|locale| negotiate(&locale, &supported))
+            .or_else(|| {
+                let header = req.headers().get(ACCEPT_LANGUAGE)?.to_str().ok()?;
+                negotiate(header, &supported)
+            });
+        chosen.unwrap_or(&self.supported[0]).to_string()
+    }
+}
+
+/// The locale negotiated for this request, such as `pt-BR`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale(pub String);
+
+impl Locale {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Locale
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Locale>().cloned().ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Locale middleware is not installed",
+        ))
+    }
+}
+
+/// Picks the request's locale from the session, then `Accept-Language`,
+/// then the default
+///
+/// Install it inside `session_middleware` so the session is available:
+///
+/// ```rust,ignore
+/// let app = Router::new()
+///     .route("/", get(index))
+///     .layer(axum::middleware::from_fn_with_state(
+///         LocaleConfig::new(&["en", "pt-BR", "es"])?,
+///         locale_middleware,
+///     ))
+///     .layer(axum::middleware::from_fn_with_state(sessions, session_middleware));
+/// ```
+pub async fn locale_middleware(
+    State(config): State<LocaleConfig>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let locale = config.resolve(&req);
+    req.extensions_mut().insert(Locale(locale.clone()));
+
+    let mut response = next.run(req).await;
+    if let Ok(value) = HeaderValue::from_str(&locale) {
+        response.headers_mut().insert(CONTENT_LANGUAGE, value);
+    }
+    response
+        .headers_mut()
+        .append(VARY, HeaderValue::from_static("accept-language"));
+    response
+}
@@ -1,2 +1,27 @@
-// Placeholder for middleware implementations
-// Will be expanded in future iterations
+pub mod auth;
+pub mod jwt;
+pub mod locale;
+pub mod page_cache;
+pub mod session;
+pub mod timezone;
+
+use thiserror::Error;
+
+pub use auth::{auth_middleware, AuthConfig, CurrentUser, AUTH_SESSION_KEY, MFA_SESSION_KEY};
+pub use jwt::{jwt_middleware, Claims, JwtConfig};
+pub use locale::{locale_middleware, Locale, LocaleConfig, LOCALE_SESSION_KEY};
+pub use page_cache::{page_cache_middleware, PageCacheConfig};
+pub use session::{session_middleware, Session, SessionConfig};
+pub use timezone::{
+    timezone_middleware, Localization, Timezone, TimezoneConfig, TIMEZONE_SESSION_KEY,
+};
+
+/// A middleware config that can't work, caught when it is built
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("at least one locale is required")]
+    NoLocales,
+
+    #[error("unknown time zone `{0}`")]
+    UnknownTimezone(String),
+}
@@ -0,0 +1,306 @@
+use axum::body::{Body, Bytes};
+use axum::extract::{Request, State};
+use axum::http::header::{
+    AUTHORIZATION, CACHE_CONTROL, CONNECTION, CONTENT_LENGTH, COOKIE, PROXY_AUTHENTICATE,
+    PROXY_AUTHORIZATION, SET_COOKIE, TE, TRAILER, TRANSFER_ENCODING, UPGRADE, VARY,
+};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use ferreiro_domain::ports::driven::Cache;
+use futures::{stream, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Headers that describe one connection, not the response, plus
+/// `Set-Cookie`, which would hand one visitor's cookie to everyone
+const NOT_STORED: [HeaderName; 9] = [
+    CONNECTION,
+    HeaderName::from_static("keep-alive"),
+    PROXY_AUTHENTICATE,
+    PROXY_AUTHORIZATION,
+    TE,
+    TRAILER,
+    TRANSFER_ENCODING,
+    UPGRADE,
+    SET_COOKIE,
+];
+
+#[derive(Clone)]
+pub struct PageCacheConfig {
+    cache: Arc<dyn Cache>,
+    ttl: Duration,
+    vary: Vec<HeaderName>,
+    session_cookie: String,
+    max_size: usize,
+}
+
+impl PageCacheConfig {
+    pub fn new(cache: Arc<dyn Cache>) -> Self {
+        Self {
+            cache,
+            ttl: Duration::from_secs(600),
+            vary: Vec::new(),
+            session_cookie: "ferreiro_session".to_string(),
+            max_size: 1024 * 1024,
+        }
+    }
+
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Keep a separate copy of each page per value of `header`, e.g.
+    /// `ACCEPT_LANGUAGE`, on top of whatever the responses' own `Vary`
+    /// headers name
+    pub fn vary(mut self, header: HeaderName) -> Self {
+        self.vary.push(header);
+        self
+    }
+
+    /// The cookie `SessionConfig` uses, if not the default; requests that
+    /// carry it are never served from or stored in the cache
+    pub fn session_cookie(mut self, name: &str) -> Self {
+        self.session_cookie = name.to_string();
+        self
+    }
+
+    /// The largest body, in bytes, worth storing; larger responses are
+    /// passed through uncached. Defaults to 1 MiB.
+    pub fn max_size(mut self, bytes: usize) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    /// Requests that may see per-user pages
+    fn is_personal(&self, req: &Request) -> bool {
+        let headers = req.headers();
+        headers.contains_key(AUTHORIZATION)
+            || headers
+                .get_all(COOKIE)
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|cookies| cookies.split(';'))
+                .filter_map(|cookie| cookie.split_once('='))
+                .any(|(name, _)| name.trim() == self.session_cookie)
+    }
+
+    /// Where the `Vary` headers last seen on the URL's responses are kept
+    fn vary_key(uri: &Uri) -> String {
+        format!("page-vary:{}", uri)
+    }
+
+    fn key(&self, uri: &Uri, headers: &HeaderMap, learned: &[HeaderName]) -> String {
+        let mut key = format!("page:{}", uri);
+        for name in self.vary.iter().chain(learned) {
+            let value = headers
+                .get_all(name)
+                .iter()
+                .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+                .collect::<Vec<_>>()
+                .join(",");
+            key.push_str(&format!("|{}={}", name, value));
+        }
+        key
+    }
+
+    async fn learned_vary(&self, uri: &Uri) -> Vec<HeaderName> {
+        match self.cache.get(&Self::vary_key(uri)).await {
+            Ok(Some(names)) => parse_vary(&String::from_utf8_lossy(&names)).unwrap_or_default(),
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                tracing::warn!("Page cache lookup failed: {}", e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Serves whole GET responses from the cache
+///
+/// Only `200 OK` responses are stored, and never ones that set a cookie,
+/// are marked `Cache-Control: private`/`no-store`/`no-cache` or say
+/// `Vary: *`. Requests with a session cookie or an `Authorization` header
+/// skip the cache both ways, since their pages may be someone's own. A
+/// response's `Vary` headers are remembered per URL and become part of the
+/// key, and every header but `Set-Cookie` and hop-by-hop ones is stored.
+/// Bodies over `max_size` are streamed through without being stored.
+/// Wrap the cache in a `NamespacedCache` and hand it to an
+/// `InvalidatingEventPublisher` so publishing or archiving a post clears
+/// every page:
+///
+/// ```rust,ignore
+/// let pages = NamespacedCache::new(cache, "pages");
+/// let app = Router::new()
+///     .route("/", get(index))
+///     .layer(axum::middleware::from_fn_with_state(
+///         PageCacheConfig::new(Arc::new(pages.clone())).vary(ACCEPT_LANGUAGE),
+///         page_cache_middleware,
+///     ));
+/// ```
+pub async fn page_cache_middleware(
+    State(config): State<PageCacheConfig>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if req.method() != Method::GET || config.is_personal(&req) {
+        return next.run(req).await;
+    }
+
+    // `next` takes the request, and the key needs these afterwards
+    let (uri, headers) = (req.uri().clone(), req.headers().clone());
+    let learned = config.learned_vary(&uri).await;
+    match config
+        .cache
+        .get(&config.key(&uri, &headers, &learned))
+        .await
+    {
+        Ok(Some(entry)) => {
+            if let Some(response) = decode(&entry) {
+                return response;
+            }
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Page cache lookup failed: {}", e),
+    }
+
+    let response = next.run(req).await;
+    let Some(vary) = cacheable_vary(&response) else {
+        return response;
+    };
+
+    let declared = response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok()?.parse::<usize>().ok());
+    if declared.is_some_and(|length| length > config.max_size) {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let mut body = body.into_data_stream();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.next().await {
+        match chunk {
+            Ok(chunk) => bytes.extend_from_slice(&chunk),
+            Err(e) => {
+                tracing::error!("Failed to read response body for the page cache: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+        if bytes.len() > config.max_size {
+            // Too big to store: send what was read, then the rest as it comes
+            let read = stream::once(async move { Ok(Bytes::from(bytes)) });
+            return Response::from_parts(parts, Body::from_stream(read.chain(body)));
+        }
+    }
+
+    let names = vary
+        .iter()
+        .map(HeaderName::as_str)
+        .collect::<Vec<_>>()
+        .join(",");
+    let stored = async {
+        config
+            .cache
+            .set(
+                &PageCacheConfig::vary_key(&uri),
+                names.as_bytes(),
+                Some(config.ttl),
+            )
+            .await?;
+        config
+            .cache
+            .set(
+                &config.key(&uri, &headers, &vary),
+                &encode(&parts.headers, &bytes),
+                Some(config.ttl),
+            )
+            .await
+    };
+    if let Err(e) = stored.await {
+        tracing::warn!("Failed to store page in cache: {}", e);
+    }
+
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// The response's `Vary` header names, if it can be cached at all
+fn cacheable_vary(response: &Response) -> Option<Vec<HeaderName>> {
+    if response.status() != StatusCode::OK || response.headers().contains_key(SET_COOKIE) {
+        return None;
+    }
+    let cache_control = joined(response.headers(), &CACHE_CONTROL).to_ascii_lowercase();
+    if ["private", "no-store", "no-cache"]
+        .iter()
+        .any(|directive| cache_control.contains(directive))
+    {
+        return None;
+    }
+    parse_vary(&joined(response.headers(), &VARY))
+}
+
+/// `None` for `Vary: *`, which no key can capture
+fn parse_vary(value: &str) -> Option<Vec<HeaderName>> {
+    let mut names = Vec::new();
+    for name in value.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        if name == "*" {
+            return None;
+        }
+        if let Ok(name) = HeaderName::try_from(name.to_ascii_lowercase()) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    Some(names)
+}
+
+fn joined(headers: &HeaderMap, name: &HeaderName) -> String {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// One `name:value` line per stored header, a blank line, then the body
+fn encode(headers: &HeaderMap, body: &[u8]) -> Vec<u8> {
+    let mut entry = Vec::new();
+    for (name, value) in headers {
+        if NOT_STORED.contains(name) {
+            continue;
+        }
+        entry.extend_from_slice(name.as_str().as_bytes());
+        entry.push(b':');
+        entry.extend_from_slice(value.as_bytes());
+        entry.push(b'\n');
+    }
+    entry.push(b'\n');
+    entry.extend_from_slice(body);
+    entry
+}
+
+fn decode(entry: &[u8]) -> Option<Response> {
+    let mut headers = HeaderMap::new();
+    let mut rest = entry;
+    loop {
+        let newline = rest.iter().position(|b| *b == b'\n')?;
+        let (line, after) = (&rest[..newline], &rest[newline + 1..]);
+        rest = after;
+        if line.is_empty() {
+            break;
+        }
+        let colon = line.iter().position(|b| *b == b':')?;
+        headers.append(
+            HeaderName::from_bytes(&line[..colon]).ok()?,
+            HeaderValue::from_bytes(&line[colon + 1..]).ok()?,
+        );
+    }
+
+    let mut response = Response::new(Body::from(rest.to_vec()));
+    *response.headers_mut() = headers;
+    Some(response)
+}
@@ -0,0 +1,191 @@
+use axum::extract::{FromRequestParts, Request, State};
+use axum::http::header::{COOKIE, SET_COOKIE};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use ferreiro_adapters_session::{Level, Message, SessionData, SessionId, SessionStore};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone)]
+pub struct SessionConfig {
+    store: Arc<dyn SessionStore>,
+    cookie_name: String,
+    secure: bool,
+}
+
+impl SessionConfig {
+    pub fn new(store: Arc<dyn SessionStore>) -> Self {
+        Self {
+            store,
+            cookie_name: "ferreiro_session".to_string(),
+            secure: false,
+        }
+    }
+
+    pub fn cookie_name(mut self, name: &str) -> Self {
+        self.cookie_name = name.to_string();
+        self
+    }
+
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    fn set_cookie(&self, id: &SessionId) -> Option<HeaderValue> {
+        let mut cookie = format!(
+            "{}={}; Path=/; HttpOnly; SameSite=Lax",
+            self.cookie_name, id
+        );
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        HeaderValue::from_str(&cookie).ok()
+    }
+}
+
+/// Session handle shared between the middleware and the handler
+#[derive(Clone)]
+pub struct Session {
+    data: Arc<Mutex<SessionData>>,
+}
+
+impl Session {
+    pub fn new(data: SessionData) -> Self {
+        Self {
+            data: Arc::new(Mutex::new(data)),
+        }
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.data.lock().unwrap().get(key)
+    }
+
+    pub fn set<T: Serialize>(&self, key: &str, value: T) {
+        self.data.lock().unwrap().set(key, value);
+    }
+
+    pub fn remove(&self, key: &str) {
+        self.data.lock().unwrap().remove(key);
+    }
+
+    pub fn clear(&self) {
+        self.data.lock().unwrap().clear();
+    }
+
+    /// Run `f` against the underlying session data
+    pub fn with<R>(&self, f: impl FnOnce(&mut SessionData) -> R) -> R {
+        f(&mut self.data.lock().unwrap())
+    }
+
+    // Flash messages
+    pub fn add_message(&self, level: Level, text: impl Into<String>) {
+        self.with(|data| data.messages().add(level, text));
+    }
+
+    pub fn debug(&self, text: impl Into<String>) {
+        self.add_message(Level::Debug, text);
+    }
+
+    pub fn info(&self, text: impl Into<String>) {
+        self.add_message(Level::Info, text);
+    }
+
+    pub fn success(&self, text: impl Into<String>) {
+        self.add_message(Level::Success, text);
+    }
+
+    pub fn warning(&self, text: impl Into<String>) {
+        self.add_message(Level::Warning, text);
+    }
+
+    pub fn error(&self, text: impl Into<String>) {
+        self.add_message(Level::Error, text);
+    }
+
+    pub fn take_messages(&self) -> Vec<Message> {
+        self.with(|data| data.messages().take())
+    }
+
+    fn snapshot(&self) -> SessionData {
+        self.data.lock().unwrap().clone()
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Session
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Session>().cloned().ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Session middleware is not installed",
+        ))
+    }
+}
+
+/// Loads the session from its cookie and saves it back when modified
+///
+/// ```rust,ignore
+/// let app = Router::new()
+///     .route("/", get(index))
+///     .layer(axum::middleware::from_fn_with_state(
+///         SessionConfig::new(Arc::new(MemorySessionStore::new())),
+///         session_middleware,
+///     ));
+/// ```
+pub async fn session_middleware(
+    State(config): State<SessionConfig>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let session_id = read_cookie(req.headers(), &config.cookie_name);
+
+    let mut data = match &session_id {
+        Some(id) => config
+            .store
+            .load(id)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default(),
+        None => SessionData::new(),
+    };
+    data.modified = false;
+
+    let session = Session::new(data);
+    req.extensions_mut().insert(session.clone());
+
+    let mut response = next.run(req).await;
+
+    let mut data = session.snapshot();
+    if data.modified {
+        data.modified = false;
+        match config.store.save(session_id.as_ref(), &data).await {
+            Ok(id) => {
+                if let Some(cookie) = config.set_cookie(&id) {
+                    response.headers_mut().append(SET_COOKIE, cookie);
+                }
+            }
+            Err(e) => tracing::error!("Failed to save session: {}", e),
+        }
+    }
+
+    response
+}
+
+fn read_cookie(headers: &HeaderMap, name: &str) -> Option<SessionId> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
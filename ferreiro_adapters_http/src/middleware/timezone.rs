@@ -0,0 +1,119 @@
+use crate::middleware::locale::Locale;
+use crate::middleware::session::Session;
+use crate::middleware::ConfigError;
+use axum::extract::{FromRequestParts, Request, State};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono_tz::Tz;
+use ferreiro_adapters_templates::{filters, Localizer};
+use std::ops::Deref;
+
+/// Session key holding the user's IANA time zone, such as `America/Sao_Paulo`
+///
+/// Copy `User::timezone` here at login so every later request uses it.
+pub const TIMEZONE_SESSION_KEY: &str = "_timezone";
+
+#[derive(Clone)]
+pub struct TimezoneConfig {
+    default: Tz,
+}
+
+impl TimezoneConfig {
+    /// `default` must be an IANA zone name, such as `America/Sao_Paulo`
+    pub fn new(default: &str) -> Result<Self, ConfigError> {
+        let default = filters::parse_timezone(default)
+            .ok_or_else(|| ConfigError::UnknownTimezone(default.to_string()))?;
+        Ok(Self { default })
+    }
+
+    fn resolve(&self, req: &Request) -> Tz {
+        req.extensions()
+            .get::<Session>()
+            .and_then(|session| session.get::<String>(TIMEZONE_SESSION_KEY))
+            .and_then(|name| filters::parse_timezone(&name))
+            .unwrap_or(self.default)
+    }
+}
+
+/// The time zone activated for this request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timezone(pub Tz);
+
+impl Timezone {
+    pub fn name(&self) -> &'static str {
+        self.0.name()
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Timezone
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Timezone>().copied().ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Timezone middleware is not installed",
+        ))
+    }
+}
+
+/// Formats dates and numbers for the request's locale and time zone
+///
+/// Uses whichever of `locale_middleware` and `timezone_middleware` are
+/// installed, falling back to unlocalized output in UTC:
+///
+/// ```rust,ignore
+/// async fn show(l10n: Localization, Path(slug): Path<String>) -> String {
+///     let post = service.get_post_by_slug(&slug).await?;
+///     l10n.date(&post.published_at().unwrap())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Localization(pub Localizer);
+
+impl Deref for Localization {
+    type Target = Localizer;
+
+    fn deref(&self) -> &Localizer {
+        &self.0
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Localization
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let timezone = parts
+            .extensions
+            .get::<Timezone>()
+            .map_or("UTC", Timezone::name);
+        let localizer = match parts.extensions.get::<Locale>() {
+            Some(locale) => Localizer::new(locale.as_str(), timezone),
+            None => Localizer::default().in_timezone(timezone),
+        };
+        Ok(Localization(localizer))
+    }
+}
+
+/// Activates the session's time zone, or the default, for this request
+///
+/// Install it inside `session_middleware`, like `locale_middleware`, and add
+/// `context_processors::timezone` so the template filters use it.
+pub async fn timezone_middleware(
+    State(config): State<TimezoneConfig>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let timezone = config.resolve(&req);
+    req.extensions_mut().insert(Timezone(timezone));
+    next.run(req).await
+}
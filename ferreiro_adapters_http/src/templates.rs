@@ -0,0 +1,200 @@
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
+use ferreiro_adapters_templates::{
+    context, Context, ContextProcessors, TemplateEngine, TemplateError,
+};
+use std::sync::Arc;
+
+/// The app's template engine together with its context processors
+///
+/// Install it with `.layer(Extension(templates))`, then render from handlers
+/// through the `Renderer` extractor:
+///
+/// ```rust,ignore
+/// async fn index(renderer: Renderer) -> Template {
+///     renderer.render("index.html", context! { title: "Home" })
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Templates {
+    engine: Arc<dyn TemplateEngine>,
+    processors: ContextProcessors<Parts>,
+    debug: bool,
+}
+
+impl Templates {
+    pub fn new(engine: Arc<dyn TemplateEngine>) -> Self {
+        Self {
+            engine,
+            processors: ContextProcessors::new(),
+            debug: false,
+        }
+    }
+
+    pub fn processor<F>(mut self, processor: F) -> Self
+    where
+        F: Fn(&Parts, &mut Context) + Send + Sync + 'static,
+    {
+        self.processors.register(processor);
+        self
+    }
+
+    /// Show template errors, with their source location, instead of the
+    /// generic error pages. Never enable this in production.
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    pub fn engine(&self) -> &Arc<dyn TemplateEngine> {
+        &self.engine
+    }
+
+    /// Builds the full context for this request — processors first, then the
+    /// handler's values on top
+    pub fn context(&self, parts: &Parts, context: Context) -> Context {
+        self.processors.apply(parts, context)
+    }
+
+    pub fn render(
+        &self,
+        parts: &Parts,
+        name: &str,
+        context: Context,
+    ) -> Result<Html<String>, TemplateError> {
+        let context = self.context(parts, context);
+        self.engine.render(name, &context).map(Html)
+    }
+
+    /// Renders `ferreiro/errors/<status>.html`, which apps can override,
+    /// falling back to plain text
+    pub fn error_page(&self, status: StatusCode) -> Response {
+        let name = format!("ferreiro/errors/{}.html", status.as_u16());
+        match self.engine.render(&name, &Context::new()) {
+            Ok(html) => (status, Html(html)).into_response(),
+            Err(_) => (status, status.canonical_reason().unwrap_or("Error")).into_response(),
+        }
+    }
+
+    fn error_response(&self, template: &str, error: &TemplateError) -> Response {
+        let status = match error {
+            TemplateError::NotFound(_) => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        tracing::error!("Failed to render template {}: {}", template, error);
+
+        if !self.debug {
+            return self.error_page(status);
+        }
+
+        let kind = match error {
+            TemplateError::NotFound(_) => "Template not found",
+            TemplateError::Parse(_) => "Template syntax error",
+            TemplateError::Render(_) => "Template render error",
+            TemplateError::Watch(_) => "Template watch error",
+        };
+        let context = context! {
+            status: status.as_u16(),
+            template: template,
+            kind: kind,
+            message: error.to_string(),
+        };
+        match self.engine.render("ferreiro/errors/debug.html", &context) {
+            Ok(html) => (status, Html(html)).into_response(),
+            Err(_) => (status, format!("{}\n\n{}", kind, error)).into_response(),
+        }
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Templates
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Templates>().cloned().ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Templates extension is not installed",
+        ))
+    }
+}
+
+/// Extractor pairing the app's `Templates` with the current request, so
+/// context processors can see it
+pub struct Renderer {
+    templates: Templates,
+    parts: Parts,
+}
+
+impl Renderer {
+    pub fn render(&self, name: &str, context: Context) -> Template {
+        Template {
+            result: self
+                .templates
+                .render(&self.parts, name, context)
+                .map(|html| html.0),
+            templates: self.templates.clone(),
+            name: name.to_string(),
+            status: StatusCode::OK,
+        }
+    }
+
+    pub fn error_page(&self, status: StatusCode) -> Response {
+        self.templates.error_page(status)
+    }
+
+    pub fn parts(&self) -> &Parts {
+        &self.parts
+    }
+}
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for Renderer
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let templates = Templates::from_request_parts(parts, state).await?;
+        Ok(Self {
+            templates,
+            parts: parts.clone(),
+        })
+    }
+}
+
+/// A rendered template, sent as `text/html`
+///
+/// Template errors become the 404 or 500 error page, or the debug page when
+/// `Templates::debug` is on.
+pub struct Template {
+    result: Result<String, TemplateError>,
+    templates: Templates,
+    name: String,
+    status: StatusCode,
+}
+
+impl Template {
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn into_result(self) -> Result<String, TemplateError> {
+        self.result
+    }
+}
+
+impl IntoResponse for Template {
+    fn into_response(self) -> Response {
+        match self.result {
+            Ok(html) => (self.status, Html(html)).into_response(),
+            Err(error) => self.templates.error_response(&self.name, &error),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use axum::body::Body;
+use axum::http::header::{AUTHORIZATION, COOKIE, SET_COOKIE, WWW_AUTHENTICATE};
+use axum::http::{Request, StatusCode};
+use axum::routing::{get, post};
+use axum::Router;
+use ferreiro_adapters_cache::InMemoryCache;
+use ferreiro_adapters_db::testing::PlainTextHasher;
+use ferreiro_adapters_db::{
+    InMemoryApiTokenRepository, InMemoryEventPublisher, InMemoryUserRepository,
+};
+use ferreiro_adapters_http::middleware::AUTH_SESSION_KEY;
+use ferreiro_adapters_http::{
+    auth_middleware, session_middleware, AuthConfig, CurrentUser, Session, SessionConfig,
+};
+use ferreiro_adapters_session::memory::MemorySessionStore;
+use ferreiro_application::services::{AccountTokens, ApiTokenServiceImpl, AuthServiceImpl};
+use ferreiro_domain::ports::driving::{
+    ApiTokenService, AuthService, CreateApiTokenCommand, LoginCommand, RegisterCommand,
+};
+use ferreiro_domain::values::UserId;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+struct Fixture {
+    app: Router,
+    api_tokens: Arc<dyn ApiTokenService>,
+    user_id: UserId,
+}
+
+async fn fixture() -> Fixture {
+    let users = Arc::new(InMemoryUserRepository::new());
+    let auth: Arc<dyn AuthService> = Arc::new(AuthServiceImpl::new(
+        users.clone(),
+        Arc::new(InMemoryEventPublisher::new()),
+        Arc::new(PlainTextHasher),
+        Arc::new(InMemoryCache::new()),
+        AccountTokens::new(b"test-secret"),
+    ));
+    let api_tokens: Arc<dyn ApiTokenService> = Arc::new(ApiTokenServiceImpl::new(
+        Arc::new(InMemoryApiTokenRepository::new()),
+        users,
+    ));
+    let user = auth
+        .register(RegisterCommand {
+            email: "ana@example.com".to_string(),
+            password: "correct horse battery".to_string(),
+            name: "Ana".to_string(),
+            identifier: None,
+        })
+        .await
+        .unwrap();
+
+    let login_auth = auth.clone();
+    let app = Router::new()
+        .route(
+            "/me",
+            get(|user: CurrentUser| async move {
+                format!("{} {}", user.user().name(), user.has_scope("posts:write"))
+            }),
+        )
+        .route(
+            "/login",
+            post(|session: Session| async move {
+                let authenticated = login_auth
+                    .login(LoginCommand {
+                        identifier: "ana@example.com".to_string(),
+                        password: "correct horse battery".to_string(),
+                        ip_address: None,
+                    })
+                    .await
+                    .unwrap();
+                session.set(AUTH_SESSION_KEY, authenticated.session_token().unwrap());
+                "ok"
+            }),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            AuthConfig::new(auth).api_tokens(api_tokens.clone()),
+            auth_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            SessionConfig::new(Arc::new(MemorySessionStore::new())),
+            session_middleware,
+        ));
+
+    Fixture {
+        app,
+        api_tokens,
+        user_id: user.id().clone(),
+    }
+}
+
+async fn get_me(app: &Router, header: Option<(&str, &str)>) -> (StatusCode, String) {
+    let mut request = Request::builder().uri("/me");
+    if let Some((name, value)) = header {
+        request = request.header(name, value);
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_bearer_tokens_authenticate_as_current_user() {
+    let fixture = fixture().await;
+    let created = fixture
+        .api_tokens
+        .create(CreateApiTokenCommand {
+            user_id: fixture.user_id.clone(),
+            name: "CI".to_string(),
+            scopes: vec!["posts:read".to_string()],
+            expires_at: None,
+        })
+        .await
+        .unwrap();
+
+    let bearer = format!("Bearer {}", created.secret);
+    let (status, body) = get_me(&fixture.app, Some((AUTHORIZATION.as_str(), &bearer))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "Ana false");
+
+    // The scheme name is case-insensitive
+    let lowercase = format!("bearer {}", created.secret);
+    let (status, _) = get_me(&fixture.app, Some((AUTHORIZATION.as_str(), &lowercase))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let response = fixture
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/me")
+                .header(AUTHORIZATION, "Bearer ferreiro_pat_nope")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(response.headers().contains_key(WWW_AUTHENTICATE));
+
+    fixture
+        .api_tokens
+        .revoke(created.token.id(), &fixture.user_id)
+        .await
+        .unwrap();
+    let (status, _) = get_me(&fixture.app, Some((AUTHORIZATION.as_str(), &bearer))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_sessions_authenticate_as_current_user() {
+    let fixture = fixture().await;
+
+    let (status, _) = get_me(&fixture.app, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let response = fixture
+        .app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/login")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let cookie = response.headers()[SET_COOKIE].to_str().unwrap();
+    let cookie = cookie.split(';').next().unwrap().to_string();
+
+    // Sessions aren't limited to scopes
+    let (status, body) = get_me(&fixture.app, Some((COOKIE.as_str(), &cookie))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "Ana true");
+}
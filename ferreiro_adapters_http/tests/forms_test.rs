@@ -0,0 +1,174 @@
+use axum::body::Body;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::Request;
+use axum::routing::post;
+use axum::Router;
+use chrono::{TimeZone, Utc};
+use ferreiro_adapters_http::forms::{Field, Form, FormData};
+use ferreiro_adapters_i18n::Catalogs;
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_adapters_templates::tera_adapter::TeraEngine;
+use ferreiro_adapters_templates::TemplateEngine;
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::models::PostStatus;
+use ferreiro_domain::values::Title;
+use tower::ServiceExt;
+
+fn post_form() -> Form {
+    Form::new()
+        .field(Field::text("title").validate(Title::new))
+        .field(Field::slug("slug"))
+        .field(Field::email("contact").optional())
+        .field(Field::choice::<PostStatus>("status"))
+        .field(Field::datetime("publish_at").optional())
+        .field(Field::checkbox("featured"))
+}
+
+/// Echoes what the form made of the submission
+fn app() -> Router {
+    Router::new().route(
+        "/",
+        post(|data: FormData| async move {
+            let form = post_form().bind(&data);
+            format!(
+                "{}|{:?}|{:?}|{}|{:?}",
+                form.is_valid(),
+                form.slug("slug").map(|slug| slug.to_string()),
+                form.choice::<PostStatus>("status"),
+                form.checked("featured"),
+                form.datetime("publish_at"),
+            )
+        }),
+    )
+}
+
+async fn submit(content_type: &str, body: String) -> String {
+    let request = Request::post("/")
+        .header(CONTENT_TYPE, content_type)
+        .body(Body::from(body))
+        .unwrap();
+    let response = app().oneshot(request).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_binds_urlencoded_and_multipart_bodies() {
+    let expected = "true|Some(\"hello-world\")|Some(Published)|true|Some(2024-03-01T12:30:00Z)";
+
+    let urlencoded = "title=Hello+World&slug=hello-world&status=published\
+                      &publish_at=2024-03-01T12%3A30&featured=on";
+    assert_eq!(
+        submit("application/x-www-form-urlencoded", urlencoded.to_string()).await,
+        expected
+    );
+
+    let mut multipart = String::new();
+    for (name, value) in [
+        ("title", "Hello World"),
+        ("slug", "hello-world"),
+        ("status", "published"),
+        ("publish_at", "2024-03-01T12:30:00Z"),
+        ("featured", "on"),
+    ] {
+        multipart.push_str(&format!(
+            "--XYZ\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+            name, value
+        ));
+    }
+    multipart.push_str(
+        "--XYZ\r\nContent-Disposition: form-data; name=\"cover\"; filename=\"a.png\"\r\n\
+         Content-Type: image/png\r\n\r\nPNG\r\n--XYZ--\r\n",
+    );
+    assert_eq!(
+        submit("multipart/form-data; boundary=XYZ", multipart).await,
+        expected
+    );
+}
+
+#[test]
+fn test_collects_domain_errors_per_field() {
+    let form = post_form().bind(&FormData::from_pairs([
+        ("title", ""),
+        ("slug", "Not A Slug"),
+        ("contact", "nobody"),
+        ("status", "deleted"),
+        ("publish_at", "tomorrow"),
+    ]));
+
+    assert!(form.is_bound());
+    assert!(!form.is_valid());
+    assert_eq!(form.errors("title"), [DomainError::FieldRequired]);
+    assert_eq!(form.errors("slug"), [DomainError::InvalidSlugCharacters]);
+    assert_eq!(form.errors("contact"), [DomainError::InvalidEmail]);
+    assert_eq!(form.errors("status"), [DomainError::InvalidChoice]);
+    assert_eq!(form.errors("publish_at"), [DomainError::InvalidDateTime]);
+    assert!(form.errors("featured").is_empty());
+
+    let long_title = "x".repeat(201);
+    let form = post_form().bind(&FormData::from_pairs([
+        ("title", long_title.as_str()),
+        ("slug", "ok"),
+        ("status", "draft"),
+    ]));
+    assert_eq!(
+        form.errors("title"),
+        [DomainError::TitleTooLong {
+            max: 200,
+            actual: 201
+        }]
+    );
+
+    let mut form = post_form().bind(&FormData::from_pairs([
+        ("title", "Fine"),
+        ("slug", "fine"),
+        ("status", "draft"),
+    ]));
+    assert!(form.is_valid());
+    form.add_error("slug", DomainError::UserAlreadyExists);
+    assert!(!form.is_valid());
+}
+
+#[test]
+fn test_renders_values_and_errors_in_both_engines() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path().to_str().unwrap();
+    let tera = TeraEngine::new(dir).unwrap();
+    let minijinja = MiniJinjaEngine::new(dir).unwrap();
+
+    let form = post_form().bind(&FormData::from_pairs([
+        ("title", "<b>Draft</b>"),
+        ("slug", "Bad Slug"),
+        ("status", "archived"),
+        ("featured", "on"),
+    ]));
+    let catalogs = Catalogs::builtin();
+
+    for engine in [&tera as &dyn TemplateEngine, &minijinja] {
+        let html = form.render(engine).unwrap();
+        // Previous values come back, escaped
+        assert!(html.contains(r#"name="title" id="id_title" value="&lt;b&gt;Draft&lt;"#));
+        assert!(!html.contains("<b>"));
+        assert!(html.contains("Slug can only contain letters, numbers, and hyphens"));
+        assert!(html.contains(r#"<option value="archived" selected>Archived</option>"#));
+        assert!(html.contains(r#"name="featured" id="id_featured" checked"#));
+        assert!(html.contains(r#"<label for="id_publish_at">Publish at</label>"#));
+
+        let html = form
+            .localized_view(&catalogs, "pt-BR")
+            .render(engine)
+            .unwrap();
+        assert!(html.contains("O slug só pode conter letras, números e hífens"));
+    }
+
+    let published = Utc.with_ymd_and_hms(2024, 3, 1, 12, 30, 0).unwrap();
+    let edit = post_form()
+        .initial("title", "Hello")
+        .initial("publish_at", published.to_rfc3339());
+    assert!(!edit.is_bound());
+    let html = edit.render(&minijinja).unwrap();
+    assert!(html.contains(r#"value="Hello""#));
+    assert!(html.contains(r#"value="2024-03-01T12:30""#));
+}
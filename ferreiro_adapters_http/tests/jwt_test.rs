@@ -0,0 +1,76 @@
+use axum::body::Body;
+use axum::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use axum::http::{Request, StatusCode};
+use axum::routing::get;
+use axum::Router;
+use ferreiro_adapters_db::InMemoryRefreshTokenRepository;
+use ferreiro_adapters_http::{jwt_middleware, Claims, JwtConfig};
+use ferreiro_adapters_jwt::{JwtKey, JwtTokenIssuer, JwtVerifier, KeySet};
+use ferreiro_domain::ports::driven::TokenIssuer;
+use ferreiro_domain::values::UserId;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+async fn status(app: &Router, authorization: Option<&str>) -> (StatusCode, bool, String) {
+    let mut request = Request::builder().uri("/me");
+    if let Some(value) = authorization {
+        request = request.header(AUTHORIZATION, value);
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let challenged = response.headers().contains_key(WWW_AUTHENTICATE);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        challenged,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_jwt_middleware_verifies_bearer_tokens_statelessly() {
+    let keys = KeySet::new().signing(JwtKey::generate_ed25519("k1").unwrap());
+    let issuer = JwtTokenIssuer::new(
+        keys.clone(),
+        Arc::new(InMemoryRefreshTokenRepository::new()),
+    )
+    .unwrap();
+
+    // The service verifying holds only the public key
+    let verifier = JwtVerifier::new(keys.verifying_keys());
+    let app = Router::new()
+        .route(
+            "/me",
+            get(|claims: Claims| async move { claims.user_id.to_string() }),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            JwtConfig::new(Arc::new(verifier)),
+            jwt_middleware,
+        ));
+
+    let user_id = UserId::generate();
+    let tokens = issuer.issue(&user_id).await.unwrap();
+
+    let bearer = format!("Bearer {}", tokens.access_token);
+    let (code, _, body) = status(&app, Some(&bearer)).await;
+    assert_eq!(code, StatusCode::OK);
+    assert_eq!(body, user_id.to_string());
+
+    let (code, _, _) = status(&app, None).await;
+    assert_eq!(code, StatusCode::UNAUTHORIZED);
+
+    let refresh = format!("Bearer {}", tokens.refresh_token);
+    let (code, challenged, _) = status(&app, Some(&refresh)).await;
+    assert_eq!(code, StatusCode::UNAUTHORIZED);
+    assert!(challenged);
+
+    let (code, challenged, _) = status(&app, Some("Basic YWxhZGRpbjpvcGVu")).await;
+    assert_eq!(code, StatusCode::UNAUTHORIZED);
+    assert!(challenged);
+}
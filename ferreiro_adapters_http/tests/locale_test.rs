@@ -0,0 +1,140 @@
+use axum::body::Body;
+use axum::extract::Path;
+use axum::http::header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE, COOKIE, SET_COOKIE};
+use axum::http::request::Parts;
+use axum::http::Request;
+use axum::routing::get;
+use axum::{Extension, Router};
+use ferreiro_adapters_http::middleware::LOCALE_SESSION_KEY;
+use ferreiro_adapters_http::{
+    context_processors, locale_middleware, session_middleware, ConfigError, Locale, LocaleConfig,
+    Session, SessionConfig, Templates,
+};
+use ferreiro_adapters_i18n::Catalogs;
+use ferreiro_adapters_session::memory::MemorySessionStore;
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_adapters_templates::Context;
+use ferreiro_domain::errors::DomainError;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+fn app(template_dir: &str) -> Router {
+    let catalogs = Arc::new(Catalogs::builtin());
+    let engine = MiniJinjaEngine::new(template_dir)
+        .unwrap()
+        .with_translator(catalogs.clone());
+    let templates = Templates::new(Arc::new(engine)).processor(context_processors::i18n);
+
+    Router::new()
+        .route(
+            "/",
+            get(|templates: Templates, parts: Parts| async move {
+                templates
+                    .render(&parts, "page.txt", Context::new())
+                    .unwrap()
+            }),
+        )
+        .route(
+            "/error",
+            get(move |locale: Locale| async move {
+                DomainError::SlugTooLong {
+                    max: 200,
+                    actual: 250,
+                }
+                .localized(&*catalogs, locale.as_str())
+            }),
+        )
+        .route(
+            "/language/:code",
+            get(|session: Session, Path(code): Path<String>| async move {
+                session.set(LOCALE_SESSION_KEY, code);
+                "ok"
+            }),
+        )
+        .layer(Extension(templates))
+        .layer(axum::middleware::from_fn_with_state(
+            LocaleConfig::new(&["en", "pt-BR", "es"]).unwrap(),
+            locale_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            SessionConfig::new(Arc::new(MemorySessionStore::new())),
+            session_middleware,
+        ))
+}
+
+async fn get_text(app: &Router, uri: &str, headers: &[(&str, &str)]) -> (String, String) {
+    let mut request = Request::get(uri);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let language = response.headers()[CONTENT_LANGUAGE]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (language, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn test_locale_is_negotiated_from_accept_language() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("page.txt"),
+        r#"{{ LANGUAGE_CODE }}: {{ trans("error.email.invalid") }}"#,
+    )
+    .unwrap();
+    let app = app(dir.path().to_str().unwrap());
+
+    let (language, body) = get_text(&app, "/", &[("accept-language", "pt-PT,pt;q=0.9")]).await;
+    assert_eq!(language, "pt-BR");
+    assert_eq!(body, "pt-BR: Endereço de e-mail inválido");
+
+    let (language, body) = get_text(&app, "/", &[("accept-language", "de")]).await;
+    assert_eq!(language, "en");
+    assert_eq!(body, "en: Invalid email address");
+
+    let (_, body) = get_text(&app, "/error", &[("accept-language", "es")]).await;
+    assert_eq!(body, "Slug demasiado largo: 250 caracteres (máximo 200)");
+}
+
+#[test]
+fn test_a_locale_config_needs_a_default() {
+    assert!(matches!(
+        LocaleConfig::new(&[]),
+        Err(ConfigError::NoLocales)
+    ));
+}
+
+#[tokio::test]
+async fn test_session_locale_overrides_accept_language() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("page.txt"),
+        r#"{{ trans("error.body.empty") }}"#,
+    )
+    .unwrap();
+    let app = app(dir.path().to_str().unwrap());
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/language/es").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let cookie = response.headers()[SET_COOKIE].to_str().unwrap();
+    let cookie = cookie.split(';').next().unwrap().to_string();
+
+    let headers = [
+        (COOKIE.as_str(), cookie.as_str()),
+        (ACCEPT_LANGUAGE.as_str(), "pt-BR"),
+    ];
+    let (language, body) = get_text(&app, "/", &headers).await;
+    assert_eq!(language, "es");
+    assert_eq!(body, "El cuerpo no puede estar vacío");
+}
@@ -0,0 +1,150 @@
+use ferreiro_adapters_db::{
+    InMemoryEventPublisher, InMemoryGroupRepository, InMemoryPostRepository, InMemoryUserRepository,
+};
+use ferreiro_adapters_http::forms::{Field, Form, FormData, ModelForm, PostForm};
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_application::services::{PostServiceImpl, RepositoryPermissionChecker};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::models::User;
+use ferreiro_domain::ports::driven::UserRepository;
+use ferreiro_domain::ports::driving::{PostService, ServiceError};
+use ferreiro_domain::values::{Email, Slug, UserId};
+use std::sync::Arc;
+
+/// The service and a superuser to write posts as
+async fn service() -> (
+    PostServiceImpl<InMemoryPostRepository, InMemoryEventPublisher>,
+    UserId,
+) {
+    let users = Arc::new(InMemoryUserRepository::new());
+    let mut author = User::new(
+        Email::new("ana@example.com").unwrap(),
+        "Ana".to_string(),
+        "hash".to_string(),
+    );
+    author.make_superuser();
+    users.save(&author).await.unwrap();
+    let service = PostServiceImpl::new(
+        Arc::new(InMemoryPostRepository::new()),
+        Arc::new(InMemoryEventPublisher::new()),
+        Arc::new(RepositoryPermissionChecker::new(
+            users,
+            Arc::new(InMemoryGroupRepository::new()),
+        )),
+    );
+    (service, author.id().clone())
+}
+
+#[tokio::test]
+async fn test_create_leaves_blank_slugs_to_the_service_and_reports_conflicts() {
+    let (service, author) = service().await;
+    let data = FormData::from_pairs([("title", "Hello, World!"), ("slug", ""), ("body", "Hi")]);
+
+    let form = PostForm::bind(&data);
+    let cmd = PostForm::create_command(&form, author.clone()).unwrap();
+    assert_eq!(cmd.slug, None);
+    let post = service.create(cmd).await.unwrap();
+    assert_eq!(post.slug().as_str(), "hello-world");
+
+    // A chosen slug that is taken lands on the slug field
+    let data = FormData::from_pairs([("title", "Again"), ("slug", "hello-world")]);
+    let mut form = PostForm::bind(&data);
+    let error = service
+        .create(PostForm::create_command(&form, author.clone()).unwrap())
+        .await
+        .unwrap_err();
+    PostForm::add_service_error(&mut form, error).unwrap();
+    assert!(!form.is_valid());
+    assert_eq!(form.errors("slug"), [DomainError::SlugAlreadyExists]);
+    assert_eq!(form.value("slug"), Some("hello-world"));
+
+    let dir = tempfile::tempdir().unwrap();
+    let engine = MiniJinjaEngine::new(dir.path().to_str().unwrap()).unwrap();
+    assert!(form
+        .render(&engine)
+        .unwrap()
+        .contains("Slug already exists"));
+
+    // Errors the form can't place are handed back
+    let mut form = PostForm::bind(&data);
+    assert!(matches!(
+        PostForm::add_service_error(&mut form, ServiceError::NotFound),
+        Err(ServiceError::NotFound)
+    ));
+    assert!(PostForm::create_command(&PostForm::bind(&FormData::default()), author).is_none());
+}
+
+#[tokio::test]
+async fn test_edit_prefills_from_the_post_and_maps_to_an_update() {
+    let (service, author) = service().await;
+    let data = FormData::from_pairs([("title", "First"), ("slug", "first"), ("body", "Old")]);
+    let cmd = PostForm::create_command(&PostForm::bind(&data), author).unwrap();
+    let post = service.create(cmd).await.unwrap();
+
+    let edit = PostForm::edit(&post);
+    assert!(!edit.is_bound());
+    assert_eq!(edit.value("title"), Some("First"));
+    assert_eq!(edit.value("body"), Some("Old"));
+    assert!(edit.fields().iter().all(|field| field.name() != "slug"));
+
+    let form = PostForm::bind_edit(&FormData::from_pairs([
+        ("title", "Second"),
+        ("body", "New"),
+    ]));
+    let cmd = PostForm::update_command(&form, post.id().clone(), post.author_id().clone()).unwrap();
+    let updated = service.update(cmd).await.unwrap();
+    assert_eq!(updated.title().as_str(), "Second");
+    assert_eq!(updated.slug().as_str(), "first");
+}
+
+struct Tag {
+    name: String,
+    slug: Slug,
+}
+
+/// A user-defined aggregate gets the same flow from the trait alone
+struct TagForm;
+
+impl ModelForm for TagForm {
+    type Model = Tag;
+
+    fn form() -> Form {
+        Form::new()
+            .field(Field::text("name"))
+            .field(Field::slug("handle"))
+    }
+
+    fn initial(tag: &Tag) -> Vec<(&'static str, String)> {
+        vec![("name", tag.name.clone()), ("handle", tag.slug.to_string())]
+    }
+
+    fn prepare(data: &mut FormData) {
+        let handle = data.get("handle").unwrap_or_default().to_lowercase();
+        data.set("handle", handle);
+    }
+
+    fn error_field(error: &DomainError) -> Option<&'static str> {
+        matches!(error, DomainError::SlugAlreadyExists).then_some("handle")
+    }
+}
+
+#[test]
+fn test_custom_aggregates_implement_model_form() {
+    let tag = Tag {
+        name: "Rust".to_string(),
+        slug: Slug::new("rust").unwrap(),
+    };
+    assert_eq!(TagForm::edit(&tag).value("handle"), Some("rust"));
+
+    let mut form = TagForm::bind(&FormData::from_pairs([
+        ("name", "Rust"),
+        ("handle", "RUST"),
+    ]));
+    assert!(form.is_valid());
+    assert_eq!(form.slug("handle").unwrap().as_str(), "rust");
+
+    TagForm::add_service_error(&mut form, DomainError::SlugAlreadyExists.into()).unwrap();
+    TagForm::add_service_error(&mut form, DomainError::InvalidEmail.into()).unwrap();
+    assert_eq!(form.errors("handle"), [DomainError::SlugAlreadyExists]);
+    assert_eq!(form.non_field_errors(), [DomainError::InvalidEmail]);
+}
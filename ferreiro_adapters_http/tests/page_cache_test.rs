@@ -0,0 +1,237 @@
+use axum::body::Body;
+use axum::http::header::{
+    ACCEPT_ENCODING, ACCEPT_LANGUAGE, AUTHORIZATION, CONTENT_TYPE, COOKIE, SET_COOKIE, VARY,
+};
+use axum::http::{HeaderName, Request, StatusCode};
+use axum::response::{Html, IntoResponse};
+use axum::routing::get;
+use axum::Router;
+use chrono::Utc;
+use ferreiro_adapters_cache::{InMemoryCache, InvalidatingEventPublisher, NamespacedCache};
+use ferreiro_adapters_db::InMemoryEventPublisher;
+use ferreiro_adapters_http::{page_cache_middleware, PageCacheConfig};
+use ferreiro_domain::events::DomainEvent;
+use ferreiro_domain::ports::driven::EventPublisher;
+use ferreiro_domain::values::{PostId, UserId};
+use futures::stream;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+/// Every handler echoes how many times it has run, so a cached response
+/// shows an old count
+fn app(pages: NamespacedCache) -> Router {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let count = move || hits.fetch_add(1, Ordering::SeqCst) + 1;
+
+    let (a, b, c, d) = (count.clone(), count.clone(), count.clone(), count);
+    Router::new()
+        .route(
+            "/posts",
+            get(move |req: Request<Body>| async move {
+                let lang = req
+                    .headers()
+                    .get(ACCEPT_LANGUAGE)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("")
+                    .to_string();
+                Html(format!("{}:{}", lang, a()))
+            }),
+        )
+        .route(
+            "/login",
+            get(move || async move { ([(SET_COOKIE, "id=1")], b().to_string()) }),
+        )
+        .route(
+            "/encoded",
+            get(move |req: Request<Body>| async move {
+                let encoding = req
+                    .headers()
+                    .get(ACCEPT_ENCODING)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("")
+                    .to_string();
+                (
+                    [
+                        (VARY, "Accept-Encoding"),
+                        (HeaderName::from_static("x-served-by"), "app"),
+                    ],
+                    format!("{}:{}", encoding, d()),
+                )
+            }),
+        )
+        .route(
+            "/missing",
+            get(move || async move { (StatusCode::NOT_FOUND, c().to_string()).into_response() }),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            PageCacheConfig::new(Arc::new(pages)).vary(ACCEPT_LANGUAGE),
+            page_cache_middleware,
+        ))
+}
+
+async fn get_body(app: &Router, uri: &str, lang: &str) -> (String, Option<String>) {
+    let request = Request::get(uri).header(ACCEPT_LANGUAGE, lang);
+    send(app, request).await
+}
+
+async fn send(app: &Router, request: axum::http::request::Builder) -> (String, Option<String>) {
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .map(|v| v.to_str().unwrap().to_string());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (String::from_utf8(body.to_vec()).unwrap(), content_type)
+}
+
+#[tokio::test]
+async fn test_pages_are_cached_per_url_and_vary_header() {
+    let app = app(NamespacedCache::new(
+        Arc::new(InMemoryCache::new()),
+        "pages",
+    ));
+
+    let (first, content_type) = get_body(&app, "/posts", "en").await;
+    assert_eq!(first, "en:1");
+    assert_eq!(content_type.as_deref(), Some("text/html; charset=utf-8"));
+
+    let (again, content_type) = get_body(&app, "/posts", "en").await;
+    assert_eq!(again, "en:1");
+    assert_eq!(content_type.as_deref(), Some("text/html; charset=utf-8"));
+
+    assert_eq!(get_body(&app, "/posts", "pt-BR").await.0, "pt-BR:2");
+    assert_eq!(get_body(&app, "/posts?page=2", "en").await.0, "en:3");
+}
+
+#[tokio::test]
+async fn test_errors_and_cookies_are_not_cached() {
+    let app = app(NamespacedCache::new(
+        Arc::new(InMemoryCache::new()),
+        "pages",
+    ));
+
+    assert_eq!(get_body(&app, "/login", "en").await.0, "1");
+    assert_eq!(get_body(&app, "/login", "en").await.0, "2");
+    assert_eq!(get_body(&app, "/missing", "en").await.0, "3");
+    assert_eq!(get_body(&app, "/missing", "en").await.0, "4");
+}
+
+#[tokio::test]
+async fn test_publishing_a_post_invalidates_cached_pages() {
+    let pages = NamespacedCache::new(Arc::new(InMemoryCache::new()), "pages");
+    let app = app(pages.clone());
+    let inner = Arc::new(InMemoryEventPublisher::new());
+    let events = InvalidatingEventPublisher::new(inner.clone(), vec![pages]);
+
+    assert_eq!(get_body(&app, "/posts", "en").await.0, "en:1");
+    events
+        .publish(DomainEvent::PostCreated {
+            post_id: PostId::generate(),
+            author_id: UserId::generate(),
+            occurred_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(get_body(&app, "/posts", "en").await.0, "en:1");
+
+    events
+        .publish(DomainEvent::PostPublished {
+            post_id: PostId::generate(),
+            occurred_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(get_body(&app, "/posts", "en").await.0, "en:2");
+    assert_eq!(inner.get_events().len(), 2);
+}
+
+#[tokio::test]
+async fn test_logged_in_requests_skip_the_cache() {
+    let app = app(NamespacedCache::new(
+        Arc::new(InMemoryCache::new()),
+        "pages",
+    ));
+
+    assert_eq!(get_body(&app, "/posts", "en").await.0, "en:1");
+    let with_session = Request::get("/posts")
+        .header(ACCEPT_LANGUAGE, "en")
+        .header(COOKIE, "theme=dark; ferreiro_session=abc");
+    assert_eq!(send(&app, with_session).await.0, "en:2");
+    let with_token = Request::get("/posts")
+        .header(ACCEPT_LANGUAGE, "en")
+        .header(AUTHORIZATION, "Bearer abc");
+    assert_eq!(send(&app, with_token).await.0, "en:3");
+    // Neither was stored, and other cookies don't matter
+    let with_theme = Request::get("/posts")
+        .header(ACCEPT_LANGUAGE, "en")
+        .header(COOKIE, "theme=dark");
+    assert_eq!(send(&app, with_theme).await.0, "en:1");
+}
+
+#[tokio::test]
+async fn test_responses_vary_and_keep_their_headers() {
+    let app = app(NamespacedCache::new(
+        Arc::new(InMemoryCache::new()),
+        "pages",
+    ));
+    let encoded = |encoding: &str| {
+        Request::get("/encoded")
+            .header(ACCEPT_LANGUAGE, "en")
+            .header(ACCEPT_ENCODING, encoding)
+    };
+
+    assert_eq!(send(&app, encoded("gzip")).await.0, "gzip:1");
+    assert_eq!(send(&app, encoded("br")).await.0, "br:2");
+    assert_eq!(send(&app, encoded("gzip")).await.0, "gzip:1");
+
+    let response = app
+        .clone()
+        .oneshot(encoded("br").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["x-served-by"], "app");
+    assert_eq!(response.headers()[VARY], "Accept-Encoding");
+    assert_eq!(
+        response.headers()[CONTENT_TYPE],
+        "text/plain; charset=utf-8"
+    );
+}
+
+#[tokio::test]
+async fn test_bodies_over_the_size_limit_pass_through_uncached() {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let count = move || hits.fetch_add(1, Ordering::SeqCst) + 1;
+    let (a, b) = (count.clone(), count);
+    let app = Router::new()
+        .route("/big", get(move || async move { format!("{:>64}", a()) }))
+        .route(
+            "/streamed",
+            get(move || async move {
+                let n = b().to_string();
+                let chunks = [" ".repeat(40), " ".repeat(40), n];
+                Body::from_stream(stream::iter(chunks.map(Ok::<_, std::io::Error>)))
+            }),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            PageCacheConfig::new(Arc::new(InMemoryCache::new())).max_size(63),
+            page_cache_middleware,
+        ));
+
+    let (first, _) = get_body(&app, "/big", "en").await;
+    let (second, _) = get_body(&app, "/big", "en").await;
+    assert_eq!((first.len(), first.trim()), (64, "1"));
+    assert_eq!(second.trim(), "2");
+
+    // No `Content-Length`, so the limit is only found while reading
+    let (first, _) = get_body(&app, "/streamed", "en").await;
+    let (second, _) = get_body(&app, "/streamed", "en").await;
+    assert_eq!((first.len(), first.trim()), (81, "3"));
+    assert_eq!(second.trim(), "4");
+}
@@ -0,0 +1,99 @@
+use axum::body::Body;
+use axum::http::request::Parts;
+use axum::http::{header, Request};
+use axum::routing::get;
+use axum::{Extension, Router};
+use ferreiro_adapters_http::context_processors;
+use ferreiro_adapters_http::{session_middleware, Session, SessionConfig, Templates};
+use ferreiro_adapters_session::cookie::CookieSessionStore;
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_adapters_templates::tera_adapter::TeraEngine;
+use ferreiro_adapters_templates::{Context, TemplateEngine};
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+
+const MESSAGES_TEMPLATE: &str =
+    "{% for message in messages %}[{{ message.level }}] {{ message.text }}{% endfor %}";
+
+fn app(template_dir: &str) -> Router {
+    let store = Arc::new(CookieSessionStore::new(
+        b"secret",
+        Duration::from_secs(3600),
+    ));
+    let engine = Arc::new(MiniJinjaEngine::new(template_dir).unwrap());
+    let templates = Templates::new(engine).processor(context_processors::messages);
+
+    Router::new()
+        .route(
+            "/publish",
+            get(|session: Session| async move {
+                session.success("Post published!");
+                "ok"
+            }),
+        )
+        .route(
+            "/",
+            get(|templates: Templates, parts: Parts| async move {
+                templates
+                    .render(&parts, "messages.html", Context::new())
+                    .unwrap()
+            }),
+        )
+        .layer(Extension(templates))
+        .layer(axum::middleware::from_fn_with_state(
+            SessionConfig::new(store),
+            session_middleware,
+        ))
+}
+
+#[tokio::test]
+async fn test_flash_message_round_trip_with_cookie_store() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("messages.html"), MESSAGES_TEMPLATE).unwrap();
+    let app = app(dir.path().to_str().unwrap());
+
+    let response = app
+        .clone()
+        .oneshot(Request::get("/publish").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let set_cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    let cookie = set_cookie.split(';').next().unwrap().to_string();
+
+    let response = app
+        .oneshot(
+            Request::get("/")
+                .header(header::COOKIE, &cookie)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    // Messages were consumed, so the session is written back without them
+    assert!(response.headers().contains_key(header::SET_COOKIE));
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&body[..], b"[success] Post published!");
+}
+
+#[test]
+fn test_messages_processor_works_with_tera() {
+    let session = Session::new(Default::default());
+    session.warning("Careful");
+
+    let (mut parts, _) = Request::new(()).into_parts();
+    parts.extensions.insert(session.clone());
+
+    let mut context = Context::new();
+    context_processors::messages(&parts, &mut context);
+
+    let engine = TeraEngine::new("templates").unwrap();
+    let html = engine.render_string(MESSAGES_TEMPLATE, &context).unwrap();
+
+    assert_eq!(html, "[warning] Careful");
+    assert!(session.take_messages().is_empty());
+}
@@ -0,0 +1,135 @@
+use axum::body::Body;
+use axum::extract::Path;
+use axum::http::request::Parts;
+use axum::http::{header, Request, StatusCode};
+use axum::routing::get;
+use axum::{Extension, Router};
+use ferreiro_adapters_http::{context_processors, Renderer, Templates};
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_adapters_templates::{context, Context};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn test_context_processors_run_before_render() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("page.txt"),
+        "{{ site_name }}|{{ request.path }}?{{ request.query }}|{{ title }}|{{ tagline }}",
+    )
+    .unwrap();
+
+    let engine = Arc::new(MiniJinjaEngine::new(dir.path().to_str().unwrap()).unwrap());
+    let templates = Templates::new(engine)
+        .processor(context_processors::request)
+        .processor(context_processors::constants(context! {
+            site_name: "Ferreiro Blog",
+            tagline: "Default tagline",
+        }))
+        .processor(|parts: &Parts, context: &mut Context| {
+            context.insert("title", format!("Page {}", parts.uri.path()));
+        });
+
+    let app = Router::new()
+        .route(
+            "/posts",
+            get(|templates: Templates, parts: Parts| async move {
+                templates
+                    .render(&parts, "page.txt", context! { tagline: "From handler" })
+                    .unwrap()
+            }),
+        )
+        .layer(Extension(templates));
+
+    let response = app
+        .oneshot(Request::get("/posts?page=2").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        &body[..],
+        b"Ferreiro Blog|/posts?page=2|Page /posts|From handler"
+    );
+}
+
+fn render_app(template_dir: &str, debug: bool) -> Router {
+    let engine = Arc::new(MiniJinjaEngine::new(template_dir).unwrap());
+    let templates = Templates::new(engine).debug(debug);
+
+    Router::new()
+        .route(
+            "/:name",
+            get(|renderer: Renderer, Path(name): Path<String>| async move {
+                renderer.render(&name, context! { title: "Hello" })
+            }),
+        )
+        .layer(Extension(templates))
+}
+
+async fn get_page(app: Router, uri: &str) -> (StatusCode, String, String) {
+    let response = app
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let content_type = response.headers()[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        content_type,
+        String::from_utf8(body.to_vec()).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_template_response_renders_html() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("ok.html"), "<h1>{{ title }}</h1>").unwrap();
+
+    let (status, content_type, body) =
+        get_page(render_app(dir.path().to_str().unwrap(), false), "/ok.html").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "text/html; charset=utf-8");
+    assert_eq!(body, "<h1>Hello</h1>");
+}
+
+#[tokio::test]
+async fn test_template_errors_map_to_error_pages() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("broken.html"), "{{ title ").unwrap();
+    let template_dir = dir.path().to_str().unwrap();
+
+    let (status, _, body) = get_page(render_app(template_dir, false), "/missing.html").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body.contains("Page not found"));
+
+    let (status, _, body) = get_page(render_app(template_dir, false), "/broken.html").await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(body.contains("Server error"));
+    assert!(!body.contains("broken.html"));
+}
+
+#[tokio::test]
+async fn test_debug_mode_shows_template_error_location() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("broken.html"), "<p>\n{{ title ").unwrap();
+
+    let (status, _, body) = get_page(
+        render_app(dir.path().to_str().unwrap(), true),
+        "/broken.html",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(body.contains("Template syntax error"));
+    assert!(body.contains("broken.html:2"));
+}
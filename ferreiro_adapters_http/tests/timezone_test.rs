@@ -0,0 +1,137 @@
+use axum::body::Body;
+use axum::extract::Path;
+use axum::http::header::{COOKIE, SET_COOKIE};
+use axum::http::request::Parts;
+use axum::http::Request;
+use axum::routing::get;
+use axum::{Extension, Router};
+use chrono::{TimeZone, Utc};
+use ferreiro_adapters_http::middleware::TIMEZONE_SESSION_KEY;
+use ferreiro_adapters_http::{
+    context_processors, locale_middleware, session_middleware, timezone_middleware, ConfigError,
+    LocaleConfig, Localization, Session, SessionConfig, Templates, Timezone, TimezoneConfig,
+};
+use ferreiro_adapters_session::memory::MemorySessionStore;
+use ferreiro_adapters_templates::tera_adapter::TeraEngine;
+use ferreiro_adapters_templates::Context;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+fn app(template_dir: &str) -> Router {
+    let engine = TeraEngine::new(template_dir).unwrap();
+    let templates = Templates::new(Arc::new(engine))
+        .processor(context_processors::i18n)
+        .processor(context_processors::timezone);
+
+    Router::new()
+        .route(
+            "/",
+            get(|templates: Templates, parts: Parts| async move {
+                let mut context = Context::new();
+                context.insert(
+                    "published",
+                    Utc.with_ymd_and_hms(2024, 3, 1, 1, 30, 0).unwrap(),
+                );
+                templates.render(&parts, "post.txt", context).unwrap()
+            }),
+        )
+        .route(
+            "/api",
+            get(|l10n: Localization, timezone: Timezone| async move {
+                let published = Utc.with_ymd_and_hms(2024, 3, 1, 1, 30, 0).unwrap();
+                format!(
+                    "{}|{}|{}",
+                    timezone.name(),
+                    l10n.format(&published, "%d/%m %H:%M").unwrap(),
+                    l10n.number(1234.5, 1)
+                )
+            }),
+        )
+        .route(
+            "/timezone/*name",
+            get(|session: Session, Path(name): Path<String>| async move {
+                session.set(TIMEZONE_SESSION_KEY, name);
+                "ok"
+            }),
+        )
+        .layer(Extension(templates))
+        .layer(axum::middleware::from_fn_with_state(
+            TimezoneConfig::new("UTC").unwrap(),
+            timezone_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            LocaleConfig::new(&["en", "pt-BR"]).unwrap(),
+            locale_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            SessionConfig::new(Arc::new(MemorySessionStore::new())),
+            session_middleware,
+        ))
+}
+
+async fn get_text(app: &Router, uri: &str, cookie: Option<&str>) -> (String, Option<String>) {
+    let mut request = Request::get(uri).header("accept-language", "pt-BR");
+    if let Some(cookie) = cookie {
+        request = request.header(COOKIE, cookie);
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let cookie = response.headers().get(SET_COOKIE).map(|value| {
+        value
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string()
+    });
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (String::from_utf8(body.to_vec()).unwrap(), cookie)
+}
+
+#[tokio::test]
+async fn test_session_time_zone_applies_to_templates_and_handlers() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("post.txt"),
+        r#"{{ published | date(format="%-d de %B, %H:%M") }}"#,
+    )
+    .unwrap();
+    let app = app(dir.path().to_str().unwrap());
+
+    let (body, _) = get_text(&app, "/", None).await;
+    assert_eq!(body, "1 de março, 01:30");
+    let (body, _) = get_text(&app, "/api", None).await;
+    assert_eq!(body, "UTC|01/03 01:30|1.234,5");
+
+    let (_, cookie) = get_text(&app, "/timezone/America/Sao_Paulo", None).await;
+    let cookie = cookie.unwrap();
+
+    let (body, _) = get_text(&app, "/", Some(&cookie)).await;
+    assert_eq!(body, "29 de fevereiro, 22:30");
+    let (body, _) = get_text(&app, "/api", Some(&cookie)).await;
+    assert_eq!(body, "America/Sao_Paulo|29/02 22:30|1.234,5");
+}
+
+#[tokio::test]
+async fn test_unknown_session_time_zone_falls_back_to_the_default() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app(dir.path().to_str().unwrap());
+
+    let (_, cookie) = get_text(&app, "/timezone/Mars/Olympus", None).await;
+    let (body, _) = get_text(&app, "/api", cookie.as_deref()).await;
+    assert_eq!(body, "UTC|01/03 01:30|1.234,5");
+}
+
+#[test]
+fn test_an_unknown_default_time_zone_is_an_error() {
+    assert!(matches!(
+        TimezoneConfig::new("Mars/Olympus"),
+        Err(ConfigError::UnknownTimezone(name)) if name == "Mars/Olympus"
+    ));
+}
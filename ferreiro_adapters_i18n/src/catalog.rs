@@ -0,0 +1,143 @@
+use crate::I18nError;
+use std::collections::HashMap;
+
+/// Translated messages for one locale, read from a gettext `.po` file
+///
+/// Only singular `msgid`/`msgstr` pairs are supported. Entries flagged
+/// `#, fuzzy` and empty translations are skipped, as `msgfmt` does.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    messages: HashMap<String, String>,
+}
+
+#[derive(PartialEq)]
+enum Field {
+    None,
+    Id,
+    Str,
+}
+
+#[derive(Default)]
+struct Entry {
+    id: String,
+    text: String,
+    fuzzy: bool,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `file` only labels parse errors
+    pub fn parse(source: &str, file: &str) -> Result<Self, I18nError> {
+        let mut catalog = Self::new();
+        let mut entry = Entry::default();
+        let mut field = Field::None;
+        // Flags are comments above the entry they apply to
+        let mut fuzzy = false;
+
+        let error = |line: usize, message: &str| I18nError::Parse {
+            file: file.to_string(),
+            line: line + 1,
+            message: message.to_string(),
+        };
+
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                fuzzy |= line.starts_with("#,") && line.contains("fuzzy");
+                continue;
+            }
+
+            let (keyword, rest) = line
+                .split_once(char::is_whitespace)
+                .map(|(k, r)| (k, r.trim()))
+                .unwrap_or((line, ""));
+            match keyword {
+                "msgid" => {
+                    catalog.finish(std::mem::take(&mut entry));
+                    entry.fuzzy = std::mem::take(&mut fuzzy);
+                    entry.id =
+                        unquote(rest).ok_or_else(|| error(number, "expected a quoted string"))?;
+                    field = Field::Id;
+                }
+                "msgstr" => {
+                    if field != Field::Id {
+                        return Err(error(number, "msgstr without a msgid"));
+                    }
+                    entry.text =
+                        unquote(rest).ok_or_else(|| error(number, "expected a quoted string"))?;
+                    field = Field::Str;
+                }
+                _ if line.starts_with('"') => {
+                    let more = unquote(line).ok_or_else(|| error(number, "unterminated string"))?;
+                    match field {
+                        Field::Id => entry.id.push_str(&more),
+                        Field::Str => entry.text.push_str(&more),
+                        Field::None => return Err(error(number, "string outside an entry")),
+                    }
+                }
+                "msgctxt" | "msgid_plural" => {
+                    return Err(error(number, &format!("`{}` is not supported", keyword)));
+                }
+                _ if keyword.starts_with("msgstr[") => {
+                    return Err(error(number, "plural forms are not supported"));
+                }
+                _ => return Err(error(number, &format!("unexpected `{}`", keyword))),
+            }
+        }
+
+        catalog.finish(entry);
+        Ok(catalog)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.messages.get(key).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, key: &str, message: &str) {
+        self.messages.insert(key.to_string(), message.to_string());
+    }
+
+    /// Adds every message from `other`, replacing existing ones
+    pub fn extend(&mut self, other: Catalog) {
+        self.messages.extend(other.messages);
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    fn finish(&mut self, entry: Entry) {
+        // The entry with an empty msgid is the catalog header
+        if !entry.fuzzy && !entry.id.is_empty() && !entry.text.is_empty() {
+            self.messages.insert(entry.id, entry.text);
+        }
+    }
+}
+
+/// A C-style quoted string, with its escapes resolved
+fn unquote(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            other => out.push(other),
+        }
+    }
+    Some(out)
+}
@@ -0,0 +1,110 @@
+use crate::catalog::Catalog;
+use crate::negotiate::canonical_locale;
+use crate::I18nError;
+use ferreiro_domain::ports::driven::Translator;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const BUILTIN: [(&str, &str); 3] = [
+    ("en", include_str!("../locale/en.po")),
+    ("es", include_str!("../locale/es.po")),
+    ("pt-BR", include_str!("../locale/pt-BR.po")),
+];
+
+/// Catalogs for every supported locale
+///
+/// A message missing from `pt-BR` is looked up in `pt`, then in the default
+/// locale.
+#[derive(Debug, Clone)]
+pub struct Catalogs {
+    catalogs: BTreeMap<String, Catalog>,
+    default_locale: String,
+}
+
+impl Catalogs {
+    pub fn new(default_locale: &str) -> Self {
+        Self {
+            catalogs: BTreeMap::new(),
+            default_locale: canonical_locale(default_locale),
+        }
+    }
+
+    /// The framework's own messages, with English as the default locale
+    pub fn builtin() -> Self {
+        let mut catalogs = Self::new("en");
+        for (locale, source) in BUILTIN {
+            let file = format!("{}.po", locale);
+            let catalog = Catalog::parse(source, &file).expect("built-in catalogs are valid");
+            catalogs.add(locale, catalog);
+        }
+        catalogs
+    }
+
+    /// Merges `catalog` into `locale`, its messages winning over existing ones
+    pub fn add(&mut self, locale: &str, catalog: Catalog) {
+        self.catalogs
+            .entry(canonical_locale(locale))
+            .or_default()
+            .extend(catalog);
+    }
+
+    /// Adds every `<locale>.po` file in `dir`, e.g. `locale/pt-BR.po`
+    pub fn load_dir(&mut self, dir: impl AsRef<Path>) -> Result<(), I18nError> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "po") {
+                let Some(locale) = path.file_stem().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+                let source = std::fs::read_to_string(&path)?;
+                self.add(
+                    locale,
+                    Catalog::parse(&source, &path.display().to_string())?,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn default_locale(&self) -> &str {
+        &self.default_locale
+    }
+
+    pub fn locales(&self) -> Vec<&str> {
+        self.catalogs.keys().map(String::as_str).collect()
+    }
+
+    fn lookup(&self, locale: &str, key: &str) -> Option<&str> {
+        let locale = canonical_locale(locale);
+        let mut chain = vec![locale.as_str()];
+        if let Some((language, _)) = locale.split_once('-') {
+            chain.push(language);
+        }
+        chain.push(&self.default_locale);
+
+        chain
+            .into_iter()
+            .find_map(|locale| self.catalogs.get(locale)?.get(key))
+    }
+}
+
+impl Default for Catalogs {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Translator for Catalogs {
+    fn translate(&self, locale: &str, key: &str, params: &[(&str, String)]) -> Option<String> {
+        let message = self.lookup(locale, key)?;
+        Some(interpolate(message, params))
+    }
+}
+
+fn interpolate(message: &str, params: &[(&str, String)]) -> String {
+    params
+        .iter()
+        .fold(message.to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{}}}", name), value)
+        })
+}
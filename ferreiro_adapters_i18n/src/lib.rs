@@ -0,0 +1,29 @@
+//! Translations for Ferreiro apps
+//!
+//! Messages live in gettext `.po` catalogs, one per locale, keyed by stable
+//! ids such as `DomainError::message_key` rather than English text.
+//! `Catalogs` implements the domain's `Translator` port and ships catalogs
+//! for every `DomainError` in English, Brazilian Portuguese and Spanish.
+
+use thiserror::Error;
+
+pub mod catalog;
+pub mod catalogs;
+pub mod negotiate;
+
+pub use catalog::Catalog;
+pub use catalogs::Catalogs;
+pub use negotiate::{canonical_locale, negotiate};
+
+#[derive(Debug, Error)]
+pub enum I18nError {
+    #[error("{file}:{line}: {message}")]
+    Parse {
+        file: String,
+        line: usize,
+        message: String,
+    },
+
+    #[error("Failed to read catalog: {0}")]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,60 @@
+/// `pt_br`, `PT-br` → `pt-BR`: language lowercase, two-letter region
+/// uppercase, `_` as `-`
+pub fn canonical_locale(locale: &str) -> String {
+    locale
+        .trim()
+        .split(['-', '_'])
+        .enumerate()
+        .map(|(i, part)| match (i, part.len()) {
+            (0, _) => part.to_ascii_lowercase(),
+            (_, 2) => part.to_ascii_uppercase(),
+            _ => part.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
+
+/// Picks the best of `supported` for an `Accept-Language` header
+///
+/// Tags are tried by quality; each matches a supported locale exactly, then
+/// by its language alone (`pt-PT` accepts `pt`), then any regional variant
+/// of its language (`pt` accepts `pt-BR`).
+pub fn negotiate<'a>(accept_language: &str, supported: &[&'a str]) -> Option<&'a str> {
+    let mut tags: Vec<(String, f32)> = accept_language
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (!tag.is_empty() && tag != "*" && quality > 0.0)
+                .then(|| (canonical_locale(tag), quality))
+        })
+        .collect();
+    tags.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let supported: Vec<(String, &'a str)> = supported
+        .iter()
+        .map(|locale| (canonical_locale(locale), *locale))
+        .collect();
+
+    tags.iter().find_map(|(tag, _)| {
+        let base = language(tag);
+        supported
+            .iter()
+            .find(|(locale, _)| locale == tag)
+            .or_else(|| supported.iter().find(|(locale, _)| locale == base))
+            .or_else(|| {
+                supported
+                    .iter()
+                    .find(|(locale, _)| language(locale) == base)
+            })
+            .map(|(_, original)| *original)
+    })
+}
@@ -0,0 +1,160 @@
+use ferreiro_adapters_i18n::{negotiate, Catalog, Catalogs, I18nError};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::ports::driven::Translator;
+
+#[test]
+fn test_parses_po_files() {
+    let source = r#"
+# Header
+msgid ""
+msgstr ""
+"Language: pt-BR\n"
+
+msgid "greeting"
+msgstr "Olá, {name}!"
+
+#. Continuation lines and escapes
+msgid "long"
+msgstr ""
+"Primeira linha\n"
+"Segunda \"linha\""
+
+#, fuzzy
+msgid "unsure"
+msgstr "Talvez"
+
+msgid "untranslated"
+msgstr ""
+"#;
+    let catalog = Catalog::parse(source, "pt-BR.po").unwrap();
+
+    assert_eq!(catalog.len(), 2);
+    assert_eq!(catalog.get("greeting"), Some("Olá, {name}!"));
+    assert_eq!(
+        catalog.get("long"),
+        Some("Primeira linha\nSegunda \"linha\"")
+    );
+    assert_eq!(catalog.get("unsure"), None);
+    assert_eq!(catalog.get("untranslated"), None);
+
+    let error = Catalog::parse("msgid \"a\"\nmsgstr[0] \"b\"\n", "es.po").unwrap_err();
+    assert!(
+        matches!(error, I18nError::Parse { line: 2, .. }),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_lookup_falls_back_through_language_and_default() {
+    let mut catalogs = Catalogs::new("en");
+    let mut en = Catalog::new();
+    en.insert("welcome", "Welcome, {name}");
+    en.insert("bye", "Bye");
+    catalogs.add("en", en);
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("pt.po"),
+        "msgid \"welcome\"\nmsgstr \"Bem-vindo, {name}\"\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("README.md"), "not a catalog").unwrap();
+    catalogs.load_dir(dir.path()).unwrap();
+    assert_eq!(catalogs.locales(), vec!["en", "pt"]);
+
+    let params = [("name", "Ana".to_string())];
+    assert_eq!(
+        catalogs.translate("pt_br", "welcome", &params).unwrap(),
+        "Bem-vindo, Ana"
+    );
+    assert_eq!(catalogs.translate("pt-BR", "bye", &[]).unwrap(), "Bye");
+    assert_eq!(
+        catalogs.translate("de", "welcome", &params).unwrap(),
+        "Welcome, Ana"
+    );
+    assert_eq!(catalogs.translate("pt", "missing", &[]), None);
+}
+
+#[test]
+fn test_builtin_catalogs_cover_every_domain_error() {
+    let errors = [
+        DomainError::CannotPublishEmptyPost,
+        DomainError::AlreadyPublished,
+        DomainError::EmptySlug,
+        DomainError::SlugTooLong {
+            max: 200,
+            actual: 250,
+        },
+        DomainError::InvalidSlugCharacters,
+        DomainError::SlugAlreadyExists,
+        DomainError::InvalidEmail,
+        DomainError::UndeliverableEmail,
+        DomainError::EmptyTitle,
+        DomainError::TitleTooLong {
+            max: 200,
+            actual: 201,
+        },
+        DomainError::EmptyBody,
+        DomainError::PasswordTooShort { min: 8 },
+        DomainError::PasswordTooWeak,
+        DomainError::PasswordTooCommon,
+        DomainError::PasswordTooSimilar,
+        DomainError::PasswordBreached,
+        DomainError::UserAlreadyExists,
+        DomainError::InvalidCredentials,
+        DomainError::InvalidToken,
+        DomainError::TooManyLoginAttempts { retry_after: 30 },
+        DomainError::InvalidScope,
+        DomainError::InvalidMfaCode,
+        DomainError::FieldRequired,
+        DomainError::InvalidChoice,
+        DomainError::InvalidDateTime,
+        DomainError::InvalidPagination,
+    ];
+    let catalogs = Catalogs::builtin();
+    assert_eq!(catalogs.locales(), vec!["en", "es", "pt-BR"]);
+
+    for error in &errors {
+        // The English catalog mirrors the `Display` messages
+        assert_eq!(error.localized(&catalogs, "en"), error.to_string());
+        for locale in ["es", "pt-BR"] {
+            let params = error.message_params();
+            let message = catalogs.translate(locale, error.message_key(), &params);
+            assert!(
+                message.is_some(),
+                "{} missing from {}",
+                error.message_key(),
+                locale
+            );
+            assert!(
+                !message.unwrap().contains('{'),
+                "{} in {}",
+                error.message_key(),
+                locale
+            );
+        }
+    }
+
+    let too_long = DomainError::TitleTooLong {
+        max: 200,
+        actual: 201,
+    };
+    assert_eq!(
+        too_long.localized(&catalogs, "pt-BR"),
+        "Título muito longo: 201 caracteres (máximo 200)"
+    );
+}
+
+#[test]
+fn test_negotiates_accept_language() {
+    let supported = ["en", "pt-BR", "es"];
+    assert_eq!(
+        negotiate("pt-BR,pt;q=0.9,en;q=0.8", &supported),
+        Some("pt-BR")
+    );
+    assert_eq!(negotiate("fr, es;q=0.5, en;q=0.7", &supported), Some("en"));
+    assert_eq!(negotiate("pt-PT", &supported), Some("pt-BR"));
+    assert_eq!(negotiate("es-MX", &supported), Some("es"));
+    assert_eq!(negotiate("de, *;q=0.1, es;q=0", &supported), None);
+}
@@ -0,0 +1,346 @@
+use crate::keys::KeySet;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ferreiro_domain::ports::driven::{
+    AccessClaims, AccessTokenVerifier, RefreshTokenRecord, RefreshTokenRepository, TokenError,
+    TokenIssuer, TokenPair,
+};
+use ferreiro_domain::values::UserId;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Fifteen minutes: short enough that a leaked token soon stops working
+const DEFAULT_ACCESS_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Thirty days, after which the user logs in again
+const DEFAULT_REFRESH_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Allowed clock skew between the issuer and verifiers
+const DEFAULT_LEEWAY: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TokenUse {
+    Access,
+    Refresh,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    jti: String,
+    iat: i64,
+    exp: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    aud: Option<String>,
+    /// Keeps a refresh token from being accepted as an access token
+    token_use: TokenUse,
+}
+
+impl Claims {
+    fn user_id(&self) -> Result<UserId, TokenError> {
+        Uuid::parse_str(&self.sub)
+            .map(UserId::from_uuid)
+            .map_err(|_| TokenError::Invalid)
+    }
+}
+
+fn timestamp(seconds: i64) -> Result<DateTime<Utc>, TokenError> {
+    DateTime::from_timestamp(seconds, 0).ok_or(TokenError::Invalid)
+}
+
+/// Verifies access tokens against a `KeySet`, with no storage
+///
+/// Give services that only accept tokens a verifier with
+/// `KeySet::verifying_keys`, so they can't mint tokens themselves.
+#[derive(Debug, Clone)]
+pub struct JwtVerifier {
+    keys: KeySet,
+    issuer: Option<String>,
+    audience: Option<String>,
+    leeway: Duration,
+}
+
+impl JwtVerifier {
+    pub fn new(keys: KeySet) -> Self {
+        Self {
+            keys,
+            issuer: None,
+            audience: None,
+            leeway: DEFAULT_LEEWAY,
+        }
+    }
+
+    /// Require the `iss` claim to be `issuer`
+    pub fn issuer(mut self, issuer: &str) -> Self {
+        self.issuer = Some(issuer.to_string());
+        self
+    }
+
+    /// Require the `aud` claim to be `audience`
+    pub fn audience(mut self, audience: &str) -> Self {
+        self.audience = Some(audience.to_string());
+        self
+    }
+
+    /// Allowed clock skew; thirty seconds by default
+    pub fn leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// The key comes from the `kid` header, and the token must use that
+    /// key's algorithm, so a token can't pick a weaker one
+    fn decode(&self, token: &str, token_use: TokenUse) -> Result<Claims, TokenError> {
+        let header = jsonwebtoken::decode_header(token).map_err(|_| TokenError::Invalid)?;
+        let key = header
+            .kid
+            .as_deref()
+            .and_then(|kid| self.keys.get(kid))
+            .ok_or(TokenError::Invalid)?;
+
+        let mut validation = Validation::new(key.algorithm());
+        validation.leeway = self.leeway.as_secs();
+        validation.set_required_spec_claims(&["exp", "sub"]);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+
+        let claims = jsonwebtoken::decode::<Claims>(token, key.decoding(), &validation)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => TokenError::Expired,
+                _ => TokenError::Invalid,
+            })?
+            .claims;
+        if claims.token_use != token_use {
+            return Err(TokenError::Invalid);
+        }
+        Ok(claims)
+    }
+
+    fn encode(
+        &self,
+        user_id: &UserId,
+        token_use: TokenUse,
+        ttl: Duration,
+    ) -> Result<(Claims, String), TokenError> {
+        let key = self
+            .keys
+            .signing_key()
+            .ok_or_else(|| TokenError::Key("no signing key".to_string()))?;
+        let encoding = key
+            .encoding()
+            .ok_or_else(|| TokenError::Key(format!("key `{}` can't sign", key.kid())))?;
+
+        let now = Utc::now().timestamp();
+        let claims = Claims {
+            sub: user_id.to_string(),
+            jti: Uuid::new_v4().simple().to_string(),
+            iat: now,
+            exp: now + ttl.as_secs() as i64,
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+            token_use,
+        };
+        let mut header = Header::new(key.algorithm());
+        header.kid = Some(key.kid().to_string());
+        let token = jsonwebtoken::encode(&header, &claims, encoding)
+            .map_err(|e| TokenError::Key(e.to_string()))?;
+        Ok((claims, token))
+    }
+}
+
+impl AccessTokenVerifier for JwtVerifier {
+    fn verify(&self, access_token: &str) -> Result<AccessClaims, TokenError> {
+        let claims = self.decode(access_token, TokenUse::Access)?;
+        Ok(AccessClaims {
+            user_id: claims.user_id()?,
+            issued_at: timestamp(claims.iat)?,
+            expires_at: timestamp(claims.exp)?,
+            token_id: claims.jti,
+        })
+    }
+}
+
+/// Issues short-lived access tokens and single-use refresh tokens
+///
+/// Access tokens are verified by signature alone. Refresh tokens are also
+/// recorded in a `RefreshTokenRepository`, so each can be used once: a
+/// refresh token presented twice means it was copied, and every token
+/// descended from the same login is revoked.
+///
+/// ```rust,ignore
+/// let issuer = JwtTokenIssuer::new(
+///     KeySet::new().signing(JwtKey::generate_ed25519("2025-06")?),
+///     Arc::new(refresh_tokens),
+/// )?
+/// .issuer("https://auth.example.com")
+/// .access_ttl(Duration::from_secs(300));
+/// let auth = AuthServiceImpl::new(users, events, hasher, cache, AccountTokens::new(&secret))
+///     .with_token_issuer(Arc::new(issuer));
+/// ```
+pub struct JwtTokenIssuer {
+    verifier: JwtVerifier,
+    refresh_tokens: Arc<dyn RefreshTokenRepository>,
+    access_ttl: Duration,
+    refresh_ttl: Duration,
+}
+
+impl JwtTokenIssuer {
+    /// `TokenError::Key` if `keys` has no signing key
+    pub fn new(
+        keys: KeySet,
+        refresh_tokens: Arc<dyn RefreshTokenRepository>,
+    ) -> Result<Self, TokenError> {
+        if keys.signing_key().is_none() {
+            return Err(TokenError::Key("no signing key".to_string()));
+        }
+        Ok(Self {
+            verifier: JwtVerifier::new(keys),
+            refresh_tokens,
+            access_ttl: DEFAULT_ACCESS_TTL,
+            refresh_ttl: DEFAULT_REFRESH_TTL,
+        })
+    }
+
+    /// Set the `iss` claim, and require it when verifying
+    pub fn issuer(mut self, issuer: &str) -> Self {
+        self.verifier = self.verifier.issuer(issuer);
+        self
+    }
+
+    /// Set the `aud` claim, and require it when verifying
+    pub fn audience(mut self, audience: &str) -> Self {
+        self.verifier = self.verifier.audience(audience);
+        self
+    }
+
+    pub fn leeway(mut self, leeway: Duration) -> Self {
+        self.verifier = self.verifier.leeway(leeway);
+        self
+    }
+
+    /// Fifteen minutes by default
+    pub fn access_ttl(mut self, ttl: Duration) -> Self {
+        self.access_ttl = ttl;
+        self
+    }
+
+    /// Thirty days by default
+    pub fn refresh_ttl(mut self, ttl: Duration) -> Self {
+        self.refresh_ttl = ttl;
+        self
+    }
+
+    /// A verifier with the same keys and claims, for this process's middleware
+    pub fn verifier(&self) -> JwtVerifier {
+        self.verifier.clone()
+    }
+
+    async fn issue_in_family(
+        &self,
+        user_id: &UserId,
+        family: String,
+    ) -> Result<TokenPair, TokenError> {
+        let (_, access_token) = self
+            .verifier
+            .encode(user_id, TokenUse::Access, self.access_ttl)?;
+        let (claims, refresh_token) =
+            self.verifier
+                .encode(user_id, TokenUse::Refresh, self.refresh_ttl)?;
+
+        self.refresh_tokens
+            .save(&RefreshTokenRecord {
+                id: claims.jti,
+                family,
+                user_id: user_id.clone(),
+                expires_at: timestamp(claims.exp)?,
+                used: false,
+                revoked: false,
+            })
+            .await
+            .map_err(|e| TokenError::Storage(e.to_string()))?;
+
+        Ok(TokenPair {
+            access_token,
+            refresh_token,
+            expires_in: self.access_ttl.as_secs(),
+        })
+    }
+
+    async fn record(&self, refresh_token: &str) -> Result<RefreshTokenRecord, TokenError> {
+        let claims = self.verifier.decode(refresh_token, TokenUse::Refresh)?;
+        self.refresh_tokens
+            .find(&claims.jti)
+            .await
+            .map_err(|e| TokenError::Storage(e.to_string()))?
+            .ok_or(TokenError::Invalid)
+    }
+}
+
+impl AccessTokenVerifier for JwtTokenIssuer {
+    fn verify(&self, access_token: &str) -> Result<AccessClaims, TokenError> {
+        self.verifier.verify(access_token)
+    }
+}
+
+#[async_trait]
+impl TokenIssuer for JwtTokenIssuer {
+    async fn issue(&self, user_id: &UserId) -> Result<TokenPair, TokenError> {
+        self.issue_in_family(user_id, Uuid::new_v4().simple().to_string())
+            .await
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<(UserId, TokenPair), TokenError> {
+        let record = self.record(refresh_token).await?;
+        if record.revoked {
+            return Err(TokenError::Revoked);
+        }
+
+        let first_use = self
+            .refresh_tokens
+            .mark_used(&record.id)
+            .await
+            .map_err(|e| TokenError::Storage(e.to_string()))?;
+        if !first_use {
+            self.refresh_tokens
+                .revoke_family(&record.family)
+                .await
+                .map_err(|e| TokenError::Storage(e.to_string()))?;
+            return Err(TokenError::Reused);
+        }
+
+        let tokens = self.issue_in_family(&record.user_id, record.family).await?;
+        Ok((record.user_id, tokens))
+    }
+
+    /// An expired refresh token can't be used anyway, so revoking it succeeds
+    async fn revoke(&self, refresh_token: &str) -> Result<(), TokenError> {
+        let record = match self.record(refresh_token).await {
+            Ok(record) => record,
+            Err(TokenError::Expired) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        self.refresh_tokens
+            .revoke_family(&record.family)
+            .await
+            .map_err(|e| TokenError::Storage(e.to_string()))
+    }
+
+    async fn revoke_all(&self, user_id: &UserId) -> Result<(), TokenError> {
+        self.refresh_tokens
+            .revoke_all_for_user(user_id)
+            .await
+            .map_err(|e| TokenError::Storage(e.to_string()))
+    }
+}
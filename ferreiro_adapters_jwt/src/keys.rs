@@ -0,0 +1,217 @@
+use ferreiro_domain::ports::driven::TokenError;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use rand::RngCore;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use rsa::pkcs1::{EncodeRsaPrivateKey, EncodeRsaPublicKey};
+use rsa::RsaPrivateKey;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A key to sign or verify tokens with, named by the `kid` header
+///
+/// Keys made from a private key sign and verify; keys made from a public
+/// key, or with `verifying_key`, only verify.
+#[derive(Clone)]
+pub struct JwtKey {
+    kid: String,
+    algorithm: Algorithm,
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+}
+
+impl JwtKey {
+    /// A shared secret; every service that verifies can also sign
+    pub fn hs256(kid: &str, secret: &[u8]) -> Self {
+        Self {
+            kid: kid.to_string(),
+            algorithm: Algorithm::HS256,
+            encoding: Some(EncodingKey::from_secret(secret)),
+            decoding: DecodingKey::from_secret(secret),
+        }
+    }
+
+    /// PEM-encoded RSA keys, PKCS#1 or PKCS#8
+    pub fn rs256_pem(kid: &str, private_pem: &[u8], public_pem: &[u8]) -> Result<Self, TokenError> {
+        Ok(Self {
+            encoding: Some(EncodingKey::from_rsa_pem(private_pem).map_err(key_error)?),
+            ..Self::rs256_public_pem(kid, public_pem)?
+        })
+    }
+
+    pub fn rs256_public_pem(kid: &str, public_pem: &[u8]) -> Result<Self, TokenError> {
+        Ok(Self {
+            kid: kid.to_string(),
+            algorithm: Algorithm::RS256,
+            encoding: None,
+            decoding: DecodingKey::from_rsa_pem(public_pem).map_err(key_error)?,
+        })
+    }
+
+    /// A PKCS#8 private key and SPKI public key, PEM-encoded
+    pub fn ed25519_pem(
+        kid: &str,
+        private_pem: &[u8],
+        public_pem: &[u8],
+    ) -> Result<Self, TokenError> {
+        Ok(Self {
+            encoding: Some(EncodingKey::from_ed_pem(private_pem).map_err(key_error)?),
+            ..Self::ed25519_public_pem(kid, public_pem)?
+        })
+    }
+
+    pub fn ed25519_public_pem(kid: &str, public_pem: &[u8]) -> Result<Self, TokenError> {
+        Ok(Self {
+            kid: kid.to_string(),
+            algorithm: Algorithm::EdDSA,
+            encoding: None,
+            decoding: DecodingKey::from_ed_pem(public_pem).map_err(key_error)?,
+        })
+    }
+
+    /// A random 256-bit secret
+    pub fn generate_hs256(kid: &str) -> Self {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        Self::hs256(kid, &secret)
+    }
+
+    /// A fresh 2048-bit RSA key pair
+    pub fn generate_rs256(kid: &str) -> Result<Self, TokenError> {
+        let private = RsaPrivateKey::new(&mut rand::thread_rng(), 2048).map_err(key_error)?;
+        let private_der = private.to_pkcs1_der().map_err(key_error)?;
+        let public_der = private.to_public_key().to_pkcs1_der().map_err(key_error)?;
+        Ok(Self {
+            kid: kid.to_string(),
+            algorithm: Algorithm::RS256,
+            encoding: Some(EncodingKey::from_rsa_der(private_der.as_bytes())),
+            decoding: DecodingKey::from_rsa_der(public_der.as_bytes()),
+        })
+    }
+
+    /// A fresh Ed25519 key pair
+    pub fn generate_ed25519(kid: &str) -> Result<Self, TokenError> {
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).map_err(key_error)?;
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).map_err(key_error)?;
+        Ok(Self {
+            kid: kid.to_string(),
+            algorithm: Algorithm::EdDSA,
+            encoding: Some(EncodingKey::from_ed_der(pkcs8.as_ref())),
+            decoding: DecodingKey::from_ed_der(pair.public_key().as_ref()),
+        })
+    }
+
+    /// This key without its private half, for services that only verify
+    ///
+    /// An HS256 key's secret verifies and signs alike, so it keeps both.
+    pub fn verifying_key(&self) -> Self {
+        Self {
+            encoding: self
+                .encoding
+                .clone()
+                .filter(|_| self.algorithm == Algorithm::HS256),
+            ..self.clone()
+        }
+    }
+
+    // Getters
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+    pub fn can_sign(&self) -> bool {
+        self.encoding.is_some()
+    }
+
+    pub(crate) fn encoding(&self) -> Option<&EncodingKey> {
+        self.encoding.as_ref()
+    }
+    pub(crate) fn decoding(&self) -> &DecodingKey {
+        &self.decoding
+    }
+}
+
+impl fmt::Debug for JwtKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtKey")
+            .field("kid", &self.kid)
+            .field("algorithm", &self.algorithm)
+            .field("can_sign", &self.can_sign())
+            .finish()
+    }
+}
+
+fn key_error(error: impl fmt::Display) -> TokenError {
+    TokenError::Key(error.to_string())
+}
+
+/// The keys tokens are signed and verified with
+///
+/// New tokens are signed with the signing key and carry its `kid`; any key
+/// in the set verifies the tokens carrying its `kid`. To rotate, sign with
+/// the new key and keep the old one for verifying until the tokens it
+/// signed have expired:
+///
+/// ```rust,ignore
+/// let keys = KeySet::new()
+///     .signing(JwtKey::rs256_pem("2025-06", &private, &public)?)
+///     .verifying(JwtKey::rs256_public_pem("2025-01", &old_public)?);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct KeySet {
+    signing: Option<String>,
+    keys: HashMap<String, JwtKey>,
+}
+
+impl KeySet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sign new tokens with `key`, replacing the previous signing key,
+    /// which is kept for verifying
+    ///
+    /// Panics if `key` can't sign.
+    pub fn signing(mut self, key: JwtKey) -> Self {
+        assert!(key.can_sign(), "key `{}` has no private key", key.kid());
+        self.signing = Some(key.kid().to_string());
+        self.verifying(key)
+    }
+
+    /// Accept tokens signed with `key`
+    pub fn verifying(mut self, key: JwtKey) -> Self {
+        self.keys.insert(key.kid().to_string(), key);
+        self
+    }
+
+    /// Stop accepting tokens signed with `kid`
+    pub fn without(mut self, kid: &str) -> Self {
+        self.keys.remove(kid);
+        if self.signing.as_deref() == Some(kid) {
+            self.signing = None;
+        }
+        self
+    }
+
+    /// Only the verifying halves, to hand to services that don't sign
+    pub fn verifying_keys(&self) -> Self {
+        Self {
+            signing: None,
+            keys: self
+                .keys
+                .iter()
+                .map(|(kid, key)| (kid.clone(), key.verifying_key()))
+                .collect(),
+        }
+    }
+
+    pub fn signing_key(&self) -> Option<&JwtKey> {
+        self.signing.as_ref().and_then(|kid| self.keys.get(kid))
+    }
+
+    pub fn get(&self, kid: &str) -> Option<&JwtKey> {
+        self.keys.get(kid)
+    }
+}
@@ -0,0 +1,13 @@
+//! JSON Web Tokens implementing `ferreiro_domain::ports::driven::TokenIssuer`
+//!
+//! `JwtTokenIssuer` signs access and refresh tokens with HS256, RS256 or
+//! EdDSA keys from a `KeySet`, which names each key by `kid` so keys can be
+//! rotated. `JwtVerifier` checks access tokens with no storage at all, for
+//! services that only accept them. `JwtKey::generate_*` make keys on the
+//! spot, for tests and development.
+
+pub mod issuer;
+pub mod keys;
+
+pub use issuer::{JwtTokenIssuer, JwtVerifier};
+pub use keys::{JwtKey, KeySet};
@@ -0,0 +1,130 @@
+use ferreiro_adapters_db::InMemoryRefreshTokenRepository;
+use ferreiro_adapters_jwt::{JwtKey, JwtTokenIssuer, JwtVerifier, KeySet};
+use ferreiro_domain::ports::driven::{AccessTokenVerifier, TokenError, TokenIssuer};
+use ferreiro_domain::values::UserId;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn issuer(keys: KeySet) -> JwtTokenIssuer {
+    JwtTokenIssuer::new(keys, Arc::new(InMemoryRefreshTokenRepository::new()))
+        .unwrap()
+        .issuer("https://auth.example.com")
+}
+
+#[tokio::test]
+async fn test_each_algorithm_round_trips_with_generated_keys() {
+    let keys = [
+        JwtKey::generate_hs256("hs"),
+        JwtKey::generate_rs256("rs").unwrap(),
+        JwtKey::generate_ed25519("ed").unwrap(),
+    ];
+    let user_id = UserId::generate();
+
+    for key in keys {
+        let issuer = issuer(KeySet::new().signing(key.clone()));
+        let tokens = issuer.issue(&user_id).await.unwrap();
+        assert_eq!(tokens.expires_in, 15 * 60);
+
+        let claims = issuer.verify(&tokens.access_token).unwrap();
+        assert_eq!(claims.user_id, user_id, "{:?}", key);
+
+        // A verifier holding only the public half accepts it too, but
+        // insists on the issuer
+        let verifier = JwtVerifier::new(KeySet::new().verifying(key.verifying_key()));
+        assert!(verifier.verify(&tokens.access_token).is_ok());
+        assert!(matches!(
+            verifier
+                .issuer("https://other.example.com")
+                .verify(&tokens.access_token),
+            Err(TokenError::Invalid)
+        ));
+
+        // Refresh tokens aren't access tokens, and signatures are checked
+        assert!(matches!(
+            issuer.verify(&tokens.refresh_token),
+            Err(TokenError::Invalid)
+        ));
+        let mut tampered = tokens.access_token.clone();
+        tampered.insert(tampered.len() - 4, 'x');
+        assert!(issuer.verify(&tampered).is_err());
+    }
+
+    let expiring = issuer(KeySet::new().signing(JwtKey::generate_hs256("hs")))
+        .access_ttl(Duration::ZERO)
+        .leeway(Duration::ZERO);
+    let tokens = expiring.issue(&user_id).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert!(matches!(
+        expiring.verify(&tokens.access_token),
+        Err(TokenError::Expired)
+    ));
+}
+
+#[tokio::test]
+async fn test_keys_rotate_by_kid() {
+    let old = JwtKey::generate_ed25519("2025-01").unwrap();
+    let new = JwtKey::generate_ed25519("2025-06").unwrap();
+    let user_id = UserId::generate();
+
+    let before = issuer(KeySet::new().signing(old.clone()));
+    let old_token = before.issue(&user_id).await.unwrap().access_token;
+
+    let after = issuer(KeySet::new().signing(old.clone()).signing(new.clone()));
+    let new_token = after.issue(&user_id).await.unwrap().access_token;
+    assert!(after.verify(&old_token).is_ok());
+    assert!(after.verify(&new_token).is_ok());
+    assert!(before.verify(&new_token).is_err());
+
+    let retired = JwtVerifier::new(
+        KeySet::new()
+            .verifying(old)
+            .verifying(new)
+            .without("2025-01")
+            .verifying_keys(),
+    );
+    assert!(retired.verify(&new_token).is_ok());
+    assert!(matches!(
+        retired.verify(&old_token),
+        Err(TokenError::Invalid)
+    ));
+
+    // Verifying keys alone can't issue anything
+    assert!(matches!(
+        JwtTokenIssuer::new(
+            KeySet::new().verifying_keys(),
+            Arc::new(InMemoryRefreshTokenRepository::new()),
+        ),
+        Err(TokenError::Key(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_reusing_a_refresh_token_revokes_its_family() {
+    let issuer = issuer(KeySet::new().signing(JwtKey::generate_hs256("hs")));
+    let user_id = UserId::generate();
+
+    let first = issuer.issue(&user_id).await.unwrap();
+    let other_login = issuer.issue(&user_id).await.unwrap();
+    let (refreshed_for, second) = issuer.refresh(&first.refresh_token).await.unwrap();
+    assert_eq!(refreshed_for, user_id);
+    assert!(issuer.verify(&second.access_token).is_ok());
+
+    // The stolen first token comes back: refused, and the thief's victim
+    // loses the tokens refreshed from it too
+    assert!(matches!(
+        issuer.refresh(&first.refresh_token).await,
+        Err(TokenError::Reused)
+    ));
+    assert!(matches!(
+        issuer.refresh(&second.refresh_token).await,
+        Err(TokenError::Revoked)
+    ));
+
+    // Other logins are unaffected until revoked themselves
+    let (_, third) = issuer.refresh(&other_login.refresh_token).await.unwrap();
+    issuer.revoke(&third.refresh_token).await.unwrap();
+    assert!(matches!(
+        issuer.refresh(&third.refresh_token).await,
+        Err(TokenError::Revoked)
+    ));
+}
@@ -0,0 +1,11 @@
+//! OAuth2 and OpenID Connect providers implementing
+//! `ferreiro_domain::ports::driven::OAuthProvider`
+//!
+//! `OidcProvider` talks to any provider given its endpoints, with presets
+//! for Google and GitHub. Hand providers to
+//! `ferreiro_application::SocialAuthServiceImpl`, which runs the login flow.
+
+mod id_token;
+pub mod provider;
+
+pub use provider::OidcProvider;
@@ -0,0 +1,364 @@
+use crate::id_token::IdTokenClaims;
+use async_trait::async_trait;
+use chrono::{Duration as ChronoDuration, Utc};
+use ferreiro_domain::models::ProviderTokens;
+use ferreiro_domain::ports::driven::{
+    AuthorizationRequest, ExternalProfile, OAuthError, OAuthProvider,
+};
+use reqwest::{Client, Response, Url};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::net::IpAddr;
+use std::time::Duration;
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    id_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<i64>,
+}
+
+/// RFC 6749's error response
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// `email_verified` is a boolean, but some providers send `"true"`
+pub(crate) fn lenient_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Bool(value) => value,
+        Value::String(value) => value == "true",
+        _ => false,
+    })
+}
+
+/// Parses one of the provider's endpoints, which must use https: the ID
+/// token is trusted because it comes straight from the token endpoint over
+/// TLS (OpenID Connect Core 3.1.3.7), and the client secret and access
+/// tokens go to them. Loopback addresses, for development, may use http.
+fn endpoint(url: &str, what: &str) -> Url {
+    let url = Url::parse(url).unwrap_or_else(|_| panic!("the {} is not a valid URL", what));
+    let loopback = url.host_str().is_some_and(|host| {
+        host == "localhost"
+            || host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    });
+    assert!(
+        url.scheme() == "https" || (url.scheme() == "http" && loopback),
+        "the {} must use https",
+        what
+    );
+    url
+}
+
+/// An OAuth2 provider, or an OpenID Connect one when `issuer` is set
+///
+/// With an issuer, the `openid` scope is requested and the ID token from
+/// the token endpoint is checked against the issuer, client id and nonce.
+/// The profile comes from the ID token's claims, completed by the
+/// userinfo endpoint when there is one. Plain OAuth2 providers need a
+/// userinfo endpoint, whose `sub` (or `id`, as GitHub's) is the subject.
+///
+/// ```rust,ignore
+/// let keycloak = OidcProvider::new(
+///     "keycloak",
+///     &client_id,
+///     &client_secret,
+///     "https://sso.example.com/realms/staff/protocol/openid-connect/auth",
+///     "https://sso.example.com/realms/staff/protocol/openid-connect/token",
+/// )
+/// .issuer("https://sso.example.com/realms/staff")
+/// .userinfo_endpoint("https://sso.example.com/realms/staff/protocol/openid-connect/userinfo");
+/// ```
+#[derive(Debug, Clone)]
+pub struct OidcProvider {
+    name: String,
+    client_id: String,
+    client_secret: String,
+    authorization_endpoint: Url,
+    token_endpoint: Url,
+    userinfo_endpoint: Option<Url>,
+    issuer: Option<String>,
+    scopes: Vec<String>,
+    client: Client,
+}
+
+impl OidcProvider {
+    /// Panics if an endpoint isn't a valid https URL
+    pub fn new(
+        name: &str,
+        client_id: &str,
+        client_secret: &str,
+        authorization_endpoint: &str,
+        token_endpoint: &str,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            authorization_endpoint: endpoint(authorization_endpoint, "authorization endpoint"),
+            token_endpoint: endpoint(token_endpoint, "token endpoint"),
+            userinfo_endpoint: None,
+            issuer: None,
+            scopes: vec!["email".to_string(), "profile".to_string()],
+            client: Self::client(Duration::from_secs(30)),
+        }
+    }
+
+    /// Google, as an OpenID Connect provider
+    pub fn google(client_id: &str, client_secret: &str) -> Self {
+        Self::new(
+            "google",
+            client_id,
+            client_secret,
+            "https://accounts.google.com/o/oauth2/v2/auth",
+            "https://oauth2.googleapis.com/token",
+        )
+        .issuer("https://accounts.google.com")
+    }
+
+    /// GitHub, which speaks plain OAuth2
+    ///
+    /// GitHub doesn't say whether the email is verified, so users signing
+    /// up with it verify their email as with a password, and users who
+    /// hide their email can't sign up with it.
+    pub fn github(client_id: &str, client_secret: &str) -> Self {
+        Self::new(
+            "github",
+            client_id,
+            client_secret,
+            "https://github.com/login/oauth/authorize",
+            "https://github.com/login/oauth/access_token",
+        )
+        .userinfo_endpoint("https://api.github.com/user")
+        .scopes(&["read:user", "user:email"])
+    }
+
+    /// Makes this an OpenID Connect provider, whose ID tokens must come
+    /// from `issuer`
+    pub fn issuer(mut self, issuer: &str) -> Self {
+        self.issuer = Some(issuer.to_string());
+        self
+    }
+
+    /// Panics if `url` isn't a valid https URL
+    pub fn userinfo_endpoint(mut self, url: &str) -> Self {
+        self.userinfo_endpoint = Some(endpoint(url, "userinfo endpoint"));
+        self
+    }
+
+    /// `email` and `profile` by default; `openid` is added for OpenID
+    /// Connect providers
+    pub fn scopes(mut self, scopes: &[&str]) -> Self {
+        self.scopes = scopes.iter().map(|scope| scope.to_string()).collect();
+        self
+    }
+
+    /// Limit for each request to the provider; 30 seconds by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.client = Self::client(timeout);
+        self
+    }
+
+    fn client(timeout: Duration) -> Client {
+        Client::builder()
+            .timeout(timeout)
+            // GitHub's API refuses requests without one
+            .user_agent("ferreiro")
+            .build()
+            .expect("the HTTP client's TLS backend failed to initialize")
+    }
+
+    fn scope(&self) -> String {
+        let openid = self.issuer.is_some() && !self.scopes.iter().any(|s| s == "openid");
+        openid
+            .then(|| "openid".to_string())
+            .into_iter()
+            .chain(self.scopes.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The body of a successful response, or the provider's reason for
+    /// refusing
+    async fn json<T: for<'de> Deserialize<'de>>(response: Response) -> Result<T, OAuthError> {
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| OAuthError::Connection(e.to_string()))?;
+        if status.is_client_error() {
+            let reason = serde_json::from_slice::<ErrorResponse>(&body)
+                .map(|e| match e.error_description {
+                    Some(description) => format!("{}: {}", e.error, description),
+                    None => e.error,
+                })
+                .unwrap_or_else(|_| status.to_string());
+            return Err(OAuthError::Rejected(reason));
+        }
+        if !status.is_success() {
+            return Err(OAuthError::InvalidResponse(status.to_string()));
+        }
+        serde_json::from_slice(&body).map_err(|e| OAuthError::InvalidResponse(e.to_string()))
+    }
+
+    async fn userinfo(&self, url: &Url, access_token: &str) -> Result<Value, OAuthError> {
+        let response = self
+            .client
+            .get(url.clone())
+            .bearer_auth(access_token)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| OAuthError::Connection(e.to_string()))?;
+        Self::json(response).await
+    }
+}
+
+#[async_trait]
+impl OAuthProvider for OidcProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn authorization_url(&self, request: &AuthorizationRequest) -> String {
+        let mut params = vec![
+            ("response_type", "code".to_string()),
+            ("client_id", self.client_id.clone()),
+            ("redirect_uri", request.redirect_uri.clone()),
+            ("scope", self.scope()),
+            ("state", request.state.clone()),
+            ("code_challenge", request.code_challenge.clone()),
+            ("code_challenge_method", "S256".to_string()),
+        ];
+        if self.issuer.is_some() {
+            params.push(("nonce", request.nonce.clone()));
+        }
+        let mut url = self.authorization_endpoint.clone();
+        url.query_pairs_mut().extend_pairs(&params);
+        url.to_string()
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> Result<ProviderTokens, OAuthError> {
+        let response = self
+            .client
+            .post(self.token_endpoint.clone())
+            .header("Accept", "application/json")
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", redirect_uri),
+                ("client_id", &self.client_id),
+                ("client_secret", &self.client_secret),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await
+            .map_err(|e| OAuthError::Connection(e.to_string()))?;
+        let tokens: TokenResponse = Self::json(response).await?;
+
+        Ok(ProviderTokens {
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+            id_token: tokens.id_token,
+            expires_at: tokens
+                .expires_in
+                .map(|seconds| Utc::now() + ChronoDuration::seconds(seconds)),
+        })
+    }
+
+    async fn profile(
+        &self,
+        tokens: &ProviderTokens,
+        nonce: &str,
+    ) -> Result<ExternalProfile, OAuthError> {
+        let claims = match (&self.issuer, &tokens.id_token) {
+            (Some(issuer), Some(id_token)) => Some(IdTokenClaims::from_token_response(
+                id_token,
+                Some(issuer),
+                &self.client_id,
+                nonce,
+            )?),
+            (Some(_), None) => {
+                return Err(OAuthError::InvalidResponse(
+                    "no ID token in the token response".to_string(),
+                ))
+            }
+            (None, _) => None,
+        };
+        let userinfo = match &self.userinfo_endpoint {
+            Some(url) => Some(self.userinfo(url, &tokens.access_token).await?),
+            None => None,
+        };
+
+        let mut profile = match claims {
+            Some(claims) => ExternalProfile {
+                subject: claims.sub,
+                email: claims.email,
+                email_verified: claims.email_verified,
+                name: claims.name,
+            },
+            None => ExternalProfile {
+                subject: String::new(),
+                email: None,
+                email_verified: false,
+                name: None,
+            },
+        };
+        if let Some(userinfo) = userinfo {
+            let subject = match userinfo.get("sub").or_else(|| userinfo.get("id")) {
+                Some(Value::String(subject)) => subject.clone(),
+                Some(Value::Number(subject)) => subject.to_string(),
+                _ => {
+                    return Err(OAuthError::InvalidResponse(
+                        "no subject in the userinfo response".to_string(),
+                    ))
+                }
+            };
+            // Userinfo must be about the ID token's user (OpenID Connect
+            // Core 5.3.2)
+            if !profile.subject.is_empty() && profile.subject != subject {
+                return Err(OAuthError::InvalidIdToken(
+                    "userinfo is for another subject".to_string(),
+                ));
+            }
+            profile.subject = subject;
+            let text = |key: &str| userinfo.get(key).and_then(Value::as_str).map(String::from);
+            if let Some(email) = text("email") {
+                match userinfo.get("email_verified") {
+                    Some(verified) => {
+                        profile.email_verified = lenient_bool(verified.clone()).unwrap_or(false)
+                    }
+                    None if profile.email.as_deref() != Some(email.as_str()) => {
+                        profile.email_verified = false
+                    }
+                    None => {}
+                }
+                profile.email = Some(email);
+            }
+            profile.name = text("name").or(profile.name);
+        }
+
+        if profile.subject.is_empty() {
+            return Err(OAuthError::InvalidResponse(
+                "the provider has neither an issuer nor a userinfo endpoint".to_string(),
+            ));
+        }
+        Ok(profile)
+    }
+}
@@ -0,0 +1,363 @@
+use axum::extract::{Form, Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64, Engine};
+use ferreiro_adapters_cache::InMemoryCache;
+use ferreiro_adapters_db::testing::PlainTextHasher;
+use ferreiro_adapters_db::{
+    InMemoryEventPublisher, InMemoryExternalIdentityRepository, InMemoryUserRepository,
+};
+use ferreiro_adapters_oauth::OidcProvider;
+use ferreiro_application::{AccountTokens, AuthServiceImpl, SocialAuthServiceImpl};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::ports::driven::UserRepository;
+use ferreiro_domain::ports::driving::{
+    AuthService, AuthorizationCallback, LoginCommand, ServiceError, SocialAuthService,
+};
+use ferreiro_domain::values::Email;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+const CLIENT_ID: &str = "ferreiro-test";
+const CLIENT_SECRET: &str = "s3cret";
+const CALLBACK: &str = "https://app.example.com/auth/mock/callback";
+
+/// A code the mock provider has handed out, and what it was issued for
+struct Grant {
+    subject: String,
+    challenge: String,
+    nonce: String,
+    redirect_uri: String,
+}
+
+/// A stand-in OpenID Connect provider: whoever is `signed_in` approves
+/// every authorization request
+#[derive(Clone, Default)]
+struct MockProvider {
+    issuer: Arc<Mutex<String>>,
+    signed_in: Arc<Mutex<String>>,
+    grants: Arc<Mutex<HashMap<String, Grant>>>,
+    issued: Arc<AtomicUsize>,
+}
+
+impl MockProvider {
+    fn email(subject: &str) -> String {
+        format!("{}@example.com", subject)
+    }
+}
+
+async fn authorize(
+    State(mock): State<MockProvider>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    assert_eq!(params["client_id"], CLIENT_ID);
+    assert_eq!(params["code_challenge_method"], "S256");
+    assert!(params["scope"].split(' ').any(|s| s == "openid"));
+
+    let code = format!("code-{}", mock.issued.fetch_add(1, Ordering::SeqCst));
+    mock.grants.lock().unwrap().insert(
+        code.clone(),
+        Grant {
+            subject: mock.signed_in.lock().unwrap().clone(),
+            challenge: params["code_challenge"].clone(),
+            nonce: params["nonce"].clone(),
+            redirect_uri: params["redirect_uri"].clone(),
+        },
+    );
+    Redirect::to(&format!(
+        "{}?code={}&state={}",
+        params["redirect_uri"], code, params["state"]
+    ))
+    .into_response()
+}
+
+async fn token(
+    State(mock): State<MockProvider>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Response {
+    let invalid_grant = (
+        StatusCode::BAD_REQUEST,
+        Json(json!({"error": "invalid_grant"})),
+    );
+    assert_eq!(form["client_secret"], CLIENT_SECRET);
+    // Codes work once
+    let Some(grant) = mock.grants.lock().unwrap().remove(&form["code"]) else {
+        return invalid_grant.into_response();
+    };
+    let challenge = BASE64.encode(Sha256::digest(form["code_verifier"].as_bytes()));
+    if challenge != grant.challenge || form["redirect_uri"] != grant.redirect_uri {
+        return invalid_grant.into_response();
+    }
+
+    let claims = json!({
+        "iss": mock.issuer.lock().unwrap().clone(),
+        "aud": CLIENT_ID,
+        "exp": chrono::Utc::now().timestamp() + 300,
+        "nonce": grant.nonce,
+        "sub": grant.subject,
+        "email": MockProvider::email(&grant.subject),
+        "email_verified": true,
+    });
+    let id_token = format!(
+        "{}.{}.unsigned",
+        BASE64.encode(br#"{"alg":"RS256"}"#),
+        BASE64.encode(claims.to_string())
+    );
+    Json(json!({
+        "access_token": format!("at-{}", grant.subject),
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "refresh_token": format!("rt-{}", grant.subject),
+        "id_token": id_token,
+    }))
+    .into_response()
+}
+
+async fn userinfo(headers: HeaderMap) -> Response {
+    let subject = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer at-"))
+        .map(String::from);
+    match subject {
+        Some(subject) => Json(json!({
+            "sub": subject,
+            "email": MockProvider::email(&subject),
+            "name": format!("User {}", subject),
+        }))
+        .into_response(),
+        None => StatusCode::UNAUTHORIZED.into_response(),
+    }
+}
+
+struct Fixture {
+    mock: MockProvider,
+    users: Arc<InMemoryUserRepository>,
+    auth: Arc<dyn AuthService>,
+    social: SocialAuthServiceImpl<
+        InMemoryUserRepository,
+        InMemoryExternalIdentityRepository,
+        InMemoryEventPublisher,
+    >,
+    browser: reqwest::Client,
+}
+
+impl Fixture {
+    async fn start() -> Self {
+        let mock = MockProvider::default();
+        let app = Router::new()
+            .route("/authorize", get(authorize))
+            .route("/token", post(token))
+            .route("/userinfo", get(userinfo))
+            .with_state(mock.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        *mock.issuer.lock().unwrap() = base.clone();
+
+        let provider = OidcProvider::new(
+            "mock",
+            CLIENT_ID,
+            CLIENT_SECRET,
+            &format!("{}/authorize", base),
+            &format!("{}/token", base),
+        )
+        .issuer(&base)
+        .userinfo_endpoint(&format!("{}/userinfo", base));
+
+        let users = Arc::new(InMemoryUserRepository::new());
+        let cache = Arc::new(InMemoryCache::new());
+        let auth = Arc::new(AuthServiceImpl::new(
+            users.clone(),
+            Arc::new(InMemoryEventPublisher::new()),
+            Arc::new(PlainTextHasher),
+            cache.clone(),
+            AccountTokens::new(b"test-secret"),
+        ));
+        let auth_service: Arc<dyn AuthService> = auth.clone();
+        let social = SocialAuthServiceImpl::new(
+            users.clone(),
+            Arc::new(InMemoryExternalIdentityRepository::new()),
+            auth.clone(),
+            cache,
+        )
+        .with_provider(Arc::new(provider));
+
+        Self {
+            mock,
+            users,
+            auth: auth_service,
+            social,
+            browser: reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .unwrap(),
+        }
+    }
+
+    /// Follows the authorization URL as the browser would, returning the
+    /// `code` and `state` the provider redirects back with
+    async fn approve(&self, url: &str) -> (String, String) {
+        let response = self.browser.get(url).send().await.unwrap();
+        let location = response.headers()[header::LOCATION].to_str().unwrap();
+        let callback = reqwest::Url::parse(location).unwrap();
+        assert!(location.starts_with(CALLBACK));
+        let params: HashMap<_, _> = callback.query_pairs().into_owned().collect();
+        (params["code"].clone(), params["state"].clone())
+    }
+}
+
+fn callback(state: &str, code: &str, binding: &str) -> AuthorizationCallback {
+    AuthorizationCallback {
+        state: state.to_string(),
+        code: code.to_string(),
+        binding: binding.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_first_login_registers_the_user_and_later_ones_find_them() {
+    let fixture = Fixture::start().await;
+    *fixture.mock.signed_in.lock().unwrap() = "ana".to_string();
+
+    let redirect = fixture.social.begin("mock", CALLBACK).await.unwrap();
+    let (code, state) = fixture.approve(&redirect.url).await;
+    assert_eq!(state, redirect.state);
+    let first = fixture
+        .social
+        .complete(callback(&state, &code, &redirect.binding))
+        .await
+        .unwrap();
+    let session = first.session_token().unwrap().to_string();
+
+    let user = first.user;
+    assert_eq!(user.email().as_str(), "ana@example.com");
+    assert_eq!(user.name(), "User ana");
+    assert!(user.is_email_verified());
+    assert!(fixture
+        .auth
+        .get_user_by_session(&session)
+        .await
+        .unwrap()
+        .is_some());
+
+    // No password logs into an account made this way
+    let stored = fixture
+        .users
+        .find_by_email(&Email::new("ana@example.com").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!stored.has_usable_password());
+    let password_login = fixture
+        .auth
+        .login(LoginCommand {
+            identifier: "ana@example.com".to_string(),
+            password: String::new(),
+            ip_address: None,
+        })
+        .await;
+    assert!(matches!(
+        password_login,
+        Err(ServiceError::Domain(DomainError::InvalidCredentials))
+    ));
+
+    let redirect = fixture.social.begin("mock", CALLBACK).await.unwrap();
+    let (code, state) = fixture.approve(&redirect.url).await;
+    let second = fixture
+        .social
+        .complete(callback(&state, &code, &redirect.binding))
+        .await
+        .unwrap();
+    assert_eq!(second.user.id(), user.id());
+
+    let identities = fixture.social.identities(user.id()).await.unwrap();
+    assert_eq!(identities.len(), 1);
+    assert_eq!(identities[0].subject(), "ana");
+    assert_eq!(identities[0].tokens().access_token, "at-ana");
+    assert_eq!(
+        identities[0].tokens().refresh_token.as_deref(),
+        Some("rt-ana")
+    );
+}
+
+#[tokio::test]
+async fn test_codes_are_bound_to_their_flow() {
+    let fixture = Fixture::start().await;
+    *fixture.mock.signed_in.lock().unwrap() = "bia".to_string();
+
+    let victim = fixture.social.begin("mock", CALLBACK).await.unwrap();
+    let (stolen_code, victim_state) = fixture.approve(&victim.url).await;
+
+    // The attacker's own flow has another PKCE verifier, so the provider
+    // refuses the stolen code
+    let attacker = fixture.social.begin("mock", CALLBACK).await.unwrap();
+    assert!(matches!(
+        fixture
+            .social
+            .complete(callback(&attacker.state, &stolen_code, &attacker.binding))
+            .await,
+        Err(ServiceError::Unauthorized)
+    ));
+
+    // Each state works once, and only states we issued work at all
+    let (code, _) = fixture.approve(&victim.url).await;
+    fixture
+        .social
+        .complete(callback(&victim_state, &code, &victim.binding))
+        .await
+        .unwrap();
+    for state in [victim_state.as_str(), "forged"] {
+        assert!(matches!(
+            fixture
+                .social
+                .complete(callback(state, &code, &victim.binding))
+                .await,
+            Err(ServiceError::Domain(DomainError::InvalidToken))
+        ));
+    }
+
+    assert!(matches!(
+        fixture.social.begin("unknown", CALLBACK).await,
+        Err(ServiceError::NotFound)
+    ));
+}
+
+#[tokio::test]
+async fn test_id_tokens_from_another_issuer_are_refused() {
+    let fixture = Fixture::start().await;
+    *fixture.mock.signed_in.lock().unwrap() = "cid".to_string();
+    *fixture.mock.issuer.lock().unwrap() = "https://evil.example.com".to_string();
+
+    let redirect = fixture.social.begin("mock", CALLBACK).await.unwrap();
+    let (code, state) = fixture.approve(&redirect.url).await;
+    assert!(matches!(
+        fixture
+            .social
+            .complete(callback(&state, &code, &redirect.binding))
+            .await,
+        Err(ServiceError::Unauthorized)
+    ));
+    assert!(!fixture
+        .users
+        .exists_by_email(&Email::new("cid@example.com").unwrap())
+        .await
+        .unwrap());
+}
+
+#[test]
+#[should_panic(expected = "the token endpoint must use https")]
+fn test_endpoints_must_use_https() {
+    OidcProvider::new(
+        "plain",
+        CLIENT_ID,
+        CLIENT_SECRET,
+        "https://sso.example.com/authorize",
+        "http://sso.example.com/token",
+    );
+}
@@ -69,3 +69,6 @@ pub enum SessionError {
 
 pub mod cookie;
 pub mod memory;
+pub mod messages;
+
+pub use messages::{Level, Message, Messages};
@@ -0,0 +1,97 @@
+use crate::SessionData;
+use serde::{Deserialize, Serialize};
+
+const MESSAGES_KEY: &str = "_messages";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Success => "success",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Message {
+    pub level: Level,
+    pub text: String,
+}
+
+/// Flash messages stored in the session until they are read
+///
+/// Messages live under a reserved session key, so they travel with whatever
+/// `SessionStore` is in use — including `CookieSessionStore`.
+pub struct Messages<'a> {
+    session: &'a mut SessionData,
+}
+
+impl<'a> Messages<'a> {
+    pub fn add(&mut self, level: Level, text: impl Into<String>) {
+        let mut pending = self.peek();
+        pending.push(Message {
+            level,
+            text: text.into(),
+        });
+        self.session.set(MESSAGES_KEY, pending);
+    }
+
+    pub fn debug(&mut self, text: impl Into<String>) {
+        self.add(Level::Debug, text);
+    }
+
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.add(Level::Info, text);
+    }
+
+    pub fn success(&mut self, text: impl Into<String>) {
+        self.add(Level::Success, text);
+    }
+
+    pub fn warning(&mut self, text: impl Into<String>) {
+        self.add(Level::Warning, text);
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.add(Level::Error, text);
+    }
+
+    /// Pending messages, left in the session
+    pub fn peek(&self) -> Vec<Message> {
+        self.session.get(MESSAGES_KEY).unwrap_or_default()
+    }
+
+    /// Pending messages, removed from the session
+    pub fn take(&mut self) -> Vec<Message> {
+        let pending = self.peek();
+        if self.session.data.contains_key(MESSAGES_KEY) {
+            self.session.remove(MESSAGES_KEY);
+        }
+        pending
+    }
+}
+
+impl SessionData {
+    pub fn messages(&mut self) -> Messages<'_> {
+        Messages { session: self }
+    }
+}
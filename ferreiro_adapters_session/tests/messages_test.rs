@@ -0,0 +1,36 @@
+use ferreiro_adapters_session::cookie::CookieSessionStore;
+use ferreiro_adapters_session::{Level, SessionData, SessionStore};
+use std::time::Duration;
+
+#[test]
+fn test_messages_are_consumed_on_read() {
+    let mut session = SessionData::new();
+    session.messages().success("Post published!");
+    session.messages().warning("Slug was changed");
+
+    let peeked = session.messages().peek();
+    assert_eq!(peeked.len(), 2);
+
+    let taken = session.messages().take();
+    assert_eq!(taken.len(), 2);
+    assert_eq!(taken[0].level, Level::Success);
+    assert_eq!(taken[0].text, "Post published!");
+    assert_eq!(taken[1].level, Level::Warning);
+
+    assert!(session.messages().take().is_empty());
+}
+
+#[tokio::test]
+async fn test_messages_survive_cookie_store() {
+    let store = CookieSessionStore::new(b"secret", Duration::from_secs(3600));
+
+    let mut session = SessionData::new();
+    session.messages().error("Something went wrong");
+    let id = store.save(None, &session).await.unwrap();
+
+    let mut loaded = store.load(&id).await.unwrap().unwrap();
+    let taken = loaded.messages().take();
+
+    assert_eq!(taken.len(), 1);
+    assert_eq!(taken[0].level, Level::Error);
+}
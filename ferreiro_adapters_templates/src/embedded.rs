@@ -0,0 +1,172 @@
+use include_dir::{Dir, DirEntry};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+pub use include_dir;
+
+static BUILTIN: Dir<'static> = include_dir::include_dir!("$CARGO_MANIFEST_DIR/templates");
+
+/// Embeds a template directory into the binary at compile time
+///
+/// ```rust,ignore
+/// let templates = embed_templates!("$CARGO_MANIFEST_DIR/templates");
+/// let engine = TeraEngine::from_embedded(templates)?;
+/// ```
+#[macro_export]
+macro_rules! embed_templates {
+    ($path:tt) => {{
+        use $crate::embedded::include_dir;
+        static DIR: include_dir::Dir<'static> = include_dir::include_dir!($path);
+        $crate::embedded::EmbeddedTemplates::new(&DIR)
+    }};
+}
+
+/// Templates compiled into the binary, optionally overridden from disk
+#[derive(Clone)]
+pub struct EmbeddedTemplates {
+    dir: &'static Dir<'static>,
+    disk: Option<PathBuf>,
+}
+
+impl EmbeddedTemplates {
+    pub fn new(dir: &'static Dir<'static>) -> Self {
+        Self { dir, disk: None }
+    }
+
+    /// Development mode: files found in `template_dir` win over the embedded
+    /// copies, so templates can be edited without rebuilding
+    ///
+    /// Engines check the directory before every render and reload when a
+    /// file in it was added, changed or removed.
+    pub fn prefer_disk(mut self, template_dir: impl Into<PathBuf>) -> Self {
+        self.disk = Some(template_dir.into());
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        if let Some(disk) = &self.disk {
+            if let Ok(source) = std::fs::read_to_string(disk.join(name)) {
+                return Some(source);
+            }
+        }
+        self.dir
+            .get_file(name)
+            .and_then(|file| file.contents_utf8())
+            .map(str::to_string)
+    }
+
+    /// Every template as `(name, source)`, names using `/` separators
+    pub fn templates(&self) -> Vec<(String, String)> {
+        let mut templates = BTreeMap::new();
+        collect_embedded(self.dir, &mut templates);
+        if let Some(disk) = &self.disk {
+            collect_disk(disk, disk, &mut templates);
+        }
+        templates.into_iter().collect()
+    }
+}
+
+/// Notices changes to the `prefer_disk` directory, for engines to reload on
+pub(crate) struct DiskChanges {
+    dir: PathBuf,
+    seen: Mutex<DiskState>,
+}
+
+/// Each file's modification time and size
+type DiskState = BTreeMap<PathBuf, (Option<SystemTime>, u64)>;
+
+impl DiskChanges {
+    /// `None` unless `templates` prefer the disk
+    pub(crate) fn watch(templates: &EmbeddedTemplates) -> Option<Self> {
+        let dir = templates.disk.clone()?;
+        let seen = Mutex::new(disk_state(&dir));
+        Some(Self { dir, seen })
+    }
+
+    /// Whether anything changed since the last call
+    pub(crate) fn changed(&self) -> bool {
+        let current = disk_state(&self.dir);
+        let mut seen = self.seen.lock().unwrap();
+        if *seen == current {
+            return false;
+        }
+        *seen = current;
+        true
+    }
+}
+
+fn disk_state(dir: &Path) -> DiskState {
+    let mut state = DiskState::new();
+    collect_state(dir, &mut state);
+    state
+}
+
+fn collect_state(dir: &Path, state: &mut DiskState) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_state(&path, state);
+        } else if let Ok(metadata) = entry.metadata() {
+            state.insert(path, (metadata.modified().ok(), metadata.len()));
+        }
+    }
+}
+
+/// Templates shipped with the framework (base layout, error pages, form
+/// fields, password reset and email verification pages and emails), all
+/// named under `ferreiro/`
+///
+/// Both engines fall back to these, and an app overrides one by providing a
+/// template with the same name.
+pub fn builtin_templates() -> EmbeddedTemplates {
+    EmbeddedTemplates::new(&BUILTIN)
+}
+
+/// Every file under `template_dir`, named relative to it; empty if the
+/// directory doesn't exist
+pub(crate) fn disk_templates(template_dir: &Path) -> Vec<(String, String)> {
+    let mut templates = BTreeMap::new();
+    collect_disk(template_dir, template_dir, &mut templates);
+    templates.into_iter().collect()
+}
+
+fn collect_embedded(dir: &Dir<'static>, templates: &mut BTreeMap<String, String>) {
+    for entry in dir.entries() {
+        match entry {
+            DirEntry::Dir(dir) => collect_embedded(dir, templates),
+            DirEntry::File(file) => {
+                if let Some(source) = file.contents_utf8() {
+                    templates.insert(template_name(file.path()), source.to_string());
+                }
+            }
+        }
+    }
+}
+
+fn collect_disk(root: &Path, dir: &Path, templates: &mut BTreeMap<String, String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_disk(root, &path, templates);
+        } else if let (Ok(relative), Ok(source)) =
+            (path.strip_prefix(root), std::fs::read_to_string(&path))
+        {
+            templates.insert(template_name(relative), source);
+        }
+    }
+}
+
+fn template_name(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
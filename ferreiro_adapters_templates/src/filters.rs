@@ -0,0 +1,370 @@
+//! Built-in filters shared by every engine
+//!
+//! The filters are plain functions here; `tera_adapter` and
+//! `minijinja_adapter` only translate arguments, so a template renders the
+//! same whichever engine feature is enabled.
+//!
+//! `date`, `slugify`, `pluralize` and `filesizeformat` replace Tera's
+//! filters of the same names on purpose, since theirs take other arguments
+//! and would render differently. Tera's `now` function is left alone, so
+//! ours is `utcnow`.
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use ferreiro_domain::values::Slug;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use std::fmt::Write;
+
+pub const DEFAULT_DATE_FORMAT: &str = "%b %-d, %Y";
+
+/// Accepts RFC 3339 strings (how `DateTime<Utc>` serializes) and Unix timestamps
+pub fn parse_datetime(value: &serde_json::Value) -> Option<DateTime<Utc>> {
+    match value {
+        serde_json::Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|dt| dt.with_timezone(&Utc)),
+        serde_json::Value::Number(n) => DateTime::from_timestamp(n.as_i64()?, 0),
+        _ => None,
+    }
+}
+
+/// `None` if `format` is not a valid strftime format
+pub fn date(value: &DateTime<Utc>, format: &str) -> Option<String> {
+    let mut out = String::new();
+    write!(out, "{}", value.format(format)).ok()?;
+    Some(out)
+}
+
+/// An IANA zone name such as `America/Sao_Paulo`
+pub fn parse_timezone(name: &str) -> Option<Tz> {
+    name.parse().ok()
+}
+
+/// Like `date`, but in `tz` and with month and weekday names in `locale`
+///
+/// Without a locale the output matches `date` for UTC.
+pub fn localized_date(
+    value: &DateTime<Utc>,
+    format: &str,
+    tz: Tz,
+    locale: Option<&str>,
+) -> Option<String> {
+    let value = value.with_timezone(&tz);
+    let mut out = String::new();
+    match locale {
+        Some(locale) => write!(
+            out,
+            "{}",
+            value.format_localized(format, posix_locale(locale))
+        ),
+        None => write!(out, "{}", value.format(format)),
+    }
+    .ok()?;
+    Some(out)
+}
+
+/// The `date` format used when a template gives none
+pub fn default_date_format(locale: Option<&str>) -> &'static str {
+    match locale.map(language) {
+        Some("pt" | "es") => "%-d de %B de %Y",
+        Some("fr") => "%-d %B %Y",
+        Some("de") => "%-d. %B %Y",
+        _ => DEFAULT_DATE_FORMAT,
+    }
+}
+
+/// "pt-BR" → `pt_BR`, "es" → `es_ES`; anything unknown formats like POSIX
+pub fn posix_locale(locale: &str) -> chrono::Locale {
+    let language = language(locale).to_lowercase();
+    let region = locale
+        .split(['-', '_'])
+        .nth(1)
+        .map(str::to_uppercase)
+        .unwrap_or_else(|| language.to_uppercase());
+
+    [
+        format!("{}_{}", language, region),
+        format!("{}_{}", language, language.to_uppercase()),
+    ]
+    .iter()
+    .find_map(|name| chrono::Locale::try_from(name.as_str()).ok())
+    .unwrap_or(chrono::Locale::POSIX)
+}
+
+fn language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
+
+/// "3 minutes ago", "in 2 hours", "now"
+pub fn naturaltime(value: &DateTime<Utc>, now: &DateTime<Utc>) -> String {
+    let seconds = (*now - *value).num_seconds();
+    let distance = seconds.unsigned_abs();
+
+    if distance < 10 {
+        return "now".to_string();
+    }
+
+    let (count, unit) = match distance {
+        d if d < 60 => (d, "second"),
+        d if d < 3_600 => (d / 60, "minute"),
+        d if d < 86_400 => (d / 3_600, "hour"),
+        d if d < 30 * 86_400 => (d / 86_400, "day"),
+        d if d < 365 * 86_400 => (d / (30 * 86_400), "month"),
+        d => (d / (365 * 86_400), "year"),
+    };
+    let amount = format!("{} {}{}", count, unit, pluralize(count as f64, "s"));
+
+    if seconds > 0 {
+        format!("{} ago", amount)
+    } else {
+        format!("in {}", amount)
+    }
+}
+
+pub fn truncatewords(value: &str, count: usize) -> String {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    if words.len() <= count {
+        return words.join(" ");
+    }
+    format!("{} …", words[..count].join(" "))
+}
+
+/// Plain text to HTML: blank lines become paragraphs, single newlines `<br>`
+pub fn linebreaks(value: &str) -> String {
+    let normalized = value.replace("\r\n", "\n");
+    normalized
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("<p>{}</p>", escape_html(p).replace('\n', "<br>")))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// `Slug::from_title`: transliterated, lowercase words joined by hyphens
+/// (empty when nothing usable is left)
+pub fn slugify(value: &str) -> String {
+    Slug::from_title(value)
+        .map(|slug| slug.as_str().to_string())
+        .unwrap_or_default()
+}
+
+/// `suffix` is either the plural suffix ("s", "es") or "singular,plural"
+pub fn pluralize(count: f64, suffix: &str) -> &str {
+    let (singular, plural) = suffix.split_once(',').unwrap_or(("", suffix));
+    if count == 1.0 {
+        singular
+    } else {
+        plural
+    }
+}
+
+pub fn filesizeformat(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["KB", "MB", "GB", "TB", "PB"];
+
+    if bytes.abs() < 1024.0 {
+        let bytes = bytes as i64;
+        return format!("{} byte{}", bytes, pluralize(bytes as f64, "s"));
+    }
+
+    let mut size = bytes / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if size.abs() < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+    format!("{:.1} {}", size, unit)
+}
+
+/// 1234567 → "1,234,567"; the fractional part is left alone, and anything
+/// that isn't a number is returned unchanged
+///
+/// The `intcomma` template filter only uses these separators when no
+/// `LANGUAGE_CODE` is active; otherwise it calls `localized_intcomma`, so
+/// the same template shows "1.234.567" in `pt-BR`.
+pub fn intcomma(number: &str) -> String {
+    group_digits(number, ",", ".")
+}
+
+/// `intcomma` with the locale's separators: "1.234.567,5" in `pt-BR`
+pub fn localized_intcomma(number: &str, locale: &str) -> String {
+    let (thousands, decimal) = number_separators(locale);
+    group_digits(number, thousands, decimal)
+}
+
+/// Rounds to `decimals` places and groups digits for `locale`
+pub fn numberformat(value: f64, decimals: usize, locale: Option<&str>) -> String {
+    let number = format!("{:.*}", decimals, value);
+    match locale {
+        Some(locale) => localized_intcomma(&number, locale),
+        None => intcomma(&number),
+    }
+}
+
+/// Thousands separator and decimal point, from the POSIX locale data
+fn number_separators(locale: &str) -> (&'static str, &'static str) {
+    let locale = posix_locale(locale);
+    let thousands = pure_rust_locales::locale_match!(locale => LC_NUMERIC::THOUSANDS_SEP);
+    let decimal = pure_rust_locales::locale_match!(locale => LC_NUMERIC::DECIMAL_POINT);
+    if thousands.is_empty() || decimal.is_empty() {
+        (",", ".")
+    } else {
+        (thousands, decimal)
+    }
+}
+
+fn group_digits(number: &str, thousands: &str, decimal: &str) -> String {
+    let (sign, digits) = match number.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", number),
+    };
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (digits, None),
+    };
+    let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if integer.is_empty() || !all_digits(integer) || !fraction.is_none_or(all_digits) {
+        return number.to_string();
+    }
+
+    let mut grouped = String::new();
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push_str(thousands);
+        }
+        grouped.push(c);
+    }
+
+    match fraction {
+        Some(fraction) => format!("{}{}{}{}", sign, grouped, decimal, fraction),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+/// Escapes the text and turns URLs and email addresses into links
+pub fn urlize(value: &str) -> String {
+    let mut out = String::new();
+
+    for (i, line) in value.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        for (j, word) in line.split(' ').enumerate() {
+            if j > 0 {
+                out.push(' ');
+            }
+            out.push_str(&urlize_word(word));
+        }
+    }
+
+    out
+}
+
+fn urlize_word(word: &str) -> String {
+    let trimmed = word.trim_end_matches(['.', ',', ';', ':', '!', '?', ')']);
+    let trailing = &word[trimmed.len()..];
+
+    let href = if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
+        Some(trimmed.to_string())
+    } else if trimmed.starts_with("www.") {
+        Some(format!("http://{}", trimmed))
+    } else if is_email_like(trimmed) {
+        Some(format!("mailto:{}", trimmed))
+    } else {
+        None
+    };
+
+    match href {
+        Some(href) => format!(
+            "<a href=\"{}\" rel=\"nofollow\">{}</a>{}",
+            escape_html(&href),
+            escape_html(trimmed),
+            escape_html(trailing)
+        ),
+        None => escape_html(word),
+    }
+}
+
+fn is_email_like(value: &str) -> bool {
+    match value.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && domain.contains('.') && !domain.contains('@'),
+        None => false,
+    }
+}
+
+/// CommonMark to HTML; raw HTML in the source is escaped, not passed through,
+/// and links or images to anything but http, https, mailto or a relative URL
+/// point nowhere
+pub fn markdown(value: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    let parser = Parser::new_ext(value, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        other => other,
+    });
+
+    let mut out = String::new();
+    html::push_html(&mut out, parser);
+    out
+}
+
+/// `url`, unless its scheme could run script or embed content, as
+/// `javascript:` and `data:` do
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    // Browsers ignore tabs and newlines in a scheme, and leading spaces
+    let cleaned: String = url
+        .trim_start_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+    let scheme = match cleaned.find([':', '/', '?', '#']) {
+        Some(end) if cleaned[end..].starts_with(':') => Some(&cleaned[..end]),
+        _ => None,
+    };
+    match scheme.map(str::to_ascii_lowercase).as_deref() {
+        None | Some("http" | "https" | "mailto") => url,
+        Some(_) => CowStr::Borrowed("#"),
+    }
+}
+
+pub fn escape_html(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#x27;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
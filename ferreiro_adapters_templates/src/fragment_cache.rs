@@ -0,0 +1,284 @@
+//! The `{% cache %}` tag, for both engines
+//!
+//! ```jinja
+//! {% cache 300 "sidebar" post.id %}
+//!     ...expensive markup...
+//! {% endcache %}
+//! ```
+//!
+//! Neither Tera nor MiniJinja lets us add block tags, so templates are
+//! rewritten when they are loaded into plain `cache_get`/`cache_set` calls
+//! that both engines understand. The first argument is the TTL in seconds
+//! (`0` keeps the fragment until it is invalidated), the second the
+//! fragment name, and any further expressions are joined into the key so
+//! each post gets its own copy. The active `LANGUAGE_CODE` and `TIME_ZONE`
+//! are part of the key too, since dates and numbers render differently in
+//! each.
+
+use crate::{i18n, TemplateError};
+use ferreiro_domain::ports::driven::Cache;
+use futures::FutureExt;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// The cache `{% cache %}` blocks read from and write to
+///
+/// Without a backend every block renders on each request. Cache errors are
+/// treated as misses: a broken cache slows pages down but never breaks them.
+///
+/// Rendering is synchronous, so each lookup waits for the backend. On a
+/// multi-threaded Tokio runtime the waiting thread hands its other tasks
+/// to the rest of the pool. On a current-thread runtime, or outside one,
+/// nothing else could drive a network backend such as Redis, so a lookup
+/// that does not answer at once fails the render instead of hanging; only
+/// in-process backends such as `InMemoryCache` work there.
+#[derive(Clone, Default)]
+pub struct FragmentCache {
+    backend: Arc<RwLock<Option<Arc<dyn Cache>>>>,
+}
+
+impl FragmentCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_backend(&self, cache: Arc<dyn Cache>) {
+        *self.backend.write().unwrap() = Some(cache);
+    }
+
+    /// `None` on a miss; a fragment that rendered empty is `Some("")`
+    pub fn get(&self, key: &str) -> Result<Option<String>, TemplateError> {
+        let Some(backend) = self.backend.read().unwrap().clone() else {
+            return Ok(None);
+        };
+        let value = wait(backend.get(&fragment_key(key)))?.ok().flatten();
+        Ok(value.and_then(|value| String::from_utf8(value).ok()))
+    }
+
+    /// A `ttl` of zero seconds means no expiry
+    pub fn store(&self, key: &str, html: &str, ttl: Duration) -> Result<(), TemplateError> {
+        let ttl = (!ttl.is_zero()).then_some(ttl);
+        let backend = self.backend.read().unwrap().clone();
+        if let Some(backend) = backend {
+            let _ = wait(backend.set(&fragment_key(key), html.as_bytes(), ttl))?;
+        }
+        Ok(())
+    }
+}
+
+fn fragment_key(key: &str) -> String {
+    let locale = i18n::active_locale().unwrap_or_default();
+    let timezone = i18n::active_timezone()
+        .map(|tz| tz.name().to_string())
+        .unwrap_or_default();
+    format!("fragment:{}|{}|{}", locale, timezone, key)
+}
+
+/// Runs a cache call to completion from inside a synchronous render
+///
+/// Blocking a current-thread runtime's only thread would deadlock any
+/// backend that needs that runtime to make progress, so there the call
+/// gets a single poll.
+fn wait<F: Future>(future: F) -> Result<F::Output, TemplateError> {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            Ok(tokio::task::block_in_place(|| handle.block_on(future)))
+        }
+        _ => future.now_or_never().ok_or_else(|| {
+            TemplateError::Render(
+                "the `{% cache %}` backend needs a multi-threaded Tokio runtime".to_string(),
+            )
+        }),
+    }
+}
+
+/// Rewrites every `{% cache %}…{% endcache %}` block in `source`
+pub fn expand_cache_tags(source: &str) -> Result<String, TemplateError> {
+    let mut counter = 0;
+    expand(source, &mut counter)
+}
+
+struct Tag<'a> {
+    start: usize,
+    end: usize,
+    trim_before: bool,
+    trim_after: bool,
+    name: &'a str,
+    args: &'a str,
+}
+
+fn expand(source: &str, counter: &mut usize) -> Result<String, TemplateError> {
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+
+    while let Some(open) = next_tag(rest, |name| name == "cache")? {
+        out.push_str(&rest[..open.start]);
+        let (body, close) = block_body(&rest[open.end..])?;
+        out.push_str(&rewrite(&open, &close, &expand(body, counter)?, counter)?);
+        rest = &rest[open.end + close.end..];
+    }
+
+    if let Some(stray) = next_tag(rest, |name| name == "endcache")? {
+        return Err(TemplateError::Parse(format!(
+            "`{{% endcache %}}` without a matching `{{% cache %}}` near `{}`",
+            snippet(&rest[stray.start..])
+        )));
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Splits `source` at the `{% endcache %}` closing the block just opened,
+/// skipping over nested blocks
+fn block_body(source: &str) -> Result<(&str, Tag<'_>), TemplateError> {
+    let mut depth = 0;
+    let mut offset = 0;
+
+    while let Some(tag) = next_tag(&source[offset..], |name| {
+        name == "cache" || name == "endcache"
+    })? {
+        let tag = Tag {
+            start: offset + tag.start,
+            end: offset + tag.end,
+            ..tag
+        };
+        offset = tag.end;
+        match (tag.name, depth) {
+            ("endcache", 0) => return Ok((&source[..tag.start], tag)),
+            ("endcache", _) => depth -= 1,
+            _ => depth += 1,
+        }
+    }
+
+    Err(TemplateError::Parse(
+        "`{% cache %}` block is missing its `{% endcache %}`".to_string(),
+    ))
+}
+
+/// Finds the next `{% … %}` tag whose name passes `wanted`
+fn next_tag<'a>(
+    source: &'a str,
+    wanted: impl Fn(&str) -> bool,
+) -> Result<Option<Tag<'a>>, TemplateError> {
+    let mut offset = 0;
+
+    while let Some(found) = source[offset..].find("{%") {
+        let start = offset + found;
+        let Some(close) = source[start..].find("%}") else {
+            return Ok(None);
+        };
+        let end = start + close + 2;
+        offset = end;
+
+        let inner = &source[start + 2..end - 2];
+        let trim_before = inner.starts_with('-');
+        let trim_after = inner.ends_with('-');
+        let inner = inner.trim_start_matches(['-', '+']);
+        let inner = inner.trim_end_matches(['-', '+']).trim();
+        let (name, args) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+
+        if wanted(name) {
+            return Ok(Some(Tag {
+                start,
+                end,
+                trim_before,
+                trim_after,
+                name,
+                args: args.trim(),
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+fn rewrite(
+    open: &Tag<'_>,
+    close: &Tag<'_>,
+    body: &str,
+    counter: &mut usize,
+) -> Result<String, TemplateError> {
+    let args = split_args(open.args)?;
+    let (ttl, name, parts) = match args.as_slice() {
+        [ttl, name, parts @ ..] => (ttl, name, parts),
+        _ => {
+            return Err(TemplateError::Parse(format!(
+                "`{{% cache {} %}}` needs a TTL and a fragment name",
+                open.args
+            )))
+        }
+    };
+
+    let name = if name.starts_with(['"', '\'']) {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name)
+    };
+    let key = std::iter::once(name)
+        .chain(parts.iter().map(|part| format!("\":\" ~ {}", part)))
+        .collect::<Vec<_>>()
+        .join(" ~ ");
+
+    let var = format!("__cache_{}", counter);
+    *counter += 1;
+    let dash = |trim: bool| if trim { "-" } else { "" };
+
+    Ok(format!(
+        "{{%{} set {var} = cache_get(key={key}) %}}\
+         {{% if {var} is string %}}{{{{ {var} | safe }}}}\
+         {{% else %}}{{% filter cache_set(key={key}, ttl={ttl}) {}%}}{body}{{%{} endfilter %}}\
+         {{% endif {}%}}",
+        dash(open.trim_before),
+        dash(open.trim_after),
+        dash(close.trim_before),
+        dash(close.trim_after),
+    ))
+}
+
+/// Whitespace-separated expressions; quoted strings may contain spaces
+fn split_args(args: &str) -> Result<Vec<&str>, TemplateError> {
+    let mut out = Vec::new();
+    let mut start = None;
+    let mut quote = None;
+
+    for (i, c) in args.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                start.get_or_insert(i);
+            }
+            (None, c) if c.is_whitespace() => {
+                if let Some(s) = start.take() {
+                    out.push(&args[s..i]);
+                }
+            }
+            (None, _) => {
+                start.get_or_insert(i);
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(TemplateError::Parse(format!(
+            "unterminated string in `{{% cache {} %}}`",
+            args
+        )));
+    }
+    if let Some(s) = start {
+        out.push(&args[s..]);
+    }
+    Ok(out)
+}
+
+fn snippet(source: &str) -> &str {
+    let end = source
+        .char_indices()
+        .nth(40)
+        .map(|(i, _)| i)
+        .unwrap_or(source.len());
+    &source[..end]
+}
@@ -0,0 +1,36 @@
+use crate::TemplateError;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+
+/// Watches a template directory and calls `on_change` whenever a file in it
+/// is created, modified or removed
+///
+/// Watching stops when the watcher is dropped.
+pub struct TemplateWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl TemplateWatcher {
+    pub fn new<F>(template_dir: &str, on_change: F) -> Result<Self, TemplateError>
+    where
+        F: Fn() + Send + 'static,
+    {
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) {
+                    on_change();
+                }
+            }
+        })
+        .map_err(|e| TemplateError::Watch(e.to_string()))?;
+
+        watcher
+            .watch(Path::new(template_dir), RecursiveMode::Recursive)
+            .map_err(|e| TemplateError::Watch(e.to_string()))?;
+
+        Ok(Self { _watcher: watcher })
+    }
+}
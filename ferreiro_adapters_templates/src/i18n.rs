@@ -0,0 +1,92 @@
+//! The `trans` function, for both engines
+//!
+//! ```jinja
+//! <h1>{{ trans("blog.title") }}</h1>
+//! <p>{{ trans(key="blog.greeting", name=user.name) }}</p>
+//! ```
+//!
+//! Messages are looked up in the locale named by the context's
+//! `LANGUAGE_CODE`, which the HTTP layer's locale middleware fills in.
+//! Tera only takes keyword arguments, so `key=` works in both engines.
+//!
+//! `TIME_ZONE` works the same way for the `date`, `localtime` and
+//! `numberformat` filters, which show times in that zone and numbers with
+//! the locale's separators. `intcomma` follows `LANGUAGE_CODE` too, so its
+//! output changes with the locale.
+
+use crate::{filters, Context};
+use chrono_tz::Tz;
+use ferreiro_domain::ports::driven::Translator;
+use std::cell::RefCell;
+use std::sync::{Arc, RwLock};
+
+/// Context variable holding the locale to render in
+pub const LANGUAGE_CODE: &str = "LANGUAGE_CODE";
+
+/// Context variable holding the IANA time zone to show times in
+pub const TIME_ZONE: &str = "TIME_ZONE";
+
+thread_local! {
+    static LOCALE: RefCell<Option<String>> = const { RefCell::new(None) };
+    static ZONE: RefCell<Option<Tz>> = const { RefCell::new(None) };
+}
+
+/// The translator `trans` calls use
+///
+/// Without one, or for a key no catalog has, `trans` returns the key.
+#[derive(Clone, Default)]
+pub struct Translations {
+    translator: Arc<RwLock<Option<Arc<dyn Translator>>>>,
+}
+
+impl Translations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_translator(&self, translator: Arc<dyn Translator>) {
+        *self.translator.write().unwrap() = Some(translator);
+    }
+
+    /// Translates into the locale of the template being rendered
+    pub fn translate(&self, key: &str, params: &[(&str, String)]) -> String {
+        let locale = active_locale().unwrap_or_default();
+        self.translator
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|translator| translator.translate(&locale, key, params))
+            .unwrap_or_else(|| key.to_string())
+    }
+}
+
+/// Runs `render` with the context's `LANGUAGE_CODE` and `TIME_ZONE` active
+///
+/// Rendering is synchronous, so a thread-local is enough to carry them to
+/// `trans` and the filters without leaking into other requests. An unknown
+/// zone name is ignored, leaving times in UTC.
+pub(crate) fn activate<R>(context: &Context, render: impl FnOnce() -> R) -> R {
+    let locale = context
+        .get(LANGUAGE_CODE)
+        .and_then(|value| value.as_str())
+        .map(str::to_string);
+    let zone = context
+        .get(TIME_ZONE)
+        .and_then(|value| value.as_str())
+        .and_then(filters::parse_timezone);
+
+    let previous_locale = LOCALE.with(|current| current.replace(locale));
+    let previous_zone = ZONE.with(|current| current.replace(zone));
+    let result = render();
+    LOCALE.with(|current| *current.borrow_mut() = previous_locale);
+    ZONE.with(|current| *current.borrow_mut() = previous_zone);
+    result
+}
+
+pub(crate) fn active_locale() -> Option<String> {
+    LOCALE.with(|locale| locale.borrow().clone())
+}
+
+pub(crate) fn active_timezone() -> Option<Tz> {
+    ZONE.with(|zone| *zone.borrow())
+}
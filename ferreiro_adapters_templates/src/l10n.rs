@@ -0,0 +1,97 @@
+//! Localized dates and numbers outside templates
+//!
+//! The same formatting the `date` and `numberformat` filters use, for
+//! handlers that build JSON, feeds or emails:
+//!
+//! ```rust
+//! use chrono::{TimeZone, Utc};
+//! use ferreiro_adapters_templates::l10n::Localizer;
+//!
+//! let l10n = Localizer::new("pt-BR", "America/Sao_Paulo");
+//! let published = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+//! assert_eq!(l10n.date(&published), "1 de março de 2024");
+//! assert_eq!(l10n.number(1234.5, 2), "1.234,50");
+//! ```
+
+use crate::filters;
+use crate::i18n::{LANGUAGE_CODE, TIME_ZONE};
+use crate::Context;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+
+/// A locale and time zone to present values in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Localizer {
+    locale: Option<String>,
+    timezone: Tz,
+}
+
+impl Localizer {
+    /// An unknown zone name falls back to UTC
+    pub fn new(locale: &str, timezone: &str) -> Self {
+        Self {
+            locale: Some(locale.to_string()),
+            timezone: Tz::UTC,
+        }
+        .in_timezone(timezone)
+    }
+
+    /// Same locale, times shown in `timezone` (UTC if unknown)
+    pub fn in_timezone(mut self, timezone: &str) -> Self {
+        self.timezone = filters::parse_timezone(timezone).unwrap_or(Tz::UTC);
+        self
+    }
+
+    /// Reads `LANGUAGE_CODE` and `TIME_ZONE`, as the filters do
+    pub fn from_context(context: &Context) -> Self {
+        let value = |key| {
+            context
+                .get(key)
+                .and_then(|value| value.as_str())
+                .map(str::to_string)
+        };
+        Self {
+            locale: value(LANGUAGE_CODE),
+            timezone: value(TIME_ZONE)
+                .and_then(|name| filters::parse_timezone(&name))
+                .unwrap_or(Tz::UTC),
+        }
+    }
+
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_deref()
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    pub fn localtime(&self, value: &DateTime<Utc>) -> DateTime<Tz> {
+        value.with_timezone(&self.timezone)
+    }
+
+    /// The locale's default date format, such as "Mar 1, 2024"
+    pub fn date(&self, value: &DateTime<Utc>) -> String {
+        self.format(value, filters::default_date_format(self.locale()))
+            .unwrap_or_default()
+    }
+
+    /// `None` if `format` is not a valid strftime format
+    pub fn format(&self, value: &DateTime<Utc>, format: &str) -> Option<String> {
+        filters::localized_date(value, format, self.timezone, self.locale())
+    }
+
+    pub fn number(&self, value: f64, decimals: usize) -> String {
+        filters::numberformat(value, decimals, self.locale())
+    }
+}
+
+impl Default for Localizer {
+    /// No locale, UTC: the same output as the unlocalized filters
+    fn default() -> Self {
+        Self {
+            locale: None,
+            timezone: Tz::UTC,
+        }
+    }
+}
@@ -2,7 +2,7 @@ use serde::Serialize;
 use std::collections::HashMap;
 use thiserror::Error;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct Context {
     pub data: HashMap<String, serde_json::Value>,
 }
@@ -17,6 +17,15 @@ impl Context {
             self.data.insert(key.to_string(), v);
         }
     }
+
+    pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
+        self.data.get(key)
+    }
+
+    /// Copies every value from `other`, replacing existing keys
+    pub fn extend(&mut self, other: Context) {
+        self.data.extend(other.data);
+    }
 }
 
 #[macro_export]
@@ -38,8 +47,24 @@ pub enum TemplateError {
 
     #[error("Render error: {0}")]
     Render(String),
+
+    #[error("Watch error: {0}")]
+    Watch(String),
 }
 
+pub mod embedded;
+pub mod filters;
+pub mod fragment_cache;
+pub mod i18n;
+pub mod l10n;
+pub mod processors;
+
+pub use embedded::{builtin_templates, EmbeddedTemplates};
+pub use fragment_cache::FragmentCache;
+pub use i18n::Translations;
+pub use l10n::Localizer;
+pub use processors::{ContextProcessor, ContextProcessors};
+
 pub trait TemplateEngine: Send + Sync {
     fn render(&self, name: &str, context: &Context) -> Result<String, TemplateError>;
     fn render_string(&self, template: &str, context: &Context) -> Result<String, TemplateError>;
@@ -50,3 +75,6 @@ pub mod tera_adapter;
 
 #[cfg(feature = "minijinja-engine")]
 pub mod minijinja_adapter;
+
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
@@ -1,35 +1,306 @@
-use crate::{Context, TemplateEngine, TemplateError};
-use minijinja::Environment;
-use std::sync::RwLock;
+use crate::embedded::{builtin_templates, DiskChanges, EmbeddedTemplates};
+use crate::fragment_cache::{expand_cache_tags, FragmentCache};
+use crate::i18n::{self, Translations};
+use crate::{filters, Context, TemplateEngine, TemplateError};
+use chrono::Utc;
+use chrono_tz::Tz;
+use ferreiro_domain::ports::driven::{Cache, Translator};
+use minijinja::value::{Kwargs, Value};
+use minijinja::{Environment, Error, ErrorKind};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+#[cfg(feature = "hot-reload")]
+use crate::hot_reload::TemplateWatcher;
 
 pub struct MiniJinjaEngine {
-    env: RwLock<Environment<'static>>,
+    env: Arc<RwLock<Environment<'static>>>,
+    /// Set for `prefer_disk` templates, whose changes drop cached templates
+    disk_changes: Option<DiskChanges>,
+    fragments: FragmentCache,
+    translations: Translations,
+    #[cfg(feature = "hot-reload")]
+    _watcher: Option<TemplateWatcher>,
 }
 
 impl MiniJinjaEngine {
     pub fn new(template_dir: &str) -> Result<Self, TemplateError> {
+        Ok(Self::with_loader(minijinja::path_loader(template_dir)))
+    }
+
+    /// Templates are read from the embedded set on first use, and again
+    /// after the `prefer_disk` directory changes
+    pub fn from_embedded(templates: EmbeddedTemplates) -> Result<Self, TemplateError> {
+        let disk_changes = DiskChanges::watch(&templates);
+        let mut engine = Self::with_loader(move |name| Ok(templates.get(name)));
+        engine.disk_changes = disk_changes;
+        Ok(engine)
+    }
+
+    /// Backs the `{% cache %}` tag with `cache`; without one, cached blocks
+    /// are rendered every time
+    pub fn with_fragment_cache(self, cache: Arc<dyn Cache>) -> Self {
+        self.fragments.set_backend(cache);
+        self
+    }
+
+    /// Translates `trans` calls, in the locale given by the context's
+    /// `LANGUAGE_CODE`
+    pub fn with_translator(self, translator: Arc<dyn Translator>) -> Self {
+        self.translations.set_translator(translator);
+        self
+    }
+
+    /// Falls back to the framework templates for names `loader` doesn't know,
+    /// and expands `{% cache %}` tags in whatever it loads
+    fn with_loader<F>(loader: F) -> Self
+    where
+        F: Fn(&str) -> Result<Option<String>, Error> + Send + Sync + 'static,
+    {
+        let builtin = builtin_templates();
+        let (fragments, translations) = (FragmentCache::new(), Translations::new());
         let mut env = Environment::new();
-        env.set_loader(minijinja::path_loader(template_dir));
-        Ok(Self {
-            env: RwLock::new(env),
-        })
+        env.set_loader(move |name| {
+            let source = match loader(name)? {
+                Some(source) => source,
+                None => match builtin.get(name) {
+                    Some(source) => source,
+                    None => return Ok(None),
+                },
+            };
+            expand_cache_tags(&source)
+                .map(Some)
+                .map_err(|e| Error::new(ErrorKind::SyntaxError, e.to_string()))
+        });
+        register_builtins(&mut env);
+        register_cache(&mut env, &fragments);
+        register_trans(&mut env, &translations);
+
+        Self {
+            env: Arc::new(RwLock::new(env)),
+            disk_changes: None,
+            fragments,
+            translations,
+            #[cfg(feature = "hot-reload")]
+            _watcher: None,
+        }
+    }
+
+    /// Like `new`, but drops cached templates whenever a file in
+    /// `template_dir` changes so the next render picks up the new source
+    #[cfg(feature = "hot-reload")]
+    pub fn with_hot_reload(template_dir: &str) -> Result<Self, TemplateError> {
+        let mut engine = Self::new(template_dir)?;
+
+        let env = engine.env.clone();
+        let watcher = TemplateWatcher::new(template_dir, move || {
+            env.write().unwrap().clear_templates();
+        })?;
+
+        engine._watcher = Some(watcher);
+        Ok(engine)
+    }
+
+    fn check_disk(&self) {
+        if self.disk_changes.as_ref().is_some_and(DiskChanges::changed) {
+            self.env.write().unwrap().clear_templates();
+        }
     }
 }
 
 impl TemplateEngine for MiniJinjaEngine {
     fn render(&self, name: &str, context: &Context) -> Result<String, TemplateError> {
+        self.check_disk();
         let env = self.env.read().unwrap();
-        let template = env
-            .get_template(name)
-            .map_err(|e| TemplateError::NotFound(e.to_string()))?;
-        template
-            .render(&context.data)
-            .map_err(|e| TemplateError::Render(e.to_string()))
+        let template = env.get_template(name).map_err(|e| match e.kind() {
+            ErrorKind::TemplateNotFound => TemplateError::NotFound(name.to_string()),
+            _ => TemplateError::Parse(describe(&e)),
+        })?;
+        i18n::activate(context, || template.render(&context.data))
+            .map_err(|e| TemplateError::Render(describe(&e)))
     }
 
     fn render_string(&self, template: &str, context: &Context) -> Result<String, TemplateError> {
+        self.check_disk();
         let env = self.env.read().unwrap();
-        env.render_str(template, &context.data)
-            .map_err(|e| TemplateError::Render(e.to_string()))
+        let template = expand_cache_tags(template)?;
+        i18n::activate(context, || env.render_str(&template, &context.data))
+            .map_err(|e| TemplateError::Render(describe(&e)))
     }
 }
+
+/// The alternate form adds the offending source lines to the message
+fn describe(error: &minijinja::Error) -> String {
+    format!("{:#}", error)
+}
+
+fn register_builtins(env: &mut Environment<'static>) {
+    env.add_filter(
+        "date",
+        |value: Value, format: Option<String>, kwargs: Kwargs| -> Result<String, Error> {
+            let locale = i18n::active_locale();
+            let format = match format {
+                Some(format) => format,
+                None => kwargs
+                    .get::<Option<String>>("format")?
+                    .unwrap_or_else(|| filters::default_date_format(locale.as_deref()).to_string()),
+            };
+            let tz = timezone(&kwargs, "date")?;
+            kwargs.assert_all_used()?;
+            filters::localized_date(&datetime(&value, "date")?, &format, tz, locale.as_deref())
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidOperation,
+                        format!("date: invalid format `{}`", format),
+                    )
+                })
+        },
+    );
+    env.add_filter(
+        "localtime",
+        |value: Value, kwargs: Kwargs| -> Result<String, Error> {
+            let tz = timezone(&kwargs, "localtime")?;
+            kwargs.assert_all_used()?;
+            let value = datetime(&value, "localtime")?;
+            Ok(value.with_timezone(&tz).to_rfc3339())
+        },
+    );
+    env.add_filter("naturaltime", |value: Value| -> Result<String, Error> {
+        let value = datetime(&value, "naturaltime")?;
+        Ok(filters::naturaltime(&value, &Utc::now()))
+    });
+    env.add_filter(
+        "truncatewords",
+        |value: String, count: Option<usize>, kwargs: Kwargs| -> Result<String, Error> {
+            let count = match count {
+                Some(count) => count,
+                None => kwargs.get::<usize>("count")?,
+            };
+            kwargs.assert_all_used()?;
+            Ok(filters::truncatewords(&value, count))
+        },
+    );
+    env.add_filter("linebreaks", |value: String| {
+        Value::from_safe_string(filters::linebreaks(&value))
+    });
+    env.add_filter("slugify", |value: String| filters::slugify(&value));
+    env.add_filter(
+        "pluralize",
+        |value: Value, suffix: Option<String>, kwargs: Kwargs| -> Result<String, Error> {
+            let suffix = match suffix {
+                Some(suffix) => suffix,
+                None => kwargs
+                    .get::<Option<String>>("suffix")?
+                    .unwrap_or_else(|| "s".to_string()),
+            };
+            kwargs.assert_all_used()?;
+            let count = match value.len() {
+                Some(len) if value.as_str().is_none() => len as f64,
+                _ => f64::try_from(value).unwrap_or(0.0),
+            };
+            Ok(filters::pluralize(count, &suffix).to_string())
+        },
+    );
+    env.add_filter("filesizeformat", |value: Value| {
+        filters::filesizeformat(f64::try_from(value).unwrap_or(0.0))
+    });
+    env.add_filter("intcomma", |value: Value| match i18n::active_locale() {
+        Some(locale) => filters::localized_intcomma(&value.to_string(), &locale),
+        None => filters::intcomma(&value.to_string()),
+    });
+    env.add_filter(
+        "numberformat",
+        |value: Value, decimals: Option<usize>, kwargs: Kwargs| -> Result<String, Error> {
+            let decimals = match decimals {
+                Some(decimals) => decimals,
+                None => kwargs.get::<Option<usize>>("decimals")?.unwrap_or(0),
+            };
+            kwargs.assert_all_used()?;
+            Ok(filters::numberformat(
+                f64::try_from(value).unwrap_or(0.0),
+                decimals,
+                i18n::active_locale().as_deref(),
+            ))
+        },
+    );
+    env.add_filter("urlize", |value: String| {
+        Value::from_safe_string(filters::urlize(&value))
+    });
+    env.add_filter("markdown", |value: String| {
+        Value::from_safe_string(filters::markdown(&value))
+    });
+
+    env.add_function("utcnow", || Utc::now().to_rfc3339());
+}
+
+/// `cache_get`/`cache_set`, which `{% cache %}` blocks expand to
+fn register_cache(env: &mut Environment<'static>, fragments: &FragmentCache) {
+    let cache = fragments.clone();
+    env.add_function("cache_get", move |kwargs: Kwargs| -> Result<Value, Error> {
+        let key: Value = kwargs.get("key")?;
+        kwargs.assert_all_used()?;
+        let html = cache
+            .get(&key.to_string())
+            .map_err(|e| Error::new(ErrorKind::InvalidOperation, e.to_string()))?;
+        Ok(html.map(Value::from_safe_string).unwrap_or_default())
+    });
+    let cache = fragments.clone();
+    env.add_filter(
+        "cache_set",
+        move |value: Value, kwargs: Kwargs| -> Result<Value, Error> {
+            let key: Value = kwargs.get("key")?;
+            let ttl: u64 = kwargs.get::<Option<u64>>("ttl")?.unwrap_or(0);
+            kwargs.assert_all_used()?;
+            cache
+                .store(
+                    &key.to_string(),
+                    &value.to_string(),
+                    Duration::from_secs(ttl),
+                )
+                .map_err(|e| Error::new(ErrorKind::InvalidOperation, e.to_string()))?;
+            Ok(value)
+        },
+    );
+}
+
+fn register_trans(env: &mut Environment<'static>, translations: &Translations) {
+    let translations = translations.clone();
+    env.add_function(
+        "trans",
+        move |key: Option<String>, kwargs: Kwargs| -> Result<String, Error> {
+            let key = match key {
+                Some(key) => key,
+                None => kwargs.get::<String>("key")?,
+            };
+            let mut params = Vec::new();
+            for name in kwargs.args().filter(|name| *name != "key") {
+                params.push((name, kwargs.get::<Value>(name)?.to_string()));
+            }
+            kwargs.assert_all_used()?;
+            Ok(translations.translate(&key, &params))
+        },
+    );
+}
+
+/// The `tz` argument, else the active `TIME_ZONE`, else UTC
+fn timezone(kwargs: &Kwargs, filter: &str) -> Result<Tz, Error> {
+    match kwargs.get::<Option<String>>("tz")? {
+        Some(name) => filters::parse_timezone(&name).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidOperation,
+                format!("{}: unknown time zone `{}`", filter, name),
+            )
+        }),
+        None => Ok(i18n::active_timezone().unwrap_or(Tz::UTC)),
+    }
+}
+
+fn datetime(value: &Value, filter: &str) -> Result<chrono::DateTime<Utc>, Error> {
+    let json = serde_json::to_value(value).unwrap_or_default();
+    filters::parse_datetime(&json).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidOperation,
+            format!("{}: expected a datetime, got {}", filter, value),
+        )
+    })
+}
@@ -0,0 +1,69 @@
+use crate::Context;
+use std::sync::Arc;
+
+pub type ContextProcessor<R> = Arc<dyn Fn(&R, &mut Context) + Send + Sync>;
+
+/// Functions that add common values (current user, flash messages, site
+/// settings...) to every template context
+///
+/// `R` is whatever request information the HTTP adapter hands over, so this
+/// crate stays independent of the web framework.
+pub struct ContextProcessors<R> {
+    processors: Vec<ContextProcessor<R>>,
+}
+
+impl<R> ContextProcessors<R> {
+    pub fn new() -> Self {
+        Self {
+            processors: Vec::new(),
+        }
+    }
+
+    pub fn with<F>(mut self, processor: F) -> Self
+    where
+        F: Fn(&R, &mut Context) + Send + Sync + 'static,
+    {
+        self.register(processor);
+        self
+    }
+
+    pub fn register<F>(&mut self, processor: F)
+    where
+        F: Fn(&R, &mut Context) + Send + Sync + 'static,
+    {
+        self.processors.push(Arc::new(processor));
+    }
+
+    /// Runs every processor in registration order, then applies `context`
+    /// on top so values set by the handler win
+    pub fn apply(&self, request: &R, context: Context) -> Context {
+        let mut merged = Context::new();
+        for processor in &self.processors {
+            processor(request, &mut merged);
+        }
+        merged.extend(context);
+        merged
+    }
+
+    pub fn len(&self) -> usize {
+        self.processors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+}
+
+impl<R> Default for ContextProcessors<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R> Clone for ContextProcessors<R> {
+    fn clone(&self) -> Self {
+        Self {
+            processors: self.processors.clone(),
+        }
+    }
+}
@@ -1,41 +1,402 @@
-use crate::{Context, TemplateEngine, TemplateError};
-use std::sync::RwLock;
-use tera::Tera;
+use crate::embedded::{builtin_templates, disk_templates, DiskChanges, EmbeddedTemplates};
+use crate::fragment_cache::{expand_cache_tags, FragmentCache};
+use crate::i18n::{self, Translations};
+use crate::{filters, Context, TemplateEngine, TemplateError};
+use chrono::Utc;
+use chrono_tz::Tz;
+use ferreiro_domain::ports::driven::{Cache, Translator};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tera::{Tera, Value};
+
+#[cfg(feature = "hot-reload")]
+use crate::hot_reload::TemplateWatcher;
 
 pub struct TeraEngine {
-    tera: RwLock<Tera>,
+    tera: Arc<RwLock<Tera>>,
+    reload_error: Arc<RwLock<Option<String>>>,
+    /// The `prefer_disk` templates `from_embedded` was given, to reload
+    embedded: Option<(EmbeddedTemplates, DiskChanges)>,
+    fragments: FragmentCache,
+    translations: Translations,
+    #[cfg(feature = "hot-reload")]
+    _watcher: Option<TemplateWatcher>,
 }
 
 impl TeraEngine {
     pub fn new(template_dir: &str) -> Result<Self, TemplateError> {
-        let glob = format!("{}/**/*", template_dir);
-        let tera = Tera::new(&glob).map_err(|e| TemplateError::Parse(e.to_string()))?;
-        Ok(Self {
-            tera: RwLock::new(tera),
-        })
+        let engine = Self::wrap(Tera::default());
+        let tera = engine.load(template_dir)?;
+        *engine.tera.write().unwrap() = tera;
+        Ok(engine)
+    }
+
+    /// Loads every template from the embedded set up front, and again
+    /// whenever the `prefer_disk` directory changes
+    pub fn from_embedded(templates: EmbeddedTemplates) -> Result<Self, TemplateError> {
+        let mut engine = Self::wrap(Tera::default());
+        *engine.tera.write().unwrap() = engine.load_embedded(&templates)?;
+        engine.embedded = DiskChanges::watch(&templates).map(|changes| (templates, changes));
+        Ok(engine)
+    }
+
+    /// Templates already parsed into `tera` can't use `{% cache %}`, only
+    /// the `cache_get`/`cache_set` calls it expands to. The framework
+    /// templates are added unless `tera` overrides them.
+    pub fn from_tera(mut tera: Tera) -> Result<Self, TemplateError> {
+        add_templates(&mut tera, Vec::new())?;
+        let engine = Self::wrap(tera);
+        engine.register_builtins(&mut engine.tera.write().unwrap());
+        Ok(engine)
+    }
+
+    /// Backs the `{% cache %}` tag with `cache`; without one, cached blocks
+    /// are rendered every time
+    pub fn with_fragment_cache(self, cache: Arc<dyn Cache>) -> Self {
+        self.fragments.set_backend(cache);
+        self
     }
 
-    pub fn from_tera(tera: Tera) -> Self {
+    /// Translates `trans` calls, in the locale given by the context's
+    /// `LANGUAGE_CODE`
+    pub fn with_translator(self, translator: Arc<dyn Translator>) -> Self {
+        self.translations.set_translator(translator);
+        self
+    }
+
+    fn wrap(tera: Tera) -> Self {
         Self {
-            tera: RwLock::new(tera),
+            tera: Arc::new(RwLock::new(tera)),
+            reload_error: Arc::new(RwLock::new(None)),
+            embedded: None,
+            fragments: FragmentCache::new(),
+            translations: Translations::new(),
+            #[cfg(feature = "hot-reload")]
+            _watcher: None,
+        }
+    }
+
+    /// Like `new`, but reloads templates whenever a file in `template_dir`
+    /// changes. A template that fails to parse is reported by `render`
+    /// until it is fixed, instead of taking the server down.
+    #[cfg(feature = "hot-reload")]
+    pub fn with_hot_reload(template_dir: &str) -> Result<Self, TemplateError> {
+        let mut engine = Self::new(template_dir)?;
+
+        // Shares the engine's state, so the watcher can load with it
+        let loader = Self {
+            tera: engine.tera.clone(),
+            reload_error: engine.reload_error.clone(),
+            embedded: None,
+            fragments: engine.fragments.clone(),
+            translations: engine.translations.clone(),
+            _watcher: None,
+        };
+        let dir = template_dir.to_string();
+        let watcher = TemplateWatcher::new(template_dir, move || match loader.load(&dir) {
+            Ok(reloaded) => {
+                *loader.tera.write().unwrap() = reloaded;
+                *loader.reload_error.write().unwrap() = None;
+            }
+            Err(e) => {
+                *loader.reload_error.write().unwrap() = Some(e.to_string());
+            }
+        })?;
+
+        engine._watcher = Some(watcher);
+        Ok(engine)
+    }
+
+    fn load(&self, template_dir: &str) -> Result<Tera, TemplateError> {
+        let mut tera = Tera::default();
+        add_templates(&mut tera, disk_templates(Path::new(template_dir)))?;
+        self.register_builtins(&mut tera);
+        Ok(tera)
+    }
+
+    fn load_embedded(&self, templates: &EmbeddedTemplates) -> Result<Tera, TemplateError> {
+        let mut tera = Tera::default();
+        add_templates(&mut tera, templates.templates())?;
+        self.register_builtins(&mut tera);
+        Ok(tera)
+    }
+
+    fn register_builtins(&self, tera: &mut Tera) {
+        register_builtins(tera);
+        register_cache(tera, &self.fragments);
+        register_trans(tera, &self.translations);
+    }
+
+    /// Reloads `prefer_disk` templates that changed, then reports the last
+    /// reload's error, if it failed
+    fn check_reload(&self) -> Result<(), TemplateError> {
+        if let Some((templates, changes)) = &self.embedded {
+            if changes.changed() {
+                match self.load_embedded(templates) {
+                    Ok(reloaded) => {
+                        *self.tera.write().unwrap() = reloaded;
+                        *self.reload_error.write().unwrap() = None;
+                    }
+                    Err(e) => *self.reload_error.write().unwrap() = Some(e.to_string()),
+                }
+            }
+        }
+        match self.reload_error.read().unwrap().as_ref() {
+            Some(error) => Err(TemplateError::Parse(error.clone())),
+            None => Ok(()),
         }
     }
 }
 
 impl TemplateEngine for TeraEngine {
     fn render(&self, name: &str, context: &Context) -> Result<String, TemplateError> {
+        self.check_reload()?;
         let tera = self.tera.read().unwrap();
         let tera_context = tera::Context::from_serialize(&context.data)
             .map_err(|e| TemplateError::Render(e.to_string()))?;
-        tera.render(name, &tera_context)
-            .map_err(|e| TemplateError::Render(e.to_string()))
+        i18n::activate(context, || tera.render(name, &tera_context)).map_err(|e| match e.kind {
+            tera::ErrorKind::TemplateNotFound(_) => TemplateError::NotFound(name.to_string()),
+            _ => TemplateError::Render(describe(&e)),
+        })
     }
 
     fn render_string(&self, template: &str, context: &Context) -> Result<String, TemplateError> {
+        self.check_reload()?;
         let mut tera = self.tera.write().unwrap();
         let tera_context = tera::Context::from_serialize(&context.data)
             .map_err(|e| TemplateError::Render(e.to_string()))?;
-        tera.render_str(template, &tera_context)
-            .map_err(|e| TemplateError::Render(e.to_string()))
+        let template = expand_cache_tags(template)?;
+        i18n::activate(context, || tera.render_str(&template, &tera_context))
+            .map_err(|e| TemplateError::Render(describe(&e)))
+    }
+}
+
+/// Adds `templates` and every framework template the app does not override,
+/// then builds the inheritance chains so app templates can extend them
+///
+/// `{% cache %}` tags are expanded on the way in.
+fn add_templates(tera: &mut Tera, templates: Vec<(String, String)>) -> Result<(), TemplateError> {
+    let mut overridden: HashSet<String> = tera.get_template_names().map(str::to_string).collect();
+    overridden.extend(templates.iter().map(|(name, _)| name.clone()));
+
+    let mut all: Vec<(String, String)> = builtin_templates()
+        .templates()
+        .into_iter()
+        .filter(|(name, _)| !overridden.contains(name))
+        .collect();
+    all.extend(templates);
+
+    let all = all
+        .into_iter()
+        .map(|(name, source)| {
+            let source = expand_cache_tags(&source)
+                .map_err(|e| TemplateError::Parse(format!("{}: {}", name, e)))?;
+            Ok((name, source))
+        })
+        .collect::<Result<Vec<_>, TemplateError>>()?;
+    tera.add_raw_templates(all)
+        .map_err(|e| TemplateError::Parse(describe(&e)))
+}
+
+/// Tera keeps the useful part of an error (line, column, cause) in its
+/// source chain, so flatten the chain into one message
+fn describe(error: &tera::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        message.push_str(&format!("\n{}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+/// Marks a filter's output as HTML that must not be escaped again
+struct Safe<F>(F);
+
+impl<F> tera::Filter for Safe<F>
+where
+    F: Fn(&Value, &HashMap<String, Value>) -> tera::Result<Value> + Send + Sync,
+{
+    fn filter(&self, value: &Value, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        (self.0)(value, args)
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
+fn register_builtins(tera: &mut Tera) {
+    tera.register_filter("date", |value: &Value, args: &HashMap<String, Value>| {
+        let locale = i18n::active_locale();
+        let format = args
+            .get("format")
+            .and_then(Value::as_str)
+            .unwrap_or_else(|| filters::default_date_format(locale.as_deref()));
+        let tz = timezone(args, "date")?;
+        filters::localized_date(&datetime(value, "date")?, format, tz, locale.as_deref())
+            .map(Value::from)
+            .ok_or_else(|| tera::Error::msg(format!("date: invalid format `{}`", format)))
+    });
+    tera.register_filter(
+        "localtime",
+        |value: &Value, args: &HashMap<String, Value>| {
+            let tz = timezone(args, "localtime")?;
+            let value = datetime(value, "localtime")?;
+            Ok(Value::from(value.with_timezone(&tz).to_rfc3339()))
+        },
+    );
+    tera.register_filter(
+        "naturaltime",
+        |value: &Value, _: &HashMap<String, Value>| {
+            let value = datetime(value, "naturaltime")?;
+            Ok(Value::from(filters::naturaltime(&value, &Utc::now())))
+        },
+    );
+    tera.register_filter(
+        "truncatewords",
+        |value: &Value, args: &HashMap<String, Value>| {
+            let count = args
+                .get("count")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| tera::Error::msg("truncatewords: expected a `count` argument"))?;
+            Ok(Value::from(filters::truncatewords(
+                &text(value),
+                count as usize,
+            )))
+        },
+    );
+    tera.register_filter(
+        "linebreaks",
+        Safe(|value: &Value, _: &HashMap<String, Value>| {
+            Ok(Value::from(filters::linebreaks(&text(value))))
+        }),
+    );
+    tera.register_filter("slugify", |value: &Value, _: &HashMap<String, Value>| {
+        Ok(Value::from(filters::slugify(&text(value))))
+    });
+    tera.register_filter(
+        "pluralize",
+        |value: &Value, args: &HashMap<String, Value>| {
+            let suffix = args.get("suffix").and_then(Value::as_str).unwrap_or("s");
+            let count = match value {
+                Value::Array(items) => items.len() as f64,
+                other => other.as_f64().unwrap_or(0.0),
+            };
+            Ok(Value::from(filters::pluralize(count, suffix)))
+        },
+    );
+    tera.register_filter(
+        "filesizeformat",
+        |value: &Value, _: &HashMap<String, Value>| {
+            Ok(Value::from(filters::filesizeformat(
+                value.as_f64().unwrap_or(0.0),
+            )))
+        },
+    );
+    tera.register_filter("intcomma", |value: &Value, _: &HashMap<String, Value>| {
+        Ok(Value::from(match i18n::active_locale() {
+            Some(locale) => filters::localized_intcomma(&text(value), &locale),
+            None => filters::intcomma(&text(value)),
+        }))
+    });
+    tera.register_filter(
+        "numberformat",
+        |value: &Value, args: &HashMap<String, Value>| {
+            let decimals = args.get("decimals").and_then(Value::as_u64).unwrap_or(0);
+            Ok(Value::from(filters::numberformat(
+                value.as_f64().unwrap_or(0.0),
+                decimals as usize,
+                i18n::active_locale().as_deref(),
+            )))
+        },
+    );
+    tera.register_filter(
+        "urlize",
+        Safe(|value: &Value, _: &HashMap<String, Value>| {
+            Ok(Value::from(filters::urlize(&text(value))))
+        }),
+    );
+    tera.register_filter(
+        "markdown",
+        Safe(|value: &Value, _: &HashMap<String, Value>| {
+            Ok(Value::from(filters::markdown(&text(value))))
+        }),
+    );
+
+    tera.register_function("utcnow", |_: &HashMap<String, Value>| {
+        Ok(Value::from(Utc::now().to_rfc3339()))
+    });
+}
+
+/// `cache_get`/`cache_set`, which `{% cache %}` blocks expand to
+fn register_cache(tera: &mut Tera, fragments: &FragmentCache) {
+    let cache = fragments.clone();
+    tera.register_function("cache_get", move |args: &HashMap<String, Value>| {
+        let key = fragment_key(args)?;
+        let html = cache
+            .get(&key)
+            .map_err(|e| tera::Error::msg(e.to_string()))?;
+        Ok(html.map(Value::from).unwrap_or(Value::Null))
+    });
+    let cache = fragments.clone();
+    tera.register_filter(
+        "cache_set",
+        Safe(move |value: &Value, args: &HashMap<String, Value>| {
+            let ttl = args.get("ttl").and_then(Value::as_u64).unwrap_or(0);
+            cache
+                .store(&fragment_key(args)?, &text(value), Duration::from_secs(ttl))
+                .map_err(|e| tera::Error::msg(e.to_string()))?;
+            Ok(value.clone())
+        }),
+    );
+}
+
+fn register_trans(tera: &mut Tera, translations: &Translations) {
+    let translations = translations.clone();
+    tera.register_function("trans", move |args: &HashMap<String, Value>| {
+        let key = args
+            .get("key")
+            .map(text)
+            .ok_or_else(|| tera::Error::msg("trans: expected a `key` argument"))?;
+        let params: Vec<(&str, String)> = args
+            .iter()
+            .filter(|(name, _)| name.as_str() != "key")
+            .map(|(name, value)| (name.as_str(), text(value)))
+            .collect();
+        Ok(Value::from(translations.translate(&key, &params)))
+    });
+}
+
+fn fragment_key(args: &HashMap<String, Value>) -> tera::Result<String> {
+    args.get("key")
+        .map(text)
+        .ok_or_else(|| tera::Error::msg("cache: expected a `key` argument"))
+}
+
+fn datetime(value: &Value, filter: &str) -> tera::Result<chrono::DateTime<Utc>> {
+    filters::parse_datetime(value)
+        .ok_or_else(|| tera::Error::msg(format!("{}: expected a datetime, got {}", filter, value)))
+}
+
+/// The `tz` argument, else the active `TIME_ZONE`, else UTC
+fn timezone(args: &HashMap<String, Value>, filter: &str) -> tera::Result<Tz> {
+    match args.get("tz") {
+        Some(name) => {
+            let name = text(name);
+            filters::parse_timezone(&name).ok_or_else(|| {
+                tera::Error::msg(format!("{}: unknown time zone `{}`", filter, name))
+            })
+        }
+        None => Ok(i18n::active_timezone().unwrap_or(Tz::UTC)),
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
     }
 }
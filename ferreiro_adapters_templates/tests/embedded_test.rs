@@ -0,0 +1,128 @@
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_adapters_templates::tera_adapter::TeraEngine;
+use ferreiro_adapters_templates::{context, embed_templates, Context, TemplateEngine};
+
+#[test]
+fn test_engines_render_embedded_templates() {
+    let ctx = context! { name: "Ferreiro" };
+
+    let tera =
+        TeraEngine::from_embedded(embed_templates!("$CARGO_MANIFEST_DIR/tests/templates")).unwrap();
+    let minijinja =
+        MiniJinjaEngine::from_embedded(embed_templates!("$CARGO_MANIFEST_DIR/tests/templates"))
+            .unwrap();
+
+    for engine in [&tera as &dyn TemplateEngine, &minijinja] {
+        let html = engine.render("hello.html", &ctx).unwrap();
+        assert!(html.contains("<title>Ferreiro</title>"));
+        assert!(html.contains("<p>Hello Ferreiro</p>"));
+    }
+}
+
+#[test]
+fn test_prefer_disk_overrides_embedded_templates() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("hello.html"), "Edited {{ name }}").unwrap();
+    std::fs::write(dir.path().join("new.html"), "New").unwrap();
+
+    let templates = embed_templates!("$CARGO_MANIFEST_DIR/tests/templates").prefer_disk(dir.path());
+    let ctx = context! { name: "Ferreiro" };
+
+    let tera = TeraEngine::from_embedded(templates.clone()).unwrap();
+    let minijinja = MiniJinjaEngine::from_embedded(templates).unwrap();
+
+    for engine in [&tera as &dyn TemplateEngine, &minijinja] {
+        assert_eq!(
+            engine.render("hello.html", &ctx).unwrap(),
+            "Edited Ferreiro"
+        );
+        assert_eq!(engine.render("new.html", &ctx).unwrap(), "New");
+    }
+
+    // Edits show up on the next render, and a removed override falls back
+    // to the embedded copy
+    std::fs::write(dir.path().join("new.html"), "Newer, longer").unwrap();
+    std::fs::remove_file(dir.path().join("hello.html")).unwrap();
+    for engine in [&tera as &dyn TemplateEngine, &minijinja] {
+        assert_eq!(engine.render("new.html", &ctx).unwrap(), "Newer, longer");
+        assert!(engine
+            .render("hello.html", &ctx)
+            .unwrap()
+            .contains("<p>Hello Ferreiro</p>"));
+    }
+}
+
+#[test]
+fn test_engines_built_from_tera_have_the_framework_templates() {
+    let mut tera = tera::Tera::default();
+    tera.add_raw_template("ferreiro/errors/404.html", "Gone")
+        .unwrap();
+    let engine = TeraEngine::from_tera(tera).unwrap();
+
+    assert_eq!(
+        engine
+            .render("ferreiro/errors/404.html", &Context::new())
+            .unwrap(),
+        "Gone"
+    );
+    assert!(engine
+        .render("ferreiro/errors/500.html", &Context::new())
+        .is_ok());
+}
+
+#[test]
+fn test_framework_templates_are_available_from_disk_engines() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/templates");
+    let tera = TeraEngine::new(dir).unwrap();
+    let minijinja = MiniJinjaEngine::new(dir).unwrap();
+
+    for engine in [&tera as &dyn TemplateEngine, &minijinja] {
+        let html = engine
+            .render("ferreiro/errors/404.html", &Context::new())
+            .unwrap();
+        assert!(html.contains("Page not found"));
+
+        let html = engine
+            .render("hello.html", &context! { name: "disk" })
+            .unwrap();
+        assert!(html.contains("<p>Hello disk</p>"));
+    }
+}
+
+#[test]
+fn test_auth_flow_templates_render() {
+    let dir = tempfile::tempdir().unwrap();
+    let tera = TeraEngine::new(dir.path().to_str().unwrap()).unwrap();
+    let minijinja = MiniJinjaEngine::new(dir.path().to_str().unwrap()).unwrap();
+    let link =
+        context! { name: "Ana", email: "ana@example.com", url: "https://example.com/r?t=a&b" };
+
+    for engine in [&tera as &dyn TemplateEngine, &minijinja] {
+        let email = engine
+            .render("ferreiro/auth/password_reset_email.txt", &link)
+            .unwrap();
+        assert!(email.starts_with("Hi Ana,"));
+        // Plain text, so the link isn't escaped
+        assert!(email.contains("https://example.com/r?t=a&b"));
+        let email = engine
+            .render("ferreiro/auth/verification_email.txt", &link)
+            .unwrap();
+        assert!(email.contains("confirm that ana@example.com"));
+
+        let page = engine
+            .render(
+                "ferreiro/auth/password_reset_confirm.html",
+                &context! { valid_link: false },
+            )
+            .unwrap();
+        assert!(page.contains("<h1>Link expired</h1>"));
+        let page = engine
+            .render(
+                "ferreiro/auth/email_verified.html",
+                &context! { verified: true },
+            )
+            .unwrap();
+        assert!(page.contains("<h1>Email confirmed</h1>"));
+        assert!(!page.contains("Log in</a>"));
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::{Duration, TimeZone, Utc};
+use ferreiro_adapters_templates::filters;
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_adapters_templates::tera_adapter::TeraEngine;
+use ferreiro_adapters_templates::{context, TemplateEngine};
+use ferreiro_domain::values::Slug;
+
+#[test]
+fn test_text_filters() {
+    assert_eq!(filters::truncatewords("one two three four", 2), "one two …");
+    assert_eq!(
+        filters::linebreaks("Hi <you>\nthere\n\nBye"),
+        "<p>Hi &lt;you&gt;<br>there</p>\n\n<p>Bye</p>"
+    );
+    assert_eq!(
+        filters::urlize("See https://example.com."),
+        "See <a href=\"https://example.com\" rel=\"nofollow\">https://example.com</a>."
+    );
+    assert_eq!(
+        filters::markdown("# Hi\n\n<script>x</script>"),
+        "<h1>Hi</h1>\n&lt;script&gt;x&lt;/script&gt;"
+    );
+    assert_eq!(
+        filters::markdown("[a](javascript:alert(1)) [b](JaVaScript:x) ![c](data:image/png;base64,AA) <vbscript:x>"),
+        "<p><a href=\"#\">a</a> <a href=\"#\">b</a> <img src=\"#\" alt=\"c\" /> <a href=\"#\">vbscript:x</a></p>\n"
+    );
+    assert_eq!(
+        filters::markdown("[a](https://example.com) [b](/posts/1?x=a:b) <mailto:ana@example.com>"),
+        "<p><a href=\"https://example.com\">a</a> <a href=\"/posts/1?x=a:b\">b</a> <a href=\"mailto:ana@example.com\">mailto:ana@example.com</a></p>\n"
+    );
+}
+
+#[test]
+fn test_slugify_produces_valid_slug() {
+    let slug = filters::slugify("  Hello, World! 2025 ");
+    assert_eq!(slug, "hello-world-2025");
+    assert!(Slug::new(&slug).is_ok());
+    assert_eq!(filters::slugify("!!!"), "");
+}
+
+#[test]
+fn test_number_filters() {
+    assert_eq!(filters::pluralize(1.0, "s"), "");
+    assert_eq!(filters::pluralize(2.0, "y,ies"), "ies");
+    assert_eq!(filters::filesizeformat(1.0), "1 byte");
+    assert_eq!(filters::filesizeformat(1536.0), "1.5 KB");
+    assert_eq!(filters::filesizeformat(5.0 * 1024.0 * 1024.0), "5.0 MB");
+    assert_eq!(filters::intcomma("1234567"), "1,234,567");
+    assert_eq!(filters::intcomma("-1234.56"), "-1,234.56");
+    assert_eq!(filters::intcomma("Ana Silva"), "Ana Silva");
+    assert_eq!(filters::intcomma("12ab34"), "12ab34");
+    assert_eq!(filters::localized_intcomma("1.2.3", "pt-BR"), "1.2.3");
+}
+
+#[test]
+fn test_naturaltime() {
+    let now = Utc::now();
+    assert_eq!(filters::naturaltime(&now, &now), "now");
+    assert_eq!(
+        filters::naturaltime(&(now - Duration::minutes(3)), &now),
+        "3 minutes ago"
+    );
+    assert_eq!(
+        filters::naturaltime(&(now + Duration::hours(1)), &now),
+        "in 1 hour"
+    );
+}
+
+#[test]
+fn test_engines_render_filters_identically() {
+    let template = "{{ published_at | date(format=\"%Y-%m-%d\") }}|\
+                    {{ body | truncatewords(count=2) }}|\
+                    {{ title | slugify }}|\
+                    {{ count }} comment{{ count | pluralize }}|\
+                    {{ size | filesizeformat }}|\
+                    {{ views | intcomma }}|\
+                    {{ body | linebreaks }}|\
+                    {{ body | markdown }}";
+
+    let ctx = context! {
+        published_at: Utc.with_ymd_and_hms(2025, 12, 29, 10, 0, 0).unwrap(),
+        body: "Ferreiro is *fast* and fun",
+        title: "Why Hexagonal Architecture?",
+        count: 3,
+        size: 2048,
+        views: 1234567,
+    };
+
+    let tera = TeraEngine::new("templates").unwrap();
+    let minijinja = MiniJinjaEngine::new("templates").unwrap();
+
+    let from_tera = tera.render_string(template, &ctx).unwrap();
+    let from_minijinja = minijinja.render_string(template, &ctx).unwrap();
+
+    assert_eq!(from_tera, from_minijinja);
+    assert!(from_tera.starts_with(
+        "2025-12-29|Ferreiro is …|why-hexagonal-architecture|3 comments|2.0 KB|1,234,567|"
+    ));
+}
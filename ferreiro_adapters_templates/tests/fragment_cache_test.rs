@@ -0,0 +1,196 @@
+use async_trait::async_trait;
+use ferreiro_adapters_cache::{InMemoryCache, NamespacedCache};
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_adapters_templates::tera_adapter::TeraEngine;
+use ferreiro_adapters_templates::{context, TemplateEngine, TemplateError};
+use ferreiro_domain::ports::driven::{Cache, CacheError};
+use std::sync::Arc;
+use std::time::Duration;
+
+const SIDEBAR: &str =
+    r#"<aside>{% cache 300 "sidebar" post.id %}<b>{{ post.title }}</b>{% endcache %}</aside>"#;
+
+fn engines(dir: &std::path::Path, cache: NamespacedCache) -> Vec<Box<dyn TemplateEngine>> {
+    let dir = dir.to_str().unwrap();
+    vec![
+        Box::new(
+            TeraEngine::new(dir)
+                .unwrap()
+                .with_fragment_cache(Arc::new(cache.clone())),
+        ),
+        Box::new(
+            MiniJinjaEngine::new(dir)
+                .unwrap()
+                .with_fragment_cache(Arc::new(cache)),
+        ),
+    ]
+}
+
+#[test]
+fn test_cache_tag_reuses_fragments_per_key() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("sidebar.html"), SIDEBAR).unwrap();
+
+    for (i, engine) in engines(
+        dir.path(),
+        NamespacedCache::new(Arc::new(InMemoryCache::new()), "fragments"),
+    )
+    .into_iter()
+    .enumerate()
+    {
+        let first = context! { post: serde_json::json!({ "id": 1, "title": "<First>" }) };
+        let renamed = context! { post: serde_json::json!({ "id": 1, "title": "Renamed" }) };
+        let other = context! { post: serde_json::json!({ "id": 2, "title": "Second" }) };
+
+        let html = engine.render("sidebar.html", &first).unwrap();
+        assert_eq!(html, "<aside><b>&lt;First&gt;</b></aside>", "engine {}", i);
+        let html = engine.render("sidebar.html", &renamed).unwrap();
+        assert_eq!(html, "<aside><b>&lt;First&gt;</b></aside>", "engine {}", i);
+        let html = engine.render("sidebar.html", &other).unwrap();
+        assert_eq!(html, "<aside><b>Second</b></aside>", "engine {}", i);
+    }
+}
+
+// Multi-threaded, as a server runs, so lookups wait with `block_in_place`
+#[tokio::test(flavor = "multi_thread")]
+async fn test_invalidating_the_namespace_renders_again() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("sidebar.html"), SIDEBAR).unwrap();
+    let cache = NamespacedCache::new(Arc::new(InMemoryCache::new()), "fragments");
+
+    for engine in engines(dir.path(), cache.clone()) {
+        cache.invalidate().await.unwrap();
+        let before = context! { post: serde_json::json!({ "id": 1, "title": "Before" }) };
+        let after = context! { post: serde_json::json!({ "id": 1, "title": "After" }) };
+
+        assert!(engine
+            .render("sidebar.html", &before)
+            .unwrap()
+            .contains("Before"));
+        assert!(engine
+            .render("sidebar.html", &after)
+            .unwrap()
+            .contains("Before"));
+        cache.invalidate().await.unwrap();
+        assert!(engine
+            .render("sidebar.html", &after)
+            .unwrap()
+            .contains("After"));
+    }
+}
+
+#[test]
+fn test_cache_tag_without_backend_or_with_bad_syntax() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("nested.txt"),
+        "{% cache 0 outer %}[{{ n }}{% cache 60 inner n %}({{ n }}){% endcache %}]{% endcache %}",
+    )
+    .unwrap();
+
+    let tera = TeraEngine::new(dir.path().to_str().unwrap()).unwrap();
+    let minijinja = MiniJinjaEngine::new(dir.path().to_str().unwrap()).unwrap();
+    for engine in [&tera as &dyn TemplateEngine, &minijinja] {
+        assert_eq!(
+            engine.render("nested.txt", &context! { n: 1 }).unwrap(),
+            "[1(1)]"
+        );
+        assert_eq!(
+            engine.render("nested.txt", &context! { n: 2 }).unwrap(),
+            "[2(2)]"
+        );
+
+        let unclosed = engine.render_string("{% cache 60 sidebar %}oops", &context! {});
+        assert!(matches!(unclosed, Err(TemplateError::Parse(_))));
+        let no_name = engine.render_string("{% cache 60 %}x{% endcache %}", &context! {});
+        assert!(matches!(no_name, Err(TemplateError::Parse(_))));
+    }
+}
+
+#[test]
+fn test_empty_fragments_are_hits_and_keys_follow_locale_and_time_zone() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("when.txt"),
+        r#"{% cache 0 "when" %}{% if show %}{{ LANGUAGE_CODE }} {{ TIME_ZONE }}{% endif %}{% endcache %}"#,
+    )
+    .unwrap();
+
+    for engine in engines(
+        dir.path(),
+        NamespacedCache::new(Arc::new(InMemoryCache::new()), "fragments"),
+    ) {
+        let hidden = context! { show: false, LANGUAGE_CODE: "en", TIME_ZONE: "UTC" };
+        assert_eq!(engine.render("when.txt", &hidden).unwrap(), "");
+        // Cached as empty, so it stays empty
+        let shown = context! { show: true, LANGUAGE_CODE: "en", TIME_ZONE: "UTC" };
+        assert_eq!(engine.render("when.txt", &shown).unwrap(), "");
+
+        let pt = context! { show: true, LANGUAGE_CODE: "pt-BR", TIME_ZONE: "UTC" };
+        assert_eq!(engine.render("when.txt", &pt).unwrap(), "pt-BR UTC");
+        let tokyo = context! { show: true, LANGUAGE_CODE: "pt-BR", TIME_ZONE: "Asia/Tokyo" };
+        assert_eq!(
+            engine.render("when.txt", &tokyo).unwrap(),
+            "pt-BR Asia/Tokyo"
+        );
+    }
+}
+
+/// Answers only after yielding to the runtime, as a network backend does
+struct Remote(InMemoryCache);
+
+#[async_trait]
+impl Cache for Remote {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, CacheError> {
+        tokio::task::yield_now().await;
+        self.0.get(key).await
+    }
+    async fn set(&self, key: &str, value: &[u8], ttl: Option<Duration>) -> Result<(), CacheError> {
+        tokio::task::yield_now().await;
+        self.0.set(key, value, ttl).await
+    }
+    async fn delete(&self, key: &str) -> Result<(), CacheError> {
+        self.0.delete(key).await
+    }
+    async fn incr(&self, key: &str, delta: i64) -> Result<i64, CacheError> {
+        self.0.incr(key, delta).await
+    }
+}
+
+// A current-thread runtime could never run the lookup while the render
+// blocks its only thread
+#[tokio::test]
+async fn test_remote_backends_fail_the_render_on_a_current_thread_runtime() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("sidebar.html"), SIDEBAR).unwrap();
+    let dir = dir.path().to_str().unwrap();
+    let post = context! { post: serde_json::json!({ "id": 1, "title": "First" }) };
+
+    let tera = TeraEngine::new(dir)
+        .unwrap()
+        .with_fragment_cache(Arc::new(Remote(InMemoryCache::new())));
+    let minijinja = MiniJinjaEngine::new(dir)
+        .unwrap()
+        .with_fragment_cache(Arc::new(Remote(InMemoryCache::new())));
+    for engine in [&tera as &dyn TemplateEngine, &minijinja] {
+        let rendered = engine.render("sidebar.html", &post);
+        assert!(matches!(rendered, Err(TemplateError::Render(_))));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_remote_backends_work_on_a_multi_threaded_runtime() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("sidebar.html"), SIDEBAR).unwrap();
+    let engine = TeraEngine::new(dir.path().to_str().unwrap())
+        .unwrap()
+        .with_fragment_cache(Arc::new(Remote(InMemoryCache::new())));
+
+    let first = context! { post: serde_json::json!({ "id": 1, "title": "First" }) };
+    let renamed = context! { post: serde_json::json!({ "id": 1, "title": "Renamed" }) };
+    engine.render("sidebar.html", &first).unwrap();
+    assert!(engine
+        .render("sidebar.html", &renamed)
+        .unwrap()
+        .contains("First"));
+}
@@ -0,0 +1,79 @@
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_adapters_templates::tera_adapter::TeraEngine;
+use ferreiro_adapters_templates::{context, TemplateEngine, TemplateError};
+use std::time::{Duration, Instant};
+
+/// Polls until `check` passes — file events are delivered asynchronously
+fn eventually(check: impl Fn() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if check() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+#[test]
+fn test_tera_reloads_changed_templates() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("hello.html");
+    std::fs::write(&path, "Hello {{ name }}").unwrap();
+
+    let engine = TeraEngine::with_hot_reload(dir.path().to_str().unwrap()).unwrap();
+    let ctx = context! { name: "Ferreiro" };
+    assert_eq!(engine.render("hello.html", &ctx).unwrap(), "Hello Ferreiro");
+
+    std::fs::write(&path, "Olá {{ name }}").unwrap();
+    assert!(eventually(|| engine
+        .render("hello.html", &ctx)
+        .is_ok_and(|html| html == "Olá Ferreiro")));
+}
+
+#[test]
+fn test_tera_reports_parse_errors_until_fixed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("hello.html");
+    std::fs::write(&path, "Hello {{ name }}").unwrap();
+
+    let engine = TeraEngine::with_hot_reload(dir.path().to_str().unwrap()).unwrap();
+    let ctx = context! { name: "Ferreiro" };
+
+    std::fs::write(&path, "Hello {{ name ").unwrap();
+    assert!(eventually(|| matches!(
+        engine.render("hello.html", &ctx),
+        Err(TemplateError::Parse(_))
+    )));
+    assert!(matches!(
+        engine.render_string("Hello {{ name }}", &ctx),
+        Err(TemplateError::Parse(_))
+    ));
+
+    std::fs::write(&path, "Hi {{ name }}").unwrap();
+    assert!(eventually(|| engine
+        .render("hello.html", &ctx)
+        .is_ok_and(|html| html == "Hi Ferreiro")));
+}
+
+#[test]
+fn test_minijinja_reloads_changed_templates() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("hello.html");
+    std::fs::write(&path, "Hello {{ name }}").unwrap();
+
+    let engine = MiniJinjaEngine::with_hot_reload(dir.path().to_str().unwrap()).unwrap();
+    let ctx = context! { name: "Ferreiro" };
+    assert_eq!(engine.render("hello.html", &ctx).unwrap(), "Hello Ferreiro");
+
+    std::fs::write(&path, "Hello {{ name ").unwrap();
+    assert!(eventually(|| matches!(
+        engine.render("hello.html", &ctx),
+        Err(TemplateError::Parse(_))
+    )));
+
+    std::fs::write(&path, "Olá {{ name }}").unwrap();
+    assert!(eventually(|| engine
+        .render("hello.html", &ctx)
+        .is_ok_and(|html| html == "Olá Ferreiro")));
+}
@@ -0,0 +1,63 @@
+use ferreiro_adapters_i18n::{Catalog, Catalogs};
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_adapters_templates::tera_adapter::TeraEngine;
+use ferreiro_adapters_templates::{context, TemplateEngine};
+use std::sync::Arc;
+
+fn catalogs() -> Arc<Catalogs> {
+    let mut catalogs = Catalogs::builtin();
+    let mut pt = Catalog::new();
+    pt.insert("blog.greeting", "Olá, {name}!");
+    catalogs.add("pt-BR", pt);
+    Arc::new(catalogs)
+}
+
+#[test]
+fn test_trans_uses_the_context_locale_in_both_engines() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("page.txt"),
+        r#"{{ trans(key="blog.greeting", name=name) }}|{{ trans(key="error.title.empty") }}|{{ trans(key="blog.unknown") }}"#,
+    )
+    .unwrap();
+    let dir = dir.path().to_str().unwrap();
+
+    let tera = TeraEngine::new(dir).unwrap().with_translator(catalogs());
+    let minijinja = MiniJinjaEngine::new(dir)
+        .unwrap()
+        .with_translator(catalogs());
+
+    for engine in [&tera as &dyn TemplateEngine, &minijinja] {
+        let pt = context! { LANGUAGE_CODE: "pt-BR", name: "Ana" };
+        assert_eq!(
+            engine.render("page.txt", &pt).unwrap(),
+            "Olá, Ana!|O título não pode ficar vazio|blog.unknown"
+        );
+
+        let es = context! { LANGUAGE_CODE: "es", name: "Ana" };
+        assert_eq!(
+            engine.render("page.txt", &es).unwrap(),
+            "blog.greeting|El título no puede estar vacío|blog.unknown"
+        );
+
+        let default = context! { name: "Ana" };
+        assert_eq!(
+            engine.render("page.txt", &default).unwrap(),
+            "blog.greeting|Title cannot be empty|blog.unknown"
+        );
+    }
+}
+
+#[test]
+fn test_minijinja_accepts_a_positional_key() {
+    let engine = MiniJinjaEngine::new("unused")
+        .unwrap()
+        .with_translator(catalogs());
+    let html = engine
+        .render_string(
+            r#"{{ trans("error.body.empty") }}"#,
+            &context! { LANGUAGE_CODE: "pt-BR" },
+        )
+        .unwrap();
+    assert_eq!(html, "O corpo não pode ficar vazio");
+}
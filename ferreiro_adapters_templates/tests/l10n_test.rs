@@ -0,0 +1,88 @@
+use chrono::{TimeZone, Utc};
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_adapters_templates::tera_adapter::TeraEngine;
+use ferreiro_adapters_templates::{context, filters, Localizer, TemplateEngine};
+
+#[test]
+fn test_engines_show_times_in_the_active_zone_and_locale() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("post.txt"),
+        r#"{{ published | date }}|{{ published | date(format="%H:%M") }}|{{ published | date(format="%H:%M", tz="Asia/Tokyo") }}|{{ published | localtime }}|{{ views | intcomma }}|{{ ratio | numberformat(decimals=2) }}"#,
+    )
+    .unwrap();
+    let dir = dir.path().to_str().unwrap();
+
+    let tera = TeraEngine::new(dir).unwrap();
+    let minijinja = MiniJinjaEngine::new(dir).unwrap();
+    // 01:30 UTC is still the previous evening in São Paulo
+    let published = Utc.with_ymd_and_hms(2024, 3, 1, 1, 30, 0).unwrap();
+
+    for engine in [&tera as &dyn TemplateEngine, &minijinja] {
+        let brazil = context! {
+            LANGUAGE_CODE: "pt-BR",
+            TIME_ZONE: "America/Sao_Paulo",
+            published: published,
+            views: 1234567,
+            ratio: 1234.5,
+        };
+        assert_eq!(
+            engine.render("post.txt", &brazil).unwrap(),
+            "29 de fevereiro de 2024|22:30|10:30|2024-02-29T22:30:00-03:00|1.234.567|1.234,50"
+        );
+
+        let unset = context! { published: published, views: 1234567, ratio: 1234.5 };
+        assert_eq!(
+            engine.render("post.txt", &unset).unwrap(),
+            "Mar 1, 2024|01:30|10:30|2024-03-01T01:30:00+00:00|1,234,567|1,234.50"
+        );
+    }
+}
+
+#[test]
+fn test_unknown_time_zone_argument_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path().to_str().unwrap();
+    let tera = TeraEngine::new(dir).unwrap();
+    let minijinja = MiniJinjaEngine::new(dir).unwrap();
+    let ctx = context! { published: "2024-03-01T01:30:00Z" };
+
+    for engine in [&tera as &dyn TemplateEngine, &minijinja] {
+        let error = engine
+            .render_string(r#"{{ published | date(tz="Mars/Olympus") }}"#, &ctx)
+            .unwrap_err();
+        assert!(format!("{:?}", error).contains("unknown time zone"));
+    }
+}
+
+#[test]
+fn test_localizer_formats_like_the_filters() {
+    let published = Utc.with_ymd_and_hms(2024, 3, 1, 1, 30, 0).unwrap();
+
+    let brazil = Localizer::new("pt-BR", "America/Sao_Paulo");
+    assert_eq!(brazil.date(&published), "29 de fevereiro de 2024");
+    assert_eq!(
+        brazil.format(&published, "%A, %H:%M").unwrap(),
+        "quinta, 22:30"
+    );
+    assert_eq!(
+        brazil.localtime(&published).to_rfc3339(),
+        "2024-02-29T22:30:00-03:00"
+    );
+    assert_eq!(brazil.number(-9876543.219, 2), "-9.876.543,22");
+
+    let ctx = context! { LANGUAGE_CODE: "es", TIME_ZONE: "Europe/Madrid" };
+    let spain = Localizer::from_context(&ctx);
+    assert_eq!(spain.date(&published), "1 de marzo de 2024");
+    assert_eq!(spain.timezone(), chrono_tz::Europe::Madrid);
+
+    assert_eq!(Localizer::default().date(&published), "Mar 1, 2024");
+    assert_eq!(
+        Localizer::new("en", "Nowhere/City").number(1234.5, 1),
+        "1,234.5"
+    );
+    assert_eq!(
+        filters::localized_intcomma("1234567.25", "de"),
+        "1.234.567,25"
+    );
+}
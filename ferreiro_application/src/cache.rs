@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use ferreiro_domain::ports::driven::{Cache, CacheError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::time::Duration;
+
+/// Typed access to any `Cache`, stored as JSON
+#[async_trait]
+pub trait CacheExt: Cache {
+    /// A value that no longer deserializes as `T` (say, after a struct
+    /// changed shape) is treated as a miss
+    async fn get_typed<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, CacheError> {
+        Ok(self
+            .get(key)
+            .await?
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok()))
+    }
+
+    async fn set_typed<T: Serialize + Sync + ?Sized>(
+        &self,
+        key: &str,
+        value: &T,
+        ttl: Option<Duration>,
+    ) -> Result<(), CacheError> {
+        let bytes =
+            serde_json::to_vec(value).map_err(|e| CacheError::Serialization(e.to_string()))?;
+        self.set(key, &bytes, ttl).await
+    }
+
+    async fn get_many_typed<T: DeserializeOwned>(
+        &self,
+        keys: &[&str],
+    ) -> Result<Vec<Option<T>>, CacheError> {
+        Ok(self
+            .get_many(keys)
+            .await?
+            .into_iter()
+            .map(|value| value.and_then(|bytes| serde_json::from_slice(&bytes).ok()))
+            .collect())
+    }
+}
+
+impl<C: Cache + ?Sized> CacheExt for C {}
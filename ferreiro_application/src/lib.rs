@@ -1,3 +1,5 @@
+pub mod cache;
 pub mod services;
 
+pub use cache::CacheExt;
 pub use services::*;
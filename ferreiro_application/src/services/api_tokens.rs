@@ -0,0 +1,149 @@
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use ferreiro_domain::models::{ApiToken, AuthUser, User};
+use ferreiro_domain::ports::driven::{ApiTokenRepository, UserRepository};
+use ferreiro_domain::ports::driving::{
+    ApiTokenService, CreateApiTokenCommand, CreatedApiToken, ServiceError,
+};
+use ferreiro_domain::values::{ApiTokenId, UserId};
+use sha2::{Digest, Sha256};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Marks a string as one of our tokens, so it can be spotted in a leak
+const SECRET_MARKER: &str = "ferreiro_pat_";
+
+/// Characters of the random part kept in `ApiToken::prefix`
+const PREFIX_LEN: usize = 8;
+
+/// `last_used_at` is written at most this often, so a busy client doesn't
+/// turn every request into a write
+const LAST_USED_GRANULARITY: Duration = Duration::minutes(1);
+
+/// Personal access tokens, stored as SHA-256 hashes
+///
+/// A secret is `ferreiro_pat_` followed by 64 random hex characters. That
+/// is far too long to guess, so a plain hash is enough to look it up by and
+/// keeps a database leak from exposing usable tokens.
+pub struct ApiTokenServiceImpl<T, U, M = User>
+where
+    T: ApiTokenRepository,
+    U: UserRepository<M>,
+    M: AuthUser,
+{
+    tokens: Arc<T>,
+    users: Arc<U>,
+    model: PhantomData<fn() -> M>,
+}
+
+impl<T, U, M> ApiTokenServiceImpl<T, U, M>
+where
+    T: ApiTokenRepository,
+    U: UserRepository<M>,
+    M: AuthUser,
+{
+    pub fn new(tokens: Arc<T>, users: Arc<U>) -> Self {
+        Self {
+            tokens,
+            users,
+            model: PhantomData,
+        }
+    }
+
+    fn hash(secret: &str) -> String {
+        format!("{:x}", Sha256::digest(secret.as_bytes()))
+    }
+}
+
+#[async_trait]
+impl<T, U, M> ApiTokenService<M> for ApiTokenServiceImpl<T, U, M>
+where
+    T: ApiTokenRepository + 'static,
+    U: UserRepository<M> + 'static,
+    M: AuthUser,
+{
+    async fn create(&self, cmd: CreateApiTokenCommand) -> Result<CreatedApiToken, ServiceError> {
+        self.users
+            .find_by_id(&cmd.user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .ok_or(ServiceError::NotFound)?;
+
+        let random = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let secret = format!("{}{}", SECRET_MARKER, random);
+        let token = ApiToken::new(
+            cmd.user_id,
+            &cmd.name,
+            Self::hash(&secret),
+            format!("{}{}", SECRET_MARKER, &random[..PREFIX_LEN]),
+            cmd.scopes.into_iter().collect(),
+            cmd.expires_at,
+        )?;
+
+        self.tokens
+            .save(&token)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+
+        Ok(CreatedApiToken { token, secret })
+    }
+
+    async fn list(&self, user_id: &UserId) -> Result<Vec<ApiToken>, ServiceError> {
+        self.tokens
+            .list_for_user(user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))
+    }
+
+    async fn revoke(&self, id: &ApiTokenId, user_id: &UserId) -> Result<(), ServiceError> {
+        let token = self
+            .tokens
+            .find_by_id(id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .filter(|token| token.user_id() == user_id)
+            .ok_or(ServiceError::NotFound)?;
+
+        self.tokens
+            .delete(token.id())
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))
+    }
+
+    async fn authenticate(&self, secret: &str) -> Result<(M, ApiToken), ServiceError> {
+        if !secret.starts_with(SECRET_MARKER) {
+            return Err(ServiceError::Unauthorized);
+        }
+
+        let now = Utc::now();
+        let mut token = self
+            .tokens
+            .find_by_hash(&Self::hash(secret))
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .filter(|token| !token.is_expired_at(now))
+            .ok_or(ServiceError::Unauthorized)?;
+
+        let user = self
+            .users
+            .find_by_id(token.user_id())
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .filter(M::is_active)
+            .ok_or(ServiceError::Unauthorized)?;
+
+        let stale = token
+            .last_used_at()
+            .is_none_or(|last_used_at| now - last_used_at >= LAST_USED_GRANULARITY);
+        if stale {
+            token.mark_used_at(now);
+            self.tokens
+                .save(&token)
+                .await
+                .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        }
+
+        Ok((user, token))
+    }
+}
@@ -0,0 +1,969 @@
+use crate::services::{
+    ensure_deliverable, ensure_not_breached, AccountTokens, LoginThrottle, TokenPurpose,
+};
+use crate::CacheExt;
+use async_trait::async_trait;
+use chrono::Utc;
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::events::DomainEvent;
+use ferreiro_domain::models::{AuthUser, NewUser, User};
+use ferreiro_domain::passwords::{PasswordPolicy, UserAttributes};
+use ferreiro_domain::ports::driven::{
+    BreachedPasswords, Cache, EventPublisher, LoginFailureStore, MxLookup, PasswordHasher,
+    RepositoryError, TokenError, TokenIssuer, TokenPair, UserRepository,
+};
+use ferreiro_domain::ports::driving::{
+    AuthService, AuthenticatedUser, ChangePasswordCommand, Credentials, LoginCommand, MfaService,
+    RegisterCommand, ResetPasswordCommand, ServiceError,
+};
+use ferreiro_domain::values::{Email, SecretToken, UserId};
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+use std::net::IpAddr;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Two weeks, as Django's `SESSION_COOKIE_AGE`
+const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+/// How long a user has to enter their second factor after the password
+const PENDING_LOGIN_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Wrong second-factor codes before the user has to start over
+const MAX_MFA_ATTEMPTS: u32 = 5;
+
+/// Checked against unknown and inactive users, so turning them away takes
+/// as long as a wrong password
+const DUMMY_PASSWORD: &str = "ferreiro-dummy-password";
+
+/// What a session token maps to; `auth_hash` stops matching once the
+/// user's password changes
+#[derive(Serialize, Deserialize)]
+struct StoredSession {
+    user_id: UserId,
+    auth_hash: String,
+}
+
+/// A login that passed the password and awaits the second factor
+#[derive(Serialize, Deserialize)]
+struct PendingLogin {
+    user_id: UserId,
+    attempts: u32,
+}
+
+/// Registration, login, password changes and resets, and email verification
+///
+/// New passwords go through `PasswordPolicy::default()` unless another
+/// policy is set, and through a breached-password check when one is
+/// configured. With `with_mx_lookup`, registering with an address whose
+/// domain takes no mail fails with `UndeliverableEmail`. Session tokens map to user ids in `sessions`, and stop
+/// working when the user's password is changed or reset, except for the
+/// session a change was made from.
+///
+/// Reset and verification links, and sessions, are signed by `tokens`;
+/// give every instance the same key from configuration, or links and
+/// sessions stop working across restarts and instances. The service
+/// publishes the tokens in `PasswordResetRequested` and
+/// `EmailVerificationRequested`; sending them is up to a subscriber.
+///
+/// With `with_token_issuer`, logging in returns an access and refresh
+/// token pair instead of a session, for stateless clients.
+///
+/// With `with_mfa`, users who enabled a second factor log in in two steps:
+/// `login` checks the password and returns `Credentials::MfaRequired`, and
+/// `complete_login` checks the code and starts the session. With
+/// `with_login_throttle` too, wrong codes are counted like wrong passwords
+/// and lock the account out the same way.
+///
+/// Every failed login publishes `LoginFailed`. With `with_login_throttle`,
+/// failures are also counted per identifier and per IP address, and
+/// repeated ones have to wait, then lock the account out (see
+/// `LoginThrottle`).
+///
+/// Users are `User`s unless `U` stores another `AuthUser` model; they log
+/// in with its identifier.
+pub struct AuthServiceImpl<U, E, M = User>
+where
+    U: UserRepository<M>,
+    E: EventPublisher,
+    M: AuthUser,
+{
+    users: Arc<U>,
+    events: Arc<E>,
+    hasher: Arc<dyn PasswordHasher>,
+    sessions: Arc<dyn Cache>,
+    password_policy: PasswordPolicy,
+    breached_passwords: Option<Arc<dyn BreachedPasswords>>,
+    mx_lookup: Option<Arc<dyn MxLookup>>,
+    session_ttl: Duration,
+    tokens: AccountTokens,
+    require_email_verification: bool,
+    token_issuer: Option<Arc<dyn TokenIssuer>>,
+    mfa: Option<Arc<dyn MfaService>>,
+    require_mfa_for_staff: bool,
+    login_failures: Option<Arc<dyn LoginFailureStore>>,
+    throttle: LoginThrottle,
+    /// Hashed on first use, from `DUMMY_PASSWORD`
+    dummy_hash: OnceLock<String>,
+    model: PhantomData<fn() -> M>,
+}
+
+impl<U, E, M> AuthServiceImpl<U, E, M>
+where
+    U: UserRepository<M>,
+    E: EventPublisher,
+    M: AuthUser,
+{
+    pub fn new(
+        users: Arc<U>,
+        events: Arc<E>,
+        hasher: Arc<dyn PasswordHasher>,
+        sessions: Arc<dyn Cache>,
+        tokens: AccountTokens,
+    ) -> Self {
+        Self {
+            users,
+            events,
+            hasher,
+            sessions,
+            password_policy: PasswordPolicy::default(),
+            breached_passwords: None,
+            mx_lookup: None,
+            session_ttl: DEFAULT_SESSION_TTL,
+            tokens,
+            require_email_verification: false,
+            token_issuer: None,
+            mfa: None,
+            require_mfa_for_staff: false,
+            login_failures: None,
+            throttle: LoginThrottle::default(),
+            dummy_hash: OnceLock::new(),
+            model: PhantomData,
+        }
+    }
+
+    pub fn with_password_policy(mut self, policy: PasswordPolicy) -> Self {
+        self.password_policy = policy;
+        self
+    }
+
+    pub fn with_breached_passwords(mut self, source: Arc<dyn BreachedPasswords>) -> Self {
+        self.breached_passwords = Some(source);
+        self
+    }
+
+    /// Check that new users' email domains take mail, as `ensure_deliverable`
+    pub fn with_mx_lookup(mut self, lookup: Arc<dyn MxLookup>) -> Self {
+        self.mx_lookup = Some(lookup);
+        self
+    }
+
+    pub fn with_session_ttl(mut self, ttl: Duration) -> Self {
+        self.session_ttl = ttl;
+        self
+    }
+
+    /// New users start inactive, so they can't log in, until they verify
+    /// their email
+    pub fn require_email_verification(mut self) -> Self {
+        self.require_email_verification = true;
+        self
+    }
+
+    /// Log in with access and refresh tokens rather than sessions
+    pub fn with_token_issuer(mut self, issuer: Arc<dyn TokenIssuer>) -> Self {
+        self.token_issuer = Some(issuer);
+        self
+    }
+
+    /// Ask users who enabled a second factor for it when they log in
+    pub fn with_mfa(mut self, mfa: Arc<dyn MfaService>) -> Self {
+        self.mfa = Some(mfa);
+        self
+    }
+
+    /// Like `with_mfa`, and staff without a second factor must enroll one
+    /// before they can log in; they get `Credentials::MfaEnrollmentRequired`
+    pub fn require_mfa_for_staff(mut self, mfa: Arc<dyn MfaService>) -> Self {
+        self.mfa = Some(mfa);
+        self.require_mfa_for_staff = true;
+        self
+    }
+
+    /// Count failed logins in `store`, and slow down or lock out repeated
+    /// ones as `throttle` says
+    pub fn with_login_throttle(
+        mut self,
+        store: Arc<dyn LoginFailureStore>,
+        throttle: LoginThrottle,
+    ) -> Self {
+        self.login_failures = Some(store);
+        self.throttle = throttle;
+        self
+    }
+
+    /// The user `token` was issued to for `purpose`, if it is still valid
+    async fn user_for_token(&self, purpose: TokenPurpose, token: &str) -> Result<M, ServiceError> {
+        let user_id = AccountTokens::user_id(token).ok_or(DomainError::InvalidToken)?;
+        let user = self
+            .users
+            .find_by_id(&user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .ok_or(DomainError::InvalidToken)?;
+
+        if !self.tokens.check_token(purpose, &user, token) {
+            return Err(DomainError::InvalidToken.into());
+        }
+        Ok(user)
+    }
+
+    /// Runs the policy, then the breach check, and hashes what passes
+    async fn hash_new_password(
+        &self,
+        password: &str,
+        user: &UserAttributes<'_>,
+    ) -> Result<String, ServiceError> {
+        self.password_policy.validate(password, user)?;
+        if let Some(source) = &self.breached_passwords {
+            ensure_not_breached(source.as_ref(), password).await?;
+        }
+        self.hasher
+            .hash(password)
+            .map_err(|e| ServiceError::Internal(e.to_string()))
+    }
+
+    /// Registers a user who signs in some other way, such as through a
+    /// social account, so there is no password to check against the policy
+    /// or the breached list
+    pub(crate) async fn register_without_password(
+        &self,
+        email: &str,
+        name: String,
+    ) -> Result<M, ServiceError> {
+        let cmd = RegisterCommand {
+            email: email.to_string(),
+            password: String::new(),
+            name,
+            identifier: None,
+        };
+        self.create_user(cmd, false).await
+    }
+
+    /// `register`, or with `usable_password` false, a user whose password
+    /// can never match, for whom `cmd.password` is ignored
+    async fn create_user(
+        &self,
+        cmd: RegisterCommand,
+        usable_password: bool,
+    ) -> Result<M, ServiceError> {
+        let email = Email::new(&cmd.email)?;
+        let name = cmd.name.trim().to_string();
+        let identifier =
+            M::normalize_identifier(cmd.identifier.as_deref().unwrap_or(email.as_str()))?;
+
+        // Spares hashing the password; two registrations racing can both
+        // pass it, and the repository's unique constraint stops the second
+        let taken = self
+            .users
+            .exists_by_email(&email)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            || self
+                .users
+                .find_by_identifier(&identifier)
+                .await
+                .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+                .is_some();
+        if taken {
+            return Err(DomainError::UserAlreadyExists.into());
+        }
+        if let Some(lookup) = &self.mx_lookup {
+            ensure_deliverable(lookup.as_ref(), &email).await?;
+        }
+
+        let password_hash = if usable_password {
+            self.hash_new_password(&cmd.password, &UserAttributes::new(email.as_str(), &name))
+                .await?
+        } else {
+            String::new()
+        };
+        let mut user = M::create(NewUser {
+            identifier,
+            email,
+            name,
+            password_hash,
+        })?;
+        if !usable_password {
+            user.set_unusable_password();
+        }
+        if self.require_email_verification {
+            user.await_verification();
+        }
+
+        self.users.save(&user).await.map_err(|e| match e {
+            RepositoryError::Conflict => DomainError::UserAlreadyExists.into(),
+            e => ServiceError::Internal(format!("{:?}", e)),
+        })?;
+
+        self.events
+            .publish(DomainEvent::UserRegistered {
+                user_id: user.id().clone(),
+                email: user.email().to_string(),
+                occurred_at: Utc::now(),
+            })
+            .await
+            .ok();
+
+        Ok(user)
+    }
+
+    /// Users without a usable password never match
+    fn verify_password(&self, user: &M, password: &str) -> Result<bool, ServiceError> {
+        if !user.has_usable_password() {
+            self.verify_dummy(password)?;
+            return Ok(false);
+        }
+        self.hasher
+            .verify(password, user.password_hash())
+            .map_err(|e| ServiceError::Internal(e.to_string()))
+    }
+
+    /// Does the work of checking a password, for a user who can't log in
+    /// anyway
+    fn verify_dummy(&self, password: &str) -> Result<(), ServiceError> {
+        let hash = match self.dummy_hash.get() {
+            Some(hash) => hash,
+            None => {
+                let hash = self
+                    .hasher
+                    .hash(DUMMY_PASSWORD)
+                    .map_err(|e| ServiceError::Internal(e.to_string()))?;
+                self.dummy_hash.get_or_init(|| hash)
+            }
+        };
+        self.hasher
+            .verify(password, hash)
+            .map(drop)
+            .map_err(|e| ServiceError::Internal(e.to_string()))
+    }
+
+    /// Normalized as lookups are, so every spelling that finds an account,
+    /// such as `ANA@EXAMPLE.COM` or an IDN in Unicode, counts against the
+    /// one key that unlocking and resetting clear
+    fn account_key(identifier: &str) -> String {
+        let normalized = M::normalize_identifier(identifier)
+            .unwrap_or_else(|_| identifier.trim().to_lowercase());
+        format!("login:account:{}", normalized)
+    }
+
+    fn ip_key(ip_address: IpAddr) -> String {
+        format!("login:ip:{}", ip_address)
+    }
+
+    /// `TooManyLoginAttempts` while the identifier or the address has to wait
+    async fn ensure_not_throttled(
+        &self,
+        identifier: &str,
+        ip_address: Option<IpAddr>,
+    ) -> Result<(), ServiceError> {
+        let Some(store) = &self.login_failures else {
+            return Ok(());
+        };
+        let mut keys = vec![(Self::account_key(identifier), self.throttle.account)];
+        if let Some(ip_address) = ip_address {
+            keys.push((Self::ip_key(ip_address), self.throttle.ip_address));
+        }
+
+        let now = Utc::now();
+        for (key, limits) in keys {
+            let failures = store
+                .get(&key)
+                .await
+                .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+            let until = failures
+                .and_then(|failures| self.throttle.blocked_until(limits, &failures))
+                .filter(|until| *until > now);
+            if let Some(until) = until {
+                let millis = (until - now).num_milliseconds();
+                return Err(DomainError::TooManyLoginAttempts {
+                    retry_after: ((millis + 999) / 1000) as u64,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Publishes `LoginFailed`, counts the failure when throttling, and
+    /// publishes `AccountLocked` when it locks the account
+    async fn login_failed(
+        &self,
+        identifier: &str,
+        user: Option<&M>,
+        ip_address: Option<IpAddr>,
+    ) -> Result<(), ServiceError> {
+        let user_id = user.map(|user| user.id().clone());
+        self.events
+            .publish(DomainEvent::LoginFailed {
+                identifier: identifier.trim().to_string(),
+                user_id: user_id.clone(),
+                ip_address,
+                occurred_at: Utc::now(),
+            })
+            .await
+            .ok();
+
+        let Some(store) = &self.login_failures else {
+            return Ok(());
+        };
+        let ttl = self.throttle.ttl();
+        if let Some(ip_address) = ip_address {
+            store
+                .record(&Self::ip_key(ip_address), ttl)
+                .await
+                .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        }
+        let failures = store
+            .record(&Self::account_key(identifier), ttl)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+
+        if let Some(locked_until) = self.throttle.locked_until(self.throttle.account, &failures) {
+            self.events
+                .publish(DomainEvent::AccountLocked {
+                    identifier: identifier.trim().to_string(),
+                    user_id,
+                    locked_until,
+                    occurred_at: Utc::now(),
+                })
+                .await
+                .ok();
+        }
+        Ok(())
+    }
+
+    /// Forgets the failures counted against `identifier`
+    async fn clear_login_failures(&self, identifier: &str) -> Result<(), ServiceError> {
+        let Some(store) = &self.login_failures else {
+            return Ok(());
+        };
+        store
+            .clear(&Self::account_key(identifier))
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))
+    }
+
+    /// Starts a session, or issues tokens, for a user another backend has
+    /// authenticated, as `SocialAuthServiceImpl` does; `Unauthorized` for
+    /// unknown and inactive users. A second factor is still asked for, as
+    /// after `login`.
+    ///
+    /// Not part of `AuthService`, which request handlers get, since it logs
+    /// in as anyone.
+    pub(crate) async fn login_user(
+        &self,
+        user_id: &UserId,
+    ) -> Result<AuthenticatedUser<M>, ServiceError> {
+        let user = self
+            .users
+            .find_by_id(user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .filter(M::is_active)
+            .ok_or(ServiceError::Unauthorized)?;
+        self.first_factor_passed(user).await
+    }
+
+    /// Starts the login of a user who passed the first factor, or asks for
+    /// the second
+    async fn first_factor_passed(&self, user: M) -> Result<AuthenticatedUser<M>, ServiceError> {
+        // `require_mfa_for_staff` always comes with an `MfaService`
+        let required = self.require_mfa_for_staff && user.is_staff();
+        let Some(mfa) = &self.mfa else {
+            return self.start(user).await;
+        };
+        let enabled = mfa.is_enabled(user.id()).await?;
+        if !enabled && !required {
+            return self.start(user).await;
+        }
+
+        let token = Uuid::new_v4().simple().to_string();
+        self.set_pending_login(
+            &token,
+            &PendingLogin {
+                user_id: user.id().clone(),
+                attempts: 0,
+            },
+        )
+        .await?;
+        Ok(AuthenticatedUser {
+            user,
+            credentials: if enabled {
+                Credentials::MfaRequired(token)
+            } else {
+                Credentials::MfaEnrollmentRequired(token)
+            },
+        })
+    }
+
+    async fn set_pending_login(
+        &self,
+        token: &str,
+        pending: &PendingLogin,
+    ) -> Result<(), ServiceError> {
+        self.sessions
+            .set_typed(
+                &Self::pending_login_key(token),
+                pending,
+                Some(PENDING_LOGIN_TTL),
+            )
+            .await
+            .map_err(|e| ServiceError::Internal(e.to_string()))
+    }
+
+    fn pending_login_key(token: &str) -> String {
+        format!("auth:mfa:{}", token)
+    }
+
+    /// A session for `user`, or tokens with a token issuer
+    async fn start(&self, user: M) -> Result<AuthenticatedUser<M>, ServiceError> {
+        if let Some(issuer) = &self.token_issuer {
+            let tokens = issuer.issue(user.id()).await.map_err(Self::token_error)?;
+            return Ok(AuthenticatedUser {
+                user,
+                credentials: Credentials::Tokens(tokens),
+            });
+        }
+
+        let session_token = Uuid::new_v4().simple().to_string();
+        self.store_session(&session_token, &user).await?;
+
+        Ok(AuthenticatedUser {
+            user,
+            credentials: Credentials::Session(session_token),
+        })
+    }
+
+    async fn store_session(&self, token: &str, user: &M) -> Result<(), ServiceError> {
+        self.sessions
+            .set_typed(
+                &Self::session_key(token),
+                &StoredSession {
+                    user_id: user.id().clone(),
+                    auth_hash: self.tokens.session_hash(user),
+                },
+                Some(self.session_ttl),
+            )
+            .await
+            .map_err(|e| ServiceError::Internal(e.to_string()))
+    }
+
+    fn session_key(token: &str) -> String {
+        format!("auth:session:{}", token)
+    }
+
+    /// Ends the token logins of a user whose password changed or who was
+    /// deactivated; sessions notice both on their own
+    async fn revoke_tokens(&self, user_id: &UserId) -> Result<(), ServiceError> {
+        match &self.token_issuer {
+            Some(issuer) => issuer.revoke_all(user_id).await.map_err(Self::token_error),
+            None => Ok(()),
+        }
+    }
+
+    /// `Unauthorized` unless `staff_id` is active staff
+    async fn ensure_staff(&self, staff_id: &UserId) -> Result<(), ServiceError> {
+        let staff = self
+            .users
+            .find_by_id(staff_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        if !staff.is_some_and(|staff| staff.is_active() && staff.is_staff()) {
+            return Err(ServiceError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Bad tokens are the client's problem; key and storage failures ours
+    fn token_error(error: TokenError) -> ServiceError {
+        match error {
+            TokenError::Key(_) | TokenError::Storage(_) => {
+                ServiceError::Internal(error.to_string())
+            }
+            _ => ServiceError::Unauthorized,
+        }
+    }
+}
+
+#[async_trait]
+impl<U, E, M> AuthService<M> for AuthServiceImpl<U, E, M>
+where
+    U: UserRepository<M> + 'static,
+    E: EventPublisher + 'static,
+    M: AuthUser,
+{
+    async fn register(&self, cmd: RegisterCommand) -> Result<M, ServiceError> {
+        self.create_user(cmd, true).await
+    }
+
+    /// Unknown identifiers, wrong passwords and inactive accounts all fail
+    /// the same way, so the error doesn't tell which accounts exist.
+    /// Throttling counts failures by the identifier tried, whether or not
+    /// it is a user's.
+    async fn login(&self, cmd: LoginCommand) -> Result<AuthenticatedUser<M>, ServiceError> {
+        let normalized = M::normalize_identifier(&cmd.identifier).ok();
+        let identifier = normalized.as_deref().unwrap_or(cmd.identifier.trim());
+        self.ensure_not_throttled(identifier, cmd.ip_address)
+            .await?;
+
+        let user = match &normalized {
+            Some(identifier) => self
+                .users
+                .find_by_identifier(identifier)
+                .await
+                .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?,
+            None => None,
+        };
+        let authenticated = match &user {
+            Some(user) if user.is_active() => self.verify_password(user, &cmd.password)?,
+            _ => {
+                self.verify_dummy(&cmd.password)?;
+                false
+            }
+        };
+        let user = match user {
+            Some(user) if authenticated => user,
+            user => {
+                self.login_failed(identifier, user.as_ref(), cmd.ip_address)
+                    .await?;
+                return Err(DomainError::InvalidCredentials.into());
+            }
+        };
+
+        // Failures are only forgiven once the user is all the way in, not
+        // while a second factor is still due
+        let authenticated = self.first_factor_passed(user).await?;
+        if authenticated.pending_mfa().is_none() {
+            self.clear_login_failures(identifier).await?;
+        }
+        Ok(authenticated)
+    }
+
+    async fn complete_login(
+        &self,
+        pending_token: &str,
+        code: &str,
+    ) -> Result<AuthenticatedUser<M>, ServiceError> {
+        let key = Self::pending_login_key(pending_token);
+        let pending = self
+            .sessions
+            .get_typed::<PendingLogin>(&key)
+            .await
+            .map_err(|e| ServiceError::Internal(e.to_string()))?
+            .ok_or(DomainError::InvalidToken)?;
+        let mfa = self.mfa.as_ref().ok_or(DomainError::InvalidToken)?;
+
+        let user = self
+            .users
+            .find_by_id(&pending.user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .filter(M::is_active);
+        let Some(user) = user else {
+            self.sessions.delete(&key).await.ok();
+            return Err(ServiceError::Unauthorized);
+        };
+
+        // Wrong codes count against the account like wrong passwords, so
+        // starting over with a new pending token doesn't reset them
+        let identifier = user.identifier();
+        self.ensure_not_throttled(&identifier, None).await?;
+        if !mfa.verify(user.id(), code).await? {
+            self.login_failed(&identifier, Some(&user), None).await?;
+            let attempts = pending.attempts + 1;
+            if attempts >= MAX_MFA_ATTEMPTS {
+                self.sessions
+                    .delete(&key)
+                    .await
+                    .map_err(|e| ServiceError::Internal(e.to_string()))?;
+            } else {
+                self.set_pending_login(
+                    pending_token,
+                    &PendingLogin {
+                        attempts,
+                        ..pending
+                    },
+                )
+                .await?;
+            }
+            return Err(DomainError::InvalidMfaCode.into());
+        }
+
+        self.sessions
+            .delete(&key)
+            .await
+            .map_err(|e| ServiceError::Internal(e.to_string()))?;
+        self.clear_login_failures(&identifier).await?;
+        self.start(user).await
+    }
+
+    async fn unlock_account(
+        &self,
+        staff_id: &UserId,
+        user_id: &UserId,
+    ) -> Result<(), ServiceError> {
+        self.ensure_staff(staff_id).await?;
+        let user = self
+            .users
+            .find_by_id(user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .ok_or(ServiceError::NotFound)?;
+
+        self.clear_login_failures(&user.identifier()).await?;
+        self.events
+            .publish(DomainEvent::AccountUnlocked {
+                user_id: user.id().clone(),
+                unlocked_by: staff_id.clone(),
+                occurred_at: Utc::now(),
+            })
+            .await
+            .ok();
+        Ok(())
+    }
+
+    async fn deactivate_user(
+        &self,
+        staff_id: &UserId,
+        user_id: &UserId,
+    ) -> Result<(), ServiceError> {
+        self.ensure_staff(staff_id).await?;
+        let mut user = self
+            .users
+            .find_by_id(user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .ok_or(ServiceError::NotFound)?;
+
+        user.set_active(false);
+        self.users
+            .save(&user)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        self.revoke_tokens(user.id()).await?;
+
+        self.events
+            .publish(DomainEvent::UserDeactivated {
+                user_id: user.id().clone(),
+                deactivated_by: staff_id.clone(),
+                occurred_at: Utc::now(),
+            })
+            .await
+            .ok();
+        Ok(())
+    }
+
+    async fn logout(&self, token: &str) -> Result<(), ServiceError> {
+        if let Some(issuer) = &self.token_issuer {
+            return issuer.revoke(token).await.map_err(Self::token_error);
+        }
+        self.sessions
+            .delete(&Self::session_key(token))
+            .await
+            .map_err(|e| ServiceError::Internal(e.to_string()))
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<TokenPair, ServiceError> {
+        let issuer = self
+            .token_issuer
+            .as_ref()
+            .ok_or(ServiceError::Unauthorized)?;
+        let (user_id, tokens) = issuer
+            .refresh(refresh_token)
+            .await
+            .map_err(Self::token_error)?;
+
+        let active = self
+            .users
+            .find_by_id(&user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .is_some_and(|user| user.is_active());
+        if !active {
+            issuer.revoke(&tokens.refresh_token).await.ok();
+            return Err(ServiceError::Unauthorized);
+        }
+        Ok(tokens)
+    }
+
+    async fn get_user_by_session(&self, session_token: &str) -> Result<Option<M>, ServiceError> {
+        let session = self
+            .sessions
+            .get_typed::<StoredSession>(&Self::session_key(session_token))
+            .await
+            .map_err(|e| ServiceError::Internal(e.to_string()))?;
+        let Some(session) = session else {
+            return Ok(None);
+        };
+
+        let user = self
+            .users
+            .find_by_id(&session.user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        Ok(user.filter(|user| {
+            user.is_active() && self.tokens.check_session_hash(user, &session.auth_hash)
+        }))
+    }
+
+    async fn change_password(&self, cmd: ChangePasswordCommand) -> Result<(), ServiceError> {
+        let mut user = self
+            .users
+            .find_by_id(&cmd.user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .ok_or(ServiceError::NotFound)?;
+
+        if !self.verify_password(&user, &cmd.current_password)? {
+            return Err(DomainError::InvalidCredentials.into());
+        }
+
+        let password_hash = self
+            .hash_new_password(&cmd.new_password, &UserAttributes::from(&user))
+            .await?;
+        user.set_password_hash(password_hash);
+
+        self.users
+            .save(&user)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        self.revoke_tokens(user.id()).await?;
+
+        // The new password logs out every other session; this one is
+        // signed again so the user stays in
+        if let Some(token) = &cmd.session_token {
+            let current = self
+                .sessions
+                .get_typed::<StoredSession>(&Self::session_key(token))
+                .await
+                .map_err(|e| ServiceError::Internal(e.to_string()))?;
+            if current.is_some_and(|session| &session.user_id == user.id()) {
+                self.store_session(token, &user).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn request_password_reset(&self, email: &str) -> Result<(), ServiceError> {
+        let Ok(email) = Email::new(email) else {
+            return Ok(());
+        };
+        let user = self
+            .users
+            .find_by_email(&email)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        let Some(user) = user.filter(M::is_active) else {
+            return Ok(());
+        };
+
+        self.events
+            .publish(DomainEvent::PasswordResetRequested {
+                user_id: user.id().clone(),
+                email: user.email().to_string(),
+                token: SecretToken::new(self.tokens.make_token(TokenPurpose::PasswordReset, &user)),
+                occurred_at: Utc::now(),
+            })
+            .await
+            .ok();
+        Ok(())
+    }
+
+    async fn reset_password(&self, cmd: ResetPasswordCommand) -> Result<(), ServiceError> {
+        let mut user = self
+            .user_for_token(TokenPurpose::PasswordReset, &cmd.token)
+            .await?;
+        if !user.is_active() {
+            return Err(DomainError::InvalidToken.into());
+        }
+
+        let password_hash = self
+            .hash_new_password(&cmd.new_password, &UserAttributes::from(&user))
+            .await?;
+        user.set_password_hash(password_hash);
+
+        self.users
+            .save(&user)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        self.revoke_tokens(user.id()).await?;
+        // Proving access to the email is as good as a staff unlock
+        self.clear_login_failures(&user.identifier()).await?;
+
+        self.events
+            .publish(DomainEvent::PasswordReset {
+                user_id: user.id().clone(),
+                occurred_at: Utc::now(),
+            })
+            .await
+            .ok();
+        Ok(())
+    }
+
+    async fn request_email_verification(&self, user_id: &UserId) -> Result<(), ServiceError> {
+        let user = self
+            .users
+            .find_by_id(user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .ok_or(ServiceError::NotFound)?;
+        if user.is_email_verified() {
+            return Ok(());
+        }
+
+        self.events
+            .publish(DomainEvent::EmailVerificationRequested {
+                user_id: user.id().clone(),
+                email: user.email().to_string(),
+                token: SecretToken::new(
+                    self.tokens
+                        .make_token(TokenPurpose::EmailVerification, &user),
+                ),
+                occurred_at: Utc::now(),
+            })
+            .await
+            .ok();
+        Ok(())
+    }
+
+    /// With `require_email_verification`, the first verification is also
+    /// what activates the account
+    async fn verify_email(&self, token: &str) -> Result<M, ServiceError> {
+        let mut user = self
+            .user_for_token(TokenPurpose::EmailVerification, token)
+            .await?;
+        // Only an account still waiting for this activates; one
+        // deactivated since, or verified before, stays as staff left it
+        if user.is_awaiting_verification() {
+            user.set_active(true);
+        }
+        user.mark_email_verified();
+
+        self.users
+            .save(&user)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+
+        self.events
+            .publish(DomainEvent::EmailVerified {
+                user_id: user.id().clone(),
+                email: user.email().to_string(),
+                occurred_at: Utc::now(),
+            })
+            .await
+            .ok();
+        Ok(user)
+    }
+}
@@ -0,0 +1,28 @@
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::ports::driven::MxLookup;
+use ferreiro_domain::ports::driving::ServiceError;
+use ferreiro_domain::values::Email;
+
+/// Rejects addresses whose domain publishes no mail exchanger, or a null MX
+///
+/// IP literals (`user@[192.0.2.1]`) are accepted without a lookup. A failed
+/// lookup is an `Internal` error, so callers decide whether a DNS outage
+/// should block a sign-up.
+pub async fn ensure_deliverable(lookup: &dyn MxLookup, email: &Email) -> Result<(), ServiceError> {
+    let domain = email.domain();
+    if domain.starts_with('[') {
+        return Ok(());
+    }
+
+    let hosts = lookup
+        .mx_hosts(domain)
+        .await
+        .map_err(|e| ServiceError::Internal(e.to_string()))?;
+    if hosts
+        .iter()
+        .all(|host| host.trim_end_matches('.').is_empty())
+    {
+        return Err(DomainError::UndeliverableEmail.into());
+    }
+    Ok(())
+}
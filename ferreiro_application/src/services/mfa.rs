@@ -0,0 +1,277 @@
+use crate::services::Totp;
+use async_trait::async_trait;
+use chrono::Utc;
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::events::DomainEvent;
+use ferreiro_domain::models::{AuthUser, TotpDevice, User};
+use ferreiro_domain::ports::driven::{EventPublisher, TotpDeviceRepository, UserRepository};
+use ferreiro_domain::ports::driving::{MfaService, ServiceError, TotpEnrollment};
+use ferreiro_domain::values::UserId;
+use sha2::{Digest, Sha256};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// How many recovery codes a user gets
+const RECOVERY_CODES: usize = 10;
+
+/// Authenticator apps (TOTP) and single-use recovery codes
+///
+/// Codes from up to `skew` steps (30 seconds each) either side of now are
+/// accepted; one by default. Recovery codes are `xxxxx-xxxxx` in lowercase
+/// hex, and hashed with SHA-256 like API tokens: they are random enough
+/// that a slow hash would add nothing.
+///
+/// Confirming an enrollment doesn't use up its code, so a user who had to
+/// enroll to log in can finish logging in with the same code.
+pub struct MfaServiceImpl<D, U, E, M = User>
+where
+    D: TotpDeviceRepository,
+    U: UserRepository<M>,
+    E: EventPublisher,
+    M: AuthUser,
+{
+    devices: Arc<D>,
+    users: Arc<U>,
+    events: Arc<E>,
+    issuer: String,
+    skew: u64,
+    model: PhantomData<fn() -> M>,
+}
+
+impl<D, U, E, M> MfaServiceImpl<D, U, E, M>
+where
+    D: TotpDeviceRepository,
+    U: UserRepository<M>,
+    E: EventPublisher,
+    M: AuthUser,
+{
+    pub fn new(devices: Arc<D>, users: Arc<U>, events: Arc<E>) -> Self {
+        Self {
+            devices,
+            users,
+            events,
+            issuer: "Ferreiro".to_string(),
+            skew: 1,
+            model: PhantomData,
+        }
+    }
+
+    /// The name authenticator apps show next to the account
+    pub fn with_issuer(mut self, issuer: &str) -> Self {
+        self.issuer = issuer.to_string();
+        self
+    }
+
+    /// Steps of clock drift to allow either way
+    pub fn with_skew(mut self, steps: u64) -> Self {
+        self.skew = steps;
+        self
+    }
+
+    async fn user(&self, user_id: &UserId) -> Result<M, ServiceError> {
+        self.users
+            .find_by_id(user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .ok_or(ServiceError::NotFound)
+    }
+
+    async fn device(&self, user_id: &UserId) -> Result<Option<TotpDevice>, ServiceError> {
+        self.devices
+            .find(user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))
+    }
+
+    async fn save(&self, device: &TotpDevice) -> Result<(), ServiceError> {
+        self.devices
+            .save(device)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))
+    }
+
+    async fn remove(&self, user_id: &UserId, disabled_by: &UserId) -> Result<(), ServiceError> {
+        self.devices
+            .delete(user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        self.events
+            .publish(DomainEvent::MfaDisabled {
+                user_id: user_id.clone(),
+                disabled_by: disabled_by.clone(),
+                occurred_at: Utc::now(),
+            })
+            .await
+            .ok();
+        Ok(())
+    }
+
+    /// Digits only, without the spaces apps show codes with
+    fn totp_code(code: &str) -> Option<String> {
+        let digits: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        (!digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())).then_some(digits)
+    }
+
+    fn hash_recovery_code(code: &str) -> String {
+        let normalized: String = code
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_lowercase();
+        format!("{:x}", Sha256::digest(normalized.as_bytes()))
+    }
+
+    /// New recovery codes for `device`, returned in the clear
+    fn new_recovery_codes(device: &mut TotpDevice) -> Vec<String> {
+        let codes: Vec<String> = (0..RECOVERY_CODES)
+            .map(|_| {
+                let random = Uuid::new_v4().simple().to_string();
+                format!("{}-{}", &random[..5], &random[5..10])
+            })
+            .collect();
+        device.set_recovery_codes(codes.iter().map(|c| Self::hash_recovery_code(c)).collect());
+        codes
+    }
+
+    /// The time step of `code`, if it is the app's current code
+    fn totp_step(&self, device: &TotpDevice, code: &str) -> Option<u64> {
+        let code = Self::totp_code(code)?;
+        Totp::new(device.secret()).verify_at(&code, Utc::now(), self.skew)
+    }
+
+    /// Uses up the app's code for `step`, unless it or a later one was
+    /// used already
+    async fn use_step(&self, user_id: &UserId, step: u64) -> Result<bool, ServiceError> {
+        self.devices
+            .use_step(user_id, step)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))
+    }
+
+    /// Checks a code from the app or a recovery code against a confirmed
+    /// device; the repository uses it up, so it can't be replayed
+    async fn check(&self, user_id: &UserId, code: &str) -> Result<bool, ServiceError> {
+        let Some(device) = self.device(user_id).await?.filter(TotpDevice::is_confirmed) else {
+            return Ok(false);
+        };
+        if let Some(step) = self.totp_step(&device, code) {
+            if self.use_step(user_id, step).await? {
+                return Ok(true);
+            }
+        }
+        self.devices
+            .use_recovery_code(user_id, &Self::hash_recovery_code(code))
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))
+    }
+}
+
+#[async_trait]
+impl<D, U, E, M> MfaService for MfaServiceImpl<D, U, E, M>
+where
+    D: TotpDeviceRepository + 'static,
+    U: UserRepository<M> + 'static,
+    E: EventPublisher + 'static,
+    M: AuthUser,
+{
+    async fn is_enabled(&self, user_id: &UserId) -> Result<bool, ServiceError> {
+        Ok(self
+            .device(user_id)
+            .await?
+            .is_some_and(|device| device.is_confirmed()))
+    }
+
+    async fn begin_enrollment(&self, user_id: &UserId) -> Result<TotpEnrollment, ServiceError> {
+        let user = self.user(user_id).await?;
+        if self.is_enabled(user_id).await? {
+            return Err(ServiceError::Conflict(
+                "Two-factor authentication is already enabled".to_string(),
+            ));
+        }
+
+        let device = TotpDevice::new(user_id.clone(), Totp::generate_secret());
+        self.save(&device).await?;
+        let totp = Totp::new(device.secret());
+        Ok(TotpEnrollment {
+            secret: totp.base32_secret(),
+            provisioning_uri: totp.provisioning_uri(&self.issuer, &user.identifier()),
+        })
+    }
+
+    async fn confirm_enrollment(
+        &self,
+        user_id: &UserId,
+        code: &str,
+    ) -> Result<Vec<String>, ServiceError> {
+        let mut device = self
+            .device(user_id)
+            .await?
+            .filter(|device| !device.is_confirmed())
+            .ok_or(ServiceError::NotFound)?;
+        if self.totp_step(&device, code).is_none() {
+            return Err(DomainError::InvalidMfaCode.into());
+        }
+
+        device.confirm();
+        let codes = Self::new_recovery_codes(&mut device);
+        self.save(&device).await?;
+
+        self.events
+            .publish(DomainEvent::MfaEnabled {
+                user_id: user_id.clone(),
+                occurred_at: Utc::now(),
+            })
+            .await
+            .ok();
+        Ok(codes)
+    }
+
+    async fn verify(&self, user_id: &UserId, code: &str) -> Result<bool, ServiceError> {
+        self.check(user_id, code).await
+    }
+
+    async fn regenerate_recovery_codes(
+        &self,
+        user_id: &UserId,
+        code: &str,
+    ) -> Result<Vec<String>, ServiceError> {
+        let mut device = self
+            .device(user_id)
+            .await?
+            .filter(TotpDevice::is_confirmed)
+            .ok_or(ServiceError::NotFound)?;
+        let Some(step) = self.totp_step(&device, code) else {
+            return Err(DomainError::InvalidMfaCode.into());
+        };
+        if !self.use_step(user_id, step).await? {
+            return Err(DomainError::InvalidMfaCode.into());
+        }
+        // Saved whole below, so it has to agree with the repository
+        device.use_step(step);
+
+        let codes = Self::new_recovery_codes(&mut device);
+        self.save(&device).await?;
+        Ok(codes)
+    }
+
+    async fn disable(&self, user_id: &UserId, code: &str) -> Result<(), ServiceError> {
+        if !self.check(user_id, code).await? {
+            return Err(DomainError::InvalidMfaCode.into());
+        }
+        self.remove(user_id, user_id).await
+    }
+
+    async fn reset(&self, staff_id: &UserId, user_id: &UserId) -> Result<(), ServiceError> {
+        let staff = self
+            .users
+            .find_by_id(staff_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        if !staff.is_some_and(|staff| staff.is_active() && staff.is_staff()) {
+            return Err(ServiceError::Unauthorized);
+        }
+        self.user(user_id).await?;
+        self.remove(user_id, staff_id).await
+    }
+}
@@ -1,3 +1,25 @@
+mod api_tokens;
+mod auth_service;
+mod deliverability;
+mod mfa;
+mod passwords;
+mod permissions;
 mod post_service;
+mod slugs;
+mod social_auth;
+mod throttle;
+mod tokens;
+mod totp;
 
+pub use api_tokens::ApiTokenServiceImpl;
+pub use auth_service::AuthServiceImpl;
+pub use deliverability::ensure_deliverable;
+pub use mfa::MfaServiceImpl;
+pub use passwords::ensure_not_breached;
+pub use permissions::RepositoryPermissionChecker;
 pub use post_service::PostServiceImpl;
+pub use slugs::unique_slug;
+pub use social_auth::SocialAuthServiceImpl;
+pub use throttle::{FailureLimits, LoginThrottle};
+pub use tokens::{AccountTokens, TokenPurpose};
+pub use totp::Totp;
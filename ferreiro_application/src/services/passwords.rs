@@ -0,0 +1,31 @@
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::ports::driven::BreachedPasswords;
+use ferreiro_domain::ports::driving::ServiceError;
+use sha1::{Digest, Sha1};
+
+/// Rejects passwords that appear in `source`
+///
+/// Only the first 5 characters of the password's SHA-1 leave the process.
+/// A failed lookup is an `Internal` error, like `ensure_deliverable`.
+pub async fn ensure_not_breached(
+    source: &dyn BreachedPasswords,
+    password: &str,
+) -> Result<(), ServiceError> {
+    let hash: String = Sha1::digest(password.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect();
+    let (prefix, suffix) = hash.split_at(5);
+
+    let range = source
+        .range(prefix)
+        .await
+        .map_err(|e| ServiceError::Internal(e.to_string()))?;
+    if range
+        .iter()
+        .any(|(candidate, count)| *count > 0 && candidate.eq_ignore_ascii_case(suffix))
+    {
+        return Err(DomainError::PasswordBreached.into());
+    }
+    Ok(())
+}
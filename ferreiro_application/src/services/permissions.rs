@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use ferreiro_domain::policies::Actor;
+use ferreiro_domain::ports::driven::{
+    GroupRepository, PermissionChecker, RepositoryError, UserRepository,
+};
+use ferreiro_domain::values::UserId;
+use std::sync::Arc;
+
+/// `PermissionChecker` that reads users and their groups from repositories
+/// on every check
+pub struct RepositoryPermissionChecker<U, G>
+where
+    U: UserRepository,
+    G: GroupRepository,
+{
+    users: Arc<U>,
+    groups: Arc<G>,
+}
+
+impl<U, G> RepositoryPermissionChecker<U, G>
+where
+    U: UserRepository,
+    G: GroupRepository,
+{
+    pub fn new(users: Arc<U>, groups: Arc<G>) -> Self {
+        Self { users, groups }
+    }
+}
+
+#[async_trait]
+impl<U, G> PermissionChecker for RepositoryPermissionChecker<U, G>
+where
+    U: UserRepository + 'static,
+    G: GroupRepository + 'static,
+{
+    async fn actor(&self, user_id: &UserId) -> Result<Option<Actor>, RepositoryError> {
+        let Some(user) = self.users.find_by_id(user_id).await? else {
+            return Ok(None);
+        };
+        if !user.is_active() {
+            return Ok(None);
+        }
+
+        let groups = if user.groups().is_empty() {
+            Vec::new()
+        } else {
+            let group_ids: Vec<_> = user.groups().iter().cloned().collect();
+            self.groups.find_many(&group_ids).await?
+        };
+        Ok(Some(Actor::from_user(&user, &groups)))
+    }
+}
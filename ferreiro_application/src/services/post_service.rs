@@ -1,14 +1,28 @@
+use crate::services::unique_slug;
 use async_trait::async_trait;
 use chrono::Utc;
+use ferreiro_domain::errors::DomainError;
 use ferreiro_domain::events::DomainEvent;
-use ferreiro_domain::models::Post;
-use ferreiro_domain::ports::driven::{EventPublisher, PostRepository};
+use ferreiro_domain::models::Permission;
+use ferreiro_domain::models::{Post, PostStatus};
+use ferreiro_domain::policies::{Actor, DefaultPostPolicy, PostAction, PostPolicy, ViewScope};
+use ferreiro_domain::ports::driven::{
+    EventPublisher, PaginatedResult, PermissionChecker, PostRepository, RepositoryError,
+};
 use ferreiro_domain::ports::driving::{
     CreatePostCommand, ListPostsQuery, PostService, ServiceError, UpdatePostCommand,
 };
-use ferreiro_domain::values::{Body, PostId, Slug, Title};
+use ferreiro_domain::values::{Body, PostId, Slug, Title, UserId};
 use std::sync::Arc;
 
+/// How often a generated slug is moved on after losing a save race
+const SLUG_RETRIES: u32 = 5;
+
+/// Creating a post takes `add_post`; everything else done to a post,
+/// including seeing it before it is published, is decided by a
+/// `PostPolicy` (`DefaultPostPolicy` unless replaced with `with_policy`).
+/// Unknown and inactive users, as `permissions` finds them, may do nothing
+/// but see published posts, as anonymous visitors do.
 pub struct PostServiceImpl<R, E>
 where
     R: PostRepository,
@@ -16,6 +30,8 @@ where
 {
     post_repo: Arc<R>,
     events: Arc<E>,
+    permissions: Arc<dyn PermissionChecker>,
+    policy: Arc<dyn PostPolicy>,
 }
 
 impl<R, E> PostServiceImpl<R, E>
@@ -23,8 +39,91 @@ where
     R: PostRepository,
     E: EventPublisher,
 {
-    pub fn new(post_repo: Arc<R>, events: Arc<E>) -> Self {
-        Self { post_repo, events }
+    pub fn new(post_repo: Arc<R>, events: Arc<E>, permissions: Arc<dyn PermissionChecker>) -> Self {
+        Self {
+            post_repo,
+            events,
+            permissions,
+            policy: Arc::new(DefaultPostPolicy),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: Arc<dyn PostPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// `None` for unknown and inactive users
+    async fn find_actor(&self, user_id: &UserId) -> Result<Option<Actor>, ServiceError> {
+        self.permissions
+            .actor(user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))
+    }
+
+    /// `Unauthorized` for unknown and inactive users
+    async fn actor(&self, user_id: &UserId) -> Result<Actor, ServiceError> {
+        self.find_actor(user_id)
+            .await?
+            .ok_or(ServiceError::Unauthorized)
+    }
+
+    /// `Unauthorized` unless `user_id` holds `permission`
+    async fn require(&self, user_id: &UserId, permission: Permission) -> Result<(), ServiceError> {
+        if !self.actor(user_id).await?.has_permission(&permission) {
+            return Err(ServiceError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// `Unauthorized` unless the policy lets `user_id` take `action` on `post`
+    async fn authorize(
+        &self,
+        user_id: &UserId,
+        action: PostAction,
+        post: &Post,
+    ) -> Result<(), ServiceError> {
+        if !self
+            .policy
+            .allows(&self.actor(user_id).await?, action, post)
+        {
+            return Err(ServiceError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Published posts are for everyone; the rest only for whom the policy
+    /// lets see them
+    fn can_view(&self, viewer: Option<&Actor>, post: &Post) -> bool {
+        post.status() == &PostStatus::Published
+            || viewer.is_some_and(|actor| self.policy.allows(actor, PostAction::View, post))
+    }
+
+    /// `post`, if `viewer` may see it
+    async fn visible(
+        &self,
+        post: Option<Post>,
+        viewer: Option<&UserId>,
+    ) -> Result<Option<Post>, ServiceError> {
+        let Some(post) = post else {
+            return Ok(None);
+        };
+        if post.status() == &PostStatus::Published {
+            return Ok(Some(post));
+        }
+        let actor = match viewer {
+            Some(viewer) => self.find_actor(viewer).await?,
+            None => None,
+        };
+        Ok(self.can_view(actor.as_ref(), &post).then_some(post))
+    }
+
+    async fn load(&self, id: &PostId) -> Result<Post, ServiceError> {
+        self.post_repo
+            .find_by_id(id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .ok_or(ServiceError::NotFound)
     }
 }
 
@@ -35,25 +134,56 @@ where
     E: EventPublisher + 'static,
 {
     async fn create(&self, cmd: CreatePostCommand) -> Result<Post, ServiceError> {
+        self.require(&cmd.author_id, Permission::add_post()).await?;
+
         let title = Title::new(&cmd.title)?;
-        let slug = Slug::new(&cmd.slug)?;
         let body = Body::new(&cmd.body);
 
-        if self
-            .post_repo
-            .exists_by_slug(&slug)
-            .await
-            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
-        {
-            return Err(ServiceError::Conflict("Slug already exists".into()));
-        }
+        let requested = cmd.slug.as_deref().map(str::trim).filter(|s| !s.is_empty());
+        // Generated slugs, retries included, are suffixed from the title's
+        // slug, never from one that already has a suffix
+        let base = Slug::from_title(&cmd.title);
+        let mut slug = match requested {
+            Some(slug) => {
+                let slug = Slug::new(slug)?;
+                if self
+                    .post_repo
+                    .exists_by_slug(&slug)
+                    .await
+                    .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+                {
+                    return Err(slug_taken());
+                }
+                slug
+            }
+            None => unique_slug(&*self.post_repo, base.clone()?)
+                .await
+                .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?,
+        };
 
-        let post = Post::new(title, slug, body, cmd.author_id.clone());
-
-        self.post_repo
-            .save(&post)
-            .await
-            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        // Another post can take the slug between the check and the save;
+        // the repository's unique constraint catches that, and a generated
+        // slug simply moves on to the next free suffix
+        let mut retries = 0;
+        let post = loop {
+            let post = Post::new(
+                title.clone(),
+                slug.clone(),
+                body.clone(),
+                cmd.author_id.clone(),
+            );
+            match self.post_repo.save(&post).await {
+                Ok(()) => break post,
+                Err(RepositoryError::Conflict) if requested.is_none() && retries < SLUG_RETRIES => {
+                    retries += 1;
+                    slug = unique_slug(&*self.post_repo, base.clone()?)
+                        .await
+                        .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+                }
+                Err(RepositoryError::Conflict) => return Err(slug_taken()),
+                Err(e) => return Err(ServiceError::Internal(format!("{:?}", e))),
+            }
+        };
 
         self.events
             .publish(DomainEvent::PostCreated {
@@ -68,12 +198,9 @@ where
     }
 
     async fn update(&self, cmd: UpdatePostCommand) -> Result<Post, ServiceError> {
-        let mut post = self
-            .post_repo
-            .find_by_id(&cmd.id)
-            .await
-            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
-            .ok_or(ServiceError::NotFound)?;
+        let mut post = self.load(&cmd.id).await?;
+        self.authorize(&cmd.actor, PostAction::Update, &post)
+            .await?;
 
         let title = Title::new(&cmd.title)?;
         let body = Body::new(&cmd.body);
@@ -88,13 +215,9 @@ where
         Ok(post)
     }
 
-    async fn publish(&self, id: &PostId) -> Result<Post, ServiceError> {
-        let mut post = self
-            .post_repo
-            .find_by_id(id)
-            .await
-            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
-            .ok_or(ServiceError::NotFound)?;
+    async fn publish(&self, id: &PostId, actor: &UserId) -> Result<Post, ServiceError> {
+        let mut post = self.load(id).await?;
+        self.authorize(actor, PostAction::Publish, &post).await?;
 
         post.publish()?;
 
@@ -114,13 +237,9 @@ where
         Ok(post)
     }
 
-    async fn archive(&self, id: &PostId) -> Result<Post, ServiceError> {
-        let mut post = self
-            .post_repo
-            .find_by_id(id)
-            .await
-            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
-            .ok_or(ServiceError::NotFound)?;
+    async fn archive(&self, id: &PostId, actor: &UserId) -> Result<Post, ServiceError> {
+        let mut post = self.load(id).await?;
+        self.authorize(actor, PostAction::Archive, &post).await?;
 
         post.archive();
 
@@ -140,7 +259,10 @@ where
         Ok(post)
     }
 
-    async fn delete(&self, id: &PostId) -> Result<(), ServiceError> {
+    async fn delete(&self, id: &PostId, actor: &UserId) -> Result<(), ServiceError> {
+        let post = self.load(id).await?;
+        self.authorize(actor, PostAction::Delete, &post).await?;
+
         self.post_repo
             .delete(id)
             .await
@@ -148,28 +270,69 @@ where
         Ok(())
     }
 
-    async fn get(&self, id: &PostId) -> Result<Option<Post>, ServiceError> {
-        self.post_repo
+    async fn get(
+        &self,
+        id: &PostId,
+        viewer: Option<&UserId>,
+    ) -> Result<Option<Post>, ServiceError> {
+        let post = self
+            .post_repo
             .find_by_id(id)
             .await
-            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        self.visible(post, viewer).await
     }
 
-    async fn get_by_slug(&self, slug: &str) -> Result<Option<Post>, ServiceError> {
+    async fn get_by_slug(
+        &self,
+        slug: &str,
+        viewer: Option<&UserId>,
+    ) -> Result<Option<Post>, ServiceError> {
         let slug = Slug::new(slug)?;
-        self.post_repo
+        let post = self
+            .post_repo
             .find_by_slug(&slug)
             .await
-            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        self.visible(post, viewer).await
     }
 
-    async fn list(
-        &self,
-        query: ListPostsQuery,
-    ) -> Result<ferreiro_domain::ports::driven::PaginatedResult<Post>, ServiceError> {
+    async fn list(&self, query: ListPostsQuery) -> Result<PaginatedResult<Post>, ServiceError> {
+        // Both come from the client, and pages are counted from 1
+        if query.pagination.page == 0 || query.pagination.per_page == 0 {
+            return Err(DomainError::InvalidPagination.into());
+        }
+        let scope = match &query.viewer {
+            Some(viewer) => self
+                .find_actor(viewer)
+                .await?
+                .map(|actor| (self.policy.view_scope(&actor), actor)),
+            None => None,
+        };
+        let mut filter = query.filter;
+        match scope {
+            // Anonymous visitors only ever see published posts
+            None | Some((ViewScope::PublishedOnly, _)) => {
+                if filter.status.get_or_insert(PostStatus::Published) != &PostStatus::Published {
+                    return Ok(PaginatedResult {
+                        items: Vec::new(),
+                        total: 0,
+                        page: query.pagination.page,
+                        per_page: query.pagination.per_page,
+                        total_pages: 0,
+                    });
+                }
+            }
+            Some((ViewScope::OwnPosts, actor)) => filter.published_or_by = Some(actor.id().clone()),
+            Some((ViewScope::AllPosts, _)) => {}
+        }
         self.post_repo
-            .list(query.filter, query.pagination)
+            .list(filter, query.pagination)
             .await
             .map_err(|e| ServiceError::Internal(format!("{:?}", e)))
     }
 }
+
+fn slug_taken() -> ServiceError {
+    ServiceError::Conflict(DomainError::SlugAlreadyExists.to_string())
+}
@@ -0,0 +1,16 @@
+use ferreiro_domain::ports::driven::{PostRepository, RepositoryError};
+use ferreiro_domain::values::Slug;
+
+/// The first of `base`, `base-2`, `base-3`… that no post uses yet
+pub async fn unique_slug<R>(repo: &R, base: Slug) -> Result<Slug, RepositoryError>
+where
+    R: PostRepository + ?Sized,
+{
+    let mut candidate = base.clone();
+    let mut n = 1;
+    while repo.exists_by_slug(&candidate).await? {
+        n += 1;
+        candidate = base.with_suffix(n);
+    }
+    Ok(candidate)
+}
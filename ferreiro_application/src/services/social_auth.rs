@@ -0,0 +1,397 @@
+use crate::services::totp::constant_time_eq;
+use crate::services::AuthServiceImpl;
+use crate::CacheExt;
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64, Engine};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::models::ProviderTokens;
+use ferreiro_domain::models::{ExternalIdentity, User};
+use ferreiro_domain::ports::driven::{
+    AuthorizationRequest, Cache, EventPublisher, ExternalIdentityRepository, ExternalProfile,
+    OAuthError, OAuthProvider, UserRepository,
+};
+use ferreiro_domain::ports::driving::{
+    AuthService, AuthenticatedUser, AuthorizationCallback, AuthorizationRedirect, ServiceError,
+    SocialAuthService,
+};
+use ferreiro_domain::values::{Email, UserId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long the user has to get through the provider's consent screen
+const FLOW_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// What `begin` remembers for `complete`, under the flow's `state`
+#[derive(Serialize, Deserialize)]
+struct Flow {
+    provider: String,
+    nonce: String,
+    code_verifier: String,
+    redirect_uri: String,
+    /// Of the `binding` the browser keeps, so the cache never holds it
+    binding_hash: String,
+}
+
+/// Sign in with OAuth2 and OpenID Connect providers, as an authentication
+/// backend next to passwords
+///
+/// Runs the authorization code flow with PKCE, keeping each flow's state,
+/// nonce and code verifier in `flows` until the provider redirects back.
+/// The flow is bound to the browser that began it: the callback must bring
+/// the `binding` it was handed, kept in its session. The provider's account
+/// is then looked up by subject in `identities`:
+///
+/// - a linked account logs its user in;
+/// - otherwise, a user with the same email fails with `UserAlreadyExists`:
+///   an account at a provider never takes over one here, whatever the
+///   provider says about the email. The user logs in as before and links
+///   the provider with `link`;
+/// - otherwise a user is registered through `AuthService::register`, with
+///   an unusable password. The provider vouching for the email counts as
+///   verifying it; otherwise, with `require_email_verification`, the new
+///   user waits for the verification email like any other.
+///
+/// Logging in goes through `auth`, so the result is a session or a token
+/// pair as configured there. The provider's tokens are kept on the
+/// `ExternalIdentity`.
+///
+/// ```rust,ignore
+/// let auth = Arc::new(AuthServiceImpl::new(users.clone(), events, hasher, cache.clone(), tokens));
+/// let social = SocialAuthServiceImpl::new(users, identities, auth, cache)
+///     .with_provider(Arc::new(OidcProvider::google(&client_id, &client_secret)));
+/// let redirect = social.begin("google", "https://example.com/auth/google/callback").await?;
+/// // ... keep `redirect.binding` in the session and redirect to
+/// // `redirect.url`; then, in the callback:
+/// let authenticated = social
+///     .complete(AuthorizationCallback {
+///         state: query.state,
+///         code: query.code,
+///         binding: session.remove("oauth_binding")?,
+///     })
+///     .await?;
+/// ```
+pub struct SocialAuthServiceImpl<U, I, E>
+where
+    U: UserRepository,
+    I: ExternalIdentityRepository,
+    E: EventPublisher,
+{
+    users: Arc<U>,
+    identities: Arc<I>,
+    auth: Arc<AuthServiceImpl<U, E>>,
+    flows: Arc<dyn Cache>,
+    providers: HashMap<String, Arc<dyn OAuthProvider>>,
+}
+
+impl<U, I, E> SocialAuthServiceImpl<U, I, E>
+where
+    U: UserRepository + 'static,
+    I: ExternalIdentityRepository,
+    E: EventPublisher + 'static,
+{
+    /// Takes the `AuthServiceImpl` itself rather than an `AuthService`, to
+    /// log users in without a password
+    pub fn new(
+        users: Arc<U>,
+        identities: Arc<I>,
+        auth: Arc<AuthServiceImpl<U, E>>,
+        flows: Arc<dyn Cache>,
+    ) -> Self {
+        Self {
+            users,
+            identities,
+            auth,
+            flows,
+            providers: HashMap::new(),
+        }
+    }
+
+    /// Replaces any provider with the same name
+    pub fn with_provider(mut self, provider: Arc<dyn OAuthProvider>) -> Self {
+        self.providers.insert(provider.name().to_string(), provider);
+        self
+    }
+
+    fn provider(&self, name: &str) -> Result<&Arc<dyn OAuthProvider>, ServiceError> {
+        self.providers.get(name).ok_or(ServiceError::NotFound)
+    }
+
+    fn flow_key(state: &str) -> String {
+        format!("social:flow:{}", state)
+    }
+
+    fn binding_hash(binding: &str) -> String {
+        BASE64.encode(Sha256::digest(binding.as_bytes()))
+    }
+
+    /// The flow `state` started, which can't be used again, if `binding`
+    /// is the one it was begun with
+    ///
+    /// Two callbacks racing with one `state` could both read it, but only
+    /// one gets past the provider: authorization codes are single-use.
+    async fn take_flow(&self, state: &str, binding: &str) -> Result<Flow, ServiceError> {
+        let key = Self::flow_key(state);
+        let flow = self
+            .flows
+            .get_typed::<Flow>(&key)
+            .await
+            .map_err(|e| ServiceError::Internal(e.to_string()))?
+            .ok_or(DomainError::InvalidToken)?;
+        self.flows
+            .delete(&key)
+            .await
+            .map_err(|e| ServiceError::Internal(e.to_string()))?;
+
+        let hash = Self::binding_hash(binding);
+        if !constant_time_eq(hash.as_bytes(), flow.binding_hash.as_bytes()) {
+            return Err(DomainError::InvalidToken.into());
+        }
+        Ok(flow)
+    }
+
+    /// Exchanges the callback's code for the provider's tokens and the
+    /// profile of whoever signed in there
+    async fn finish_flow(
+        &self,
+        callback: &AuthorizationCallback,
+    ) -> Result<(&Arc<dyn OAuthProvider>, ExternalProfile, ProviderTokens), ServiceError> {
+        let flow = self.take_flow(&callback.state, &callback.binding).await?;
+        let provider = self.provider(&flow.provider)?;
+        let tokens = provider
+            .exchange_code(&callback.code, &flow.code_verifier, &flow.redirect_uri)
+            .await
+            .map_err(Self::oauth_error)?;
+        let profile = provider
+            .profile(&tokens, &flow.nonce)
+            .await
+            .map_err(Self::oauth_error)?;
+        Ok((provider, profile, tokens))
+    }
+
+    fn profile_email(profile: &ExternalProfile) -> Option<Email> {
+        profile
+            .email
+            .as_deref()
+            .and_then(|email| Email::new(email).ok())
+    }
+
+    /// The PKCE S256 challenge for `verifier` (RFC 7636)
+    fn code_challenge(verifier: &str) -> String {
+        BASE64.encode(Sha256::digest(verifier.as_bytes()))
+    }
+
+    fn random() -> String {
+        format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+    }
+
+    /// A refused code or a mismatched ID token is the client's problem;
+    /// anything else is ours or the provider's
+    fn oauth_error(error: OAuthError) -> ServiceError {
+        match error {
+            OAuthError::Rejected(_) | OAuthError::InvalidIdToken(_) => ServiceError::Unauthorized,
+            _ => ServiceError::Internal(error.to_string()),
+        }
+    }
+
+    /// A new user for a provider account nobody has linked; existing users
+    /// link theirs with `link`
+    async fn new_user_for(&self, profile: &ExternalProfile) -> Result<User, ServiceError> {
+        let email = Email::new(profile.email.as_deref().unwrap_or_default())?;
+        let exists = self
+            .users
+            .exists_by_email(&email)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        if exists {
+            return Err(DomainError::UserAlreadyExists.into());
+        }
+
+        let name = profile.name.clone().unwrap_or_else(|| {
+            email
+                .as_str()
+                .split('@')
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        });
+        let mut user = self
+            .auth
+            .register_without_password(email.as_str(), name)
+            .await?;
+        if profile.email_verified {
+            user.mark_email_verified();
+            user.activate();
+        }
+        self.users
+            .save(&user)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+
+        if !user.is_active() {
+            self.auth.request_email_verification(user.id()).await?;
+        }
+        Ok(user)
+    }
+}
+
+#[async_trait]
+impl<U, I, E> SocialAuthService for SocialAuthServiceImpl<U, I, E>
+where
+    U: UserRepository + 'static,
+    I: ExternalIdentityRepository + 'static,
+    E: EventPublisher + 'static,
+{
+    async fn begin(
+        &self,
+        provider: &str,
+        redirect_uri: &str,
+    ) -> Result<AuthorizationRedirect, ServiceError> {
+        let provider = self.provider(provider)?;
+        let state = Self::random();
+        let binding = Self::random();
+        let flow = Flow {
+            provider: provider.name().to_string(),
+            nonce: Self::random(),
+            code_verifier: Self::random(),
+            redirect_uri: redirect_uri.to_string(),
+            binding_hash: Self::binding_hash(&binding),
+        };
+        let url = provider.authorization_url(&AuthorizationRequest {
+            state: state.clone(),
+            nonce: flow.nonce.clone(),
+            code_challenge: Self::code_challenge(&flow.code_verifier),
+            redirect_uri: flow.redirect_uri.clone(),
+        });
+
+        self.flows
+            .set_typed(&Self::flow_key(&state), &flow, Some(FLOW_TTL))
+            .await
+            .map_err(|e| ServiceError::Internal(e.to_string()))?;
+        Ok(AuthorizationRedirect {
+            url,
+            state,
+            binding,
+        })
+    }
+
+    async fn complete(
+        &self,
+        callback: AuthorizationCallback,
+    ) -> Result<AuthenticatedUser, ServiceError> {
+        let (provider, profile, tokens) = self.finish_flow(&callback).await?;
+        let email = Self::profile_email(&profile);
+
+        let existing = self
+            .identities
+            .find(provider.name(), &profile.subject)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        let identity = match existing {
+            Some(mut identity) => {
+                identity.logged_in(email, tokens);
+                identity
+            }
+            None => {
+                let user = self.new_user_for(&profile).await?;
+                ExternalIdentity::new(
+                    provider.name(),
+                    &profile.subject,
+                    user.id().clone(),
+                    email,
+                    tokens,
+                )
+            }
+        };
+        self.identities
+            .save(&identity)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+
+        self.auth.login_user(identity.user_id()).await
+    }
+
+    async fn link(
+        &self,
+        user_id: &UserId,
+        callback: AuthorizationCallback,
+    ) -> Result<ExternalIdentity, ServiceError> {
+        self.users
+            .find_by_id(user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .filter(User::is_active)
+            .ok_or(ServiceError::Unauthorized)?;
+        let (provider, profile, tokens) = self.finish_flow(&callback).await?;
+        let email = Self::profile_email(&profile);
+
+        let existing = self
+            .identities
+            .find(provider.name(), &profile.subject)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        let identity = match existing {
+            Some(identity) if identity.user_id() != user_id => {
+                return Err(ServiceError::Conflict(
+                    "This account is linked to another user".to_string(),
+                ))
+            }
+            Some(mut identity) => {
+                identity.logged_in(email, tokens);
+                identity
+            }
+            None => ExternalIdentity::new(
+                provider.name(),
+                &profile.subject,
+                user_id.clone(),
+                email,
+                tokens,
+            ),
+        };
+        self.identities
+            .save(&identity)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        Ok(identity)
+    }
+
+    async fn identities(&self, user_id: &UserId) -> Result<Vec<ExternalIdentity>, ServiceError> {
+        self.identities
+            .list_for_user(user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))
+    }
+
+    async fn unlink(&self, user_id: &UserId, provider: &str) -> Result<(), ServiceError> {
+        let user = self
+            .users
+            .find_by_id(user_id)
+            .await
+            .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?
+            .ok_or(ServiceError::NotFound)?;
+        let (unlinking, keeping): (Vec<_>, Vec<_>) = self
+            .identities(user_id)
+            .await?
+            .into_iter()
+            .partition(|identity| identity.provider() == provider);
+        if unlinking.is_empty() {
+            return Err(ServiceError::NotFound);
+        }
+        if keeping.is_empty() && !user.has_usable_password() {
+            return Err(ServiceError::Conflict(
+                "Set a password before unlinking your last sign-in provider".to_string(),
+            ));
+        }
+
+        for identity in unlinking {
+            self.identities
+                .delete(identity.provider(), identity.subject())
+                .await
+                .map_err(|e| ServiceError::Internal(format!("{:?}", e)))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use ferreiro_domain::ports::driven::LoginFailures;
+use std::time::Duration;
+
+/// When failed logins slow down, and then lock out, further attempts
+///
+/// Failures are counted against the email tried and against the client's
+/// IP address. Once a key has `free_attempts` failures, each attempt waits
+/// `base_delay` after the last failure, doubling with every further
+/// failure up to `max_delay`; at `lockout_after` the key is locked for
+/// `lockout`. Attempts turned away while waiting aren't counted.
+///
+/// Addresses get more attempts than accounts by default, since many users
+/// can share one behind a proxy.
+#[derive(Debug, Clone)]
+pub struct LoginThrottle {
+    pub account: FailureLimits,
+    pub ip_address: FailureLimits,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub lockout: Duration,
+    /// How long failures are remembered after the last one
+    pub window: Duration,
+}
+
+/// Failure counts for one kind of key
+#[derive(Debug, Clone, Copy)]
+pub struct FailureLimits {
+    /// Failures before attempts have to wait
+    pub free_attempts: u32,
+    /// Failures that lock the key out
+    pub lockout_after: u32,
+}
+
+impl Default for LoginThrottle {
+    fn default() -> Self {
+        Self {
+            account: FailureLimits {
+                free_attempts: 3,
+                lockout_after: 10,
+            },
+            ip_address: FailureLimits {
+                free_attempts: 20,
+                lockout_after: 100,
+            },
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5 * 60),
+            lockout: Duration::from_secs(15 * 60),
+            window: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+impl LoginThrottle {
+    /// When a key with `failures` can try again, if it has to wait
+    pub(crate) fn blocked_until(
+        &self,
+        limits: FailureLimits,
+        failures: &LoginFailures,
+    ) -> Option<DateTime<Utc>> {
+        if let Some(until) = self.locked_until(limits, failures) {
+            return Some(until);
+        }
+        let doublings = failures.count.checked_sub(limits.free_attempts)?;
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(doublings))
+            .min(self.max_delay);
+        Some(failures.last_failed_at + chrono::Duration::from_std(delay).ok()?)
+    }
+
+    /// When a lockout ends, if `failures` are enough for one
+    pub(crate) fn locked_until(
+        &self,
+        limits: FailureLimits,
+        failures: &LoginFailures,
+    ) -> Option<DateTime<Utc>> {
+        if failures.count < limits.lockout_after {
+            return None;
+        }
+        Some(failures.last_failed_at + chrono::Duration::from_std(self.lockout).ok()?)
+    }
+
+    /// How long the store must keep a count
+    pub(crate) fn ttl(&self) -> Duration {
+        self.window.max(self.lockout)
+    }
+}
@@ -0,0 +1,161 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use ferreiro_domain::models::AuthUser;
+use ferreiro_domain::values::UserId;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Three days, as Django's `PASSWORD_RESET_TIMEOUT`
+const DEFAULT_TTL: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+
+/// What a token was issued for; a token for one purpose never passes as
+/// the other
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenPurpose {
+    PasswordReset,
+    EmailVerification,
+}
+
+impl TokenPurpose {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::PasswordReset => "password-reset",
+            Self::EmailVerification => "email-verification",
+        }
+    }
+}
+
+/// Signed, expiring links for password resets and email verification
+///
+/// A token is `<user id>.<issued at>.<signature>`, so nothing is stored.
+/// The signature covers the user's current password hash, email and
+/// verification state, so a token stops working once it has been used:
+/// resetting changes the hash and verifying flips the flag. Changing the
+/// password or email any other way revokes outstanding tokens too.
+#[derive(Clone)]
+pub struct AccountTokens {
+    secret: Vec<u8>,
+    ttl: Duration,
+}
+
+impl AccountTokens {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            secret: secret.to_vec(),
+            ttl: DEFAULT_TTL,
+        }
+    }
+
+    /// How long a token stays valid; three days by default
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn make_token<M: AuthUser>(&self, purpose: TokenPurpose, user: &M) -> String {
+        self.make_token_at(purpose, user, Utc::now())
+    }
+
+    pub fn make_token_at<M: AuthUser>(
+        &self,
+        purpose: TokenPurpose,
+        user: &M,
+        issued_at: DateTime<Utc>,
+    ) -> String {
+        let issued_at = issued_at.timestamp();
+        let signature = self.mac(purpose, user, issued_at).finalize().into_bytes();
+        format!(
+            "{}.{}.{}",
+            user.id().as_uuid().simple(),
+            issued_at,
+            BASE64.encode(signature)
+        )
+    }
+
+    /// The user a token claims to be for, to load before `check_token`
+    pub fn user_id(token: &str) -> Option<UserId> {
+        let (user_id, _) = token.split_once('.')?;
+        Uuid::try_parse(user_id).ok().map(UserId::from_uuid)
+    }
+
+    /// Whether `token` was issued for `purpose` to `user` as they are now,
+    /// and hasn't expired
+    pub fn check_token<M: AuthUser>(&self, purpose: TokenPurpose, user: &M, token: &str) -> bool {
+        self.check_token_at(purpose, user, token, Utc::now())
+    }
+
+    pub fn check_token_at<M: AuthUser>(
+        &self,
+        purpose: TokenPurpose,
+        user: &M,
+        token: &str,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let mut parts = token.split('.');
+        let (Some(user_id), Some(issued_at), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return false;
+        };
+        let (Ok(issued_at), Ok(signature)) = (issued_at.parse::<i64>(), BASE64.decode(signature))
+        else {
+            return false;
+        };
+
+        if Uuid::try_parse(user_id).ok().as_ref() != Some(user.id().as_uuid()) {
+            return false;
+        }
+        let age = now.timestamp() - issued_at;
+        if age < 0 || age as u64 > self.ttl.as_secs() {
+            return false;
+        }
+        self.mac(purpose, user, issued_at)
+            .verify_slice(&signature)
+            .is_ok()
+    }
+
+    /// Stored with a session, and checked on every request, so changing
+    /// the password logs out the user's sessions, like Django's
+    /// `get_session_auth_hash`
+    pub fn session_hash<M: AuthUser>(&self, user: &M) -> String {
+        BASE64.encode(self.session_mac(user).finalize().into_bytes())
+    }
+
+    pub fn check_session_hash<M: AuthUser>(&self, user: &M, hash: &str) -> bool {
+        BASE64
+            .decode(hash)
+            .is_ok_and(|hash| self.session_mac(user).verify_slice(&hash).is_ok())
+    }
+
+    fn session_mac<M: AuthUser>(&self, user: &M) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC can take key of any size");
+        for part in ["session", &user.id().to_string(), user.password_hash()] {
+            mac.update(&(part.len() as u64).to_be_bytes());
+            mac.update(part.as_bytes());
+        }
+        mac
+    }
+
+    fn mac<M: AuthUser>(&self, purpose: TokenPurpose, user: &M, issued_at: i64) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC can take key of any size");
+        for part in [
+            purpose.as_str(),
+            &user.id().to_string(),
+            &issued_at.to_string(),
+            user.password_hash(),
+            &user.email().normalized(),
+            if user.is_email_verified() { "1" } else { "0" },
+        ] {
+            // Length-prefixed, so no two different inputs run together
+            mac.update(&(part.len() as u64).to_be_bytes());
+            mac.update(part.as_bytes());
+        }
+        mac
+    }
+}
@@ -0,0 +1,118 @@
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha1::Sha1;
+
+type HmacSha1 = Hmac<Sha1>;
+
+/// Seconds each code is valid for
+const PERIOD: i64 = 30;
+
+const DIGITS: u32 = 6;
+
+/// 160 bits, as RFC 4226 recommends
+const SECRET_LEN: usize = 20;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Time-based one-time passwords (RFC 6238), with the parameters every
+/// authenticator app supports: HMAC-SHA1, six digits, 30-second steps
+pub struct Totp<'a> {
+    secret: &'a [u8],
+}
+
+impl<'a> Totp<'a> {
+    pub fn new(secret: &'a [u8]) -> Self {
+        Self { secret }
+    }
+
+    /// A random secret for a new device
+    pub fn generate_secret() -> Vec<u8> {
+        let mut secret = vec![0; SECRET_LEN];
+        OsRng.fill_bytes(&mut secret);
+        secret
+    }
+
+    /// The time step `at` falls in
+    pub fn step(at: DateTime<Utc>) -> u64 {
+        (at.timestamp() / PERIOD).max(0) as u64
+    }
+
+    /// The code for time step `step`
+    pub fn code_at(&self, step: u64) -> String {
+        let mut mac = HmacSha1::new_from_slice(self.secret).expect("HMAC can take key of any size");
+        mac.update(&step.to_be_bytes());
+        let digest = mac.finalize().into_bytes();
+
+        // Dynamic truncation (RFC 4226, section 5.3)
+        let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+        let value = u32::from_be_bytes([
+            digest[offset] & 0x7f,
+            digest[offset + 1],
+            digest[offset + 2],
+            digest[offset + 3],
+        ]);
+        format!(
+            "{:0width$}",
+            value % 10u32.pow(DIGITS),
+            width = DIGITS as usize
+        )
+    }
+
+    /// The time step `code` is from, if it is from within `skew` steps of
+    /// `now` either way, to allow for clocks that drift and slow typists
+    pub fn verify_at(&self, code: &str, now: DateTime<Utc>, skew: u64) -> Option<u64> {
+        let current = Self::step(now);
+        (current.saturating_sub(skew)..=current + skew)
+            .find(|&step| constant_time_eq(self.code_at(step).as_bytes(), code.as_bytes()))
+    }
+
+    /// The `otpauth://` URI authenticator apps read from a QR code
+    pub fn provisioning_uri(&self, issuer: &str, account: &str) -> String {
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+            percent_encode(issuer),
+            percent_encode(account),
+            self.base32_secret(),
+            percent_encode(issuer),
+            DIGITS,
+            PERIOD
+        )
+    }
+
+    /// The secret in unpadded base32, for typing into an app by hand
+    pub fn base32_secret(&self) -> String {
+        let mut encoded = String::new();
+        for chunk in self.secret.chunks(5) {
+            let mut buffer = [0u8; 5];
+            buffer[..chunk.len()].copy_from_slice(chunk);
+            let bits = u64::from_be_bytes([
+                0, 0, 0, buffer[0], buffer[1], buffer[2], buffer[3], buffer[4],
+            ]);
+            let chars = (chunk.len() * 8).div_ceil(5);
+            for i in 0..chars {
+                let index = (bits >> (35 - i * 5)) & 0x1f;
+                encoded.push(BASE32_ALPHABET[index as usize] as char);
+            }
+        }
+        encoded
+    }
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Everything but RFC 3986's unreserved characters
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
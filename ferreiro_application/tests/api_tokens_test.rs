@@ -0,0 +1,146 @@
+use chrono::{Duration, Utc};
+use ferreiro_adapters_db::{InMemoryApiTokenRepository, InMemoryUserRepository};
+use ferreiro_application::services::ApiTokenServiceImpl;
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::models::User;
+use ferreiro_domain::ports::driven::{ApiTokenRepository, UserRepository};
+use ferreiro_domain::ports::driving::{ApiTokenService, CreateApiTokenCommand, ServiceError};
+use ferreiro_domain::values::{Email, UserId};
+use std::sync::Arc;
+
+struct Fixture {
+    users: Arc<InMemoryUserRepository>,
+    tokens: Arc<InMemoryApiTokenRepository>,
+    service: ApiTokenServiceImpl<InMemoryApiTokenRepository, InMemoryUserRepository>,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let tokens = Arc::new(InMemoryApiTokenRepository::new());
+        let service = ApiTokenServiceImpl::new(tokens.clone(), users.clone());
+        Self {
+            users,
+            tokens,
+            service,
+        }
+    }
+
+    async fn user(&self, name: &str) -> User {
+        let email = Email::new(&format!("{}@example.com", name)).unwrap();
+        let user = User::new(email, name.to_string(), "hash".to_string());
+        self.users.save(&user).await.unwrap();
+        user
+    }
+}
+
+fn create(user_id: &UserId, name: &str, scopes: &[&str]) -> CreateApiTokenCommand {
+    CreateApiTokenCommand {
+        user_id: user_id.clone(),
+        name: name.to_string(),
+        scopes: scopes.iter().map(|s| s.to_string()).collect(),
+        expires_at: None,
+    }
+}
+
+#[tokio::test]
+async fn test_tokens_are_stored_hashed_and_authenticate_their_user() {
+    let fixture = Fixture::new();
+    let ana = fixture.user("ana").await;
+
+    let created = fixture
+        .service
+        .create(create(ana.id(), "CI", &["posts:read"]))
+        .await
+        .unwrap();
+    assert!(created.secret.starts_with("ferreiro_pat_"));
+    assert!(created.secret.starts_with(created.token.prefix()));
+    assert_ne!(created.token.token_hash(), created.secret);
+    assert!(!created.token.token_hash().contains(&created.secret));
+    assert_eq!(created.token.last_used_at(), None);
+
+    let (user, token) = fixture.service.authenticate(&created.secret).await.unwrap();
+    assert_eq!(user.id(), ana.id());
+    assert!(token.has_scope("posts:read"));
+    assert!(!token.has_scope("posts:write"));
+
+    // The use was recorded
+    let stored = fixture
+        .tokens
+        .find_by_id(created.token.id())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(stored.last_used_at().is_some());
+
+    assert!(matches!(
+        fixture.service.authenticate("ferreiro_pat_guess").await,
+        Err(ServiceError::Unauthorized)
+    ));
+    assert!(matches!(
+        fixture
+            .service
+            .create(create(ana.id(), "Bad", &["Posts Read"]))
+            .await,
+        Err(ServiceError::Domain(DomainError::InvalidScope))
+    ));
+}
+
+#[tokio::test]
+async fn test_expired_revoked_and_inactive_tokens_are_refused() {
+    let fixture = Fixture::new();
+    let ana = fixture.user("ana").await;
+    let bruno = fixture.user("bruno").await;
+
+    let mut expiring = create(ana.id(), "Old", &[]);
+    expiring.expires_at = Some(Utc::now() - Duration::minutes(1));
+    let expired = fixture.service.create(expiring).await.unwrap();
+    assert!(matches!(
+        fixture.service.authenticate(&expired.secret).await,
+        Err(ServiceError::Unauthorized)
+    ));
+
+    let phone = fixture
+        .service
+        .create(create(ana.id(), "Phone", &[]))
+        .await
+        .unwrap();
+    let names: Vec<String> = fixture
+        .service
+        .list(ana.id())
+        .await
+        .unwrap()
+        .iter()
+        .map(|t| t.name().to_string())
+        .collect();
+    assert_eq!(names.len(), 2);
+    assert!(names.contains(&"Phone".to_string()));
+    assert!(fixture.service.list(bruno.id()).await.unwrap().is_empty());
+
+    // Only the owner can revoke a token
+    assert!(matches!(
+        fixture.service.revoke(phone.token.id(), bruno.id()).await,
+        Err(ServiceError::NotFound)
+    ));
+    fixture.service.authenticate(&phone.secret).await.unwrap();
+
+    let mut ana = ana;
+    ana.deactivate();
+    fixture.users.save(&ana).await.unwrap();
+    assert!(matches!(
+        fixture.service.authenticate(&phone.secret).await,
+        Err(ServiceError::Unauthorized)
+    ));
+
+    fixture
+        .service
+        .revoke(phone.token.id(), ana.id())
+        .await
+        .unwrap();
+    assert!(fixture
+        .tokens
+        .find_by_id(phone.token.id())
+        .await
+        .unwrap()
+        .is_none());
+}
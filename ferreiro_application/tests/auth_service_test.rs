@@ -0,0 +1,645 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use ferreiro_adapters_cache::InMemoryCache;
+use ferreiro_adapters_db::testing::PlainTextHasher;
+use ferreiro_adapters_db::{
+    InMemoryBreachedPasswords, InMemoryEventPublisher, InMemoryRefreshTokenRepository,
+    InMemoryUserRepository,
+};
+use ferreiro_adapters_jwt::{JwtKey, JwtTokenIssuer, KeySet};
+use ferreiro_application::services::{AccountTokens, AuthServiceImpl, TokenPurpose};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::events::DomainEvent;
+use ferreiro_domain::models::User;
+use ferreiro_domain::ports::driven::{
+    AccessTokenVerifier, HashError, PasswordHasher, RepositoryError, UserRepository,
+};
+use ferreiro_domain::ports::driving::{
+    AuthService, ChangePasswordCommand, LoginCommand, RegisterCommand, ResetPasswordCommand,
+    ServiceError,
+};
+use ferreiro_domain::values::{Email, UserId};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+
+const BREACHED: &str = include_str!("fixtures/breached-passwords.txt");
+
+fn service(
+    events: Arc<InMemoryEventPublisher>,
+) -> AuthServiceImpl<InMemoryUserRepository, InMemoryEventPublisher> {
+    common::auth_service(Arc::new(InMemoryUserRepository::new()), events)
+        .with_breached_passwords(Arc::new(InMemoryBreachedPasswords::from_hashes(BREACHED)))
+}
+
+fn register(password: &str) -> RegisterCommand {
+    RegisterCommand {
+        email: "Ana.Silva@example.com".to_string(),
+        password: password.to_string(),
+        name: "Ana Silva".to_string(),
+        identifier: None,
+    }
+}
+
+fn domain_error(result: Result<impl Sized, ServiceError>) -> DomainError {
+    match result {
+        Err(ServiceError::Domain(error)) => error,
+        Err(other) => panic!("expected a domain error, got {:?}", other),
+        Ok(_) => panic!("expected a domain error"),
+    }
+}
+
+#[tokio::test]
+async fn test_register_applies_the_password_policy() {
+    let events = Arc::new(InMemoryEventPublisher::new());
+    let service = service(events.clone());
+
+    let rejected = [
+        ("short", DomainError::PasswordTooShort { min: 8 }),
+        ("letmein1", DomainError::PasswordTooCommon),
+        ("2718281828", DomainError::PasswordTooWeak),
+        ("anasilva1", DomainError::PasswordTooSimilar),
+        // Passes every local rule, but is in the breach fixture
+        (
+            "correct horse battery staple",
+            DomainError::PasswordBreached,
+        ),
+    ];
+    for (password, expected) in rejected {
+        assert_eq!(
+            domain_error(service.register(register(password)).await),
+            expected
+        );
+    }
+    assert!(events.get_events().is_empty());
+
+    let user = service
+        .register(register("vivid-otter-parade"))
+        .await
+        .unwrap();
+    assert_eq!(user.password_hash(), "plain$vivid-otter-parade");
+    assert!(matches!(
+        events.get_events().as_slice(),
+        [DomainEvent::UserRegistered { .. }]
+    ));
+    assert_eq!(
+        domain_error(service.register(register("another-good-one")).await),
+        DomainError::UserAlreadyExists
+    );
+}
+
+/// Finds nobody by email or identifier, as when another registration
+/// saves between the check and the save
+struct Racing(InMemoryUserRepository);
+
+#[async_trait]
+impl UserRepository for Racing {
+    async fn find_by_id(&self, id: &UserId) -> Result<Option<User>, RepositoryError> {
+        self.0.find_by_id(id).await
+    }
+    async fn find_by_email(&self, _email: &Email) -> Result<Option<User>, RepositoryError> {
+        Ok(None)
+    }
+    async fn find_by_identifier(&self, _identifier: &str) -> Result<Option<User>, RepositoryError> {
+        Ok(None)
+    }
+    async fn save(&self, user: &User) -> Result<(), RepositoryError> {
+        self.0.save(user).await
+    }
+    async fn delete(&self, id: &UserId) -> Result<(), RepositoryError> {
+        self.0.delete(id).await
+    }
+    async fn exists_by_email(&self, _email: &Email) -> Result<bool, RepositoryError> {
+        Ok(false)
+    }
+}
+
+#[tokio::test]
+async fn test_racing_registrations_leave_one_user() {
+    let events = Arc::new(InMemoryEventPublisher::new());
+    let auth = AuthServiceImpl::new(
+        Arc::new(Racing(InMemoryUserRepository::new())),
+        events.clone(),
+        Arc::new(PlainTextHasher),
+        Arc::new(InMemoryCache::new()),
+        AccountTokens::new(b"test-secret"),
+    );
+
+    auth.register(register("vivid-otter-parade")).await.unwrap();
+    assert_eq!(
+        domain_error(auth.register(register("quiet-lynx-harbor")).await),
+        DomainError::UserAlreadyExists
+    );
+    let registered = events
+        .get_events()
+        .iter()
+        .filter(|event| matches!(event, DomainEvent::UserRegistered { .. }))
+        .count();
+    assert_eq!(registered, 1);
+}
+
+#[tokio::test]
+async fn test_change_password_checks_the_current_one_and_the_policy() {
+    let service = service(Arc::new(InMemoryEventPublisher::new()));
+    let user = service
+        .register(register("vivid-otter-parade"))
+        .await
+        .unwrap();
+    let change = |current: &str, new: &str| ChangePasswordCommand {
+        user_id: user.id().clone(),
+        current_password: current.to_string(),
+        new_password: new.to_string(),
+        session_token: None,
+    };
+
+    assert_eq!(
+        domain_error(
+            service
+                .change_password(change("wrong", "quiet-lynx-harbor"))
+                .await
+        ),
+        DomainError::InvalidCredentials
+    );
+    assert_eq!(
+        domain_error(
+            service
+                .change_password(change("vivid-otter-parade", "P@ssw0rd!"))
+                .await
+        ),
+        DomainError::PasswordBreached
+    );
+    assert_eq!(
+        domain_error(
+            service
+                .change_password(change("vivid-otter-parade", "silva.ana"))
+                .await
+        ),
+        DomainError::PasswordTooSimilar
+    );
+
+    service
+        .change_password(change("vivid-otter-parade", "quiet-lynx-harbor"))
+        .await
+        .unwrap();
+    let login = |password: &str| LoginCommand {
+        identifier: "ana.silva@EXAMPLE.com".to_string(),
+        password: password.to_string(),
+        ip_address: None,
+    };
+    assert_eq!(
+        domain_error(service.login(login("vivid-otter-parade")).await),
+        DomainError::InvalidCredentials
+    );
+
+    let session = service.login(login("quiet-lynx-harbor")).await.unwrap();
+    let current = service
+        .get_user_by_session(session.session_token().unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(current.id(), user.id());
+
+    service
+        .logout(session.session_token().unwrap())
+        .await
+        .unwrap();
+    assert!(service
+        .get_user_by_session(session.session_token().unwrap())
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_change_password_ends_the_other_sessions() {
+    let service = service(Arc::new(InMemoryEventPublisher::new()));
+    let user = service
+        .register(register("vivid-otter-parade"))
+        .await
+        .unwrap();
+    let login = || LoginCommand {
+        identifier: "ana.silva@example.com".to_string(),
+        password: "vivid-otter-parade".to_string(),
+        ip_address: None,
+    };
+    let here = service.login(login()).await.unwrap();
+    let elsewhere = service.login(login()).await.unwrap();
+
+    service
+        .change_password(ChangePasswordCommand {
+            user_id: user.id().clone(),
+            current_password: "vivid-otter-parade".to_string(),
+            new_password: "quiet-lynx-harbor".to_string(),
+            session_token: here.session_token().map(str::to_string),
+        })
+        .await
+        .unwrap();
+
+    for (session, kept) in [(here, true), (elsewhere, false)] {
+        let user = service
+            .get_user_by_session(session.session_token().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(user.is_some(), kept);
+    }
+}
+
+/// Counts password checks, to see that failed logins all do the work
+struct CountingHasher(AtomicUsize);
+
+impl PasswordHasher for CountingHasher {
+    fn hash(&self, password: &str) -> Result<String, HashError> {
+        PlainTextHasher.hash(password)
+    }
+
+    fn verify(&self, password: &str, hash: &str) -> Result<bool, HashError> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        PlainTextHasher.verify(password, hash)
+    }
+}
+
+#[tokio::test]
+async fn test_unknown_and_inactive_users_still_check_a_password() {
+    let hasher = Arc::new(CountingHasher(AtomicUsize::new(0)));
+    let users = Arc::new(InMemoryUserRepository::new());
+    let service = AuthServiceImpl::new(
+        users.clone(),
+        Arc::new(InMemoryEventPublisher::new()),
+        hasher.clone(),
+        Arc::new(InMemoryCache::new()),
+        AccountTokens::new(b"test-secret"),
+    );
+    let mut user = service
+        .register(register("vivid-otter-parade"))
+        .await
+        .unwrap();
+    user.deactivate();
+    users.save(&user).await.unwrap();
+
+    for identifier in ["nobody@example.com", "ana.silva@example.com"] {
+        let result = service
+            .login(LoginCommand {
+                identifier: identifier.to_string(),
+                password: "vivid-otter-parade".to_string(),
+                ip_address: None,
+            })
+            .await;
+        assert_eq!(domain_error(result), DomainError::InvalidCredentials);
+    }
+    assert_eq!(hasher.0.load(Ordering::SeqCst), 2);
+}
+
+fn tokens_in(events: &InMemoryEventPublisher) -> Vec<String> {
+    events
+        .get_events()
+        .into_iter()
+        .filter_map(|event| match event {
+            DomainEvent::PasswordResetRequested { token, .. }
+            | DomainEvent::EmailVerificationRequested { token, .. } => {
+                Some(token.expose().to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_password_reset_tokens_work_once() {
+    let events = Arc::new(InMemoryEventPublisher::new());
+    let service = service(events.clone());
+    service
+        .register(register("vivid-otter-parade"))
+        .await
+        .unwrap();
+    events.clear();
+
+    // Unknown addresses succeed quietly
+    service
+        .request_password_reset("bob@example.com")
+        .await
+        .unwrap();
+    service
+        .request_password_reset("not an email")
+        .await
+        .unwrap();
+    assert!(events.get_events().is_empty());
+
+    let login = |password: &str| LoginCommand {
+        identifier: "ana.silva@example.com".to_string(),
+        password: password.to_string(),
+        ip_address: None,
+    };
+    let session = service.login(login("vivid-otter-parade")).await.unwrap();
+
+    service
+        .request_password_reset("ANA.SILVA@example.com")
+        .await
+        .unwrap();
+    // Events can be logged without giving the token away
+    let event = format!("{:?}", events.get_events().last().unwrap());
+    let token = tokens_in(&events).pop().unwrap();
+    assert!(!event.contains(&token));
+    let reset = |password: &str| ResetPasswordCommand {
+        token: token.clone(),
+        new_password: password.to_string(),
+    };
+
+    assert_eq!(
+        domain_error(service.reset_password(reset("12345678")).await),
+        DomainError::PasswordTooCommon
+    );
+    assert_eq!(
+        domain_error(service.verify_email(&token).await),
+        DomainError::InvalidToken
+    );
+    service
+        .reset_password(reset("quiet-lynx-harbor"))
+        .await
+        .unwrap();
+    assert!(matches!(
+        events.get_events().last(),
+        Some(DomainEvent::PasswordReset { .. })
+    ));
+
+    // The new hash invalidates the token
+    assert_eq!(
+        domain_error(service.reset_password(reset("another-fine-pass")).await),
+        DomainError::InvalidToken
+    );
+    service.login(login("quiet-lynx-harbor")).await.unwrap();
+
+    // Whoever knew the old password is logged out
+    assert!(service
+        .get_user_by_session(session.session_token().unwrap())
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_email_verification_activates_new_users() {
+    let events = Arc::new(InMemoryEventPublisher::new());
+    let service = service(events.clone()).require_email_verification();
+    let user = service
+        .register(register("vivid-otter-parade"))
+        .await
+        .unwrap();
+    assert!(!user.is_active() && !user.is_email_verified());
+
+    let login = LoginCommand {
+        identifier: "ana.silva@example.com".to_string(),
+        password: "vivid-otter-parade".to_string(),
+        ip_address: None,
+    };
+    assert_eq!(
+        domain_error(service.login(login).await),
+        DomainError::InvalidCredentials
+    );
+    // Inactive users can't reset their way in either
+    service
+        .request_password_reset("ana.silva@example.com")
+        .await
+        .unwrap();
+    assert!(tokens_in(&events).is_empty());
+
+    service.request_email_verification(user.id()).await.unwrap();
+    let token = tokens_in(&events).pop().unwrap();
+    assert_eq!(
+        domain_error(service.verify_email(&format!("{}x", token)).await),
+        DomainError::InvalidToken
+    );
+
+    let verified = service.verify_email(&token).await.unwrap();
+    assert!(verified.is_active() && verified.is_email_verified());
+    assert!(matches!(
+        events.get_events().last(),
+        Some(DomainEvent::EmailVerified { .. })
+    ));
+    assert_eq!(
+        domain_error(service.verify_email(&token).await),
+        DomainError::InvalidToken
+    );
+
+    // Nothing more to send once verified
+    events.clear();
+    service.request_email_verification(user.id()).await.unwrap();
+    assert!(events.get_events().is_empty());
+}
+
+#[tokio::test]
+async fn test_email_verification_leaves_deactivated_users_inactive() {
+    let users = Arc::new(InMemoryUserRepository::new());
+    let service = common::auth_service(users.clone(), Arc::new(InMemoryEventPublisher::new()))
+        .require_email_verification();
+    let mut user = service
+        .register(register("vivid-otter-parade"))
+        .await
+        .unwrap();
+    user.mark_email_verified();
+    user.deactivate();
+    users.save(&user).await.unwrap();
+
+    let token =
+        AccountTokens::new(b"test-secret").make_token(TokenPurpose::EmailVerification, &user);
+    let verified = service.verify_email(&token).await.unwrap();
+    assert!(!verified.is_active());
+}
+
+#[tokio::test]
+async fn test_email_verification_leaves_users_deactivated_while_unverified_inactive() {
+    let users = Arc::new(InMemoryUserRepository::new());
+    let events = Arc::new(InMemoryEventPublisher::new());
+    let service = common::auth_service(users.clone(), events.clone()).require_email_verification();
+    let user = service
+        .register(register("vivid-otter-parade"))
+        .await
+        .unwrap();
+    service.request_email_verification(user.id()).await.unwrap();
+    let token = tokens_in(&events).pop().unwrap();
+
+    let staff = common::save_user(&users, "staff", true).await;
+    service
+        .deactivate_user(staff.id(), user.id())
+        .await
+        .unwrap();
+    let verified = service.verify_email(&token).await.unwrap();
+    assert!(!verified.is_active() && verified.is_email_verified());
+}
+
+#[test]
+fn test_account_tokens_expire_and_are_bound_to_the_user() {
+    let tokens = AccountTokens::new(b"secret").with_ttl(Duration::from_secs(3600));
+    let mut user = User::new(
+        Email::new("ana@example.com").unwrap(),
+        "Ana".to_string(),
+        "plain$old".to_string(),
+    );
+    let issued = Utc::now();
+    let token = tokens.make_token_at(TokenPurpose::PasswordReset, &user, issued);
+    let check = |user: &User, token: &str, at| {
+        tokens.check_token_at(TokenPurpose::PasswordReset, user, token, at)
+    };
+
+    assert_eq!(AccountTokens::user_id(&token).as_ref(), Some(user.id()));
+    assert!(check(&user, &token, issued + chrono::Duration::minutes(59)));
+    assert!(!check(
+        &user,
+        &token,
+        issued + chrono::Duration::minutes(61)
+    ));
+    assert!(!check(&user, &token, issued - chrono::Duration::minutes(1)));
+    assert!(!tokens.check_token(TokenPurpose::EmailVerification, &user, &token));
+    assert!(!AccountTokens::new(b"other").check_token(TokenPurpose::PasswordReset, &user, &token));
+
+    let other = User::new(
+        user.email().clone(),
+        "Ana".to_string(),
+        "plain$old".to_string(),
+    );
+    assert!(!check(&other, &token, issued));
+
+    user.set_password_hash("plain$new".to_string());
+    assert!(!check(&user, &token, issued));
+}
+
+#[tokio::test]
+async fn test_login_with_a_token_issuer_returns_a_token_pair() {
+    let users = Arc::new(InMemoryUserRepository::new());
+    let issuer = Arc::new(
+        JwtTokenIssuer::new(
+            KeySet::new().signing(JwtKey::generate_hs256("test")),
+            Arc::new(InMemoryRefreshTokenRepository::new()),
+        )
+        .unwrap(),
+    );
+    let service = common::auth_service(users.clone(), Arc::new(InMemoryEventPublisher::new()))
+        .with_token_issuer(issuer.clone());
+    let user = service
+        .register(register("vivid-otter-parade"))
+        .await
+        .unwrap();
+
+    let login = service
+        .login(LoginCommand {
+            identifier: "ana.silva@example.com".to_string(),
+            password: "vivid-otter-parade".to_string(),
+            ip_address: None,
+        })
+        .await
+        .unwrap();
+    assert!(login.session_token().is_none());
+    let tokens = login.tokens().unwrap().clone();
+    assert_eq!(
+        &issuer.verify(&tokens.access_token).unwrap().user_id,
+        user.id()
+    );
+
+    let renewed = service.refresh(&tokens.refresh_token).await.unwrap();
+    assert!(matches!(
+        service.refresh(&tokens.refresh_token).await,
+        Err(ServiceError::Unauthorized)
+    ));
+    // Reuse revoked the renewed token along with it
+    assert!(matches!(
+        service.refresh(&renewed.refresh_token).await,
+        Err(ServiceError::Unauthorized)
+    ));
+
+    // Deactivated users can't renew, and logging out revokes
+    let second = service
+        .login(LoginCommand {
+            identifier: "ana.silva@example.com".to_string(),
+            password: "vivid-otter-parade".to_string(),
+            ip_address: None,
+        })
+        .await
+        .unwrap();
+    let second = second.tokens().unwrap();
+    service.logout(&second.refresh_token).await.unwrap();
+    assert!(service.refresh(&second.refresh_token).await.is_err());
+
+    let third = service
+        .login(LoginCommand {
+            identifier: "ana.silva@example.com".to_string(),
+            password: "vivid-otter-parade".to_string(),
+            ip_address: None,
+        })
+        .await
+        .unwrap();
+    let mut user = user;
+    user.deactivate();
+    users.save(&user).await.unwrap();
+    assert!(matches!(
+        service
+            .refresh(&third.tokens().unwrap().refresh_token)
+            .await,
+        Err(ServiceError::Unauthorized)
+    ));
+}
+
+#[tokio::test]
+async fn test_password_changes_and_deactivation_revoke_refresh_tokens() {
+    let users = Arc::new(InMemoryUserRepository::new());
+    let issuer = Arc::new(
+        JwtTokenIssuer::new(
+            KeySet::new().signing(JwtKey::generate_hs256("test")),
+            Arc::new(InMemoryRefreshTokenRepository::new()),
+        )
+        .unwrap(),
+    );
+    let events = Arc::new(InMemoryEventPublisher::new());
+    let service = common::auth_service(users.clone(), events.clone()).with_token_issuer(issuer);
+    let user = service
+        .register(register("vivid-otter-parade"))
+        .await
+        .unwrap();
+    let mut staff = User::new(
+        Email::new("staff@example.com").unwrap(),
+        "Staff".to_string(),
+        "plain$staff-password".to_string(),
+    );
+    staff.make_staff();
+    users.save(&staff).await.unwrap();
+
+    let login = |password: &str| LoginCommand {
+        identifier: "ana.silva@example.com".to_string(),
+        password: password.to_string(),
+        ip_address: None,
+    };
+    let before = service.login(login("vivid-otter-parade")).await.unwrap();
+    service
+        .change_password(ChangePasswordCommand {
+            user_id: user.id().clone(),
+            current_password: "vivid-otter-parade".to_string(),
+            new_password: "quiet-lynx-harbor".to_string(),
+            session_token: None,
+        })
+        .await
+        .unwrap();
+    let refresh_token = &before.tokens().unwrap().refresh_token;
+    assert!(service.refresh(refresh_token).await.is_err());
+
+    let after = service.login(login("quiet-lynx-harbor")).await.unwrap();
+    assert!(matches!(
+        service.deactivate_user(user.id(), user.id()).await,
+        Err(ServiceError::Unauthorized)
+    ));
+    service
+        .deactivate_user(staff.id(), user.id())
+        .await
+        .unwrap();
+    assert!(service
+        .refresh(&after.tokens().unwrap().refresh_token)
+        .await
+        .is_err());
+    assert!(matches!(
+        events.get_events().last(),
+        Some(DomainEvent::UserDeactivated { .. })
+    ));
+    assert_eq!(
+        domain_error(service.login(login("quiet-lynx-harbor")).await),
+        DomainError::InvalidCredentials
+    );
+}
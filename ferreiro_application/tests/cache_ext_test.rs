@@ -0,0 +1,29 @@
+use ferreiro_adapters_cache::{InMemoryCache, NamespacedCache};
+use ferreiro_application::CacheExt;
+use ferreiro_domain::ports::driven::Cache;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Stats {
+    views: u64,
+    tags: Vec<String>,
+}
+
+#[tokio::test]
+async fn test_typed_values_round_trip() {
+    let cache = NamespacedCache::new(Arc::new(InMemoryCache::new()), "stats");
+    let stats = Stats {
+        views: 42,
+        tags: vec!["rust".to_string()],
+    };
+    cache.set_typed("post:1", &stats, None).await.unwrap();
+    cache.set("post:2", b"not json", None).await.unwrap();
+
+    assert_eq!(
+        cache.get_typed::<Stats>("post:1").await.unwrap(),
+        Some(stats)
+    );
+    let many: Vec<Option<Stats>> = cache.get_many_typed(&["post:2", "post:3"]).await.unwrap();
+    assert_eq!(many, vec![None, None]);
+}
@@ -0,0 +1,36 @@
+//! Set-up shared by the service tests; each test binary uses only some of it
+#![allow(dead_code)]
+
+use ferreiro_adapters_cache::InMemoryCache;
+use ferreiro_adapters_db::testing::PlainTextHasher;
+use ferreiro_adapters_db::{InMemoryEventPublisher, InMemoryUserRepository};
+use ferreiro_application::services::{AccountTokens, AuthServiceImpl};
+use ferreiro_domain::models::{AuthUser, User};
+use ferreiro_domain::ports::driven::UserRepository;
+use ferreiro_domain::values::Email;
+use std::sync::Arc;
+
+/// An auth service over in-memory stores, with passwords kept readable
+pub fn auth_service<M: AuthUser>(
+    users: Arc<InMemoryUserRepository<M>>,
+    events: Arc<InMemoryEventPublisher>,
+) -> AuthServiceImpl<InMemoryUserRepository<M>, InMemoryEventPublisher, M> {
+    AuthServiceImpl::new(
+        users,
+        events,
+        Arc::new(PlainTextHasher),
+        Arc::new(InMemoryCache::new()),
+        AccountTokens::new(b"test-secret"),
+    )
+}
+
+/// Saves `<name>@example.com`, whose password is `password`
+pub async fn save_user(users: &InMemoryUserRepository, name: &str, staff: bool) -> User {
+    let email = Email::new(&format!("{}@example.com", name)).unwrap();
+    let mut user = User::new(email, name.to_string(), "plain$password".to_string());
+    if staff {
+        user.make_staff();
+    }
+    users.save(&user).await.unwrap();
+    user
+}
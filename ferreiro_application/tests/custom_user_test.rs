@@ -0,0 +1,203 @@
+use ferreiro_adapters_db::{InMemoryEventPublisher, InMemoryUserRepository};
+use ferreiro_application::services::AuthServiceImpl;
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::events::DomainEvent;
+use ferreiro_domain::models::{AuthUser, NewUser};
+use ferreiro_domain::ports::driven::UserRepository;
+use ferreiro_domain::ports::driving::{
+    AuthService, LoginCommand, RegisterCommand, ResetPasswordCommand, ServiceError,
+};
+use ferreiro_domain::values::{Email, UserId};
+use std::sync::Arc;
+
+mod common;
+
+/// A forum member who logs in with a username
+#[derive(Debug, Clone)]
+struct Member {
+    id: UserId,
+    username: String,
+    email: Email,
+    name: String,
+    password_hash: String,
+    is_active: bool,
+    email_verified: bool,
+    awaiting_verification: bool,
+    karma: u32,
+}
+
+impl AuthUser for Member {
+    const IDENTIFIER_FIELD: &'static str = "username";
+
+    fn create(registration: NewUser) -> Result<Self, DomainError> {
+        Ok(Self {
+            id: UserId::generate(),
+            username: registration.identifier,
+            email: registration.email,
+            name: registration.name,
+            password_hash: registration.password_hash,
+            is_active: true,
+            email_verified: false,
+            awaiting_verification: false,
+            karma: 1,
+        })
+    }
+
+    fn normalize_identifier(identifier: &str) -> Result<String, DomainError> {
+        let username = identifier.trim().to_lowercase();
+        let valid = (3..=30).contains(&username.len())
+            && username
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(DomainError::InvalidCredentials);
+        }
+        Ok(username)
+    }
+
+    fn id(&self) -> &UserId {
+        &self.id
+    }
+    fn identifier(&self) -> String {
+        self.username.clone()
+    }
+    fn email(&self) -> &Email {
+        &self.email
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn password_hash(&self) -> &str {
+        &self.password_hash
+    }
+    fn is_active(&self) -> bool {
+        self.is_active
+    }
+    fn is_staff(&self) -> bool {
+        false
+    }
+    fn is_superuser(&self) -> bool {
+        false
+    }
+    fn is_email_verified(&self) -> bool {
+        self.email_verified
+    }
+    fn is_awaiting_verification(&self) -> bool {
+        self.awaiting_verification
+    }
+    fn set_password_hash(&mut self, password_hash: String) {
+        self.password_hash = password_hash;
+    }
+    fn set_active(&mut self, active: bool) {
+        self.is_active = active;
+        self.awaiting_verification = false;
+    }
+    fn await_verification(&mut self) {
+        self.is_active = false;
+        self.awaiting_verification = true;
+    }
+    fn mark_email_verified(&mut self) {
+        self.email_verified = true;
+    }
+}
+
+type Members = InMemoryUserRepository<Member>;
+
+fn service(
+    members: Arc<Members>,
+    events: Arc<InMemoryEventPublisher>,
+) -> AuthServiceImpl<Members, InMemoryEventPublisher, Member> {
+    common::auth_service(members, events)
+}
+
+fn register(username: &str, email: &str) -> RegisterCommand {
+    RegisterCommand {
+        email: email.to_string(),
+        password: "vivid-otter-parade".to_string(),
+        name: "Ana Silva".to_string(),
+        identifier: Some(username.to_string()),
+    }
+}
+
+fn login(identifier: &str, password: &str) -> LoginCommand {
+    LoginCommand {
+        identifier: identifier.to_string(),
+        password: password.to_string(),
+        ip_address: None,
+    }
+}
+
+#[tokio::test]
+async fn test_custom_models_register_and_log_in_by_their_identifier() {
+    let members = Arc::new(Members::default());
+    let auth = service(members.clone(), Arc::new(InMemoryEventPublisher::new()));
+
+    let ana = auth
+        .register(register("Ana_S", "ana@example.com"))
+        .await
+        .unwrap();
+    assert_eq!(ana.username, "ana_s");
+    assert_eq!(ana.karma, 1);
+    assert!(matches!(
+        auth.register(register("ana_s", "other@example.com")).await,
+        Err(ServiceError::Domain(DomainError::UserAlreadyExists))
+    ));
+    assert!(matches!(
+        auth.register(register("ana_2", "Ana@Example.com")).await,
+        Err(ServiceError::Domain(DomainError::UserAlreadyExists))
+    ));
+    assert!(auth
+        .register(register("no spaces", "bia@example.com"))
+        .await
+        .is_err());
+
+    let authenticated = auth
+        .login(login("ANA_S", "vivid-otter-parade"))
+        .await
+        .unwrap();
+    assert_eq!(authenticated.user.id(), ana.id());
+    let session = authenticated.session_token().unwrap();
+    let current = auth.get_user_by_session(session).await.unwrap().unwrap();
+    assert_eq!(current.karma, 1);
+
+    // The email isn't what this model logs in with
+    assert!(matches!(
+        auth.login(login("ana@example.com", "vivid-otter-parade"))
+            .await,
+        Err(ServiceError::Domain(DomainError::InvalidCredentials))
+    ));
+    assert!(members.find_by_identifier("ana_s").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_custom_models_reset_passwords_by_email() {
+    let members = Arc::new(Members::default());
+    let events = Arc::new(InMemoryEventPublisher::new());
+    let auth = service(members, events.clone());
+    auth.register(register("ana_s", "ana@example.com"))
+        .await
+        .unwrap();
+
+    auth.request_password_reset("ana@example.com")
+        .await
+        .unwrap();
+    let token = events
+        .get_events()
+        .into_iter()
+        .find_map(|event| match event {
+            DomainEvent::PasswordResetRequested { token, .. } => Some(token.expose().to_string()),
+            _ => None,
+        })
+        .unwrap();
+    auth.reset_password(ResetPasswordCommand {
+        token,
+        new_password: "quiet-lynx-harbor".to_string(),
+    })
+    .await
+    .unwrap();
+
+    assert!(auth
+        .login(login("ana_s", "quiet-lynx-harbor"))
+        .await
+        .is_ok());
+}
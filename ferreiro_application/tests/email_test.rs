@@ -0,0 +1,94 @@
+use ferreiro_adapters_db::{InMemoryEventPublisher, InMemoryMxLookup, InMemoryUserRepository};
+use ferreiro_application::services::ensure_deliverable;
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::models::User;
+use ferreiro_domain::ports::driven::UserRepository;
+use ferreiro_domain::ports::driving::{AuthService, RegisterCommand, ServiceError};
+use ferreiro_domain::values::Email;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+mod common;
+
+#[tokio::test]
+async fn test_find_by_email_uses_the_normalized_form() {
+    let repo = InMemoryUserRepository::new();
+    let user = User::new(
+        Email::new("Ana@Bücher.de").unwrap(),
+        "Ana".to_string(),
+        "hash".to_string(),
+    );
+    repo.save(&user).await.unwrap();
+
+    let found = repo
+        .find_by_email(&Email::new("ana@XN--BCHER-KVA.DE").unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.id(), user.id());
+    // The stored address keeps the case it was registered with
+    assert_eq!(found.email().as_str(), "Ana@xn--bcher-kva.de");
+    assert!(repo
+        .exists_by_email(&Email::new("ANA@bücher.de").unwrap())
+        .await
+        .unwrap());
+    assert!(!repo
+        .exists_by_email(&Email::new("bob@bücher.de").unwrap())
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn test_ensure_deliverable_checks_mx_records() {
+    let lookup = InMemoryMxLookup::new()
+        .with_domain("example.com", &["mx1.example.com."])
+        .with_domain("no-mail.example", &["."]);
+
+    let check = |address: &str| {
+        let email = Email::new(address).unwrap();
+        let lookup = lookup.clone();
+        async move { ensure_deliverable(&lookup, &email).await }
+    };
+
+    assert!(check("user@Example.com").await.is_ok());
+    assert!(check("user@[192.0.2.1]").await.is_ok());
+    for address in ["user@no-mail.example", "user@unknown.example"] {
+        assert!(matches!(
+            check(address).await,
+            Err(ServiceError::Domain(DomainError::UndeliverableEmail))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_registration_checks_mx_records_when_configured() {
+    let users = Arc::new(InMemoryUserRepository::new());
+    let lookup = InMemoryMxLookup::new().with_domain("example.com", &["mx1.example.com."]);
+    let auth = common::auth_service(users.clone(), Arc::new(InMemoryEventPublisher::new()))
+        .with_mx_lookup(Arc::new(lookup));
+    let register = |email: &str| RegisterCommand {
+        email: email.to_string(),
+        password: "vivid-otter-parade".to_string(),
+        name: "Ana".to_string(),
+        identifier: None,
+    };
+
+    assert!(matches!(
+        auth.register(register("ana@no-mail.example")).await,
+        Err(ServiceError::Domain(DomainError::UndeliverableEmail))
+    ));
+    assert!(!users
+        .exists_by_email(&Email::new("ana@no-mail.example").unwrap())
+        .await
+        .unwrap());
+    auth.register(register("ana@example.com")).await.unwrap();
+}
+
+#[test]
+fn test_equal_emails_hash_alike() {
+    let upper = Email::from_trusted("ÁNA@example.com".to_string());
+    let lower = Email::from_trusted("ána@example.com".to_string());
+    let set: HashSet<_> = [upper.clone()].into_iter().collect();
+    assert_eq!(upper == lower, set.contains(&lower));
+    assert_eq!(upper, lower);
+}
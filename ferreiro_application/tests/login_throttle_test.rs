@@ -0,0 +1,216 @@
+use ferreiro_adapters_db::{
+    InMemoryEventPublisher, InMemoryLoginFailureStore, InMemoryUserRepository,
+};
+use ferreiro_application::services::{AuthServiceImpl, FailureLimits, LoginThrottle};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::events::DomainEvent;
+use ferreiro_domain::models::User;
+use ferreiro_domain::ports::driving::{AuthService, AuthenticatedUser, LoginCommand, ServiceError};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+
+struct Fixture {
+    users: Arc<InMemoryUserRepository>,
+    events: Arc<InMemoryEventPublisher>,
+    auth: AuthServiceImpl<InMemoryUserRepository, InMemoryEventPublisher>,
+}
+
+impl Fixture {
+    fn new(throttle: LoginThrottle) -> Self {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let events = Arc::new(InMemoryEventPublisher::new());
+        let auth = common::auth_service(users.clone(), events.clone())
+            .with_login_throttle(Arc::new(InMemoryLoginFailureStore::new()), throttle);
+        Self {
+            users,
+            events,
+            auth,
+        }
+    }
+
+    async fn user(&self, name: &str, staff: bool) -> User {
+        common::save_user(&self.users, name, staff).await
+    }
+
+    async fn login(
+        &self,
+        email: &str,
+        password: &str,
+        ip_address: &str,
+    ) -> Result<AuthenticatedUser, ServiceError> {
+        self.auth
+            .login(LoginCommand {
+                identifier: email.to_string(),
+                password: password.to_string(),
+                ip_address: Some(ip_address.parse::<IpAddr>().unwrap()),
+            })
+            .await
+    }
+}
+
+fn retry_after(result: Result<AuthenticatedUser, ServiceError>) -> Option<u64> {
+    match result {
+        Err(ServiceError::Domain(DomainError::TooManyLoginAttempts { retry_after })) => {
+            Some(retry_after)
+        }
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn test_repeated_failures_lock_the_account_until_staff_unlock_it() {
+    let fixture = Fixture::new(LoginThrottle {
+        account: FailureLimits {
+            free_attempts: 2,
+            lockout_after: 4,
+        },
+        base_delay: Duration::ZERO,
+        lockout: Duration::from_secs(10 * 60),
+        ..LoginThrottle::default()
+    });
+    let admin = fixture.user("admin", true).await;
+    let ana = fixture.user("ana", false).await;
+
+    for _ in 0..4 {
+        assert!(matches!(
+            fixture.login("ana@example.com", "guess", "192.0.2.1").await,
+            Err(ServiceError::Domain(DomainError::InvalidCredentials))
+        ));
+    }
+    // Locked now, even with the right password, and from anywhere
+    let wait = retry_after(
+        fixture
+            .login("ANA@example.com", "password", "192.0.2.2")
+            .await,
+    );
+    assert!(wait.is_some_and(|wait| (599..=600).contains(&wait)));
+
+    // Emails nobody has lock out the same way
+    for _ in 0..4 {
+        fixture
+            .login("ghost@example.com", "guess", "192.0.2.1")
+            .await
+            .ok();
+    }
+    assert!(retry_after(
+        fixture
+            .login("ghost@example.com", "guess", "192.0.2.1")
+            .await
+    )
+    .is_some());
+
+    let events = fixture.events.get_events();
+    let failed = events
+        .iter()
+        .filter(|event| {
+            matches!(
+                event,
+                DomainEvent::LoginFailed { user_id: Some(user_id), ip_address: Some(_), .. }
+                    if user_id == ana.id()
+            )
+        })
+        .count();
+    assert_eq!(failed, 4);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        DomainEvent::AccountLocked { user_id: Some(user_id), .. } if user_id == ana.id()
+    )));
+
+    assert!(matches!(
+        fixture.auth.unlock_account(ana.id(), ana.id()).await,
+        Err(ServiceError::Unauthorized)
+    ));
+    fixture
+        .auth
+        .unlock_account(admin.id(), ana.id())
+        .await
+        .unwrap();
+    assert!(fixture
+        .login("ana@example.com", "password", "192.0.2.1")
+        .await
+        .is_ok());
+    assert!(fixture.events.get_events().iter().any(|event| matches!(
+        event,
+        DomainEvent::AccountUnlocked { user_id, unlocked_by, .. }
+            if user_id == ana.id() && unlocked_by == admin.id()
+    )));
+}
+
+#[tokio::test]
+async fn test_failures_back_off_per_account_and_per_address() {
+    let fixture = Fixture::new(LoginThrottle {
+        account: FailureLimits {
+            free_attempts: 1,
+            lockout_after: 100,
+        },
+        ip_address: FailureLimits {
+            free_attempts: 3,
+            lockout_after: 100,
+        },
+        base_delay: Duration::from_secs(60),
+        ..LoginThrottle::default()
+    });
+    fixture.user("ana", false).await;
+    fixture.user("dani", false).await;
+
+    // One failure is free; after it, the account waits a minute
+    fixture
+        .login("ana@example.com", "guess", "192.0.2.1")
+        .await
+        .ok();
+    let wait = retry_after(
+        fixture
+            .login("ana@example.com", "password", "192.0.2.9")
+            .await,
+    );
+    assert!(wait.is_some_and(|wait| (59..=60).contains(&wait)));
+
+    // Trying one password on many accounts catches up with the address
+    for name in ["bia", "caio"] {
+        assert!(retry_after(
+            fixture
+                .login(&format!("{}@example.com", name), "guess", "192.0.2.1")
+                .await
+        )
+        .is_none());
+    }
+    assert!(retry_after(
+        fixture
+            .login("dani@example.com", "password", "192.0.2.1")
+            .await
+    )
+    .is_some());
+    assert!(fixture
+        .login("dani@example.com", "password", "192.0.2.2")
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_every_spelling_of_an_email_counts_against_the_account() {
+    let fixture = Fixture::new(LoginThrottle {
+        account: FailureLimits {
+            free_attempts: 10,
+            lockout_after: 3,
+        },
+        ..LoginThrottle::default()
+    });
+    fixture.user("ana", false).await;
+
+    for spelling in [
+        "ANA@EXAMPLE.COM",
+        "ana@exa\u{AD}mple.com",
+        "ana@\u{FF45}xample.com",
+    ] {
+        assert!(retry_after(fixture.login(spelling, "guess", "192.0.2.1").await).is_none());
+    }
+    assert!(retry_after(
+        fixture
+            .login("ana@example.com", "password", "192.0.2.2")
+            .await
+    )
+    .is_some());
+}
@@ -0,0 +1,283 @@
+use chrono::{DateTime, Utc};
+use ferreiro_adapters_db::{
+    InMemoryEventPublisher, InMemoryLoginFailureStore, InMemoryTotpDeviceRepository,
+    InMemoryUserRepository,
+};
+use ferreiro_application::services::{
+    AuthServiceImpl, FailureLimits, LoginThrottle, MfaServiceImpl, Totp,
+};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::events::DomainEvent;
+use ferreiro_domain::models::User;
+use ferreiro_domain::ports::driven::TotpDeviceRepository;
+use ferreiro_domain::ports::driving::{
+    AuthService, Credentials, LoginCommand, MfaService, ServiceError,
+};
+use ferreiro_domain::values::UserId;
+use std::sync::Arc;
+
+mod common;
+
+type Mfa =
+    MfaServiceImpl<InMemoryTotpDeviceRepository, InMemoryUserRepository, InMemoryEventPublisher>;
+
+struct Fixture {
+    users: Arc<InMemoryUserRepository>,
+    devices: Arc<InMemoryTotpDeviceRepository>,
+    events: Arc<InMemoryEventPublisher>,
+    mfa: Arc<Mfa>,
+    auth: AuthServiceImpl<InMemoryUserRepository, InMemoryEventPublisher>,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let devices = Arc::new(InMemoryTotpDeviceRepository::new());
+        let events = Arc::new(InMemoryEventPublisher::new());
+        let mfa = Arc::new(
+            MfaServiceImpl::new(devices.clone(), users.clone(), events.clone())
+                .with_issuer("Ferreiro Blog"),
+        );
+        let auth =
+            common::auth_service(users.clone(), events.clone()).require_mfa_for_staff(mfa.clone());
+        Self {
+            users,
+            devices,
+            events,
+            mfa,
+            auth,
+        }
+    }
+
+    async fn user(&self, name: &str, staff: bool) -> User {
+        common::save_user(&self.users, name, staff).await
+    }
+
+    async fn login(&self, user: &User) -> Credentials {
+        self.auth
+            .login(LoginCommand {
+                identifier: user.email().to_string(),
+                password: "password".to_string(),
+                ip_address: None,
+            })
+            .await
+            .unwrap()
+            .credentials
+    }
+
+    /// The app's code `steps` steps from now
+    async fn code(&self, user_id: &UserId, steps: i64) -> String {
+        let device = self.devices.find(user_id).await.unwrap().unwrap();
+        let now = Totp::step(Utc::now()) as i64;
+        Totp::new(device.secret()).code_at((now + steps) as u64)
+    }
+}
+
+#[test]
+fn test_totp_matches_the_rfc_6238_vectors() {
+    let totp = Totp::new(b"12345678901234567890");
+    for (time, code) in [
+        (59, "287082"),
+        (1111111109, "081804"),
+        (1234567890, "005924"),
+    ] {
+        let at = DateTime::from_timestamp(time, 0).unwrap();
+        assert_eq!(totp.code_at(Totp::step(at)), code);
+        assert_eq!(totp.verify_at(code, at, 1), Some(Totp::step(at)));
+    }
+
+    // One step of skew either way, no more
+    let at = DateTime::from_timestamp(1111111109, 0).unwrap();
+    let later = at + chrono::Duration::seconds(30);
+    assert!(totp.verify_at("081804", later, 1).is_some());
+    assert!(totp.verify_at("081804", later, 0).is_none());
+    assert!(totp.verify_at("81804", at, 1).is_none());
+
+    assert_eq!(totp.base32_secret(), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+    assert_eq!(
+        totp.provisioning_uri("Ferreiro Blog", "ana@example.com"),
+        "otpauth://totp/Ferreiro%20Blog:ana%40example.com\
+         ?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Ferreiro%20Blog\
+         &algorithm=SHA1&digits=6&period=30"
+    );
+}
+
+#[tokio::test]
+async fn test_login_asks_for_the_second_factor_once_enabled() {
+    let fixture = Fixture::new();
+    let ana = fixture.user("ana", false).await;
+    assert!(matches!(fixture.login(&ana).await, Credentials::Session(_)));
+
+    let enrollment = fixture.mfa.begin_enrollment(ana.id()).await.unwrap();
+    assert!(enrollment.provisioning_uri.contains(&enrollment.secret));
+    assert!(matches!(
+        fixture.mfa.confirm_enrollment(ana.id(), "000000").await,
+        Err(ServiceError::Domain(DomainError::InvalidMfaCode))
+    ));
+    let code = fixture.code(ana.id(), 0).await;
+    let recovery_codes = fixture
+        .mfa
+        .confirm_enrollment(ana.id(), &code)
+        .await
+        .unwrap();
+    assert_eq!(recovery_codes.len(), 10);
+
+    // The password alone no longer logs in
+    let Credentials::MfaRequired(pending) = fixture.login(&ana).await else {
+        panic!("expected a second factor to be required");
+    };
+    assert!(matches!(
+        fixture.auth.complete_login(&pending, "not a code").await,
+        Err(ServiceError::Domain(DomainError::InvalidMfaCode))
+    ));
+    let code = fixture.code(ana.id(), 1).await;
+    let authenticated = fixture.auth.complete_login(&pending, &code).await.unwrap();
+    assert!(authenticated.session_token().is_some());
+    assert!(matches!(
+        fixture.auth.complete_login(&pending, &code).await,
+        Err(ServiceError::Domain(DomainError::InvalidToken))
+    ));
+
+    // Codes can't be replayed, and recovery codes work once each
+    let Credentials::MfaRequired(pending) = fixture.login(&ana).await else {
+        panic!("expected a second factor to be required");
+    };
+    assert!(fixture.auth.complete_login(&pending, &code).await.is_err());
+    let recovery = recovery_codes[0].to_uppercase();
+    assert!(fixture
+        .auth
+        .complete_login(&pending, &recovery)
+        .await
+        .is_ok());
+    assert!(!fixture
+        .mfa
+        .verify(ana.id(), &recovery_codes[0])
+        .await
+        .unwrap());
+
+    // Too many wrong codes and the user starts over
+    let Credentials::MfaRequired(pending) = fixture.login(&ana).await else {
+        panic!("expected a second factor to be required");
+    };
+    for _ in 0..5 {
+        assert!(fixture
+            .auth
+            .complete_login(&pending, "000000")
+            .await
+            .is_err());
+    }
+    assert!(matches!(
+        fixture
+            .auth
+            .complete_login(&pending, &recovery_codes[1])
+            .await,
+        Err(ServiceError::Domain(DomainError::InvalidToken))
+    ));
+}
+
+#[tokio::test]
+async fn test_staff_must_enroll_and_can_reset_others() {
+    let fixture = Fixture::new();
+    let admin = fixture.user("admin", true).await;
+    let bia = fixture.user("bia", false).await;
+
+    // Staff without a second factor enroll before getting in
+    let Credentials::MfaEnrollmentRequired(pending) = fixture.login(&admin).await else {
+        panic!("expected enrollment to be required");
+    };
+    fixture.mfa.begin_enrollment(admin.id()).await.unwrap();
+    let code = fixture.code(admin.id(), 0).await;
+    fixture
+        .mfa
+        .confirm_enrollment(admin.id(), &code)
+        .await
+        .unwrap();
+    fixture.auth.complete_login(&pending, &code).await.unwrap();
+
+    // Knowing the password isn't enough to swap the device
+    assert!(matches!(
+        fixture.mfa.begin_enrollment(admin.id()).await,
+        Err(ServiceError::Conflict(_))
+    ));
+
+    fixture.mfa.begin_enrollment(bia.id()).await.unwrap();
+    let code = fixture.code(bia.id(), 0).await;
+    fixture
+        .mfa
+        .confirm_enrollment(bia.id(), &code)
+        .await
+        .unwrap();
+    assert!(matches!(
+        fixture.mfa.reset(bia.id(), admin.id()).await,
+        Err(ServiceError::Unauthorized)
+    ));
+
+    fixture.mfa.reset(admin.id(), bia.id()).await.unwrap();
+    assert!(!fixture.mfa.is_enabled(bia.id()).await.unwrap());
+    assert!(matches!(fixture.login(&bia).await, Credentials::Session(_)));
+    assert!(fixture.events.get_events().iter().any(|event| matches!(
+        event,
+        DomainEvent::MfaDisabled { user_id, disabled_by, .. }
+            if user_id == bia.id() && disabled_by == admin.id()
+    )));
+}
+
+#[tokio::test]
+async fn test_wrong_codes_lock_the_account_out() {
+    let fixture = Fixture::new();
+    let ana = fixture.user("ana", false).await;
+    fixture.mfa.begin_enrollment(ana.id()).await.unwrap();
+    let code = fixture.code(ana.id(), 0).await;
+    fixture
+        .mfa
+        .confirm_enrollment(ana.id(), &code)
+        .await
+        .unwrap();
+
+    let throttle = LoginThrottle {
+        account: FailureLimits {
+            free_attempts: 10,
+            lockout_after: 3,
+        },
+        ..LoginThrottle::default()
+    };
+    let auth = common::auth_service(fixture.users.clone(), fixture.events.clone())
+        .with_mfa(fixture.mfa.clone())
+        .with_login_throttle(Arc::new(InMemoryLoginFailureStore::new()), throttle);
+    let login = || LoginCommand {
+        identifier: "ana@example.com".to_string(),
+        password: "password".to_string(),
+        ip_address: None,
+    };
+
+    // The right password doesn't forgive wrong codes, so starting over
+    // gets no fresh attempts
+    let Credentials::MfaRequired(first) = auth.login(login()).await.unwrap().credentials else {
+        panic!("expected a second factor to be required");
+    };
+    for _ in 0..2 {
+        assert!(auth.complete_login(&first, "000000").await.is_err());
+    }
+    let Credentials::MfaRequired(pending) = auth.login(login()).await.unwrap().credentials else {
+        panic!("expected a second factor to be required");
+    };
+    assert!(auth.complete_login(&pending, "000000").await.is_err());
+    let code = fixture.code(ana.id(), 1).await;
+    assert!(matches!(
+        auth.complete_login(&pending, &code).await,
+        Err(ServiceError::Domain(
+            DomainError::TooManyLoginAttempts { .. }
+        ))
+    ));
+    assert!(matches!(
+        auth.login(login()).await,
+        Err(ServiceError::Domain(
+            DomainError::TooManyLoginAttempts { .. }
+        ))
+    ));
+    assert!(fixture
+        .events
+        .get_events()
+        .iter()
+        .any(|event| matches!(event, DomainEvent::AccountLocked { .. })));
+}
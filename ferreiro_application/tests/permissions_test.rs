@@ -0,0 +1,206 @@
+use ferreiro_adapters_db::{
+    InMemoryEventPublisher, InMemoryGroupRepository, InMemoryPostRepository, InMemoryUserRepository,
+};
+use ferreiro_application::services::{PostServiceImpl, RepositoryPermissionChecker};
+use ferreiro_domain::models::{Group, Permission, PostStatus, User};
+use ferreiro_domain::ports::driven::{GroupRepository, PermissionChecker, UserRepository};
+use ferreiro_domain::ports::driving::{
+    CreatePostCommand, PostService, ServiceError, UpdatePostCommand,
+};
+use ferreiro_domain::values::{Email, UserId};
+use std::sync::Arc;
+
+struct Fixture {
+    users: Arc<InMemoryUserRepository>,
+    groups: Arc<InMemoryGroupRepository>,
+    checker: Arc<RepositoryPermissionChecker<InMemoryUserRepository, InMemoryGroupRepository>>,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let groups = Arc::new(InMemoryGroupRepository::new());
+        let checker = Arc::new(RepositoryPermissionChecker::new(
+            users.clone(),
+            groups.clone(),
+        ));
+        Self {
+            users,
+            groups,
+            checker,
+        }
+    }
+
+    async fn group(&self, name: &str, permissions: Vec<Permission>) -> Group {
+        let mut group = Group::new(name);
+        for permission in permissions {
+            group.grant(permission);
+        }
+        self.groups.save(&group).await.unwrap();
+        group
+    }
+
+    async fn user(&self, name: &str, setup: impl FnOnce(&mut User)) -> UserId {
+        let email = Email::new(&format!("{}@example.com", name)).unwrap();
+        let mut user = User::new(email, name.to_string(), "hash".to_string());
+        setup(&mut user);
+        self.users.save(&user).await.unwrap();
+        user.id().clone()
+    }
+}
+
+#[tokio::test]
+async fn test_permissions_come_from_users_groups_and_superuser() {
+    let fixture = Fixture::new();
+    let editors = fixture.group("Editors", Permission::all_post()).await;
+    let publish = Permission::publish_post();
+
+    let editor = fixture
+        .user("editor", |u| u.add_to_group(editors.id().clone()))
+        .await;
+    let direct = fixture
+        .user("direct", |u| u.grant_permission(Permission::publish_post()))
+        .await;
+    let admin = fixture.user("admin", User::make_superuser).await;
+    let reader = fixture.user("reader", |_| {}).await;
+    let inactive = fixture
+        .user("inactive", |u| {
+            u.make_superuser();
+            u.deactivate();
+        })
+        .await;
+
+    let checker = &fixture.checker;
+    assert!(checker.has_permission(&editor, &publish).await.unwrap());
+    assert!(checker.has_permission(&direct, &publish).await.unwrap());
+    assert!(!checker
+        .has_permission(&direct, &Permission::delete_post())
+        .await
+        .unwrap());
+    assert!(checker
+        .has_permission(&admin, &Permission::new("anything_at_all"))
+        .await
+        .unwrap());
+    assert!(!checker.has_permission(&reader, &publish).await.unwrap());
+    assert!(!checker.has_permission(&inactive, &publish).await.unwrap());
+    assert!(!checker
+        .has_permission(&UserId::generate(), &publish)
+        .await
+        .unwrap());
+
+    // Revoking from the group takes effect on the next check
+    let mut editors = editors;
+    editors.revoke(&publish);
+    fixture.groups.save(&editors).await.unwrap();
+    assert!(!checker.has_permission(&editor, &publish).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_post_service_checks_permissions() {
+    let fixture = Fixture::new();
+    let writers = fixture.group("Writers", vec![Permission::add_post()]).await;
+    let editors = fixture
+        .group("Editors", vec![Permission::publish_post()])
+        .await;
+
+    let writer = fixture
+        .user("writer", |u| u.add_to_group(writers.id().clone()))
+        .await;
+    let other_writer = fixture
+        .user("other", |u| u.add_to_group(writers.id().clone()))
+        .await;
+    let editor = fixture
+        .user("editor", |u| u.add_to_group(editors.id().clone()))
+        .await;
+    let admin = fixture.user("admin", User::make_superuser).await;
+
+    let service = PostServiceImpl::new(
+        Arc::new(InMemoryPostRepository::new()),
+        Arc::new(InMemoryEventPublisher::new()),
+        fixture.checker.clone(),
+    );
+    let create = |author_id: &UserId, title: &str| CreatePostCommand {
+        title: title.to_string(),
+        slug: None,
+        body: "Body".to_string(),
+        author_id: author_id.clone(),
+    };
+
+    assert!(matches!(
+        service.create(create(&editor, "Not mine to write")).await,
+        Err(ServiceError::Unauthorized)
+    ));
+    let first = service.create(create(&writer, "First")).await.unwrap();
+    let second = service.create(create(&writer, "Second")).await.unwrap();
+    let third = service.create(create(&admin, "Third")).await.unwrap();
+
+    // Authors publish their own posts; others need publish_post
+    assert!(matches!(
+        service.publish(first.id(), &other_writer).await,
+        Err(ServiceError::Unauthorized)
+    ));
+    let published = service.publish(first.id(), &writer).await.unwrap();
+    assert_eq!(published.status(), &PostStatus::Published);
+    service.publish(second.id(), &editor).await.unwrap();
+    service.publish(third.id(), &admin).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_post_service_applies_the_post_policy() {
+    let fixture = Fixture::new();
+    let editors = fixture
+        .group("Editors", vec![Permission::change_post()])
+        .await;
+
+    let author = fixture
+        .user("author", |u| u.grant_permission(Permission::add_post()))
+        .await;
+    let stranger = fixture.user("stranger", |_| {}).await;
+    let editor = fixture
+        .user("editor", |u| u.add_to_group(editors.id().clone()))
+        .await;
+
+    let service = PostServiceImpl::new(
+        Arc::new(InMemoryPostRepository::new()),
+        Arc::new(InMemoryEventPublisher::new()),
+        fixture.checker.clone(),
+    );
+    let post = service
+        .create(CreatePostCommand {
+            title: "Mine".to_string(),
+            slug: None,
+            body: "Body".to_string(),
+            author_id: author.clone(),
+        })
+        .await
+        .unwrap();
+    let update = |actor: &UserId| UpdatePostCommand {
+        id: post.id().clone(),
+        title: "Edited".to_string(),
+        body: "Body".to_string(),
+        actor: actor.clone(),
+    };
+
+    assert!(matches!(
+        service.update(update(&stranger)).await,
+        Err(ServiceError::Unauthorized)
+    ));
+    service.update(update(&author)).await.unwrap();
+    assert!(matches!(
+        service.archive(post.id(), &stranger).await,
+        Err(ServiceError::Unauthorized)
+    ));
+    assert!(matches!(
+        service.delete(post.id(), &editor).await,
+        Err(ServiceError::Unauthorized)
+    ));
+
+    // Published posts are out of the author's hands, but not an editor's
+    service.publish(post.id(), &author).await.unwrap();
+    assert!(matches!(
+        service.update(update(&author)).await,
+        Err(ServiceError::Unauthorized)
+    ));
+    service.update(update(&editor)).await.unwrap();
+    service.archive(post.id(), &author).await.unwrap();
+}
@@ -1,25 +1,84 @@
-use ferreiro_application::services::PostServiceImpl;
-use ferreiro_domain::models::PostStatus;
-use ferreiro_domain::ports::driven::{Pagination, PostFilter};
-use ferreiro_domain::ports::driving::{CreatePostCommand, ListPostsQuery, PostService};
-use ferreiro_domain::values::UserId;
+use async_trait::async_trait;
+use ferreiro_application::services::{unique_slug, PostServiceImpl, RepositoryPermissionChecker};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::models::{Permission, Post, PostStatus, User};
+use ferreiro_domain::ports::driven::{
+    PaginatedResult, Pagination, PostFilter, PostRepository, RepositoryError, UserRepository,
+};
+use ferreiro_domain::ports::driving::{
+    CreatePostCommand, ListPostsQuery, PostService, ServiceError,
+};
+use ferreiro_domain::values::{Body, Email, PostId, Slug, Title, UserId};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 // Import in-memory implementations from ferreiro_adapters_db
-use ferreiro_adapters_db::{InMemoryEventPublisher, InMemoryPostRepository};
+use ferreiro_adapters_db::{
+    InMemoryEventPublisher, InMemoryGroupRepository, InMemoryPostRepository, InMemoryUserRepository,
+};
+
+type Service = PostServiceImpl<InMemoryPostRepository, InMemoryEventPublisher>;
+
+/// A service whose users are `author`, who may add posts, and whoever
+/// `user` saves
+struct Fixture {
+    users: Arc<InMemoryUserRepository>,
+    service: Service,
+    author: UserId,
+}
+
+impl Fixture {
+    async fn new(repo: Arc<InMemoryPostRepository>, events: Arc<InMemoryEventPublisher>) -> Self {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let checker = Arc::new(RepositoryPermissionChecker::new(
+            users.clone(),
+            Arc::new(InMemoryGroupRepository::new()),
+        ));
+        let mut author = User::new(
+            Email::new("author@example.com").unwrap(),
+            "author".to_string(),
+            "hash".to_string(),
+        );
+        author.grant_permission(Permission::add_post());
+        users.save(&author).await.unwrap();
+        Self {
+            users,
+            service: PostServiceImpl::new(repo, events, checker),
+            author: author.id().clone(),
+        }
+    }
+
+    async fn user(&self, name: &str, setup: impl FnOnce(&mut User)) -> UserId {
+        let email = Email::new(&format!("{}@example.com", name)).unwrap();
+        let mut user = User::new(email, name.to_string(), "hash".to_string());
+        setup(&mut user);
+        self.users.save(&user).await.unwrap();
+        user.id().clone()
+    }
+
+    fn draft(&self, title: &str) -> CreatePostCommand {
+        CreatePostCommand {
+            title: title.to_string(),
+            slug: None,
+            body: "Content".to_string(),
+            author_id: self.author.clone(),
+        }
+    }
+}
 
 #[tokio::test]
 async fn test_create_post() {
     let repo = Arc::new(InMemoryPostRepository::new());
     let events = Arc::new(InMemoryEventPublisher::new());
-    let service = PostServiceImpl::new(repo, events.clone());
+    let fixture = Fixture::new(repo, events.clone()).await;
+    let service = &fixture.service;
 
     let post = service
         .create(CreatePostCommand {
             title: "Test Post".to_string(),
-            slug: "test-post".to_string(),
+            slug: Some("test-post".to_string()),
             body: "This is a test".to_string(),
-            author_id: UserId::generate(),
+            author_id: fixture.author.clone(),
         })
         .await
         .unwrap();
@@ -37,19 +96,20 @@ async fn test_create_post() {
 async fn test_publish_post() {
     let repo = Arc::new(InMemoryPostRepository::new());
     let events = Arc::new(InMemoryEventPublisher::new());
-    let service = PostServiceImpl::new(repo, events.clone());
+    let fixture = Fixture::new(repo, events.clone()).await;
+    let service = &fixture.service;
 
     let post = service
         .create(CreatePostCommand {
             title: "Test Post".to_string(),
-            slug: "test-post".to_string(),
+            slug: Some("test-post".to_string()),
             body: "This is a test".to_string(),
-            author_id: UserId::generate(),
+            author_id: fixture.author.clone(),
         })
         .await
         .unwrap();
 
-    let published = service.publish(post.id()).await.unwrap();
+    let published = service.publish(post.id(), post.author_id()).await.unwrap();
 
     assert_eq!(published.status(), &PostStatus::Published);
     assert!(published.published_at().is_some());
@@ -63,16 +123,17 @@ async fn test_publish_post() {
 async fn test_list_posts() {
     let repo = Arc::new(InMemoryPostRepository::new());
     let events = Arc::new(InMemoryEventPublisher::new());
-    let service = PostServiceImpl::new(repo, events);
+    let fixture = Fixture::new(repo, events).await;
+    let service = &fixture.service;
 
     // Create multiple posts
     for i in 1..=5 {
         service
             .create(CreatePostCommand {
                 title: format!("Post {}", i),
-                slug: format!("post-{}", i),
+                slug: Some(format!("post-{}", i)),
                 body: format!("Content {}", i),
-                author_id: UserId::generate(),
+                author_id: fixture.author.clone(),
             })
             .await
             .unwrap();
@@ -82,35 +143,341 @@ async fn test_list_posts() {
         .list(ListPostsQuery {
             filter: PostFilter::default(),
             pagination: Pagination::default(),
+            viewer: Some(fixture.author.clone()),
         })
         .await
         .unwrap();
 
     assert_eq!(result.total, 5);
     assert_eq!(result.items.len(), 5);
+
+    for (page, per_page) in [(0, 20), (1, 0)] {
+        for viewer in [None, Some(fixture.author.clone())] {
+            let result = service
+                .list(ListPostsQuery {
+                    filter: PostFilter::default(),
+                    pagination: Pagination { page, per_page },
+                    viewer,
+                })
+                .await;
+            assert!(matches!(
+                result,
+                Err(ServiceError::Domain(DomainError::InvalidPagination))
+            ));
+        }
+    }
 }
 
 #[tokio::test]
 async fn test_get_by_slug() {
     let repo = Arc::new(InMemoryPostRepository::new());
     let events = Arc::new(InMemoryEventPublisher::new());
-    let service = PostServiceImpl::new(repo, events);
+    let fixture = Fixture::new(repo, events).await;
+    let service = &fixture.service;
 
     service
         .create(CreatePostCommand {
             title: "Unique Post".to_string(),
-            slug: "unique-post".to_string(),
+            slug: Some("unique-post".to_string()),
             body: "Content".to_string(),
-            author_id: UserId::generate(),
+            author_id: fixture.author.clone(),
         })
         .await
         .unwrap();
 
-    let found = service.get_by_slug("unique-post").await.unwrap();
+    let found = service
+        .get_by_slug("unique-post", Some(&fixture.author))
+        .await
+        .unwrap();
 
     assert!(found.is_some());
     assert_eq!(found.unwrap().title().as_str(), "Unique Post");
 
-    let not_found = service.get_by_slug("does-not-exist").await.unwrap();
+    let not_found = service.get_by_slug("does-not-exist", None).await.unwrap();
     assert!(not_found.is_none());
 }
+
+#[tokio::test]
+async fn test_create_generates_unique_slugs_from_the_title() {
+    let repo = Arc::new(InMemoryPostRepository::new());
+    let events = Arc::new(InMemoryEventPublisher::new());
+    let fixture = Fixture::new(repo.clone(), events).await;
+    let service = &fixture.service;
+
+    let mut slugs = Vec::new();
+    for _ in 0..3 {
+        let post = service
+            .create(CreatePostCommand {
+                title: "Olá Mundo!".to_string(),
+                slug: None,
+                body: "Content".to_string(),
+                author_id: fixture.author.clone(),
+            })
+            .await
+            .unwrap();
+        slugs.push(post.slug().to_string());
+    }
+    assert_eq!(slugs, ["ola-mundo", "ola-mundo-2", "ola-mundo-3"]);
+
+    // A slug asked for explicitly is never changed
+    let taken = service
+        .create(CreatePostCommand {
+            title: "Other".to_string(),
+            slug: Some("ola-mundo".to_string()),
+            body: "Content".to_string(),
+            author_id: fixture.author.clone(),
+        })
+        .await;
+    assert!(matches!(taken, Err(ServiceError::Conflict(_))));
+
+    let free = unique_slug(&*repo, Slug::new("fresh").unwrap())
+        .await
+        .unwrap();
+    assert_eq!(free.as_str(), "fresh");
+}
+
+#[tokio::test]
+async fn test_drafts_are_seen_only_by_whom_the_policy_allows() {
+    let repo = Arc::new(InMemoryPostRepository::new());
+    let fixture = Fixture::new(repo, Arc::new(InMemoryEventPublisher::new())).await;
+    let service = &fixture.service;
+    let stranger = fixture.user("stranger", |_| {}).await;
+    let editor = fixture
+        .user("editor", |u| u.grant_permission(Permission::view_post()))
+        .await;
+
+    let draft = service.create(fixture.draft("Draft")).await.unwrap();
+    let out = service.create(fixture.draft("Out")).await.unwrap();
+    service.publish(out.id(), &fixture.author).await.unwrap();
+
+    let unknown = UserId::generate();
+    for viewer in [None, Some(&stranger), Some(&unknown)] {
+        assert!(service.get(draft.id(), viewer).await.unwrap().is_none());
+        assert!(service
+            .get_by_slug(out.slug().as_str(), viewer)
+            .await
+            .unwrap()
+            .is_some());
+    }
+    for viewer in [&fixture.author, &editor] {
+        assert!(service
+            .get_by_slug(draft.slug().as_str(), Some(viewer))
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    let list = |status: Option<PostStatus>, viewer: Option<&UserId>| ListPostsQuery {
+        filter: PostFilter {
+            status,
+            ..PostFilter::default()
+        },
+        pagination: Pagination {
+            page: 1,
+            per_page: 1,
+        },
+        viewer: viewer.cloned(),
+    };
+    let anonymous = service.list(list(None, None)).await.unwrap();
+    assert_eq!(anonymous.total, 1);
+    assert_eq!(anonymous.items[0].id(), out.id());
+    let drafts = service
+        .list(list(Some(PostStatus::Draft), None))
+        .await
+        .unwrap();
+    assert_eq!(drafts.total, 0);
+    assert_eq!(
+        service
+            .list(list(None, Some(&stranger)))
+            .await
+            .unwrap()
+            .total,
+        1
+    );
+
+    // What the policy lets through is paged like everything else
+    let mine = service
+        .list(list(None, Some(&fixture.author)))
+        .await
+        .unwrap();
+    assert_eq!((mine.total, mine.items.len(), mine.total_pages), (2, 1, 2));
+    let theirs = service
+        .list(list(Some(PostStatus::Draft), Some(&editor)))
+        .await
+        .unwrap();
+    assert_eq!(theirs.items[0].id(), draft.id());
+    let strangers_drafts = service
+        .list(list(Some(PostStatus::Draft), Some(&stranger)))
+        .await
+        .unwrap();
+    assert_eq!(strangers_drafts.total, 0);
+}
+
+#[tokio::test]
+async fn test_inactive_users_may_not_touch_even_their_own_posts() {
+    let repo = Arc::new(InMemoryPostRepository::new());
+    let fixture = Fixture::new(repo, Arc::new(InMemoryEventPublisher::new())).await;
+    let service = &fixture.service;
+    let draft = service.create(fixture.draft("Draft")).await.unwrap();
+
+    let mut author = fixture
+        .users
+        .find_by_id(&fixture.author)
+        .await
+        .unwrap()
+        .unwrap();
+    author.deactivate();
+    fixture.users.save(&author).await.unwrap();
+
+    assert!(matches!(
+        service.create(fixture.draft("Another")).await,
+        Err(ServiceError::Unauthorized)
+    ));
+    assert!(matches!(
+        service.publish(draft.id(), &fixture.author).await,
+        Err(ServiceError::Unauthorized)
+    ));
+    assert!(matches!(
+        service.delete(draft.id(), &fixture.author).await,
+        Err(ServiceError::Unauthorized)
+    ));
+    assert!(service
+        .get(draft.id(), Some(&fixture.author))
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_slug_from_title() {
+    let slug = |title: &str| Slug::from_title(title).map(|slug| slug.to_string());
+
+    assert_eq!(slug("  Olá, Mundo!  ").unwrap(), "ola-mundo");
+    assert_eq!(
+        slug("Ação — São Paulo & Zürich").unwrap(),
+        "acao-sao-paulo-zurich"
+    );
+    assert_eq!(slug("C++ / Rust: 2025").unwrap(), "c-rust-2025");
+    assert_eq!(slug("?!").unwrap_err(), DomainError::EmptySlug);
+
+    // Long titles stop at the last whole word that fits
+    let title = "word ".repeat(50);
+    let long = slug(&title).unwrap();
+    assert_eq!(long.len(), 199);
+    assert!(long.ends_with("-word"));
+    assert_eq!(slug(&"x".repeat(250)).unwrap().len(), 200);
+
+    let base = Slug::from_title(&title).unwrap();
+    let suffixed = base.with_suffix(12);
+    assert!(suffixed.as_str().len() <= 200);
+    assert!(suffixed.as_str().ends_with("word-12"));
+}
+
+#[test]
+fn test_with_suffix_cuts_trusted_slugs_on_a_char_boundary() {
+    let base = Slug::from_trusted(format!("{}é", "a".repeat(196)));
+    let suffixed = base.with_suffix(2);
+    assert!(suffixed.as_str().len() <= 200);
+    assert!(suffixed.as_str().ends_with("-2"));
+}
+
+#[tokio::test]
+async fn test_the_repository_rejects_a_taken_slug() {
+    let repo = Arc::new(InMemoryPostRepository::new());
+    let events = Arc::new(InMemoryEventPublisher::new());
+    let fixture = Fixture::new(repo.clone(), events).await;
+    let service = &fixture.service;
+
+    let first = service
+        .create(CreatePostCommand {
+            title: "Race".to_string(),
+            slug: None,
+            body: "Content".to_string(),
+            author_id: fixture.author.clone(),
+        })
+        .await
+        .unwrap();
+
+    // As if another request had saved the same slug after our check
+    let rival = Post::new(
+        Title::new("Rival").unwrap(),
+        first.slug().clone(),
+        Body::new("Content"),
+        UserId::generate(),
+    );
+    assert!(matches!(
+        repo.save(&rival).await,
+        Err(RepositoryError::Conflict)
+    ));
+}
+
+/// Lets a rival save the same slug between the service's check and its
+/// save, the first time only
+#[derive(Default)]
+struct Racing {
+    inner: InMemoryPostRepository,
+    raced: AtomicBool,
+}
+
+#[async_trait]
+impl PostRepository for Racing {
+    async fn find_by_id(&self, id: &PostId) -> Result<Option<Post>, RepositoryError> {
+        self.inner.find_by_id(id).await
+    }
+    async fn find_by_slug(&self, slug: &Slug) -> Result<Option<Post>, RepositoryError> {
+        self.inner.find_by_slug(slug).await
+    }
+    async fn save(&self, post: &Post) -> Result<(), RepositoryError> {
+        if !self.raced.swap(true, Ordering::SeqCst) {
+            let rival = Post::new(
+                Title::new("Rival").unwrap(),
+                post.slug().clone(),
+                Body::new("Content"),
+                UserId::generate(),
+            );
+            self.inner.save(&rival).await?;
+        }
+        self.inner.save(post).await
+    }
+    async fn delete(&self, id: &PostId) -> Result<(), RepositoryError> {
+        self.inner.delete(id).await
+    }
+    async fn list(
+        &self,
+        filter: PostFilter,
+        pagination: Pagination,
+    ) -> Result<PaginatedResult<Post>, RepositoryError> {
+        self.inner.list(filter, pagination).await
+    }
+    async fn exists_by_slug(&self, slug: &Slug) -> Result<bool, RepositoryError> {
+        self.inner.exists_by_slug(slug).await
+    }
+}
+
+#[tokio::test]
+async fn test_losing_a_slug_race_moves_on_from_the_title_slug() {
+    let repo = Arc::new(InMemoryPostRepository::new());
+    let fixture = Fixture::new(repo, Arc::new(InMemoryEventPublisher::new())).await;
+    let racing = Arc::new(Racing::default());
+    racing.raced.store(true, Ordering::SeqCst);
+    let existing = Post::new(
+        Title::new("Race").unwrap(),
+        Slug::new("race").unwrap(),
+        Body::new("Content"),
+        UserId::generate(),
+    );
+    racing.save(&existing).await.unwrap();
+    racing.raced.store(false, Ordering::SeqCst);
+
+    let service = PostServiceImpl::new(
+        racing,
+        Arc::new(InMemoryEventPublisher::new()),
+        Arc::new(RepositoryPermissionChecker::new(
+            fixture.users.clone(),
+            Arc::new(InMemoryGroupRepository::new()),
+        )),
+    );
+    // "race-2" is taken by the rival before the save, so not "race-2-2"
+    let post = service.create(fixture.draft("Race")).await.unwrap();
+    assert_eq!(post.slug().as_str(), "race-3");
+}
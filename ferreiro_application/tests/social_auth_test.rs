@@ -0,0 +1,280 @@
+use async_trait::async_trait;
+use ferreiro_adapters_cache::InMemoryCache;
+use ferreiro_adapters_db::testing::PlainTextHasher;
+use ferreiro_adapters_db::{
+    InMemoryEventPublisher, InMemoryExternalIdentityRepository, InMemoryUserRepository,
+};
+use ferreiro_application::services::{AccountTokens, AuthServiceImpl, SocialAuthServiceImpl};
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::models::{ExternalIdentity, ProviderTokens, User};
+use ferreiro_domain::passwords::{MinimumLength, PasswordPolicy};
+use ferreiro_domain::ports::driven::{
+    AuthorizationRequest, BreachLookupError, BreachedPasswords, ExternalProfile, OAuthError,
+    OAuthProvider, UserRepository,
+};
+use ferreiro_domain::ports::driving::{
+    AuthorizationCallback, AuthorizationRedirect, ServiceError, SocialAuthService,
+};
+use ferreiro_domain::values::{Email, UserId};
+use std::sync::{Arc, Mutex};
+
+/// Hands out whatever profile the test sets, for any code
+struct FakeProvider {
+    name: &'static str,
+    profile: Mutex<Option<ExternalProfile>>,
+}
+
+impl FakeProvider {
+    fn new(name: &'static str) -> Arc<Self> {
+        Arc::new(Self {
+            name,
+            profile: Mutex::new(None),
+        })
+    }
+
+    fn signs_in(&self, subject: &str, email: &str, email_verified: bool) {
+        *self.profile.lock().unwrap() = Some(ExternalProfile {
+            subject: subject.to_string(),
+            email: Some(email.to_string()),
+            email_verified,
+            name: None,
+        });
+    }
+}
+
+#[async_trait]
+impl OAuthProvider for FakeProvider {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn authorization_url(&self, request: &AuthorizationRequest) -> String {
+        format!(
+            "https://{}.example.com/authorize?state={}",
+            self.name, request.state
+        )
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        _code_verifier: &str,
+        _redirect_uri: &str,
+    ) -> Result<ProviderTokens, OAuthError> {
+        Ok(ProviderTokens {
+            access_token: format!("access-{}", code),
+            refresh_token: None,
+            id_token: None,
+            expires_at: None,
+        })
+    }
+
+    async fn profile(
+        &self,
+        _tokens: &ProviderTokens,
+        _nonce: &str,
+    ) -> Result<ExternalProfile, OAuthError> {
+        self.profile
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| OAuthError::Rejected("nobody signed in".to_string()))
+    }
+}
+
+struct Fixture {
+    users: Arc<InMemoryUserRepository>,
+    google: Arc<FakeProvider>,
+    github: Arc<FakeProvider>,
+    social: SocialAuthServiceImpl<
+        InMemoryUserRepository,
+        InMemoryExternalIdentityRepository,
+        InMemoryEventPublisher,
+    >,
+}
+
+type Auth = AuthServiceImpl<InMemoryUserRepository, InMemoryEventPublisher>;
+
+impl Fixture {
+    fn new() -> Self {
+        Self::with_auth(|auth| auth)
+    }
+
+    fn with_auth(setup: impl FnOnce(Auth) -> Auth) -> Self {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let cache = Arc::new(InMemoryCache::new());
+        let auth = Arc::new(setup(AuthServiceImpl::new(
+            users.clone(),
+            Arc::new(InMemoryEventPublisher::new()),
+            Arc::new(PlainTextHasher),
+            cache.clone(),
+            AccountTokens::new(b"test-secret"),
+        )));
+        let google = FakeProvider::new("google");
+        let github = FakeProvider::new("github");
+        let social = SocialAuthServiceImpl::new(
+            users.clone(),
+            Arc::new(InMemoryExternalIdentityRepository::new()),
+            auth,
+            cache,
+        )
+        .with_provider(google.clone())
+        .with_provider(github.clone());
+        Self {
+            users,
+            google,
+            github,
+            social,
+        }
+    }
+
+    async fn begin(&self, provider: &str) -> AuthorizationRedirect {
+        self.social
+            .begin(provider, "https://app.example.com/callback")
+            .await
+            .unwrap()
+    }
+
+    fn callback(redirect: AuthorizationRedirect) -> AuthorizationCallback {
+        AuthorizationCallback {
+            state: redirect.state,
+            code: "code".to_string(),
+            binding: redirect.binding,
+        }
+    }
+
+    async fn sign_in(&self, provider: &str) -> Result<User, ServiceError> {
+        let redirect = self.begin(provider).await;
+        Ok(self.social.complete(Self::callback(redirect)).await?.user)
+    }
+
+    async fn link(
+        &self,
+        user_id: &UserId,
+        provider: &str,
+    ) -> Result<ExternalIdentity, ServiceError> {
+        let redirect = self.begin(provider).await;
+        self.social.link(user_id, Self::callback(redirect)).await
+    }
+}
+
+#[tokio::test]
+async fn test_existing_users_link_providers_themselves() {
+    let fixture = Fixture::new();
+    let ana = User::new(
+        Email::new("ana@example.com").unwrap(),
+        "Ana".to_string(),
+        "plain$correct horse".to_string(),
+    );
+    fixture.users.save(&ana).await.unwrap();
+
+    // Whatever the provider says about the email, it doesn't get the
+    // account; Ana links it once logged in
+    fixture.github.signs_in("1234", "ana@example.com", false);
+    fixture.google.signs_in("g-ana", "Ana@Example.com", true);
+    for provider in ["github", "google"] {
+        assert!(matches!(
+            fixture.sign_in(provider).await,
+            Err(ServiceError::Domain(DomainError::UserAlreadyExists))
+        ));
+    }
+    let identity = fixture.link(ana.id(), "google").await.unwrap();
+    assert_eq!(identity.user_id(), ana.id());
+    let linked = fixture.sign_in("google").await.unwrap();
+    assert_eq!(linked.id(), ana.id());
+    assert!(linked.has_usable_password());
+
+    // Once linked, the subject finds the user even if the email changes
+    fixture
+        .google
+        .signs_in("g-ana", "ana@elsewhere.example", true);
+    assert_eq!(fixture.sign_in("google").await.unwrap().id(), ana.id());
+
+    // Nor can anyone else link it now
+    fixture.google.signs_in("g-bia", "bia@example.com", true);
+    let bia = fixture.sign_in("google").await.unwrap();
+    fixture.google.signs_in("g-ana", "ana@example.com", true);
+    assert!(matches!(
+        fixture.link(bia.id(), "google").await,
+        Err(ServiceError::Conflict(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_only_the_browser_that_began_a_flow_can_finish_it() {
+    let fixture = Fixture::new();
+    fixture.google.signs_in("g-ana", "ana@example.com", true);
+
+    // An attacker's callback, opened in the victim's browser, comes with
+    // the victim's binding or none
+    let attacker = fixture.begin("google").await;
+    let victim = fixture.begin("google").await;
+    for binding in [victim.binding.clone(), String::new()] {
+        assert!(matches!(
+            fixture
+                .social
+                .complete(AuthorizationCallback {
+                    state: attacker.state.clone(),
+                    code: "code".to_string(),
+                    binding,
+                })
+                .await,
+            Err(ServiceError::Domain(DomainError::InvalidToken))
+        ));
+    }
+    assert!(fixture
+        .social
+        .complete(Fixture::callback(victim))
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn test_the_last_way_to_sign_in_cannot_be_unlinked() {
+    let fixture = Fixture::new();
+    fixture.google.signs_in("g-bia", "bia@example.com", true);
+    let bia = fixture.sign_in("google").await.unwrap();
+    assert!(!bia.has_usable_password());
+
+    assert!(matches!(
+        fixture.social.unlink(bia.id(), "google").await,
+        Err(ServiceError::Conflict(_))
+    ));
+    assert!(matches!(
+        fixture.social.unlink(bia.id(), "github").await,
+        Err(ServiceError::NotFound)
+    ));
+
+    fixture.github.signs_in("5678", "bia@example.com", true);
+    fixture.link(bia.id(), "github").await.unwrap();
+    assert_eq!(fixture.sign_in("github").await.unwrap().id(), bia.id());
+    fixture.social.unlink(bia.id(), "google").await.unwrap();
+
+    let identities = fixture.social.identities(bia.id()).await.unwrap();
+    assert_eq!(identities.len(), 1);
+    assert_eq!(identities[0].provider(), "github");
+}
+
+/// A breach service that is down
+struct Unreachable;
+
+#[async_trait]
+impl BreachedPasswords for Unreachable {
+    async fn range(&self, _prefix: &str) -> Result<Vec<(String, u64)>, BreachLookupError> {
+        Err(BreachLookupError::Lookup("unreachable".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_new_social_users_skip_the_password_checks() {
+    // Either check would refuse any password, yet there is none to check
+    let fixture = Fixture::with_auth(|auth| {
+        auth.with_password_policy(PasswordPolicy::new().with(MinimumLength::new(1000)))
+            .with_breached_passwords(Arc::new(Unreachable))
+    });
+    fixture.google.signs_in("g-ana", "ana@example.com", true);
+
+    let ana = fixture.sign_in("google").await.unwrap();
+    assert!(!ana.has_usable_password());
+    assert!(ana.is_active());
+}
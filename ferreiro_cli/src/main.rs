@@ -1,4 +1,7 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use ferreiro_adapters_templates::minijinja_adapter::MiniJinjaEngine;
+use ferreiro_adapters_templates::tera_adapter::TeraEngine;
+use ferreiro_adapters_templates::{TemplateEngine, TemplateError};
 
 #[derive(Parser)]
 #[command(name = "ferreiro")]
@@ -24,6 +27,14 @@ enum Commands {
         #[arg(short, long, default_value = "8000")]
         port: u16,
 
+        /// Directory the templates are loaded from
+        #[arg(long, default_value = "templates")]
+        templates: String,
+
+        #[arg(long, value_enum, default_value_t = Engine::Tera)]
+        template_engine: Engine,
+
+        /// Reload templates when they change (`hot-reload` feature)
         #[arg(long)]
         hot_reload: bool,
     },
@@ -47,6 +58,47 @@ enum Commands {
     Shell,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum Engine {
+    Tera,
+    Minijinja,
+}
+
+fn template_engine(
+    engine: Engine,
+    template_dir: &str,
+    hot_reload: bool,
+) -> Result<Box<dyn TemplateEngine>, TemplateError> {
+    if hot_reload {
+        return hot_reloading_engine(engine, template_dir);
+    }
+    Ok(match engine {
+        Engine::Tera => Box::new(TeraEngine::new(template_dir)?),
+        Engine::Minijinja => Box::new(MiniJinjaEngine::new(template_dir)?),
+    })
+}
+
+#[cfg(feature = "hot-reload")]
+fn hot_reloading_engine(
+    engine: Engine,
+    template_dir: &str,
+) -> Result<Box<dyn TemplateEngine>, TemplateError> {
+    Ok(match engine {
+        Engine::Tera => Box::new(TeraEngine::with_hot_reload(template_dir)?),
+        Engine::Minijinja => Box::new(MiniJinjaEngine::with_hot_reload(template_dir)?),
+    })
+}
+
+#[cfg(not(feature = "hot-reload"))]
+fn hot_reloading_engine(
+    _engine: Engine,
+    _template_dir: &str,
+) -> Result<Box<dyn TemplateEngine>, TemplateError> {
+    Err(TemplateError::Watch(
+        "--hot-reload needs the CLI built with the `hot-reload` feature".to_string(),
+    ))
+}
+
 fn main() {
     let cli = Cli::parse();
 
@@ -62,11 +114,21 @@ fn main() {
         Commands::Runserver {
             host,
             port,
+            templates,
+            template_engine: engine,
             hot_reload,
         } => {
             println!("Starting server at {}:{}", host, port);
+            // Kept alive for the whole run; dropping it stops the watcher
+            let _templates = match template_engine(engine, &templates, hot_reload) {
+                Ok(templates) => templates,
+                Err(e) => {
+                    eprintln!("Could not load templates from {}: {}", templates, e);
+                    std::process::exit(1);
+                }
+            };
             if hot_reload {
-                println!("Hot reload enabled");
+                println!("Reloading templates in {} when they change", templates);
             }
             println!("Not yet implemented. This will be added in future iterations.");
         }
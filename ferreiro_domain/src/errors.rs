@@ -1,3 +1,4 @@
+use crate::ports::driven::Translator;
 use thiserror::Error;
 
 #[derive(Debug, Clone, PartialEq, Eq, Error)]
@@ -19,10 +20,16 @@ pub enum DomainError {
     #[error("Slug can only contain letters, numbers, and hyphens")]
     InvalidSlugCharacters,
 
+    #[error("Slug already exists")]
+    SlugAlreadyExists,
+
     // Email
     #[error("Invalid email address")]
     InvalidEmail,
 
+    #[error("Email domain does not accept mail")]
+    UndeliverableEmail,
+
     // Title
     #[error("Title cannot be empty")]
     EmptyTitle,
@@ -41,10 +48,103 @@ pub enum DomainError {
     #[error("Password is too weak")]
     PasswordTooWeak,
 
+    #[error("Password is too common")]
+    PasswordTooCommon,
+
+    #[error("Password is too similar to your email or name")]
+    PasswordTooSimilar,
+
+    #[error("Password has appeared in a data breach")]
+    PasswordBreached,
+
     // User
     #[error("User already exists")]
     UserAlreadyExists,
 
     #[error("Invalid credentials")]
     InvalidCredentials,
+
+    #[error("This link is invalid or has expired")]
+    InvalidToken,
+
+    #[error("Too many failed login attempts; try again in {retry_after} seconds")]
+    TooManyLoginAttempts { retry_after: u64 },
+
+    // API tokens
+    #[error("Scopes can only contain lowercase letters, numbers, dots, colons and underscores")]
+    InvalidScope,
+
+    // Multi-factor authentication
+    #[error("Invalid authentication code")]
+    InvalidMfaCode,
+
+    // Forms
+    #[error("This field is required")]
+    FieldRequired,
+
+    #[error("Select a valid choice")]
+    InvalidChoice,
+
+    #[error("Enter a valid date and time")]
+    InvalidDateTime,
+
+    // Pagination
+    #[error("Page and page size must be at least 1")]
+    InvalidPagination,
+}
+
+impl DomainError {
+    /// Stable key for translation catalogs; unlike the English message it
+    /// never changes wording
+    pub fn message_key(&self) -> &'static str {
+        match self {
+            Self::CannotPublishEmptyPost => "error.post.cannot_publish_empty",
+            Self::AlreadyPublished => "error.post.already_published",
+            Self::EmptySlug => "error.slug.empty",
+            Self::SlugTooLong { .. } => "error.slug.too_long",
+            Self::InvalidSlugCharacters => "error.slug.invalid_characters",
+            Self::SlugAlreadyExists => "error.slug.already_exists",
+            Self::InvalidEmail => "error.email.invalid",
+            Self::UndeliverableEmail => "error.email.undeliverable",
+            Self::EmptyTitle => "error.title.empty",
+            Self::TitleTooLong { .. } => "error.title.too_long",
+            Self::EmptyBody => "error.body.empty",
+            Self::PasswordTooShort { .. } => "error.password.too_short",
+            Self::PasswordTooWeak => "error.password.too_weak",
+            Self::PasswordTooCommon => "error.password.too_common",
+            Self::PasswordTooSimilar => "error.password.too_similar",
+            Self::PasswordBreached => "error.password.breached",
+            Self::UserAlreadyExists => "error.user.already_exists",
+            Self::InvalidCredentials => "error.user.invalid_credentials",
+            Self::InvalidToken => "error.user.invalid_token",
+            Self::TooManyLoginAttempts { .. } => "error.user.too_many_login_attempts",
+            Self::InvalidScope => "error.api_token.invalid_scope",
+            Self::InvalidMfaCode => "error.mfa.invalid_code",
+            Self::FieldRequired => "error.field.required",
+            Self::InvalidChoice => "error.field.invalid_choice",
+            Self::InvalidDateTime => "error.field.invalid_datetime",
+            Self::InvalidPagination => "error.pagination.invalid",
+        }
+    }
+
+    /// Values for the `{name}` placeholders in the translated message
+    pub fn message_params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Self::SlugTooLong { max, actual } | Self::TitleTooLong { max, actual } => {
+                vec![("max", max.to_string()), ("actual", actual.to_string())]
+            }
+            Self::PasswordTooShort { min } => vec![("min", min.to_string())],
+            Self::TooManyLoginAttempts { retry_after } => {
+                vec![("retry_after", retry_after.to_string())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The message in `locale`, or the English one if no catalog has it
+    pub fn localized(&self, translator: &dyn Translator, locale: &str) -> String {
+        translator
+            .translate(locale, self.message_key(), &self.message_params())
+            .unwrap_or_else(|| self.to_string())
+    }
 }
@@ -1,5 +1,6 @@
-use crate::values::{PostId, UserId};
+use crate::values::{PostId, SecretToken, UserId};
 use chrono::{DateTime, Utc};
+use std::net::IpAddr;
 
 #[derive(Debug, Clone)]
 pub enum DomainEvent {
@@ -21,6 +22,66 @@ pub enum DomainEvent {
         email: String,
         occurred_at: DateTime<Utc>,
     },
+    /// Carries the token to mail to `email`, which `Debug` leaves out
+    PasswordResetRequested {
+        user_id: UserId,
+        email: String,
+        token: SecretToken,
+        occurred_at: DateTime<Utc>,
+    },
+    PasswordReset {
+        user_id: UserId,
+        occurred_at: DateTime<Utc>,
+    },
+    /// Carries the token to mail to `email`, which `Debug` leaves out
+    EmailVerificationRequested {
+        user_id: UserId,
+        email: String,
+        token: SecretToken,
+        occurred_at: DateTime<Utc>,
+    },
+    EmailVerified {
+        user_id: UserId,
+        email: String,
+        occurred_at: DateTime<Utc>,
+    },
+    MfaEnabled {
+        user_id: UserId,
+        occurred_at: DateTime<Utc>,
+    },
+    /// `disabled_by` is the user themselves, or the staff member who reset
+    /// their second factor
+    MfaDisabled {
+        user_id: UserId,
+        disabled_by: UserId,
+        occurred_at: DateTime<Utc>,
+    },
+    /// A wrong password, or an unknown or inactive account; `user_id` is
+    /// set when the identifier (usually the email) belongs to a user
+    LoginFailed {
+        identifier: String,
+        user_id: Option<UserId>,
+        ip_address: Option<IpAddr>,
+        occurred_at: DateTime<Utc>,
+    },
+    /// Too many failed logins; nobody can log in as `identifier` until
+    /// `locked_until`, or until staff unlock it
+    AccountLocked {
+        identifier: String,
+        user_id: Option<UserId>,
+        locked_until: DateTime<Utc>,
+        occurred_at: DateTime<Utc>,
+    },
+    AccountUnlocked {
+        user_id: UserId,
+        unlocked_by: UserId,
+        occurred_at: DateTime<Utc>,
+    },
+    UserDeactivated {
+        user_id: UserId,
+        deactivated_by: UserId,
+        occurred_at: DateTime<Utc>,
+    },
 }
 
 impl DomainEvent {
@@ -30,6 +91,16 @@ impl DomainEvent {
             Self::PostPublished { occurred_at, .. } => *occurred_at,
             Self::PostArchived { occurred_at, .. } => *occurred_at,
             Self::UserRegistered { occurred_at, .. } => *occurred_at,
+            Self::PasswordResetRequested { occurred_at, .. } => *occurred_at,
+            Self::PasswordReset { occurred_at, .. } => *occurred_at,
+            Self::EmailVerificationRequested { occurred_at, .. } => *occurred_at,
+            Self::EmailVerified { occurred_at, .. } => *occurred_at,
+            Self::MfaEnabled { occurred_at, .. } => *occurred_at,
+            Self::MfaDisabled { occurred_at, .. } => *occurred_at,
+            Self::LoginFailed { occurred_at, .. } => *occurred_at,
+            Self::AccountLocked { occurred_at, .. } => *occurred_at,
+            Self::AccountUnlocked { occurred_at, .. } => *occurred_at,
+            Self::UserDeactivated { occurred_at, .. } => *occurred_at,
         }
     }
 }
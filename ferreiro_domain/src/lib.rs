@@ -1,6 +1,9 @@
 pub mod errors;
 pub mod events;
+pub mod mail;
 pub mod models;
+pub mod passwords;
+pub mod policies;
 pub mod ports;
 pub mod values;
 
@@ -0,0 +1,200 @@
+//! Outgoing email, as handed to an `EmailSender`
+//!
+//! ```rust,ignore
+//! let message = EmailMessage::new("Welcome")
+//!     .to(Mailbox::named("Ana", Email::new("ana@example.com")?))
+//!     .text("Hi Ana, thanks for signing up.")
+//!     .html("<p>Hi Ana, thanks for signing up.</p>")
+//!     .attach(Attachment::new("terms.pdf", "application/pdf", pdf_bytes));
+//! sender.send(&message).await?;
+//! ```
+
+use crate::values::Email;
+use std::fmt;
+
+/// An address with an optional display name: `Ana Silva <ana@example.com>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mailbox {
+    name: Option<String>,
+    email: Email,
+}
+
+impl Mailbox {
+    pub fn new(email: Email) -> Self {
+        Self { name: None, email }
+    }
+
+    pub fn named(name: impl Into<String>, email: Email) -> Self {
+        Self {
+            name: Some(name.into()),
+            email,
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn email(&self) -> &Email {
+        &self.email
+    }
+}
+
+impl From<Email> for Mailbox {
+    fn from(email: Email) -> Self {
+        Self::new(email)
+    }
+}
+
+impl fmt::Display for Mailbox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} <{}>", name, self.email),
+            None => write!(f, "{}", self.email),
+        }
+    }
+}
+
+/// A file sent along with a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    filename: String,
+    content_type: String,
+    data: Vec<u8>,
+}
+
+impl Attachment {
+    pub fn new(
+        filename: impl Into<String>,
+        content_type: impl Into<String>,
+        data: impl Into<Vec<u8>>,
+    ) -> Self {
+        Self {
+            filename: filename.into(),
+            content_type: content_type.into(),
+            data: data.into(),
+        }
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// A message with a plain-text body, an HTML alternative, or both
+///
+/// Without `from`, the sender's default address is used. Bcc recipients get
+/// the message but never appear in its headers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmailMessage {
+    subject: String,
+    from: Option<Mailbox>,
+    to: Vec<Mailbox>,
+    cc: Vec<Mailbox>,
+    bcc: Vec<Mailbox>,
+    reply_to: Option<Mailbox>,
+    text: Option<String>,
+    html: Option<String>,
+    attachments: Vec<Attachment>,
+    headers: Vec<(String, String)>,
+}
+
+impl EmailMessage {
+    pub fn new(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn from(mut self, mailbox: impl Into<Mailbox>) -> Self {
+        self.from = Some(mailbox.into());
+        self
+    }
+
+    pub fn to(mut self, mailbox: impl Into<Mailbox>) -> Self {
+        self.to.push(mailbox.into());
+        self
+    }
+
+    pub fn cc(mut self, mailbox: impl Into<Mailbox>) -> Self {
+        self.cc.push(mailbox.into());
+        self
+    }
+
+    pub fn bcc(mut self, mailbox: impl Into<Mailbox>) -> Self {
+        self.bcc.push(mailbox.into());
+        self
+    }
+
+    pub fn reply_to(mut self, mailbox: impl Into<Mailbox>) -> Self {
+        self.reply_to = Some(mailbox.into());
+        self
+    }
+
+    pub fn text(mut self, body: impl Into<String>) -> Self {
+        self.text = Some(body.into());
+        self
+    }
+
+    pub fn html(mut self, body: impl Into<String>) -> Self {
+        self.html = Some(body.into());
+        self
+    }
+
+    pub fn attach(mut self, attachment: Attachment) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
+    /// An extra header, such as `List-Unsubscribe`
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    // Getters
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+    pub fn from_mailbox(&self) -> Option<&Mailbox> {
+        self.from.as_ref()
+    }
+    pub fn to_mailboxes(&self) -> &[Mailbox] {
+        &self.to
+    }
+    pub fn cc_mailboxes(&self) -> &[Mailbox] {
+        &self.cc
+    }
+    pub fn bcc_mailboxes(&self) -> &[Mailbox] {
+        &self.bcc
+    }
+    pub fn reply_to_mailbox(&self) -> Option<&Mailbox> {
+        self.reply_to.as_ref()
+    }
+    pub fn text_body(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+    pub fn html_body(&self) -> Option<&str> {
+        self.html.as_deref()
+    }
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Everyone the message goes to: to, cc and bcc
+    pub fn recipients(&self) -> impl Iterator<Item = &Mailbox> {
+        self.to.iter().chain(&self.cc).chain(&self.bcc)
+    }
+}
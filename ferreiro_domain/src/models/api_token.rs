@@ -0,0 +1,103 @@
+use crate::errors::DomainError;
+use crate::values::{ApiTokenId, UserId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// A personal access token, for clients that can't hold a session cookie
+///
+/// Only a hash of the secret is kept; the secret itself is shown once, when
+/// the token is created. `prefix` is the start of the secret, enough for a
+/// user to tell their tokens apart.
+///
+/// Scopes are free-form names such as `posts:read`; a token with none is
+/// only good for identifying its user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    id: ApiTokenId,
+    user_id: UserId,
+    name: String,
+    token_hash: String,
+    prefix: String,
+    scopes: BTreeSet<String>,
+    created_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    last_used_at: Option<DateTime<Utc>>,
+}
+
+impl ApiToken {
+    pub fn new(
+        user_id: UserId,
+        name: &str,
+        token_hash: String,
+        prefix: String,
+        scopes: BTreeSet<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Self, DomainError> {
+        if name.trim().is_empty() {
+            return Err(DomainError::FieldRequired);
+        }
+        if !scopes.iter().all(|scope| Self::is_valid_scope(scope)) {
+            return Err(DomainError::InvalidScope);
+        }
+        Ok(Self {
+            id: ApiTokenId::generate(),
+            user_id,
+            name: name.trim().to_string(),
+            token_hash,
+            prefix,
+            scopes,
+            created_at: Utc::now(),
+            expires_at,
+            last_used_at: None,
+        })
+    }
+
+    /// `posts:read`, `admin.users`; lowercase ASCII, digits, `.`, `:`, `_`
+    pub fn is_valid_scope(scope: &str) -> bool {
+        !scope.is_empty()
+            && scope.bytes().all(|b| {
+                b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'.' | b':' | b'_')
+            })
+    }
+
+    // Getters
+    pub fn id(&self) -> &ApiTokenId {
+        &self.id
+    }
+    pub fn user_id(&self) -> &UserId {
+        &self.user_id
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn token_hash(&self) -> &str {
+        &self.token_hash
+    }
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+    pub fn scopes(&self) -> &BTreeSet<String> {
+        &self.scopes
+    }
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.contains(scope)
+    }
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires_at
+    }
+    pub fn last_used_at(&self) -> Option<DateTime<Utc>> {
+        self.last_used_at
+    }
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    // Setters
+    pub fn mark_used_at(&mut self, now: DateTime<Utc>) {
+        self.last_used_at = Some(now);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::errors::DomainError;
+use crate::values::{Email, UserId};
+use uuid::Uuid;
+
+/// No hasher produces hashes starting with it
+pub(crate) const UNUSABLE_PASSWORD_PREFIX: &str = "!";
+
+/// What authentication needs from a user model, as Django's
+/// `AbstractBaseUser`
+///
+/// `User` is the default. A project with its own user type implements this
+/// and `UserRepository` for it, then hands the repository to
+/// `AuthServiceImpl`, much as Django's `AUTH_USER_MODEL` names the model.
+/// Groups, permissions and social login still work with `User` only.
+///
+/// Users log in with their identifier, the field `IDENTIFIER_FIELD` names:
+/// the email for `User`, a username for many other models. Every user has
+/// an email too, for password resets and verification.
+pub trait AuthUser: Clone + Send + Sync + 'static {
+    /// As Django's `USERNAME_FIELD`; also the field's name in the admin
+    const IDENTIFIER_FIELD: &'static str;
+
+    /// A user registering, with their password already hashed
+    fn create(registration: NewUser) -> Result<Self, DomainError>;
+
+    /// The form an identifier is stored and looked up in, or why what the
+    /// user typed isn't one
+    fn normalize_identifier(identifier: &str) -> Result<String, DomainError>;
+
+    fn id(&self) -> &UserId;
+    /// Normalized, as `normalize_identifier` returns it
+    fn identifier(&self) -> String;
+    fn email(&self) -> &Email;
+    fn name(&self) -> &str;
+    fn password_hash(&self) -> &str;
+    fn is_active(&self) -> bool;
+    fn is_staff(&self) -> bool;
+    fn is_superuser(&self) -> bool;
+    fn is_email_verified(&self) -> bool;
+    /// Inactive only until the email is verified, as `await_verification`
+    /// left the user
+    fn is_awaiting_verification(&self) -> bool;
+
+    /// Callers hash the new password, after checking it against a
+    /// `PasswordPolicy`
+    fn set_password_hash(&mut self, password_hash: String);
+    /// Also ends any wait for verification, so verifying the email later
+    /// never undoes a deactivation
+    fn set_active(&mut self, active: bool);
+    /// Inactive until the email is verified, which then activates the user
+    fn await_verification(&mut self);
+    fn mark_email_verified(&mut self);
+
+    /// False for users who sign in some other way, such as through an
+    /// OAuth provider; no password logs them in
+    fn has_usable_password(&self) -> bool {
+        !self.password_hash().starts_with(UNUSABLE_PASSWORD_PREFIX)
+    }
+
+    /// As Django's `set_unusable_password`; a random suffix keeps the hash
+    /// from being mistaken for any other user's
+    fn set_unusable_password(&mut self) {
+        self.set_password_hash(format!(
+            "{}{}",
+            UNUSABLE_PASSWORD_PREFIX,
+            Uuid::new_v4().simple()
+        ));
+    }
+}
+
+/// A registration, for `AuthUser::create`
+#[derive(Debug, Clone)]
+pub struct NewUser {
+    /// Normalized; the same as `email` unless the command had an identifier
+    pub identifier: String,
+    pub email: Email,
+    pub name: String,
+    pub password_hash: String,
+}
@@ -0,0 +1,90 @@
+use crate::values::{Email, UserId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// What a provider returns for an authorization code
+///
+/// Kept with the identity so the application can call the provider's APIs
+/// on the user's behalf later.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderTokens {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    /// OpenID Connect providers only
+    pub id_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A user's account at an OAuth2 or OpenID Connect provider, linked to a
+/// local `User`
+///
+/// Identified by the provider's name and its `subject` for the account,
+/// which unlike the email never changes. `email` is what the provider last
+/// reported, for display.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalIdentity {
+    provider: String,
+    subject: String,
+    user_id: UserId,
+    email: Option<Email>,
+    tokens: ProviderTokens,
+    linked_at: DateTime<Utc>,
+    last_login_at: DateTime<Utc>,
+}
+
+impl ExternalIdentity {
+    pub fn new(
+        provider: &str,
+        subject: &str,
+        user_id: UserId,
+        email: Option<Email>,
+        tokens: ProviderTokens,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            provider: provider.to_string(),
+            subject: subject.to_string(),
+            user_id,
+            email,
+            tokens,
+            linked_at: now,
+            last_login_at: now,
+        }
+    }
+
+    // Getters
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+    pub fn user_id(&self) -> &UserId {
+        &self.user_id
+    }
+    pub fn email(&self) -> Option<&Email> {
+        self.email.as_ref()
+    }
+    pub fn tokens(&self) -> &ProviderTokens {
+        &self.tokens
+    }
+    pub fn linked_at(&self) -> DateTime<Utc> {
+        self.linked_at
+    }
+    pub fn last_login_at(&self) -> DateTime<Utc> {
+        self.last_login_at
+    }
+
+    /// Records a login with fresh tokens. Providers often leave out the
+    /// refresh token after the first consent, so the old one is kept then.
+    pub fn logged_in(&mut self, email: Option<Email>, mut tokens: ProviderTokens) {
+        if tokens.refresh_token.is_none() {
+            tokens.refresh_token = self.tokens.refresh_token.take();
+        }
+        if email.is_some() {
+            self.email = email;
+        }
+        self.tokens = tokens;
+        self.last_login_at = Utc::now();
+    }
+}
@@ -1,5 +1,15 @@
+mod api_token;
+mod auth_user;
+mod external_identity;
+mod permission;
 mod post;
+mod totp_device;
 mod user;
 
+pub use api_token::ApiToken;
+pub use auth_user::{AuthUser, NewUser};
+pub use external_identity::{ExternalIdentity, ProviderTokens};
+pub use permission::{Group, Permission};
 pub use post::{Post, PostStatus};
-pub use user::User;
+pub use totp_device::TotpDevice;
+pub use user::{User, UserRecord};
@@ -0,0 +1,117 @@
+use crate::values::GroupId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Something a user may be allowed to do, named by codename as in Django:
+/// `publish_post`, `change_tag`
+///
+/// Each model gets `add`, `change`, `delete` and `view` permissions; `Post`
+/// also has `publish`. Your own aggregates use `for_model` the same way.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Permission(String);
+
+impl Permission {
+    pub fn new(codename: &str) -> Self {
+        Self(codename.to_string())
+    }
+
+    /// `for_model("change", "post")` is `change_post`
+    pub fn for_model(action: &str, model: &str) -> Self {
+        Self(format!("{}_{}", action, model))
+    }
+
+    pub fn codename(&self) -> &str {
+        &self.0
+    }
+
+    pub fn add_post() -> Self {
+        Self::for_model("add", "post")
+    }
+
+    pub fn change_post() -> Self {
+        Self::for_model("change", "post")
+    }
+
+    pub fn delete_post() -> Self {
+        Self::for_model("delete", "post")
+    }
+
+    pub fn view_post() -> Self {
+        Self::for_model("view", "post")
+    }
+
+    pub fn publish_post() -> Self {
+        Self::for_model("publish", "post")
+    }
+
+    /// Every permission on `Post`
+    pub fn all_post() -> Vec<Self> {
+        vec![
+            Self::add_post(),
+            Self::change_post(),
+            Self::delete_post(),
+            Self::view_post(),
+            Self::publish_post(),
+        ]
+    }
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A named set of permissions; users in the group have all of them
+///
+/// Grant "Editors" `publish_post` once instead of to every editor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Group {
+    id: GroupId,
+    name: String,
+    permissions: BTreeSet<Permission>,
+}
+
+impl Group {
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: GroupId::generate(),
+            name: name.to_string(),
+            permissions: BTreeSet::new(),
+        }
+    }
+
+    pub fn reconstitute(id: GroupId, name: String, permissions: BTreeSet<Permission>) -> Self {
+        Self {
+            id,
+            name,
+            permissions,
+        }
+    }
+
+    // Getters
+    pub fn id(&self) -> &GroupId {
+        &self.id
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn permissions(&self) -> &BTreeSet<Permission> {
+        &self.permissions
+    }
+    pub fn has_permission(&self, permission: &Permission) -> bool {
+        self.permissions.contains(permission)
+    }
+
+    // Setters
+    pub fn rename(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+    pub fn grant(&mut self, permission: Permission) {
+        self.permissions.insert(permission);
+    }
+    pub fn revoke(&mut self, permission: &Permission) {
+        self.permissions.remove(permission);
+    }
+}
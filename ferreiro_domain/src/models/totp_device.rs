@@ -0,0 +1,82 @@
+use crate::values::UserId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// A user's authenticator app, for time-based one-time passwords (RFC 6238)
+///
+/// A device starts unconfirmed, when the user is shown its secret, and only
+/// counts as a second factor once they have entered a code from it. Each
+/// user has at most one.
+///
+/// `last_used_step` is the time step of the last code accepted, so a code
+/// can't be replayed. Recovery codes are stored as hashes, and each works
+/// once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotpDevice {
+    user_id: UserId,
+    secret: Vec<u8>,
+    created_at: DateTime<Utc>,
+    confirmed_at: Option<DateTime<Utc>>,
+    last_used_step: Option<u64>,
+    recovery_codes: BTreeSet<String>,
+}
+
+impl TotpDevice {
+    pub fn new(user_id: UserId, secret: Vec<u8>) -> Self {
+        Self {
+            user_id,
+            secret,
+            created_at: Utc::now(),
+            confirmed_at: None,
+            last_used_step: None,
+            recovery_codes: BTreeSet::new(),
+        }
+    }
+
+    // Getters
+    pub fn user_id(&self) -> &UserId {
+        &self.user_id
+    }
+    pub fn secret(&self) -> &[u8] {
+        &self.secret
+    }
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+    pub fn confirmed_at(&self) -> Option<DateTime<Utc>> {
+        self.confirmed_at
+    }
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+    pub fn last_used_step(&self) -> Option<u64> {
+        self.last_used_step
+    }
+    /// How many recovery codes are left
+    pub fn recovery_codes_left(&self) -> usize {
+        self.recovery_codes.len()
+    }
+
+    // Setters
+    pub fn confirm(&mut self) {
+        self.confirmed_at.get_or_insert_with(Utc::now);
+    }
+    /// Records a code from time step `step` as used; false if a code from
+    /// that step, or a later one, already was
+    pub fn use_step(&mut self, step: u64) -> bool {
+        if self.last_used_step.is_some_and(|last| step <= last) {
+            return false;
+        }
+        self.last_used_step = Some(step);
+        true
+    }
+    /// Replaces every recovery code
+    pub fn set_recovery_codes(&mut self, code_hashes: BTreeSet<String>) {
+        self.recovery_codes = code_hashes;
+    }
+    /// Removes the recovery code hashed as `code_hash`; false if there is none
+    pub fn use_recovery_code(&mut self, code_hash: &str) -> bool {
+        self.recovery_codes.remove(code_hash)
+    }
+}
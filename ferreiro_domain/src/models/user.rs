@@ -1,7 +1,34 @@
-use crate::values::{Email, UserId};
+use crate::errors::DomainError;
+use crate::models::auth_user::UNUSABLE_PASSWORD_PREFIX;
+use crate::models::{AuthUser, NewUser, Permission};
+use crate::values::{Email, GroupId, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use uuid::Uuid;
 
+/// A `User` as persistence stores it, field for field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserRecord {
+    pub id: UserId,
+    pub email: Email,
+    pub name: String,
+    pub password_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub is_active: bool,
+    pub is_staff: bool,
+    pub is_superuser: bool,
+    pub email_verified: bool,
+    pub awaiting_verification: bool,
+    pub groups: BTreeSet<GroupId>,
+    pub permissions: BTreeSet<Permission>,
+    pub timezone: Option<String>,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub locale: Option<String>,
+}
+
+/// The default user model; logs in with its email
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     id: UserId,
@@ -12,6 +39,27 @@ pub struct User {
     is_active: bool,
     is_staff: bool,
     is_superuser: bool,
+    #[serde(default)]
+    email_verified: bool,
+    /// Inactive only until the email is verified
+    #[serde(default)]
+    awaiting_verification: bool,
+    #[serde(default)]
+    groups: BTreeSet<GroupId>,
+    /// Granted to this user directly, on top of those from `groups`
+    #[serde(default)]
+    permissions: BTreeSet<Permission>,
+    /// IANA zone name, such as `America/Sao_Paulo`, to show times in
+    #[serde(default)]
+    timezone: Option<String>,
+    /// Shown instead of `name` when set, such as a nickname
+    #[serde(default)]
+    display_name: Option<String>,
+    #[serde(default)]
+    avatar_url: Option<String>,
+    /// BCP 47 tag, such as `pt-BR`, to show pages in
+    #[serde(default)]
+    locale: Option<String>,
 }
 
 impl User {
@@ -25,29 +73,58 @@ impl User {
             is_active: true,
             is_staff: false,
             is_superuser: false,
+            email_verified: false,
+            awaiting_verification: false,
+            groups: BTreeSet::new(),
+            permissions: BTreeSet::new(),
+            timezone: None,
+            display_name: None,
+            avatar_url: None,
+            locale: None,
         }
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn reconstitute(
-        id: UserId,
-        email: Email,
-        name: String,
-        password_hash: String,
-        created_at: DateTime<Utc>,
-        is_active: bool,
-        is_staff: bool,
-        is_superuser: bool,
-    ) -> Self {
+    /// Reconstitute from persistence — no validation, no events
+    pub fn reconstitute(record: UserRecord) -> Self {
         Self {
-            id,
-            email,
-            name,
-            password_hash,
-            created_at,
-            is_active,
-            is_staff,
-            is_superuser,
+            id: record.id,
+            email: record.email,
+            name: record.name,
+            password_hash: record.password_hash,
+            created_at: record.created_at,
+            is_active: record.is_active,
+            is_staff: record.is_staff,
+            is_superuser: record.is_superuser,
+            email_verified: record.email_verified,
+            awaiting_verification: record.awaiting_verification,
+            groups: record.groups,
+            permissions: record.permissions,
+            timezone: record.timezone,
+            display_name: record.display_name,
+            avatar_url: record.avatar_url,
+            locale: record.locale,
+        }
+    }
+
+    /// Every field, for persistence to store; `reconstitute` takes it back
+    pub fn to_record(&self) -> UserRecord {
+        UserRecord {
+            id: self.id.clone(),
+            email: self.email.clone(),
+            name: self.name.clone(),
+            password_hash: self.password_hash.clone(),
+            created_at: self.created_at,
+            is_active: self.is_active,
+            is_staff: self.is_staff,
+            is_superuser: self.is_superuser,
+            email_verified: self.email_verified,
+            awaiting_verification: self.awaiting_verification,
+            groups: self.groups.clone(),
+            permissions: self.permissions.clone(),
+            timezone: self.timezone.clone(),
+            display_name: self.display_name.clone(),
+            avatar_url: self.avatar_url.clone(),
+            locale: self.locale.clone(),
         }
     }
 
@@ -76,13 +153,47 @@ impl User {
     pub fn is_superuser(&self) -> bool {
         self.is_superuser
     }
+    pub fn is_email_verified(&self) -> bool {
+        self.email_verified
+    }
+    pub fn is_awaiting_verification(&self) -> bool {
+        self.awaiting_verification
+    }
+    pub fn groups(&self) -> &BTreeSet<GroupId> {
+        &self.groups
+    }
+    /// Direct permissions only; `PermissionChecker` also counts groups
+    pub fn permissions(&self) -> &BTreeSet<Permission> {
+        &self.permissions
+    }
+    pub fn timezone(&self) -> Option<&str> {
+        self.timezone.as_deref()
+    }
+    /// The display name if set, otherwise the name
+    pub fn display_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+    pub fn avatar_url(&self) -> Option<&str> {
+        self.avatar_url.as_deref()
+    }
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_deref()
+    }
+    /// False for users who sign in some other way, such as through an
+    /// OAuth provider; no password logs them in
+    pub fn has_usable_password(&self) -> bool {
+        !self.password_hash.starts_with(UNUSABLE_PASSWORD_PREFIX)
+    }
 
     // Setters
     pub fn deactivate(&mut self) {
-        self.is_active = false;
+        self.set_active(false);
     }
     pub fn activate(&mut self) {
-        self.is_active = true;
+        self.set_active(true);
+    }
+    pub fn mark_email_verified(&mut self) {
+        self.email_verified = true;
     }
     pub fn make_staff(&mut self) {
         self.is_staff = true;
@@ -91,4 +202,100 @@ impl User {
         self.is_superuser = true;
         self.is_staff = true;
     }
+    /// Callers hash the new password, after checking it against a
+    /// `PasswordPolicy`
+    pub fn set_password_hash(&mut self, password_hash: String) {
+        self.password_hash = password_hash;
+    }
+    /// As Django's `set_unusable_password`; a random suffix keeps the hash
+    /// from being mistaken for any other user's
+    pub fn set_unusable_password(&mut self) {
+        self.password_hash = format!("{}{}", UNUSABLE_PASSWORD_PREFIX, Uuid::new_v4().simple());
+    }
+    pub fn add_to_group(&mut self, group: GroupId) {
+        self.groups.insert(group);
+    }
+    pub fn remove_from_group(&mut self, group: &GroupId) {
+        self.groups.remove(group);
+    }
+    pub fn grant_permission(&mut self, permission: Permission) {
+        self.permissions.insert(permission);
+    }
+    pub fn revoke_permission(&mut self, permission: &Permission) {
+        self.permissions.remove(permission);
+    }
+    pub fn set_timezone(&mut self, timezone: Option<String>) {
+        self.timezone = timezone;
+    }
+    pub fn set_display_name(&mut self, display_name: Option<String>) {
+        self.display_name = display_name;
+    }
+    pub fn set_avatar_url(&mut self, avatar_url: Option<String>) {
+        self.avatar_url = avatar_url;
+    }
+    pub fn set_locale(&mut self, locale: Option<String>) {
+        self.locale = locale;
+    }
+}
+
+impl AuthUser for User {
+    const IDENTIFIER_FIELD: &'static str = "email";
+
+    fn create(registration: NewUser) -> Result<Self, DomainError> {
+        Ok(Self::new(
+            registration.email,
+            registration.name,
+            registration.password_hash,
+        ))
+    }
+
+    fn normalize_identifier(identifier: &str) -> Result<String, DomainError> {
+        Ok(Email::new(identifier)?.normalized())
+    }
+
+    fn id(&self) -> &UserId {
+        &self.id
+    }
+    fn identifier(&self) -> String {
+        self.email.normalized()
+    }
+    fn email(&self) -> &Email {
+        &self.email
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn password_hash(&self) -> &str {
+        &self.password_hash
+    }
+    fn is_active(&self) -> bool {
+        self.is_active
+    }
+    fn is_staff(&self) -> bool {
+        self.is_staff
+    }
+    fn is_superuser(&self) -> bool {
+        self.is_superuser
+    }
+    fn is_email_verified(&self) -> bool {
+        self.email_verified
+    }
+    fn is_awaiting_verification(&self) -> bool {
+        self.awaiting_verification
+    }
+
+    fn set_password_hash(&mut self, password_hash: String) {
+        self.password_hash = password_hash;
+    }
+    fn set_active(&mut self, active: bool) {
+        self.is_active = active;
+        self.awaiting_verification = false;
+    }
+    fn await_verification(&mut self) {
+        self.is_active = false;
+        self.awaiting_verification = true;
+    }
+    fn mark_email_verified(&mut self) {
+        self.email_verified = true;
+    }
 }
@@ -0,0 +1,99 @@
+//! Password strength rules, checked before a password is hashed
+//!
+//! Like Django's `AUTH_PASSWORD_VALIDATORS`: a `PasswordPolicy` runs a list
+//! of `PasswordValidator`s, and the default one is what Django ships with.
+//! Checking a password against known breaches needs the network, so that
+//! lives behind the `BreachedPasswords` port instead.
+//!
+//! ```rust,ignore
+//! let policy = PasswordPolicy::new()
+//!     .with(MinimumLength::new(12))
+//!     .with(CommonPasswords::new());
+//! policy.validate("correct horse", &UserAttributes::new("ana@example.com", "Ana"))?;
+//! ```
+
+mod validators;
+
+pub use validators::{CommonPasswords, MinimumLength, NumericPassword, UserAttributeSimilarity};
+
+use crate::errors::DomainError;
+use crate::models::AuthUser;
+use thiserror::Error;
+
+/// Why a validator can't be built with the settings it was given
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ValidatorConfigError {
+    #[error("max_similarity must be at least 0.1, got {0}")]
+    SimilarityTooLow(f64),
+}
+
+/// The details of the account a password is for, so it can't just repeat
+/// them. At registration there is no user yet, so build one by hand.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UserAttributes<'a> {
+    pub email: &'a str,
+    pub name: &'a str,
+}
+
+impl<'a> UserAttributes<'a> {
+    pub fn new(email: &'a str, name: &'a str) -> Self {
+        Self { email, name }
+    }
+}
+
+impl<'a, M: AuthUser> From<&'a M> for UserAttributes<'a> {
+    fn from(user: &'a M) -> Self {
+        Self::new(user.email().as_str(), user.name())
+    }
+}
+
+/// One rule a password has to pass
+pub trait PasswordValidator: Send + Sync {
+    fn validate(&self, password: &str, user: &UserAttributes<'_>) -> Result<(), DomainError>;
+}
+
+/// The validators a new password is run through, in order
+pub struct PasswordPolicy {
+    validators: Vec<Box<dyn PasswordValidator>>,
+}
+
+impl PasswordPolicy {
+    /// A policy that accepts anything; add validators with `with`
+    pub fn new() -> Self {
+        Self {
+            validators: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, validator: impl PasswordValidator + 'static) -> Self {
+        self.validators.push(Box::new(validator));
+        self
+    }
+
+    /// Every rule the password breaks, for showing them all at once
+    pub fn errors(&self, password: &str, user: &UserAttributes<'_>) -> Vec<DomainError> {
+        self.validators
+            .iter()
+            .filter_map(|validator| validator.validate(password, user).err())
+            .collect()
+    }
+
+    /// The first rule the password breaks
+    pub fn validate(&self, password: &str, user: &UserAttributes<'_>) -> Result<(), DomainError> {
+        self.validators
+            .iter()
+            .try_for_each(|validator| validator.validate(password, user))
+    }
+}
+
+/// Similarity to the user's details, at least 8 characters, not a common
+/// password and not all digits
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self::new()
+            .with(UserAttributeSimilarity::default())
+            .with(MinimumLength::default())
+            .with(CommonPasswords::default())
+            .with(NumericPassword)
+    }
+}
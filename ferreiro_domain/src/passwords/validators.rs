@@ -0,0 +1,168 @@
+use super::{PasswordValidator, UserAttributes, ValidatorConfigError};
+use crate::errors::DomainError;
+use std::collections::{HashMap, HashSet};
+
+const COMMON_PASSWORDS: &str = include_str!("common-passwords.txt");
+
+/// Rejects passwords shorter than `min` characters (8 by default)
+pub struct MinimumLength {
+    min: usize,
+}
+
+impl MinimumLength {
+    pub fn new(min: usize) -> Self {
+        Self { min }
+    }
+}
+
+impl Default for MinimumLength {
+    fn default() -> Self {
+        Self::new(8)
+    }
+}
+
+impl PasswordValidator for MinimumLength {
+    fn validate(&self, password: &str, _user: &UserAttributes<'_>) -> Result<(), DomainError> {
+        if password.chars().count() < self.min {
+            return Err(DomainError::PasswordTooShort { min: self.min });
+        }
+        Ok(())
+    }
+}
+
+/// Rejects passwords from a list, ignoring case and surrounding whitespace
+///
+/// `new` uses the bundled list of the most used passwords; `from_list`
+/// takes your own.
+pub struct CommonPasswords {
+    passwords: HashSet<String>,
+}
+
+impl CommonPasswords {
+    pub fn new() -> Self {
+        Self::from_list(COMMON_PASSWORDS.lines())
+    }
+
+    pub fn from_list<S: AsRef<str>>(passwords: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            passwords: passwords
+                .into_iter()
+                .map(|password| password.as_ref().trim().to_lowercase())
+                .filter(|password| !password.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl Default for CommonPasswords {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PasswordValidator for CommonPasswords {
+    fn validate(&self, password: &str, _user: &UserAttributes<'_>) -> Result<(), DomainError> {
+        if self.passwords.contains(&password.trim().to_lowercase()) {
+            return Err(DomainError::PasswordTooCommon);
+        }
+        Ok(())
+    }
+}
+
+/// Rejects passwords made only of digits, as `PasswordTooWeak`
+pub struct NumericPassword;
+
+impl PasswordValidator for NumericPassword {
+    fn validate(&self, password: &str, _user: &UserAttributes<'_>) -> Result<(), DomainError> {
+        if !password.is_empty() && password.chars().all(char::is_numeric) {
+            return Err(DomainError::PasswordTooWeak);
+        }
+        Ok(())
+    }
+}
+
+/// Rejects passwords too close to the user's email or name, or to any word
+/// in them (`ana.silva@example.com` is checked as a whole and as `ana`,
+/// `silva`, `example` and `com`)
+///
+/// Similarity is the share of characters the two have in common, from 0 to
+/// 1; at `max_similarity` (0.7 by default) or above the password fails.
+/// Words much shorter than the password are skipped, so a long password
+/// that happens to contain a short name still passes.
+pub struct UserAttributeSimilarity {
+    max_similarity: f64,
+}
+
+impl UserAttributeSimilarity {
+    /// `SimilarityTooLow` below 0.1, which would reject nearly every
+    /// password
+    pub fn new(max_similarity: f64) -> Result<Self, ValidatorConfigError> {
+        if max_similarity.is_nan() || max_similarity < 0.1 {
+            return Err(ValidatorConfigError::SimilarityTooLow(max_similarity));
+        }
+        Ok(Self { max_similarity })
+    }
+
+    /// A word under a tenth of the password's length, and too short to
+    /// reach `max_similarity` anyway
+    fn is_negligible(&self, password_len: usize, word_len: usize) -> bool {
+        password_len >= 10 * word_len
+            && (word_len as f64) < self.max_similarity / 2.0 * password_len as f64
+    }
+}
+
+impl Default for UserAttributeSimilarity {
+    fn default() -> Self {
+        Self {
+            max_similarity: 0.7,
+        }
+    }
+}
+
+impl PasswordValidator for UserAttributeSimilarity {
+    fn validate(&self, password: &str, user: &UserAttributes<'_>) -> Result<(), DomainError> {
+        let password = password.to_lowercase();
+        let password_len = password.chars().count();
+
+        for value in [user.email, user.name] {
+            let value = value.to_lowercase();
+            let words = value
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .chain([value.as_str()])
+                .filter(|word| !word.is_empty());
+
+            for word in words {
+                if self.is_negligible(password_len, word.chars().count()) {
+                    continue;
+                }
+                if similarity(&password, word) >= self.max_similarity {
+                    return Err(DomainError::PasswordTooSimilar);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Twice the characters `a` and `b` share (counting repeats) over their
+/// total length
+fn similarity(a: &str, b: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in b.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+
+    let mut shared = 0;
+    for c in a.chars() {
+        if let Some(count) = counts.get_mut(&c).filter(|count| **count > 0) {
+            *count -= 1;
+            shared += 1;
+        }
+    }
+
+    let total = a.chars().count() + b.chars().count();
+    if total == 0 {
+        return 1.0;
+    }
+    2.0 * shared as f64 / total as f64
+}
@@ -0,0 +1,86 @@
+//! Who may do what to which aggregate
+//!
+//! A policy is a plain trait per aggregate, such as `PostPolicy`, that looks
+//! at an `Actor` and the aggregate and answers yes or no. Policies do no I/O,
+//! so they can be tested by building an `Actor` by hand:
+//!
+//! ```rust,ignore
+//! let author = Actor::new(post.author_id().clone());
+//! assert!(DefaultPostPolicy.allows(&author, PostAction::Update, &post));
+//! ```
+//!
+//! Services resolve the actor, with the permissions of their groups, through
+//! `PermissionChecker::actor` and return `ServiceError::Unauthorized` when
+//! the policy says no. For your own aggregates, write a trait the same way.
+
+mod post;
+
+pub use post::{DefaultPostPolicy, PostAction, PostPolicy, ViewScope};
+
+use crate::models::{Group, Permission, User};
+use crate::values::UserId;
+use std::collections::BTreeSet;
+
+/// The user performing an action, with every permission they hold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Actor {
+    user_id: UserId,
+    is_staff: bool,
+    is_superuser: bool,
+    permissions: BTreeSet<Permission>,
+}
+
+impl Actor {
+    /// A user with no permissions
+    pub fn new(user_id: UserId) -> Self {
+        Self {
+            user_id,
+            is_staff: false,
+            is_superuser: false,
+            permissions: BTreeSet::new(),
+        }
+    }
+
+    /// `user`'s own permissions plus those of `groups`, which should be the
+    /// groups `user` belongs to
+    pub fn from_user(user: &User, groups: &[Group]) -> Self {
+        let permissions = user
+            .permissions()
+            .iter()
+            .chain(groups.iter().flat_map(|group| group.permissions()))
+            .cloned()
+            .collect();
+        Self {
+            user_id: user.id().clone(),
+            is_staff: user.is_staff(),
+            is_superuser: user.is_superuser(),
+            permissions,
+        }
+    }
+
+    pub fn with_permission(mut self, permission: Permission) -> Self {
+        self.permissions.insert(permission);
+        self
+    }
+
+    pub fn superuser(mut self) -> Self {
+        self.is_superuser = true;
+        self.is_staff = true;
+        self
+    }
+
+    // Getters
+    pub fn id(&self) -> &UserId {
+        &self.user_id
+    }
+    pub fn is_staff(&self) -> bool {
+        self.is_staff
+    }
+    pub fn is_superuser(&self) -> bool {
+        self.is_superuser
+    }
+    /// Always true for superusers
+    pub fn has_permission(&self, permission: &Permission) -> bool {
+        self.is_superuser || self.permissions.contains(permission)
+    }
+}
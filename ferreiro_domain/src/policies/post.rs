@@ -0,0 +1,80 @@
+use super::Actor;
+use crate::models::{Permission, Post, PostStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostAction {
+    View,
+    Update,
+    Publish,
+    Archive,
+    Delete,
+}
+
+/// Which unpublished posts an actor may see, in a form storage can filter
+/// by; published posts are for everyone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewScope {
+    PublishedOnly,
+    /// Their own drafts and archived posts too
+    OwnPosts,
+    AllPosts,
+}
+
+/// Decides whether an actor may perform an action on a post
+///
+/// Creating is checked with `add_post` alone, as there is no post yet.
+pub trait PostPolicy: Send + Sync {
+    fn allows(&self, actor: &Actor, action: PostAction, post: &Post) -> bool;
+
+    /// What listings show `actor`; should agree with `allows` for `View`.
+    /// Published posts only, unless overridden.
+    fn view_scope(&self, _actor: &Actor) -> ViewScope {
+        ViewScope::PublishedOnly
+    }
+}
+
+/// Authors look after their own posts; permissions extend that to others
+///
+/// | Action  | Author                 | Anyone else with |
+/// |---------|------------------------|------------------|
+/// | View    | always                 | `view_post`, or the post is published |
+/// | Update  | while it is a draft    | `change_post`    |
+/// | Publish | always                 | `publish_post`   |
+/// | Archive | always                 | `publish_post`   |
+/// | Delete  | while it is a draft    | `delete_post`    |
+///
+/// Superusers may do everything.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultPostPolicy;
+
+impl PostPolicy for DefaultPostPolicy {
+    fn allows(&self, actor: &Actor, action: PostAction, post: &Post) -> bool {
+        let is_author = post.author_id() == actor.id();
+        let is_draft = post.status() == &PostStatus::Draft;
+
+        match action {
+            PostAction::View => {
+                post.status() == &PostStatus::Published
+                    || is_author
+                    || actor.has_permission(&Permission::view_post())
+            }
+            PostAction::Update => {
+                (is_author && is_draft) || actor.has_permission(&Permission::change_post())
+            }
+            PostAction::Publish | PostAction::Archive => {
+                is_author || actor.has_permission(&Permission::publish_post())
+            }
+            PostAction::Delete => {
+                (is_author && is_draft) || actor.has_permission(&Permission::delete_post())
+            }
+        }
+    }
+
+    fn view_scope(&self, actor: &Actor) -> ViewScope {
+        if actor.has_permission(&Permission::view_post()) {
+            ViewScope::AllPosts
+        } else {
+            ViewScope::OwnPosts
+        }
+    }
+}
@@ -1,8 +1,15 @@
 use crate::events::DomainEvent;
-use crate::models::{Post, PostStatus, User};
-use crate::values::{Email, PostId, Slug, UserId};
+use crate::mail::EmailMessage;
+use crate::models::{
+    ApiToken, AuthUser, ExternalIdentity, Group, Permission, Post, PostStatus, ProviderTokens,
+    TotpDevice, User,
+};
+use crate::policies::Actor;
+use crate::values::{ApiTokenId, Email, GroupId, PostId, Slug, UserId};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use thiserror::Error;
 
 // ============= Repository Filters & Pagination =============
@@ -12,6 +19,9 @@ pub struct PostFilter {
     pub author_id: Option<UserId>,
     pub status: Option<PostStatus>,
     pub published_after: Option<DateTime<Utc>>,
+    /// Only posts that are published or by this author, which is what a
+    /// user without `view_post` may see
+    pub published_or_by: Option<UserId>,
 }
 
 #[derive(Debug, Clone)]
@@ -44,6 +54,8 @@ pub struct PaginatedResult<T> {
 pub trait PostRepository: Send + Sync {
     async fn find_by_id(&self, id: &PostId) -> Result<Option<Post>, RepositoryError>;
     async fn find_by_slug(&self, slug: &Slug) -> Result<Option<Post>, RepositoryError>;
+    /// Slugs are unique: saving a post under another post's slug fails
+    /// with `Conflict`
     async fn save(&self, post: &Post) -> Result<(), RepositoryError>;
     async fn delete(&self, id: &PostId) -> Result<(), RepositoryError>;
     async fn list(
@@ -56,15 +68,252 @@ pub trait PostRepository: Send + Sync {
 
 // ============= User Repository =============
 
+/// Stores users of model `M`, `User` unless the project has its own
+///
+/// Email lookups match `Email::normalized`, so `Ana@Example.com` finds the
+/// user who registered as `ana@example.com`.
 #[async_trait]
-pub trait UserRepository: Send + Sync {
-    async fn find_by_id(&self, id: &UserId) -> Result<Option<User>, RepositoryError>;
-    async fn find_by_email(&self, email: &Email) -> Result<Option<User>, RepositoryError>;
-    async fn save(&self, user: &User) -> Result<(), RepositoryError>;
+pub trait UserRepository<M: AuthUser = User>: Send + Sync {
+    async fn find_by_id(&self, id: &UserId) -> Result<Option<M>, RepositoryError>;
+    async fn find_by_email(&self, email: &Email) -> Result<Option<M>, RepositoryError>;
+    /// `identifier` is normalized, as `AuthUser::identifier` returns it
+    async fn find_by_identifier(&self, identifier: &str) -> Result<Option<M>, RepositoryError>;
+    /// Emails, compared normalized, and identifiers are unique: saving a
+    /// user with another user's fails with `Conflict`
+    async fn save(&self, user: &M) -> Result<(), RepositoryError>;
     async fn delete(&self, id: &UserId) -> Result<(), RepositoryError>;
     async fn exists_by_email(&self, email: &Email) -> Result<bool, RepositoryError>;
 }
 
+// ============= Group Repository =============
+
+#[async_trait]
+pub trait GroupRepository: Send + Sync {
+    async fn find_by_id(&self, id: &GroupId) -> Result<Option<Group>, RepositoryError>;
+    async fn find_by_name(&self, name: &str) -> Result<Option<Group>, RepositoryError>;
+    /// The groups that exist among `ids`, in no particular order
+    async fn find_many(&self, ids: &[GroupId]) -> Result<Vec<Group>, RepositoryError>;
+    async fn list(&self) -> Result<Vec<Group>, RepositoryError>;
+    async fn save(&self, group: &Group) -> Result<(), RepositoryError>;
+    async fn delete(&self, id: &GroupId) -> Result<(), RepositoryError>;
+}
+
+// ============= API Token Repository =============
+
+#[async_trait]
+pub trait ApiTokenRepository: Send + Sync {
+    async fn find_by_id(&self, id: &ApiTokenId) -> Result<Option<ApiToken>, RepositoryError>;
+    async fn find_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>, RepositoryError>;
+    /// Newest first
+    async fn list_for_user(&self, user_id: &UserId) -> Result<Vec<ApiToken>, RepositoryError>;
+    async fn save(&self, token: &ApiToken) -> Result<(), RepositoryError>;
+    async fn delete(&self, id: &ApiTokenId) -> Result<(), RepositoryError>;
+}
+
+// ============= Refresh Token Repository =============
+
+/// One issued refresh token; the tokens descended from one login by
+/// refreshing share a `family`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefreshTokenRecord {
+    pub id: String,
+    pub family: String,
+    pub user_id: UserId,
+    pub expires_at: DateTime<Utc>,
+    pub used: bool,
+    pub revoked: bool,
+}
+
+#[async_trait]
+pub trait RefreshTokenRepository: Send + Sync {
+    async fn find(&self, id: &str) -> Result<Option<RefreshTokenRecord>, RepositoryError>;
+    async fn save(&self, record: &RefreshTokenRecord) -> Result<(), RepositoryError>;
+    /// Marks the token used and returns whether it wasn't already; must be
+    /// atomic, so two requests racing with one token can't both succeed
+    async fn mark_used(&self, id: &str) -> Result<bool, RepositoryError>;
+    async fn revoke_family(&self, family: &str) -> Result<(), RepositoryError>;
+    /// Revokes every token issued to `user_id`, from every login
+    async fn revoke_all_for_user(&self, user_id: &UserId) -> Result<(), RepositoryError>;
+}
+
+// ============= External Identity Repository =============
+
+#[async_trait]
+pub trait ExternalIdentityRepository: Send + Sync {
+    async fn find(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<ExternalIdentity>, RepositoryError>;
+    /// Oldest link first
+    async fn list_for_user(
+        &self,
+        user_id: &UserId,
+    ) -> Result<Vec<ExternalIdentity>, RepositoryError>;
+    /// Replaces the identity with the same provider and subject
+    async fn save(&self, identity: &ExternalIdentity) -> Result<(), RepositoryError>;
+    async fn delete(&self, provider: &str, subject: &str) -> Result<(), RepositoryError>;
+}
+
+// ============= TOTP Device Repository =============
+
+/// One device per user, so devices are found by user
+#[async_trait]
+pub trait TotpDeviceRepository: Send + Sync {
+    async fn find(&self, user_id: &UserId) -> Result<Option<TotpDevice>, RepositoryError>;
+    async fn save(&self, device: &TotpDevice) -> Result<(), RepositoryError>;
+    async fn delete(&self, user_id: &UserId) -> Result<(), RepositoryError>;
+    /// Records that the code for time step `step` was used, and returns
+    /// whether no code from it or a later step was before, as
+    /// `TotpDevice::use_step`; must be atomic, so two requests racing with
+    /// one code can't both succeed
+    async fn use_step(&self, user_id: &UserId, step: u64) -> Result<bool, RepositoryError>;
+    /// Removes the recovery code with `code_hash` and returns whether it
+    /// was there; atomic like `use_step`
+    async fn use_recovery_code(
+        &self,
+        user_id: &UserId,
+        code_hash: &str,
+    ) -> Result<bool, RepositoryError>;
+}
+
+// ============= Login Failures =============
+
+/// Recent failed logins under one key, such as an account or an IP address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginFailures {
+    pub count: u32,
+    pub last_failed_at: DateTime<Utc>,
+}
+
+/// Counts failed logins, so password guessing can be slowed down and
+/// locked out
+///
+/// A count is forgotten `ttl` after its last failure. Stores shared by
+/// several servers should count atomically, so failures racing each other
+/// are all counted.
+#[async_trait]
+pub trait LoginFailureStore: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<LoginFailures>, RepositoryError>;
+    /// Counts one more failure under `key` and returns the new total
+    async fn record(&self, key: &str, ttl: Duration) -> Result<LoginFailures, RepositoryError>;
+    async fn clear(&self, key: &str) -> Result<(), RepositoryError>;
+}
+
+// ============= Permissions =============
+
+/// Resolves who a user is for authorization: their flags, and every
+/// permission they hold directly or through a group
+///
+/// Inactive and unknown users resolve to `None`, and hold no permissions.
+#[async_trait]
+pub trait PermissionChecker: Send + Sync {
+    async fn actor(&self, user_id: &UserId) -> Result<Option<Actor>, RepositoryError>;
+
+    /// Superusers hold every permission
+    async fn has_permission(
+        &self,
+        user_id: &UserId,
+        permission: &Permission,
+    ) -> Result<bool, RepositoryError> {
+        Ok(self
+            .actor(user_id)
+            .await?
+            .is_some_and(|actor| actor.has_permission(permission)))
+    }
+}
+
+// ============= Access Tokens =============
+
+/// An access token and the refresh token to renew it with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Seconds until `access_token` expires
+    pub expires_in: u64,
+}
+
+/// What a verified access token says about its bearer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessClaims {
+    pub user_id: UserId,
+    pub token_id: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Checks access tokens without any I/O, so a service that only accepts
+/// them needs nothing but the keys
+pub trait AccessTokenVerifier: Send + Sync {
+    fn verify(&self, access_token: &str) -> Result<AccessClaims, TokenError>;
+}
+
+#[async_trait]
+pub trait TokenIssuer: AccessTokenVerifier {
+    async fn issue(&self, user_id: &UserId) -> Result<TokenPair, TokenError>;
+    /// Exchanges a refresh token for a new pair. Each refresh token works
+    /// once: presenting one again revokes its whole family and fails with
+    /// `TokenError::Reused`, as it has most likely been stolen.
+    async fn refresh(&self, refresh_token: &str) -> Result<(UserId, TokenPair), TokenError>;
+    /// Revokes `refresh_token` and every other token from the same login
+    async fn revoke(&self, refresh_token: &str) -> Result<(), TokenError>;
+    /// Revokes the refresh tokens of every login `user_id` has; access
+    /// tokens already issued last until they expire
+    async fn revoke_all(&self, user_id: &UserId) -> Result<(), TokenError>;
+}
+
+// ============= OAuth Providers =============
+
+/// What to send the user to a provider with
+///
+/// `state` comes back with the code and ties it to this flow; `nonce` ends
+/// up in the ID token; `code_challenge` is the PKCE S256 challenge, so a
+/// stolen code is useless without the verifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorizationRequest {
+    pub state: String,
+    pub nonce: String,
+    pub code_challenge: String,
+    pub redirect_uri: String,
+}
+
+/// Who the provider says the user is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalProfile {
+    /// The provider's stable id for the account
+    pub subject: String,
+    pub email: Option<String>,
+    /// Whether the provider checked the user owns `email`; only then is it
+    /// trusted to match an existing account
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+/// An OAuth2 authorization server, usually also an OpenID Connect provider
+///
+/// Implementations hold the client id and secret, and know the provider's
+/// endpoints.
+#[async_trait]
+pub trait OAuthProvider: Send + Sync {
+    /// Names the provider in URLs and in `ExternalIdentity`, such as `google`
+    fn name(&self) -> &str;
+    fn authorization_url(&self, request: &AuthorizationRequest) -> String;
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> Result<ProviderTokens, OAuthError>;
+    /// The profile behind `tokens`; OpenID Connect providers must check the
+    /// ID token's `nonce` is `nonce`
+    async fn profile(
+        &self,
+        tokens: &ProviderTokens,
+        nonce: &str,
+    ) -> Result<ExternalProfile, OAuthError>;
+}
+
 // ============= Event Publisher =============
 
 #[async_trait]
@@ -80,6 +329,81 @@ pub trait PasswordHasher: Send + Sync {
     fn verify(&self, password: &str, hash: &str) -> Result<bool, HashError>;
 }
 
+// ============= Cache =============
+
+/// Key-value cache for rendered HTML and other derived data
+///
+/// `ttl` of `None` keeps the entry until it is deleted or evicted. The
+/// application layer's `CacheExt` stores typed values instead of bytes.
+#[async_trait]
+pub trait Cache: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, CacheError>;
+    async fn set(&self, key: &str, value: &[u8], ttl: Option<Duration>) -> Result<(), CacheError>;
+    async fn delete(&self, key: &str) -> Result<(), CacheError>;
+    /// Adds `delta` to the integer stored at `key`, starting from 0, and
+    /// returns the new value
+    async fn incr(&self, key: &str, delta: i64) -> Result<i64, CacheError>;
+
+    /// One result per key, in order; backends override this to fetch them
+    /// in a single round trip
+    async fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<Vec<u8>>>, CacheError> {
+        let mut values = Vec::with_capacity(keys.len());
+        for key in keys {
+            values.push(self.get(key).await?);
+        }
+        Ok(values)
+    }
+}
+
+// ============= Translator =============
+
+/// Looks up messages by stable key, such as `DomainError::message_key`
+pub trait Translator: Send + Sync {
+    /// `{name}` placeholders are filled from `params`. `None` when neither
+    /// `locale` nor any fallback locale has the key.
+    fn translate(&self, locale: &str, key: &str, params: &[(&str, String)]) -> Option<String>;
+}
+
+// ============= Mail Exchange Lookup =============
+
+/// Finds the hosts that accept mail for a domain, to catch addresses that
+/// can't receive anything before mailing them
+#[async_trait]
+pub trait MxLookup: Send + Sync {
+    /// Best first; `domain` is in ASCII form. Empty, or just `.` (a null MX),
+    /// when the domain takes no mail.
+    async fn mx_hosts(&self, domain: &str) -> Result<Vec<String>, MxLookupError>;
+}
+
+// ============= Breached Passwords =============
+
+/// Known-breached passwords, queried by k-anonymity as the Pwned Passwords
+/// range API is: only the first 5 hex characters of the password's SHA-1
+/// are sent, and the match is made locally
+#[async_trait]
+pub trait BreachedPasswords: Send + Sync {
+    /// The remaining 35 characters of every breached hash starting with
+    /// `prefix` (uppercase hex), each with the number of times it was seen
+    async fn range(&self, prefix: &str) -> Result<Vec<(String, u64)>, BreachLookupError>;
+}
+
+// ============= Email Sender =============
+
+/// Delivers outgoing mail; see `ferreiro_adapters_email` for backends
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    async fn send(&self, message: &EmailMessage) -> Result<(), EmailError>;
+
+    /// Stops at the first failure; backends that can reuse a connection
+    /// override this
+    async fn send_all(&self, messages: &[EmailMessage]) -> Result<(), EmailError> {
+        for message in messages {
+            self.send(message).await?;
+        }
+        Ok(())
+    }
+}
+
 // ============= Errors =============
 
 #[derive(Debug, Error)]
@@ -111,3 +435,78 @@ pub enum HashError {
     #[error("Verification failed: {0}")]
     VerificationFailed(String),
 }
+
+#[derive(Debug, Error)]
+pub enum CacheError {
+    #[error("Cache backend error: {0}")]
+    Backend(String),
+
+    #[error("Value at {0} is not an integer")]
+    NotAnInteger(String),
+
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+}
+
+#[derive(Debug, Error)]
+pub enum MxLookupError {
+    #[error("DNS lookup failed: {0}")]
+    Lookup(String),
+}
+
+#[derive(Debug, Error)]
+pub enum BreachLookupError {
+    #[error("Breached password lookup failed: {0}")]
+    Lookup(String),
+}
+
+#[derive(Debug, Error)]
+pub enum EmailError {
+    #[error("Invalid message: {0}")]
+    InvalidMessage(String),
+
+    #[error("Mail server connection failed: {0}")]
+    Connection(String),
+
+    #[error("Mail server rejected the message: {code} {message}")]
+    Rejected { code: u16, message: String },
+
+    #[error("Could not store message: {0}")]
+    Storage(String),
+}
+
+#[derive(Debug, Error)]
+pub enum TokenError {
+    #[error("Token is malformed or its signature is invalid")]
+    Invalid,
+
+    #[error("Token has expired")]
+    Expired,
+
+    #[error("Token has been revoked")]
+    Revoked,
+
+    #[error("Refresh token was already used; its login has been revoked")]
+    Reused,
+
+    #[error("Signing key error: {0}")]
+    Key(String),
+
+    #[error("Token storage error: {0}")]
+    Storage(String),
+}
+
+#[derive(Debug, Error)]
+pub enum OAuthError {
+    #[error("Provider refused the request: {0}")]
+    Rejected(String),
+
+    #[error("ID token is not for this request: {0}")]
+    InvalidIdToken(String),
+
+    #[error("Provider response is invalid: {0}")]
+    InvalidResponse(String),
+
+    #[error("Provider connection failed: {0}")]
+    Connection(String),
+}
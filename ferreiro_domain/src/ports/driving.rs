@@ -1,15 +1,18 @@
 use crate::errors::DomainError;
-use crate::models::{Post, User};
-use crate::ports::driven::{PaginatedResult, Pagination, PostFilter};
-use crate::values::{PostId, UserId};
+use crate::models::{ApiToken, AuthUser, ExternalIdentity, Post, User};
+use crate::ports::driven::{PaginatedResult, Pagination, PostFilter, TokenPair};
+use crate::values::{ApiTokenId, PostId, UserId};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::net::IpAddr;
 use thiserror::Error;
 
 // ============= Post Service Commands =============
 
 pub struct CreatePostCommand {
     pub title: String,
-    pub slug: String,
+    /// Generated from the title, and made unique, when `None`
+    pub slug: Option<String>,
     pub body: String,
     pub author_id: UserId,
 }
@@ -18,11 +21,16 @@ pub struct UpdatePostCommand {
     pub id: PostId,
     pub title: String,
     pub body: String,
+    /// Who is making the change
+    pub actor: UserId,
 }
 
 pub struct ListPostsQuery {
     pub filter: PostFilter,
     pub pagination: Pagination,
+    /// Who is looking; `None` for anonymous visitors, who see published
+    /// posts only
+    pub viewer: Option<UserId>,
 }
 
 // ============= Post Service =============
@@ -31,11 +39,20 @@ pub struct ListPostsQuery {
 pub trait PostService: Send + Sync {
     async fn create(&self, cmd: CreatePostCommand) -> Result<Post, ServiceError>;
     async fn update(&self, cmd: UpdatePostCommand) -> Result<Post, ServiceError>;
-    async fn publish(&self, id: &PostId) -> Result<Post, ServiceError>;
-    async fn archive(&self, id: &PostId) -> Result<Post, ServiceError>;
-    async fn delete(&self, id: &PostId) -> Result<(), ServiceError>;
-    async fn get(&self, id: &PostId) -> Result<Option<Post>, ServiceError>;
-    async fn get_by_slug(&self, slug: &str) -> Result<Option<Post>, ServiceError>;
+    /// `actor` is the user acting; `Unauthorized` when the policy forbids it
+    async fn publish(&self, id: &PostId, actor: &UserId) -> Result<Post, ServiceError>;
+    async fn archive(&self, id: &PostId, actor: &UserId) -> Result<Post, ServiceError>;
+    async fn delete(&self, id: &PostId, actor: &UserId) -> Result<(), ServiceError>;
+    /// `None` too for a post `viewer` may not see; anonymous visitors, with
+    /// `None`, see published posts only
+    async fn get(&self, id: &PostId, viewer: Option<&UserId>)
+        -> Result<Option<Post>, ServiceError>;
+    async fn get_by_slug(
+        &self,
+        slug: &str,
+        viewer: Option<&UserId>,
+    ) -> Result<Option<Post>, ServiceError>;
+    /// Only the posts `query.viewer` may see
     async fn list(&self, query: ListPostsQuery) -> Result<PaginatedResult<Post>, ServiceError>;
 }
 
@@ -45,26 +62,255 @@ pub struct RegisterCommand {
     pub email: String,
     pub password: String,
     pub name: String,
+    /// What the user will log in with, for models whose
+    /// `AuthUser::IDENTIFIER_FIELD` isn't the email; `None` uses the email
+    pub identifier: Option<String>,
 }
 
 pub struct LoginCommand {
-    pub email: String,
+    /// The email, or whatever else the user model logs in with
+    pub identifier: String,
     pub password: String,
+    /// The client's address, so failures from it can be throttled across
+    /// accounts
+    pub ip_address: Option<IpAddr>,
+}
+
+pub struct ChangePasswordCommand {
+    pub user_id: UserId,
+    pub current_password: String,
+    pub new_password: String,
+    /// The session the change is made from, which stays logged in; the
+    /// user's other sessions, and all their refresh tokens, stop working
+    pub session_token: Option<String>,
+}
+
+/// Sets a new password with a token from `request_password_reset`
+pub struct ResetPasswordCommand {
+    pub token: String,
+    pub new_password: String,
+}
+
+/// What a client presents on later requests to prove who it is
+#[derive(Debug, Clone)]
+pub enum Credentials {
+    /// Look the user up with `AuthService::get_user_by_session`
+    Session(String),
+    /// From a `TokenIssuer`; renew with `AuthService::refresh`
+    Tokens(TokenPair),
+    /// The password was right, but the user has a second factor: keep this
+    /// token in the session and pass it to `AuthService::complete_login`
+    /// with their code. Not yet a login.
+    MfaRequired(String),
+    /// As `MfaRequired`, for a user who must use a second factor but has
+    /// none: they enroll with `MfaService` first
+    MfaEnrollmentRequired(String),
+}
+
+pub struct AuthenticatedUser<M = User> {
+    pub user: M,
+    pub credentials: Credentials,
 }
 
-pub struct AuthenticatedUser {
-    pub user: User,
-    pub session_token: String,
+impl<M> AuthenticatedUser<M> {
+    pub fn session_token(&self) -> Option<&str> {
+        match &self.credentials {
+            Credentials::Session(token) => Some(token),
+            _ => None,
+        }
+    }
+
+    pub fn tokens(&self) -> Option<&TokenPair> {
+        match &self.credentials {
+            Credentials::Tokens(tokens) => Some(tokens),
+            _ => None,
+        }
+    }
+
+    /// The token to complete the login with, while a second factor is due
+    pub fn pending_mfa(&self) -> Option<&str> {
+        match &self.credentials {
+            Credentials::MfaRequired(token) | Credentials::MfaEnrollmentRequired(token) => {
+                Some(token)
+            }
+            _ => None,
+        }
+    }
 }
 
 // ============= Auth Service =============
 
+/// Registration and login for users of model `M`, `User` unless the
+/// project has its own
+#[async_trait]
+pub trait AuthService<M: AuthUser = User>: Send + Sync {
+    async fn register(&self, cmd: RegisterCommand) -> Result<M, ServiceError>;
+    /// Users with a second factor get `Credentials::MfaRequired` rather
+    /// than a session or tokens. With throttling, repeated failures for an
+    /// identifier or from an address get `DomainError::TooManyLoginAttempts`.
+    async fn login(&self, cmd: LoginCommand) -> Result<AuthenticatedUser<M>, ServiceError>;
+    /// Finishes a login awaiting a second factor, with a code from the
+    /// user's authenticator app or a recovery code. A few wrong codes
+    /// discard `pending_token`, and the user starts over; with throttling,
+    /// wrong codes also count as failed logins of the account.
+    async fn complete_login(
+        &self,
+        pending_token: &str,
+        code: &str,
+    ) -> Result<AuthenticatedUser<M>, ServiceError>;
+    /// `token` is the session token, or the refresh token when logging in
+    /// returns `Credentials::Tokens`
+    async fn logout(&self, token: &str) -> Result<(), ServiceError>;
+    /// A new token pair for a refresh token; `Unauthorized` if it is
+    /// invalid, expired, reused or its user is inactive
+    async fn refresh(&self, refresh_token: &str) -> Result<TokenPair, ServiceError>;
+    async fn get_user_by_session(&self, session_token: &str) -> Result<Option<M>, ServiceError>;
+    async fn change_password(&self, cmd: ChangePasswordCommand) -> Result<(), ServiceError>;
+    /// Publishes `PasswordResetRequested` when `email` belongs to an active
+    /// user, and succeeds either way so callers can't probe for accounts
+    async fn request_password_reset(&self, email: &str) -> Result<(), ServiceError>;
+    async fn reset_password(&self, cmd: ResetPasswordCommand) -> Result<(), ServiceError>;
+    /// Publishes `EmailVerificationRequested`, unless already verified
+    async fn request_email_verification(&self, user_id: &UserId) -> Result<(), ServiceError>;
+    async fn verify_email(&self, token: &str) -> Result<M, ServiceError>;
+    /// Lets a user locked out by failed logins try again straight away;
+    /// `Unauthorized` unless `staff_id` is active staff
+    async fn unlock_account(&self, staff_id: &UserId, user_id: &UserId)
+        -> Result<(), ServiceError>;
+    /// Keeps a user from logging in, and ends their sessions and refresh
+    /// tokens; `Unauthorized` unless `staff_id` is active staff
+    async fn deactivate_user(
+        &self,
+        staff_id: &UserId,
+        user_id: &UserId,
+    ) -> Result<(), ServiceError>;
+}
+
+// ============= Social Auth Service =============
+
+/// Where to send the user to sign in with a provider
+#[derive(Debug, Clone)]
+pub struct AuthorizationRedirect {
+    pub url: String,
+    /// Also in `url`; comes back with the code
+    pub state: String,
+    /// Keep in the user's session, such as in an `HttpOnly` cookie, and
+    /// pass back with the callback: only the browser that began the flow
+    /// can finish it, so nobody can log a victim into their own account
+    pub binding: String,
+}
+
+/// What the provider redirects back with, and the `binding` kept from
+/// `AuthorizationRedirect`
+pub struct AuthorizationCallback {
+    pub state: String,
+    pub code: String,
+    pub binding: String,
+}
+
+/// Signing in with OAuth2 and OpenID Connect providers
+#[async_trait]
+pub trait SocialAuthService: Send + Sync {
+    /// `NotFound` for a provider that isn't configured. `redirect_uri` is
+    /// the callback route, registered with the provider.
+    async fn begin(
+        &self,
+        provider: &str,
+        redirect_uri: &str,
+    ) -> Result<AuthorizationRedirect, ServiceError>;
+    /// Handles the callback: exchanges the code, then logs in the user
+    /// linked to the provider account, creating one on first login. An
+    /// existing user with the same email is `DomainError::UserAlreadyExists`
+    /// until they `link` the provider. Each `state` works once, and only
+    /// with its `binding`; anything else is `DomainError::InvalidToken`.
+    async fn complete(
+        &self,
+        callback: AuthorizationCallback,
+    ) -> Result<AuthenticatedUser, ServiceError>;
+    /// Handles the callback of a flow begun by a logged-in user, linking the
+    /// provider account to `user_id`, who must come from their session;
+    /// `Conflict` when the provider account belongs to another user
+    async fn link(
+        &self,
+        user_id: &UserId,
+        callback: AuthorizationCallback,
+    ) -> Result<ExternalIdentity, ServiceError>;
+    async fn identities(&self, user_id: &UserId) -> Result<Vec<ExternalIdentity>, ServiceError>;
+    /// `Conflict` when it is the user's only way to sign in
+    async fn unlink(&self, user_id: &UserId, provider: &str) -> Result<(), ServiceError>;
+}
+
+// ============= MFA Service =============
+
+/// A new authenticator app secret, to show the user once
+#[derive(Debug, Clone)]
+pub struct TotpEnrollment {
+    /// Base32, for typing into the app by hand
+    pub secret: String,
+    /// The `otpauth://` URI to render as a QR code
+    pub provisioning_uri: String,
+}
+
+/// Second factors: authenticator apps (TOTP) and recovery codes
+#[async_trait]
+pub trait MfaService: Send + Sync {
+    /// Whether the user has a confirmed authenticator app
+    async fn is_enabled(&self, user_id: &UserId) -> Result<bool, ServiceError>;
+    /// Starts over with a new secret; `Conflict` if MFA is already enabled,
+    /// so knowing the password isn't enough to replace the device
+    async fn begin_enrollment(&self, user_id: &UserId) -> Result<TotpEnrollment, ServiceError>;
+    /// Enables MFA once the user enters a code from the app, returning
+    /// their recovery codes, the only time they are shown
+    async fn confirm_enrollment(
+        &self,
+        user_id: &UserId,
+        code: &str,
+    ) -> Result<Vec<String>, ServiceError>;
+    /// Checks a code from the app, or uses up a recovery code; each code is
+    /// accepted once
+    async fn verify(&self, user_id: &UserId, code: &str) -> Result<bool, ServiceError>;
+    /// Replaces the recovery codes, given a code from the app
+    async fn regenerate_recovery_codes(
+        &self,
+        user_id: &UserId,
+        code: &str,
+    ) -> Result<Vec<String>, ServiceError>;
+    /// Turns MFA off, given a code from the app or a recovery code
+    async fn disable(&self, user_id: &UserId, code: &str) -> Result<(), ServiceError>;
+    /// For staff to turn off the MFA of a user who lost their device;
+    /// `Unauthorized` unless `staff_id` is active staff
+    async fn reset(&self, staff_id: &UserId, user_id: &UserId) -> Result<(), ServiceError>;
+}
+
+// ============= API Token Service Commands =============
+
+#[derive(Debug, Clone)]
+pub struct CreateApiTokenCommand {
+    pub user_id: UserId,
+    pub name: String,
+    pub scopes: Vec<String>,
+    /// `None` for a token that never expires
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A new token, with the only copy of its secret
+pub struct CreatedApiToken {
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+// ============= API Token Service =============
+
+/// Personal access tokens, for users of model `M`
 #[async_trait]
-pub trait AuthService: Send + Sync {
-    async fn register(&self, cmd: RegisterCommand) -> Result<User, ServiceError>;
-    async fn login(&self, cmd: LoginCommand) -> Result<AuthenticatedUser, ServiceError>;
-    async fn logout(&self, session_token: &str) -> Result<(), ServiceError>;
-    async fn get_user_by_session(&self, session_token: &str) -> Result<Option<User>, ServiceError>;
+pub trait ApiTokenService<M: AuthUser = User>: Send + Sync {
+    async fn create(&self, cmd: CreateApiTokenCommand) -> Result<CreatedApiToken, ServiceError>;
+    async fn list(&self, user_id: &UserId) -> Result<Vec<ApiToken>, ServiceError>;
+    /// `NotFound` unless the token belongs to `user_id`
+    async fn revoke(&self, id: &ApiTokenId, user_id: &UserId) -> Result<(), ServiceError>;
+    /// The token and its active user, recording the use; `Unauthorized`
+    /// for unknown, expired or revoked tokens and inactive users
+    async fn authenticate(&self, secret: &str) -> Result<(M, ApiToken), ServiceError>;
 }
 
 // ============= Service Errors =============
@@ -1,19 +1,39 @@
 use crate::errors::DomainError;
 use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+use std::net::{Ipv4Addr, Ipv6Addr};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// RFC 5321 limits, in octets
+const MAX_LENGTH: usize = 254;
+const MAX_LOCAL_LENGTH: usize = 64;
+const MAX_DOMAIN_LENGTH: usize = 253;
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// An address as RFC 5321/5322 define it
+///
+/// The local part keeps the case it was given, since some servers treat it
+/// as significant, but two addresses compare (and hash) equal whatever the
+/// case. Domains are stored in their ASCII form, so `user@bücher.de` and
+/// `user@xn--bcher-kva.de` are the same address.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct Email(String);
 
 impl Email {
     pub fn new(value: &str) -> Result<Self, DomainError> {
-        let normalized = value.trim().to_lowercase();
+        let value = value.trim();
+        let (local, domain) = value.rsplit_once('@').ok_or(DomainError::InvalidEmail)?;
 
-        if !normalized.contains('@') || !normalized.contains('.') {
+        if !is_valid_local_part(local) {
             return Err(DomainError::InvalidEmail);
         }
+        let domain = normalize_domain(domain).ok_or(DomainError::InvalidEmail)?;
 
-        Ok(Self(normalized))
+        let address = format!("{}@{}", local, domain);
+        if address.len() > MAX_LENGTH {
+            return Err(DomainError::InvalidEmail);
+        }
+        Ok(Self(address))
     }
 
     pub fn from_trusted(value: String) -> Self {
@@ -24,8 +44,38 @@ impl Email {
         &self.0
     }
 
+    pub fn local_part(&self) -> &str {
+        self.0.rsplit_once('@').map_or("", |(local, _)| local)
+    }
+
+    /// The ASCII (punycode) form, as used for DNS lookups
     pub fn domain(&self) -> &str {
-        self.0.split('@').nth(1).unwrap_or("")
+        self.0.rsplit_once('@').map_or("", |(_, domain)| domain)
+    }
+
+    /// The domain as users write it: `xn--bcher-kva.de` → `bücher.de`
+    pub fn domain_unicode(&self) -> String {
+        idna::domain_to_unicode(self.domain()).0
+    }
+
+    /// Lowercased throughout; what repositories should store and look up by
+    pub fn normalized(&self) -> String {
+        self.0.to_lowercase()
+    }
+}
+
+/// By `normalized`, as `Hash` is, so equal addresses always hash alike
+impl PartialEq for Email {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl Eq for Email {}
+
+impl Hash for Email {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized().hash(state);
     }
 }
 
@@ -34,3 +84,71 @@ impl std::fmt::Display for Email {
         write!(f, "{}", self.0)
     }
 }
+
+/// A dot-atom (`first.last+tag`) or a quoted string (`"john doe"`)
+fn is_valid_local_part(local: &str) -> bool {
+    if local.is_empty() || local.len() > MAX_LOCAL_LENGTH {
+        return false;
+    }
+
+    if let Some(quoted) = local
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        let mut escaped = false;
+        for c in quoted.chars() {
+            match (escaped, c) {
+                (true, ' '..='~' | '\t') => escaped = false,
+                (true, _) => return false,
+                (false, '\\') => escaped = true,
+                (false, '"') => return false,
+                (false, ' '..='~' | '\t') => {}
+                (false, _) => return false,
+            }
+        }
+        return !escaped;
+    }
+
+    local.split('.').all(|atom| {
+        !atom.is_empty()
+            && atom
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c))
+    })
+}
+
+/// Lowercase ASCII hostname, or a bracketed IP literal; `None` if invalid
+fn normalize_domain(domain: &str) -> Option<String> {
+    if let Some(literal) = domain
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        let valid = match literal.get(..5) {
+            Some(tag) if tag.eq_ignore_ascii_case("IPv6:") => {
+                literal[5..].parse::<Ipv6Addr>().is_ok()
+            }
+            _ => literal.parse::<Ipv4Addr>().is_ok(),
+        };
+        return valid.then(|| domain.to_string());
+    }
+
+    let ascii = idna::domain_to_ascii(domain).ok()?;
+    if ascii.is_empty() || ascii.len() > MAX_DOMAIN_LENGTH {
+        return None;
+    }
+
+    let labels: Vec<&str> = ascii.split('.').collect();
+    let valid_labels = labels.iter().all(|label| {
+        !label.is_empty()
+            && label.len() <= MAX_LABEL_LENGTH
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+    // A dotless domain can't be reached from the public internet, and a
+    // numeric TLD means this was really an IP address
+    let tld = labels.last()?;
+    let valid_tld = labels.len() > 1 && !tld.chars().all(|c| c.is_ascii_digit());
+
+    (valid_labels && valid_tld).then_some(ascii)
+}
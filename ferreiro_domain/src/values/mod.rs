@@ -1,9 +1,11 @@
 mod email;
 mod ids;
+mod secret;
 mod slug;
 mod text;
 
 pub use email::Email;
-pub use ids::{PostId, UserId};
+pub use ids::{ApiTokenId, GroupId, PostId, UserId};
+pub use secret::SecretToken;
 pub use slug::Slug;
 pub use text::{Body, Title};
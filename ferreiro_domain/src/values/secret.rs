@@ -0,0 +1,22 @@
+/// A token only its owner should see, such as a password reset token
+///
+/// `Debug` prints a placeholder, so events and structs carrying one can be
+/// logged; `expose` is for the code that delivers it.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretToken(String);
+
+impl SecretToken {
+    pub fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for SecretToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretToken(..)")
+    }
+}
@@ -1,6 +1,8 @@
 use crate::errors::DomainError;
 use serde::{Deserialize, Serialize};
 
+const MAX_LENGTH: usize = 200;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct Slug(String);
@@ -13,9 +15,9 @@ impl Slug {
             return Err(DomainError::EmptySlug);
         }
 
-        if normalized.len() > 200 {
+        if normalized.len() > MAX_LENGTH {
             return Err(DomainError::SlugTooLong {
-                max: 200,
+                max: MAX_LENGTH,
                 actual: normalized.len(),
             });
         }
@@ -30,6 +32,53 @@ impl Slug {
         Ok(Self(normalized))
     }
 
+    /// Builds a slug from free text: "Olá Mundo!" → `ola-mundo`
+    ///
+    /// Unicode is transliterated to ASCII, every run of other characters
+    /// becomes one hyphen, and a long result is cut at the last whole word
+    /// that fits. Fails with `EmptySlug` if nothing usable is left.
+    pub fn from_title(title: &str) -> Result<Self, DomainError> {
+        let ascii = deunicode::deunicode(title).to_lowercase();
+        let words: Vec<&str> = ascii
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        let mut slug = String::new();
+        for word in words {
+            let separator = usize::from(!slug.is_empty());
+            if slug.len() + separator + word.len() > MAX_LENGTH {
+                if slug.is_empty() {
+                    slug.push_str(&word[..MAX_LENGTH]);
+                }
+                break;
+            }
+            if separator == 1 {
+                slug.push('-');
+            }
+            slug.push_str(word);
+        }
+
+        Self::new(&slug)
+    }
+
+    /// `post` → `post-2`; a long base loses whole words to stay in bounds
+    pub fn with_suffix(&self, n: u32) -> Self {
+        let suffix = format!("-{}", n);
+        let mut room = (MAX_LENGTH - suffix.len()).min(self.0.len());
+        // Trusted slugs from storage may not be ASCII
+        while !self.0.is_char_boundary(room) {
+            room -= 1;
+        }
+        let cut = &self.0[..room];
+        let base = if self.0.len() > room && self.0.as_bytes()[room] != b'-' {
+            cut.rfind('-').map_or(cut, |end| &cut[..end])
+        } else {
+            cut
+        };
+        Self(format!("{}{}", base.trim_end_matches('-'), suffix))
+    }
+
     /// For reconstitution from persistence — assumes valid
     pub fn from_trusted(value: String) -> Self {
         Self(value)
@@ -0,0 +1,74 @@
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::values::Email;
+use std::collections::HashSet;
+
+#[test]
+fn test_email_parsing_follows_the_rfcs() {
+    let valid = [
+        "user@example.com",
+        "first.last+tag@sub.example.co.uk",
+        "o'brien@example.ie",
+        "\"john doe\"@example.com",
+        "\"quote\\\"inside\"@example.com",
+        "admin@[192.0.2.1]",
+        "admin@[IPv6:2001:db8::1]",
+        "x@xn--bcher-kva.de",
+    ];
+    for address in valid {
+        assert!(Email::new(address).is_ok(), "{} should be valid", address);
+    }
+
+    let local = "a".repeat(65);
+    let label = "b".repeat(64);
+    let long_domain = format!("{}.com", vec!["c".repeat(60); 5].join("."));
+    let invalid = [
+        "@.",
+        "a@b@c.",
+        "plain",
+        "user@",
+        "@example.com",
+        ".user@example.com",
+        "user.@example.com",
+        "us..er@example.com",
+        "us er@example.com",
+        "\"unclosed@example.com",
+        "user@example",
+        "user@-example.com",
+        "user@example-.com",
+        "user@exa_mple.com",
+        "user@example..com",
+        "user@1.2.3.4",
+        "user@[300.1.1.1]",
+        &format!("{}@example.com", local),
+        &format!("user@{}.com", label),
+        &format!("user@{}", long_domain),
+    ];
+    for address in invalid {
+        assert_eq!(
+            Email::new(address),
+            Err(DomainError::InvalidEmail),
+            "{} should be invalid",
+            address
+        );
+    }
+}
+
+#[test]
+fn test_email_keeps_local_case_and_normalizes_the_domain() {
+    let email = Email::new("  Ana.Silva@Bücher.DE ").unwrap();
+    assert_eq!(email.as_str(), "Ana.Silva@xn--bcher-kva.de");
+    assert_eq!(email.local_part(), "Ana.Silva");
+    assert_eq!(email.domain(), "xn--bcher-kva.de");
+    assert_eq!(email.domain_unicode(), "bücher.de");
+    assert_eq!(email.normalized(), "ana.silva@xn--bcher-kva.de");
+
+    // Comparison and hashing ignore case
+    let same = Email::new("ANA.SILVA@xn--BCHER-KVA.de").unwrap();
+    assert_eq!(email, same);
+    let set: HashSet<Email> = [email, same].into_iter().collect();
+    assert_eq!(set.len(), 1);
+    assert_ne!(
+        Email::new("ana@example.com").unwrap(),
+        Email::new("ana@example.org").unwrap()
+    );
+}
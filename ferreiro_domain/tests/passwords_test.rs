@@ -0,0 +1,108 @@
+use ferreiro_domain::errors::DomainError;
+use ferreiro_domain::passwords::{
+    CommonPasswords, MinimumLength, PasswordPolicy, PasswordValidator, UserAttributeSimilarity,
+    UserAttributes, ValidatorConfigError,
+};
+
+const ANA: UserAttributes<'static> = UserAttributes {
+    email: "ana.silva@example.com",
+    name: "Ana Silva",
+};
+
+#[test]
+fn test_default_policy_rejects_weak_passwords() {
+    let policy = PasswordPolicy::default();
+
+    assert_eq!(
+        policy.validate("x7#kQ", &ANA),
+        Err(DomainError::PasswordTooShort { min: 8 })
+    );
+    assert_eq!(
+        policy.validate("Password123", &ANA),
+        Err(DomainError::PasswordTooCommon)
+    );
+    assert_eq!(
+        policy.validate("  QWERTYUIOP ", &ANA),
+        Err(DomainError::PasswordTooCommon)
+    );
+    assert_eq!(
+        policy.validate("80417263", &ANA),
+        Err(DomainError::PasswordTooWeak)
+    );
+    assert_eq!(
+        policy.validate("AnaSilva!", &ANA),
+        Err(DomainError::PasswordTooSimilar)
+    );
+    assert_eq!(
+        policy.validate("example.com", &ANA),
+        Err(DomainError::PasswordTooSimilar)
+    );
+
+    // Every broken rule, in order
+    assert_eq!(
+        policy.errors("1234567", &ANA),
+        [
+            DomainError::PasswordTooShort { min: 8 },
+            DomainError::PasswordTooCommon,
+            DomainError::PasswordTooWeak,
+        ]
+    );
+
+    for password in [
+        "vivid-otter-parade",
+        "correct horse battery staple",
+        "ana is 42 today!?",
+    ] {
+        assert_eq!(policy.validate(password, &ANA), Ok(()), "{}", password);
+    }
+}
+
+struct NoSpaces;
+
+impl PasswordValidator for NoSpaces {
+    fn validate(&self, password: &str, _user: &UserAttributes<'_>) -> Result<(), DomainError> {
+        if password.contains(' ') {
+            return Err(DomainError::PasswordTooWeak);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_policies_are_built_from_validators() {
+    let policy = PasswordPolicy::new()
+        .with(MinimumLength::new(12))
+        .with(CommonPasswords::from_list(["Ferreiro2024!"]))
+        .with(NoSpaces);
+
+    assert_eq!(
+        policy.validate("short", &ANA),
+        Err(DomainError::PasswordTooShort { min: 12 })
+    );
+    assert_eq!(
+        policy.validate("ferreiro2024!", &ANA),
+        Err(DomainError::PasswordTooCommon)
+    );
+    assert_eq!(
+        policy.validate("has some spaces", &ANA),
+        Err(DomainError::PasswordTooWeak)
+    );
+    // The bundled list and similarity checks only run when added
+    assert_eq!(policy.validate("passwordpassword", &ANA), Ok(()));
+    assert_eq!(PasswordPolicy::new().validate("", &ANA), Ok(()));
+}
+
+#[test]
+fn test_similarity_thresholds_that_reject_everything_are_refused() {
+    assert_eq!(
+        UserAttributeSimilarity::new(0.05).err(),
+        Some(ValidatorConfigError::SimilarityTooLow(0.05))
+    );
+    assert!(UserAttributeSimilarity::new(f64::NAN).is_err());
+
+    let strict = PasswordPolicy::new().with(UserAttributeSimilarity::new(0.5).unwrap());
+    assert_eq!(
+        strict.validate("silvana", &ANA),
+        Err(DomainError::PasswordTooSimilar)
+    );
+}
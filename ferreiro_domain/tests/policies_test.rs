@@ -0,0 +1,62 @@
+use ferreiro_domain::models::{Permission, Post};
+use ferreiro_domain::policies::{Actor, DefaultPostPolicy, PostAction, PostPolicy, ViewScope};
+use ferreiro_domain::values::{Body, Slug, Title, UserId};
+
+fn draft(author_id: &UserId) -> Post {
+    Post::new(
+        Title::new("Draft").unwrap(),
+        Slug::new("draft").unwrap(),
+        Body::new("Body"),
+        author_id.clone(),
+    )
+}
+
+#[test]
+fn test_authors_manage_their_own_drafts() {
+    let policy = DefaultPostPolicy;
+    let author = Actor::new(UserId::generate());
+    let stranger = Actor::new(UserId::generate());
+    let mut post = draft(author.id());
+
+    for action in [
+        PostAction::View,
+        PostAction::Update,
+        PostAction::Publish,
+        PostAction::Archive,
+        PostAction::Delete,
+    ] {
+        assert!(policy.allows(&author, action, &post), "{:?}", action);
+        assert!(!policy.allows(&stranger, action, &post), "{:?}", action);
+    }
+
+    // Once published, anyone may read it but the author can no longer
+    // edit or delete it without a permission
+    post.publish().unwrap();
+    assert!(policy.allows(&stranger, PostAction::View, &post));
+    assert!(!policy.allows(&author, PostAction::Update, &post));
+    assert!(!policy.allows(&author, PostAction::Delete, &post));
+    assert!(policy.allows(&author, PostAction::Archive, &post));
+}
+
+#[test]
+fn test_permissions_extend_to_other_authors_posts() {
+    let policy = DefaultPostPolicy;
+    let mut post = draft(&UserId::generate());
+    post.publish().unwrap();
+
+    let editor = Actor::new(UserId::generate())
+        .with_permission(Permission::change_post())
+        .with_permission(Permission::publish_post());
+    assert!(policy.allows(&editor, PostAction::Update, &post));
+    assert!(policy.allows(&editor, PostAction::Archive, &post));
+    assert!(!policy.allows(&editor, PostAction::Delete, &post));
+
+    let admin = Actor::new(UserId::generate()).superuser();
+    assert!(policy.allows(&admin, PostAction::Delete, &post));
+
+    // Listings follow the same View rule
+    assert_eq!(policy.view_scope(&editor), ViewScope::OwnPosts);
+    let reviewer = Actor::new(UserId::generate()).with_permission(Permission::view_post());
+    assert_eq!(policy.view_scope(&reviewer), ViewScope::AllPosts);
+    assert_eq!(policy.view_scope(&admin), ViewScope::AllPosts);
+}
@@ -0,0 +1,31 @@
+use ferreiro_domain::models::{Group, Permission, User};
+use ferreiro_domain::values::Email;
+
+#[test]
+fn test_users_round_trip_through_their_record() {
+    let mut user = User::new(
+        Email::new("ana@example.com").unwrap(),
+        "Ana Silva".to_string(),
+        "argon2$hash".to_string(),
+    );
+    user.make_staff();
+    user.mark_email_verified();
+    user.add_to_group(Group::new("Editors").id().clone());
+    user.grant_permission(Permission::publish_post());
+    user.set_timezone(Some("America/Sao_Paulo".to_string()));
+    user.set_display_name(Some("Ana".to_string()));
+    user.set_avatar_url(Some("https://example.com/ana.png".to_string()));
+    user.set_locale(Some("pt-BR".to_string()));
+    user.deactivate();
+
+    let record = user.to_record();
+    let loaded = User::reconstitute(record.clone());
+    assert_eq!(loaded.to_record(), record);
+    assert!(loaded.is_staff() && loaded.is_email_verified() && !loaded.is_active());
+    assert_eq!(loaded.groups(), user.groups());
+    assert!(loaded.permissions().contains(&Permission::publish_post()));
+    assert_eq!(loaded.timezone(), Some("America/Sao_Paulo"));
+    assert_eq!(loaded.display_name(), "Ana");
+    assert_eq!(loaded.avatar_url(), Some("https://example.com/ana.png"));
+    assert_eq!(loaded.locale(), Some("pt-BR"));
+}